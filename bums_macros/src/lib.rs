@@ -1,21 +1,24 @@
 extern crate proc_macro;
+use parse::Parser;
 use parse::{Parse, ParseStream};
 use proc_macro::{Span, TokenStream};
 #[allow(unused_imports)]
 use proc_macro_error::*;
 use punctuated::Punctuated;
 use quote::quote;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::fs::File;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io::{BufRead, BufReader};
 use syn::*;
 use z3::{Config, Context};
 
 use bums::common::*;
+use bums::computer::AlignmentCheck;
 
 #[derive(Debug)]
 struct CallColon {
+    attrs: Vec<Attribute>, // i.e. doc comments, kept on the wrapper
+    vis: Visibility,
     item_fn: Signature,
     _end_token: Token![;],
 }
@@ -23,6 +26,8 @@ struct CallColon {
 impl Parse for CallColon {
     fn parse(input: ParseStream) -> Result<Self> {
         return Ok(Self {
+            attrs: input.call(Attribute::parse_outer)?,
+            vis: input.parse()?,
             item_fn: input.parse()?,
             _end_token: input.parse()?,
         });
@@ -31,7 +36,7 @@ impl Parse for CallColon {
 
 #[derive(Debug)]
 struct AttributeList {
-    filename: LitStr,
+    filename: Option<LitStr>, // not written for assembly in the Rust source
     _separator: Option<Token![,]>,
     argument_list: Punctuated<Expr, Token![,]>,
 }
@@ -39,99 +44,407 @@ struct AttributeList {
 impl Parse for AttributeList {
     fn parse(input: ParseStream) -> Result<Self> {
         return Ok(Self {
-            filename: input.parse()?,
+            filename: match input.peek(LitStr) {
+                true => Some(input.parse()?),
+                false => None,
+            },
             _separator: input.parse()?,
             argument_list: punctuated::Punctuated::<Expr, Token![,]>::parse_terminated(input)?,
         });
     }
 }
 
-fn calculate_size_of(ty: String) -> usize {
-    match ty.as_str() {
-        "i8" => std::mem::size_of::<i8>(),
-        "i16" => std::mem::size_of::<i16>(),
-        "i32" => std::mem::size_of::<i32>(),
-        "i64" => std::mem::size_of::<i64>(),
-        "u8" => std::mem::size_of::<u8>(),
-        "u16" => std::mem::size_of::<u16>(),
-        "u32" => std::mem::size_of::<u32>(),
-        "u64" => std::mem::size_of::<u64>(),
-        "u128" => std::mem::size_of::<u128>(),
-        "usize" => std::mem::size_of::<u128>(),
-        "isize" => std::mem::size_of::<isize>(),
-        p => {
-            if let Ok(v) = p.parse::<usize>() {
-                return v;
-            } else {
-                todo!("size of undefined type")
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Deny, // a failed proof is a compile error
+    Warn, // a failed proof is a compiler warning
+    Skip, // the assembly is linked without being checked
+}
+
+fn parse_mode(value: &str) -> Option<Mode> {
+    match value {
+        "deny" => Some(Mode::Deny),
+        "warn" => Some(Mode::Warn),
+        "skip" => Some(Mode::Skip),
+        _ => None,
+    }
+}
+
+struct Options {
+    mode: Mode,
+    allow: Option<String>, // why a failed proof is acceptable
+    aliases: HashMap<String, Type>,
+    layouts: HashMap<String, ItemStruct>, // repr(C) structs passed by reference
+    rows: HashMap<String, [Expr; 3]>,     // stride, width and height of 2D buffers
+    ensures: Vec<ExprBinary>,             // what the return value ret satisfies
+    result: bool,                         // failed preconditions are returned as errors
+    secrets: Vec<Ident>,                  // arguments the running time may not depend on
+    initialises: Vec<Ident>,              // buffers the assembly has to fill, i.e. MaybeUninit
+    zeroise: Vec<Ident>,                  // stack and/or registers, cleared of secrets on return
+    overlapping: Vec<(Ident, Ident, Aliasing)>, // buffers that may alias, in place operations
+    alignment: AlignmentCheck,            // accesses that have to be shown aligned
+}
+
+// arguments named like the invariants name them, secret = [key, input]
+fn argument_names(assign: &ExprAssign, option: &str) -> Vec<Ident> {
+    let Expr::Array(a) = &*assign.right else {
+        abort!(
+            assign.right,
+            "{} is written as {} = [argument, ..]",
+            option,
+            option
+        )
+    };
+    a.elems
+        .iter()
+        .map(|e| match e {
+            Expr::Path(p) if p.path.get_ident().is_some() => p.path.get_ident().unwrap().clone(),
+            e => abort!(e, "{} lists argument names", option),
+        })
+        .collect()
+}
+
+// options are written as name = "value" anywhere in the argument list, like mode = "warn"
+fn take_options(attributes: &mut AttributeList) -> Options {
+    let mut options = Options {
+        mode: Mode::Deny,
+        allow: None,
+        aliases: HashMap::new(),
+        layouts: HashMap::new(),
+        rows: HashMap::new(),
+        ensures: Vec::new(),
+        result: false,
+        secrets: Vec::new(),
+        initialises: Vec::new(),
+        zeroise: Vec::new(),
+        overlapping: Vec::new(),
+        alignment: AlignmentCheck::Required,
+    };
+    for argument in std::mem::take(&mut attributes.argument_list) {
+        // aliasing is written like a call, may_alias(input, output) or exact_alias(input, output)
+        if let Expr::Call(call) = &argument {
+            let aliasing = match &*call.func {
+                Expr::Path(p) if p.path.is_ident("may_alias") => Some(Aliasing::May),
+                Expr::Path(p) if p.path.is_ident("exact_alias") => Some(Aliasing::Exact),
+                _ => None,
+            };
+            if let Some(aliasing) = aliasing {
+                let names: Vec<Ident> = call
+                    .args
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Path(p) if p.path.get_ident().is_some() => {
+                            p.path.get_ident().unwrap().clone()
+                        }
+                        e => abort!(e, "buffers that alias are named by their arguments"),
+                    })
+                    .collect();
+                let [a, b] = &names[..] else {
+                    abort!(call, "aliasing is between two arguments")
+                };
+                options.overlapping.push((a.clone(), b.clone(), aliasing));
+                continue;
+            }
+        }
+        let Expr::Assign(assign) = argument else {
+            attributes.argument_list.push(argument);
+            continue;
+        };
+        // postconditions are written like the invariants, ensures = [ret <= 1]
+        if matches!(&*assign.left, Expr::Path(p) if p.path.is_ident("ensures")) {
+            let Expr::Array(a) = &*assign.right else {
+                abort!(
+                    assign.right,
+                    "postconditions are written as ensures = [ret <= 1, ..]"
+                )
+            };
+            for e in &a.elems {
+                let Expr::Binary(b) = e else {
+                    abort!(e, "postconditions are comparisons")
+                };
+                options.ensures.push(b.clone());
             }
+            continue;
+        }
+        let names = match &*assign.left {
+            Expr::Path(p) if p.path.is_ident("secret") => Some(&mut options.secrets),
+            Expr::Path(p) if p.path.is_ident("initialises") => Some(&mut options.initialises),
+            Expr::Path(p) if p.path.is_ident("zeroise") => Some(&mut options.zeroise),
+            _ => None,
+        };
+        if let Some(names) = names {
+            let option = source_text(&assign.left);
+            names.extend(argument_names(&assign, &option));
+            continue;
+        }
+        let value = match &*assign.right {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => s.value(),
+            right => abort!(right, "option values are string literals"),
+        };
+        match &*assign.left {
+            Expr::Path(p) if p.path.is_ident("mode") => {
+                options.mode = parse_mode(&value).unwrap_or_else(|| {
+                    abort!(
+                        assign.right,
+                        "mode is one of \"deny\", \"warn\" or \"skip\""
+                    )
+                });
+            }
+            Expr::Path(p) if p.path.is_ident("allow") => {
+                if value.trim().is_empty() {
+                    abort!(
+                        assign.right,
+                        "allowing a failed proof needs a justification"
+                    );
+                }
+                options.allow = Some(value);
+            }
+            // macros cannot see type aliases, they are repeated like alias = "Limb = u64"
+            Expr::Path(p) if p.path.is_ident("alias") => {
+                let (name, ty) = value.split_once('=').unwrap_or_else(|| {
+                    abort!(assign.right, "aliases are written as \"Name = type\"")
+                });
+                match parse_str::<Type>(ty) {
+                    Ok(ty) => options.aliases.insert(name.trim().to_string(), ty),
+                    Err(error) => abort!(assign.right, "{}", error),
+                };
+            }
+            // struct definitions are not visible either, layout = "Name { field: type, .. }"
+            Expr::Path(p) if p.path.is_ident("layout") => {
+                let layout = match parse_str::<ItemStruct>(&format!("struct {}", value)) {
+                    Ok(layout) => layout,
+                    Err(error) => abort!(assign.right, "{}", error),
+                };
+                if !matches!(layout.fields, Fields::Named(_)) {
+                    abort!(
+                        assign.right,
+                        "layouts are written as \"Name { field: type, .. }\""
+                    );
+                }
+                options.layouts.insert(layout.ident.to_string(), layout);
+            }
+            // a slice holding rows of pixels, rows = "dst: dst_stride, w, h"
+            Expr::Path(p) if p.path.is_ident("rows") => {
+                let syntax = "rows are written as \"slice: stride, width, height\"";
+                let (name, dimensions) = value
+                    .split_once(':')
+                    .unwrap_or_else(|| abort!(assign.right, syntax));
+                let dimensions = Punctuated::<Expr, Token![,]>::parse_terminated
+                    .parse_str(dimensions)
+                    .unwrap_or_else(|error| abort!(assign.right, "{}", error));
+                let dimensions: [Expr; 3] = Vec::from_iter(dimensions)
+                    .try_into()
+                    .unwrap_or_else(|_| abort!(assign.right, syntax));
+                options.rows.insert(name.trim().to_string(), dimensions);
+            }
+            Expr::Path(p) if p.path.is_ident("preconditions") => {
+                options.result = match value.as_str() {
+                    "assert" => false,
+                    "result" => true,
+                    _ => abort!(
+                        assign.right,
+                        "preconditions are either \"assert\"ed or returned as a \"result\""
+                    ),
+                };
+            }
+            Expr::Path(p) if p.path.is_ident("alignment") => {
+                options.alignment = match value.as_str() {
+                    "required" => AlignmentCheck::Required,
+                    "natural" => AlignmentCheck::Natural,
+                    "off" => AlignmentCheck::Off,
+                    _ => abort!(
+                        assign.right,
+                        "alignment is one of \"required\", \"natural\" or \"off\""
+                    ),
+                };
+            }
+            left => abort!(
+                left,
+                "unknown option, expected mode, allow, alias, layout, rows, ensures, secret, preconditions or alignment"
+            ),
         }
     }
+
+    // one switch for the whole build, i.e. to skip checking in quick iterations
+    if let Ok(value) = std::env::var("BUMS_CHECK_MODE") {
+        options.mode = parse_mode(&value).unwrap_or_else(|| {
+            abort_call_site!(
+                "BUMS_CHECK_MODE is one of deny, warn or skip, not {:?}",
+                value
+            )
+        });
+    }
+    options
 }
 
-fn calculate_size_of_array(a: &TypeArray) -> usize {
-    let elem: String;
-    let len;
-    match &*a.elem {
-        Type::Path(b) => {
-            elem = b.path.segments[0].ident.to_string();
-        }
-        _ => todo!("calculate size of array that is not given using path"),
+// warnings from proc-macro-error are dropped on stable, a deprecated item is reported there too
+fn deprecated_warning(span: SpanRange, item: &str, message: String) -> proc_macro2::TokenStream {
+    let name = Ident::new(item, span.first);
+    let definition = Ident::new(item, proc_macro2::Span::call_site());
+    quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #definition: () = ();
+            #name
+        };
     }
+}
+
+fn not_proven_warning(span: SpanRange, message: String) -> proc_macro2::TokenStream {
+    deprecated_warning(span, "memory_safety_not_proven", message)
+}
+
+// size and alignment of primitive types on AArch64 (LP64), std::ffi names included
+fn scalar_layout(name: &str) -> Option<(usize, usize)> {
+    let size = match name {
+        "u8" | "i8" | "bool" | "c_char" | "c_schar" | "c_uchar" => 1,
+        "u16" | "i16" | "c_short" | "c_ushort" => 2,
+        "u32" | "i32" | "f32" | "char" | "c_int" | "c_uint" | "c_float" => 4,
+        "u64" | "i64" | "f64" | "usize" | "isize" | "c_long" | "c_ulong" | "c_longlong"
+        | "c_ulonglong" | "c_double" => 8,
+        "u128" | "i128" => 16,
+        _ => return None,
+    };
+    Some((size, size))
+}
+
+fn round_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+fn array_len(a: &TypeArray) -> usize {
     match &a.len {
-        Expr::Lit(b) => match &b.lit {
-            Lit::Int(i) => {
-                len = i
-                    .token()
-                    .to_string()
-                    .parse::<usize>()
-                    .expect("calculate_size_array");
-            }
-            _ => todo!("size of array1"),
-        },
-        _ => todo!("size of array2"),
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(i), ..
+        }) => i.base10_parse::<usize>().expect("array length"),
+        len => abort!(len, "array length must be an integer literal"),
     }
-    return calculate_size_of(elem) * len;
 }
 
-fn calculate_type_of_array_ptr(a: &TypeArray) -> String {
-    let elem: String;
-    match &*a.elem {
-        Type::Path(b) => {
-            elem = b.path.segments[0].ident.to_string();
+// the type an alias stands for, or the type itself
+fn resolve<'a>(ty: &'a Type, aliases: &'a HashMap<String, Type>) -> &'a Type {
+    if let Type::Path(p) = ty {
+        let name = p.path.segments.last().expect("type name").ident.to_string();
+        if let Some(alias) = aliases.get(&name) {
+            return resolve(alias, aliases);
         }
-        Type::Array(r) => {
-            let inner;
-            let size;
-            match &*r.elem {
-                Type::Path(p) => {
-                    inner = p.path.segments[0].ident.to_string();
+    }
+    ty
+}
+
+// the declared layout of a struct type, if there is one
+fn struct_layout<'a>(ty: &Type, options: &'a Options) -> Option<&'a ItemStruct> {
+    match resolve(ty, &options.aliases) {
+        Type::Path(p) => {
+            let name = p.path.segments.last().expect("type name").ident.to_string();
+            options.layouts.get(&name)
+        }
+        _ => None,
+    }
+}
+
+// offset of each field, size and alignment of a repr(C) struct with the given field types
+fn c_layout<'a>(
+    fields: impl Iterator<Item = &'a Type>,
+    options: &Options,
+) -> (Vec<usize>, usize, usize) {
+    let (mut offsets, mut size, mut align) = (Vec::new(), 0, 1);
+    for ty in fields {
+        let (s, a) = layout_of(ty, options);
+        size = round_up(size, a);
+        offsets.push(size);
+        size = size + s;
+        align = align.max(a);
+    }
+    (offsets, round_up(size, align), align)
+}
+
+// size and alignment of a type as the assembly sees it
+fn layout_of(ty: &Type, options: &Options) -> (usize, usize) {
+    if let Some(s) = struct_layout(ty, options) {
+        let (_, size, align) = c_layout(s.fields.iter().map(|f| &f.ty), options);
+        return (size, align);
+    }
+    match resolve(ty, &options.aliases) {
+        Type::Path(p) => {
+            let last = p.path.segments.last().expect("type name");
+            let name = last.ident.to_string();
+            // MaybeUninit<T> is laid out like T
+            if let (PathArguments::AngleBracketed(a), "MaybeUninit") = (&last.arguments, &*name) {
+                if let Some(GenericArgument::Type(t)) = a.args.first() {
+                    return layout_of(t, options);
                 }
-                _ => todo!("calculate type of array not with path {:?}", r),
             }
-            match &r.len {
-                Expr::Lit(l) => match &l.lit {
-                    Lit::Int(i) => size = i.to_string(),
-                    _ => todo!(),
-                },
-                _ => todo!(),
-            }
-            elem = "[".to_owned() + &inner + &";" + &size + "]";
+            scalar_layout(&name).unwrap_or_else(|| {
+                abort!(
+                    p,
+                    "size of type `{}` is not known", name;
+                    help = "declare what it stands for with alias = \"{} = <type>\" or layout = \"{} {{ field: type, .. }}\"", name, name;
+                )
+            })
         }
-        _ => todo!("calculate type of array not with path {:?}", a),
+        Type::Array(a) => {
+            let (size, align) = layout_of(&a.elem, options);
+            (size * array_len(a), align)
+        }
+        // laid out like the repr(C) struct the tuple is passed as
+        Type::Tuple(t) => {
+            let (_, size, align) = c_layout(t.elems.iter(), options);
+            (size, align)
+        }
+        Type::Reference(r) if matches!(*r.elem, Type::Slice(_)) => (16, 8),
+        Type::Ptr(_) | Type::Reference(_) => (8, 8),
+        Type::Paren(p) => layout_of(&p.elem, options),
+        Type::Group(g) => layout_of(&g.elem, options),
+        ty => abort!(ty, "size of this type is not known"),
     }
-    return elem;
 }
 
-fn calculate_type_of_slice_ptr(a: &TypeSlice) -> String {
-    let elem: String;
-    match &*a.elem {
-        Type::Path(b) => {
-            elem = b.path.segments[0].ident.to_string();
+// scalar fields are loaded into registers and compared, arrays and structs only take up space
+fn is_scalar(ty: &Type, options: &Options) -> bool {
+    match resolve(ty, &options.aliases) {
+        Type::Path(p) => {
+            let name = p.path.segments.last().expect("type name").ident.to_string();
+            scalar_layout(&name).is_some()
         }
-        _ => todo!("calculate type of slice"),
+        _ => false,
+    }
+}
+
+// the type as_ptr of an argument points to
+fn element_type(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Array(a) => Some(*a.elem.clone()),
+        Type::Reference(r) => match &*r.elem {
+            Type::Array(a) => Some(*a.elem.clone()),
+            Type::Slice(s) => Some(*s.elem.clone()),
+            _ => None,
+        },
+        _ => None,
     }
-    return elem;
+}
+
+// whether the argument is a mutable reference, none if it is not a reference
+fn reference_mutability(inputs: &Punctuated<FnArg, Token![,]>, name: &str) -> Option<bool> {
+    inputs.iter().find_map(|arg| match arg {
+        FnArg::Typed(t) => match (&*t.pat, &*t.ty) {
+            (Pat::Ident(p), Type::Reference(r)) if p.ident == name => Some(r.mutability.is_some()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+// nested regions are named after the argument they are reached from, the longest name that fits
+fn violated_argument<'a, T>(region: &str, arguments: &'a HashMap<String, T>) -> Option<&'a T> {
+    arguments
+        .iter()
+        .filter(|(arg, _)| region == *arg || region.starts_with(&format!("{}_", arg)))
+        .max_by_key(|(arg, _)| arg.len())
+        .map(|(_, value)| value)
 }
 
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
@@ -140,6 +453,35 @@ fn calculate_hash<T: Hash>(t: &T) -> u64 {
     s.finish()
 }
 
+// successful proofs are remembered next to the build output, keyed by everything that decides
+// them: the assembly, the attribute and signature the regions and invariants come from, and
+// the checker itself
+fn proof_cache(out_dir: &std::path::Path, assembly: &[u8], annotation: &str) -> std::path::PathBuf {
+    let mut hasher = Sha256::new();
+    for part in [
+        assembly,
+        annotation.as_bytes(),
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        bums::VERSION.as_bytes(),
+    ] {
+        // each part after its length, so two of them cannot run together
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let key: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    // OUT_DIR is target/<profile>/build/<crate>/out, cargo clean removes the cache with it
+    let profile = out_dir
+        .ancestors()
+        .find(|p| p.ends_with("build"))
+        .and_then(|p| p.parent())
+        .unwrap_or(out_dir);
+    profile.join("bums-cache").join(key)
+}
+
 fn binary_to_abstract_expression(input: &ExprBinary) -> AbstractExpression {
     let left_expr = syn_expr_to_abstract_expression(&input.left);
     let right_expr = syn_expr_to_abstract_expression(&input.right);
@@ -150,7 +492,7 @@ fn binary_to_abstract_expression(input: &ExprBinary) -> AbstractExpression {
         BinOp::Div(_) => return generate_expression("/", left_expr, right_expr),
         BinOp::Mul(_) => return generate_expression("*", left_expr, right_expr),
         BinOp::Rem(_) => return generate_expression("%", left_expr, right_expr),
-        _ => todo!("expression binary to abstract {:?}", input.op),
+        _ => abort!(input.op, "only + - * / and % can be used in expressions"),
     }
 }
 
@@ -165,7 +507,7 @@ fn binary_to_abstract_comparison(input: &ExprBinary) -> AbstractComparison {
         BinOp::Ne(_) => return generate_comparison("!=", left_expr, right_expr),
         BinOp::Ge(_) => return generate_comparison(">=", left_expr, right_expr),
         BinOp::Gt(_) => return generate_comparison(">", left_expr, right_expr),
-        _ => todo!("comparison conversion"),
+        _ => abort!(input.op, "only comparisons can be used as invariants"),
     }
 }
 fn unary_to_abstract_expression(input: &ExprUnary) -> AbstractExpression {
@@ -173,7 +515,7 @@ fn unary_to_abstract_expression(input: &ExprUnary) -> AbstractExpression {
     match input.op {
         UnOp::Not(_) => return generate_expression("!", expr, AbstractExpression::Empty),
         UnOp::Neg(_) => return generate_expression("-", expr, AbstractExpression::Empty),
-        _ => todo!("unary conversion"),
+        _ => abort!(input.op, "only ! and - can be used in expressions"),
     }
 }
 
@@ -186,7 +528,7 @@ fn syn_expr_to_abstract_expression(input: &Expr) -> AbstractExpression {
                     i.base10_parse::<i64>().expect("undefined integer"),
                 )
             }
-            _ => todo!("Input Literal type"),
+            _ => abort!(l, "only string and integer literals are supported"),
         },
         Expr::Binary(b) => return binary_to_abstract_expression(b),
         Expr::Unary(b) => return unary_to_abstract_expression(b),
@@ -196,7 +538,7 @@ fn syn_expr_to_abstract_expression(input: &Expr) -> AbstractExpression {
                 Expr::Path(a) => {
                     var_name = a.path.segments[0].ident.to_string();
                 }
-                _ => todo!("method matching to get name"),
+                _ => abort!(c.receiver, "methods can only be called on variables"),
             }
             match c.method.to_string().as_str() {
                 "len" => {
@@ -208,29 +550,285 @@ fn syn_expr_to_abstract_expression(input: &Expr) -> AbstractExpression {
                 "as_mut_ptr" => {
                     var_name = var_name + "_as_mut_ptr";
                 }
-                _ => todo!("method matching"),
+                m => abort!(
+                    c.method,
+                    "unsupported method `{}`, expected len, as_ptr or as_mut_ptr",
+                    m
+                ),
             };
             return AbstractExpression::Abstract(var_name);
         }
         Expr::Path(p) => {
             return AbstractExpression::Abstract(p.path.segments[0].ident.to_string());
         }
+        Expr::Field(_) | Expr::Index(_) => {
+            return AbstractExpression::Abstract(place_name(input));
+        }
+        _ => abort!(input, "unsupported expression"),
+    }
+}
+
+// fields and constant indices name what is stored there, descs[1].blocks is descs_1_fieldblocks
+fn place_name(input: &Expr) -> String {
+    match input {
+        Expr::Path(p) => p.path.segments[0].ident.to_string(),
         Expr::Field(f) => {
-            let base = match &*f.base {
-                Expr::Path(p) => p.clone().path.segments[0].ident.to_string(),
-                _ => todo!("field processing {:?}", f.base),
-            };
-            let index = match &f.member {
+            let member = match &f.member {
                 Member::Named(n) => n.to_string(),
                 Member::Unnamed(n) => n.index.to_string(),
             };
-            let new_name = base.to_owned() + "_field" + &index;
-            return AbstractExpression::Abstract(new_name);
+            place_name(&f.base) + "_field" + &member
         }
-        _ => todo!("Input type {:?}", input),
+        Expr::Index(i) => match &*i.index {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(n), ..
+            }) => place_name(&i.expr) + "_" + n.base10_digits(),
+            index => abort!(index, "only constant indices are supported"),
+        },
+        Expr::Paren(p) => place_name(&p.expr),
+        e => abort!(e, "fields can only be taken of variables"),
+    }
+}
+
+fn region_kind(mutable: bool) -> RegionType {
+    if mutable {
+        RegionType::WRITE
+    } else {
+        RegionType::READ
     }
 }
 
+// a mutable buffer holds what the caller put there, unless it is MaybeUninit or has to be filled
+fn buffer_kind(pointer: &TypePtr, root: &str, options: &Options) -> RegionType {
+    let uninitialised = matches!(&*pointer.elem, Type::Path(p)
+        if p.path.segments.last().is_some_and(|s| s.ident == "MaybeUninit"))
+        || options.initialises.iter().any(|s| *s == root);
+    match (pointer.mutability, uninitialised) {
+        (Some(_), false) => RegionType::RW,
+        (mutability, _) => region_kind(mutability.is_some()),
+    }
+}
+
+// lengths in layouts name the fields next to them, #[len(blocks * 64)]
+fn field_expression(input: &Expr, prefix: &str) -> AbstractExpression {
+    match input {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(i), ..
+        }) => AbstractExpression::Immediate(i.base10_parse::<i64>().expect("undefined integer")),
+        Expr::Path(p) if p.path.get_ident().is_some() => AbstractExpression::Abstract(format!(
+            "{}_field{}",
+            prefix,
+            p.path.get_ident().expect("field name")
+        )),
+        Expr::Paren(p) => field_expression(&p.expr, prefix),
+        Expr::Binary(b) => {
+            let op = match b.op {
+                BinOp::Add(_) => "+",
+                BinOp::Sub(_) => "-",
+                BinOp::Mul(_) => "*",
+                BinOp::Div(_) => "/",
+                _ => abort!(b.op, "only + - * and / can be used in lengths"),
+            };
+            generate_expression(
+                op,
+                field_expression(&b.left, prefix),
+                field_expression(&b.right, prefix),
+            )
+        }
+        e => abort!(
+            e,
+            "lengths are made of integers and fields of the same struct"
+        ),
+    }
+}
+
+/*
+ * what a region holds at offset, named after the place it is found in:
+ * scalars are inputs like key_fieldrounds, pointers lead to regions of their own
+ * structs and arrays of anything but scalars are taken apart, i.e. descs_1_fieldptr
+ */
+fn add_contents(
+    engine: &mut bums::engine::ExecutionEngine,
+    region: &str,
+    offset: usize,
+    name: &str,
+    ty: &Type,
+    length: Option<AbstractExpression>,
+    options: &Options,
+) {
+    if let Some(s) = struct_layout(ty, options) {
+        let (offsets, _, _) = c_layout(s.fields.iter().map(|f| &f.ty), options);
+        for (f, field_offset) in s.fields.iter().zip(offsets) {
+            let field = f.ident.as_ref().expect("need name of field");
+            let length = f.attrs.iter().find(|a| a.path().is_ident("len")).map(|a| {
+                match a.parse_args::<Expr>() {
+                    Ok(e) => field_expression(&e, name),
+                    Err(error) => abort!(a, "{}", error),
+                }
+            });
+            add_contents(
+                engine,
+                region,
+                offset + field_offset,
+                &format!("{}_field{}", name, field),
+                &f.ty,
+                length,
+                options,
+            );
+        }
+        return;
+    }
+
+    match resolve(ty, &options.aliases) {
+        Type::Path(_) if is_scalar(ty, options) => {
+            engine.add_abstract_to_memory(
+                region.to_string(),
+                offset as i64,
+                AbstractExpression::Abstract(name.to_string()),
+            );
+        }
+        Type::Array(a) if !is_scalar(&a.elem, options) => {
+            let size = layout_of(&a.elem, options).0;
+            for i in 0..array_len(a) {
+                add_contents(
+                    engine,
+                    region,
+                    offset + i * size,
+                    &format!("{}_{}", name, i),
+                    &a.elem,
+                    length.clone(),
+                    options,
+                );
+            }
+        }
+        Type::Ptr(p) => {
+            // without a length the buffer is name_len elements long, which nothing bounds
+            let count =
+                length.unwrap_or_else(|| AbstractExpression::Abstract(format!("{}_len", name)));
+            let bound = match layout_of(&p.elem, options).0 {
+                1 => count,
+                size => generate_expression("*", count, AbstractExpression::Immediate(size as i64)),
+            };
+            engine.add_region(buffer_kind(p, name, options), name.to_string(), bound);
+            engine.add_region_alignment(name.to_string(), layout_of(&p.elem, options).1 as i64);
+            engine.add_abstract_to_memory(
+                region.to_string(),
+                offset as i64,
+                AbstractExpression::Abstract(name.to_string()),
+            );
+        }
+        Type::Reference(r) if !matches!(*r.elem, Type::Slice(_)) => {
+            add_referenced_region(engine, name, r, options);
+            engine.add_abstract_to_memory(
+                region.to_string(),
+                offset as i64,
+                AbstractExpression::Abstract(name.to_string()),
+            );
+        }
+        _ => (),
+    }
+}
+
+// facts that hold for any value of the parameters' types, lengths of arrays and unsigned numbers
+fn type_facts(inputs: &Punctuated<FnArg, Token![,]>, options: &Options) -> Vec<AbstractComparison> {
+    let mut facts = Vec::new();
+    for i in inputs {
+        let FnArg::Typed(pat_type) = i else { continue };
+        let Pat::Ident(p) = &*pat_type.pat else {
+            continue;
+        };
+        let name = p.ident.to_string();
+        let ty = match resolve(&pat_type.ty, &options.aliases) {
+            Type::Reference(r) => resolve(&r.elem, &options.aliases),
+            ty => ty,
+        };
+        let length = AbstractExpression::Abstract(name.clone() + "_len");
+        let zero = AbstractExpression::Immediate(0);
+        facts.push(match ty {
+            Type::Array(a) => generate_comparison(
+                "==",
+                length,
+                AbstractExpression::Immediate(array_len(a) as i64),
+            ),
+            Type::Slice(_) => generate_comparison(">=", length, zero),
+            Type::Path(t) if t.path.get_ident().is_some_and(is_unsigned) => {
+                generate_comparison(">=", AbstractExpression::Abstract(name), zero)
+            }
+            _ => continue,
+        });
+    }
+    facts
+}
+
+fn is_unsigned(ty: &Ident) -> bool {
+    matches!(
+        ty.to_string().as_str(),
+        "u8" | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "c_uchar"
+            | "c_ushort"
+            | "c_uint"
+            | "c_ulong"
+            | "c_ulonglong"
+    )
+}
+
+// a precondition that follows from the types cannot fail, it is only checked in debug builds
+fn implied_by_types(condition: &AbstractComparison, facts: &[AbstractComparison]) -> bool {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = z3::Solver::new(&ctx);
+    for fact in facts {
+        solver.assert(&comparison_to_ast(&ctx, fact.clone()).expect("fact about a type"));
+    }
+    let condition = comparison_to_ast(&ctx, condition.clone()).expect("precondition");
+    let implied = solver.check_assumptions(&[condition.not()]) == z3::SatResult::Unsat;
+    implied
+}
+
+// conditions as written in the attribute, for messages and documentation
+fn source_text<T: quote::ToTokens>(tokens: &T) -> String {
+    let mut text = quote!(#tokens).to_string();
+    for (spaced, tight) in [
+        (" . ", "."),
+        (" (", "("),
+        ("( ", "("),
+        (" )", ")"),
+        (" [", "["),
+        ("[ ", "["),
+        (" ]", "]"),
+        (" ,", ","),
+    ] {
+        text = text.replace(spaced, tight);
+    }
+    text
+}
+
+// a reference points at exactly one value, its region is as large as the value
+fn add_referenced_region(
+    engine: &mut bums::engine::ExecutionEngine,
+    name: &str,
+    r: &TypeReference,
+    options: &Options,
+) {
+    let (size, align) = layout_of(&r.elem, options);
+    // what a reference points at is initialised, even when it is mutable
+    let kind = match r.mutability {
+        Some(_) => RegionType::RW,
+        None => RegionType::READ,
+    };
+    engine.add_region(
+        kind,
+        name.to_string(),
+        AbstractExpression::Immediate(size as i64),
+    );
+    engine.add_region_alignment(name.to_string(), align as i64);
+    add_contents(engine, name, 0, name, &r.elem, None, options);
+}
+
 fn tuple_to_struct(name: String, tuple: TypeTuple) -> ItemStruct {
     let span = Span::call_site().into();
 
@@ -246,22 +844,137 @@ fn tuple_to_struct(name: String, tuple: TypeTuple) -> ItemStruct {
     parse_quote! { #[repr(C)] struct #struct_name { #fields }}
 }
 
-// ATTRIBUTE ON EXTERN BLOCK
+// what is generated for a checked function besides the proof
+#[derive(Debug, Clone)]
+enum Output {
+    // a safe function that checks the preconditions and calls the assembly
+    Wrapper(Vec<Attribute>, Visibility),
+    // nothing, the function is declared in an extern block and stays unsafe
+    Declaration,
+    // the checks of the preconditions, i.e. at the start of a function using asm!
+    Checks,
+}
+
+// the error of wrappers with preconditions = "result", declared once in the crate using them
+#[proc_macro]
+pub fn precondition_error(item: TokenStream) -> TokenStream {
+    let vis = parse_macro_input!(item as Visibility);
+    quote! {
+        /// A precondition of a function checked for memory safety does not hold.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #vis struct PreconditionError(pub &'static str);
+
+        impl ::core::fmt::Display for PreconditionError {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "precondition `{}` does not hold", self.0)
+            }
+        }
+
+        impl ::std::error::Error for PreconditionError {}
+    }
+    .into()
+}
+
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let vars = parse_macro_input!(item as CallColon);
-    let mut attributes = parse_macro_input!(attr as AttributeList);
-    let fn_name = &vars.item_fn.ident;
-    let output = &vars.item_fn.output;
+    let checked = if let Ok(vars) = parse::<CallColon>(item.clone()) {
+        let annotation = attr.to_string() + &item.to_string();
+        let attributes = parse_macro_input!(attr as AttributeList);
+        let wrapper = Output::Wrapper(vars.attrs, vars.vis);
+        check_signature(annotation, vars.item_fn, attributes, None, wrapper)
+    } else {
+        match parse_macro_input!(item as Item) {
+            Item::ForeignMod(block) => check_extern_block(attr.into(), block, None),
+            Item::Mod(module) => check_global_asm(attr.into(), module),
+            Item::Fn(function) => check_inline_asm(attr.into(), function),
+            item => abort!(
+                item,
+                "expected a function signature, an extern block, a module or a function using asm!"
+            ),
+        }
+    };
+    let tracked = tracked_env();
+    quote! {
+        #checked
+        #tracked
+    }
+    .into()
+}
+
+// cargo reruns the macro when a variable read with option_env! changes, so the ones read with
+// std::env below are read there too (proc_macro::tracked_env is not stable)
+fn tracked_env() -> proc_macro2::TokenStream {
+    quote! {
+        const _: ::core::option::Option<&str> = ::core::option_env!("BUMS_CHECK_MODE");
+        const _: ::core::option::Option<&str> = ::core::option_env!("BUMS_REVERIFY");
+    }
+}
+
+// the assembly is taken from the file in the attribute, or from the source, named after the macro
+// that holds it
+fn check_signature(
+    annotation: String,
+    item_fn: Signature,
+    mut attributes: AttributeList,
+    source: Option<(&str, String)>,
+    kind: Output,
+) -> proc_macro2::TokenStream {
+    let options = take_options(&mut attributes);
+    let fn_name = &item_fn.ident;
+    let output = &item_fn.output;
+    if let (ReturnType::Default, Some(e)) = (output, options.ensures.first()) {
+        abort!(e, "postconditions need a return value"; help = "ret is the value returned in x0";);
+    }
+    for argument in options
+        .secrets
+        .iter()
+        .chain(&options.initialises)
+        .chain(options.overlapping.iter().flat_map(|(a, b, _)| [a, b]))
+    {
+        let named = |arg: &FnArg| matches!(arg, FnArg::Typed(t) if matches!(&*t.pat, Pat::Ident(p) if p.ident == *argument));
+        if !item_fn.inputs.iter().any(named) {
+            abort!(argument, "{} is not an argument of {}", argument, fn_name);
+        }
+    }
 
+    // borrow rules already rule out a mutable reference aliasing anything
+    for (a, b, _) in &options.overlapping {
+        for (x, y) in [(a, b), (b, a)] {
+            if reference_mutability(&item_fn.inputs, &x.to_string()) == Some(true) {
+                abort!(x, "{} is a mutable reference, it cannot alias {}", x, y);
+            }
+        }
+    }
+    for place in &options.zeroise {
+        if place != "stack" && place != "registers" {
+            abort!(place, "only the stack and registers are zeroised");
+        }
+    }
+    if let (Some(place), true) = (options.zeroise.first(), options.secrets.is_empty()) {
+        abort!(place, "nothing is secret"; help = "name the secret arguments with secret = [..]";);
+    }
+
+    // wrappers may return failed preconditions, the checks elsewhere can only panic
+    let result = options.result && matches!(kind, Output::Wrapper(..));
+    let facts = type_facts(&item_fn.inputs, &options);
     let mut invariants: Vec<AbstractComparison> = Vec::new();
     let mut asserts = quote! {};
+    let mut doc = Vec::new();
     if let Some(Expr::Array(a)) = attributes.argument_list.last() {
         for e in &a.elems {
             if let Expr::Binary(b) = e {
-                invariants.push(binary_to_abstract_comparison(b));
-                asserts = quote! { #asserts assert!(#e);};
+                let invariant = binary_to_abstract_comparison(b);
+                let text = source_text(e);
+                doc.push(format!("- `{}`", text));
+                asserts = if implied_by_types(&invariant, &facts) {
+                    quote! { #asserts debug_assert!(#e);}
+                } else if result {
+                    quote! { #asserts if !(#e) { return Err(PreconditionError(#text)); }}
+                } else {
+                    quote! { #asserts assert!(#e);}
+                };
+                invariants.push(invariant);
             } else {
                 emit_call_site_error!("Cannot define an invariant that is not a binary expression");
             }
@@ -269,17 +982,42 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
         attributes.argument_list.pop();
     }
 
+    // the compiler knows the real struct, a layout that does not match it is rejected
+    let mut layout_checks = quote! {};
+    for i in &item_fn.inputs {
+        let FnArg::Typed(pat_type) = i else { continue };
+        let Type::Reference(r) = &*pat_type.ty else {
+            continue;
+        };
+        let Some(s) = struct_layout(&r.elem, &options) else {
+            continue;
+        };
+        let ty = &r.elem;
+        let (offsets, size, _) = c_layout(s.fields.iter().map(|f| &f.ty), &options);
+        let message = format!("layout of {} does not match its definition", s.ident);
+        layout_checks = quote! { #layout_checks
+            const _: () = assert!(::core::mem::size_of::<#ty>() == #size, #message);
+        };
+        for (f, offset) in s.fields.iter().zip(offsets) {
+            let field = &f.ident;
+            layout_checks = quote! { #layout_checks
+                const _: () = assert!(::core::mem::offset_of!(#ty, #field) == #offset, #message);
+            };
+        }
+    }
+
     //get args from function call to pass to invocation
     let mut arguments_to_memory_safe_regions = Vec::new();
     let mut input_sizes = HashMap::new();
-    let mut pointer_sizes = HashMap::new();
+    let mut element_types = HashMap::new();
     let mut input_types = HashMap::new();
     let mut input_expressions = HashMap::new();
     let mut arguments_to_pass: Punctuated<_, _> = Punctuated::new();
     let mut new_structs = HashMap::new();
+    let mut arg_spans = HashMap::new();
     // if caller did not specify arguments in macro, grab names from function call
     if attributes.argument_list.is_empty() {
-        for i in &vars.item_fn.inputs {
+        for i in &item_fn.inputs {
             arguments_to_memory_safe_regions.push(i.clone());
             match i {
                 FnArg::Typed(pat_type) => match &*pat_type.pat {
@@ -299,14 +1037,16 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                             },
                         });
                         arguments_to_pass.push(w);
+                        arg_spans
+                            .insert(a.ident.to_string(), SpanRange::single_span(a.ident.span()));
                     }
-                    _ => todo!("non-ident name"),
+                    p => abort!(p, "arguments need to be named"),
                 },
-                _ => todo!("non-typed name"),
+                FnArg::Receiver(r) => abort!(r, "methods cannot be checked"),
             }
         }
     } else {
-        for i in &vars.item_fn.inputs {
+        for i in &item_fn.inputs {
             match i {
                 FnArg::Typed(pat_type) => {
                     // get name
@@ -315,66 +1055,31 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                         Pat::Ident(b) => {
                             name = b.ident.clone().to_string();
                         }
-                        _ => todo!("non-ident typed name in inputs"),
+                        p => abort!(p, "arguments need to be named"),
                     }
                     let ty = &*pat_type.ty;
                     input_types.insert(name.clone(), ty);
+                    if let Some(elem) = element_type(ty) {
+                        element_types.insert(name.clone(), elem);
+                    }
                     match ty {
-                        Type::Array(a) => {
-                            let ty = calculate_type_of_array_ptr(a);
-                            let size = calculate_size_of_array(a);
-                            input_sizes.insert(name.clone(), size * 2);
-                            pointer_sizes.insert(name, ty);
-                        }
                         Type::Reference(a) => match &*a.elem {
-                            Type::Array(b) => {
-                                let ty = calculate_type_of_array_ptr(b);
-                                let size = calculate_size_of_array(b);
-                                pointer_sizes.insert(name.clone(), ty);
-                                input_sizes.insert(name, size * 2);
-                            }
-                            Type::Slice(b) => {
-                                let ty = calculate_type_of_slice_ptr(b);
-                                pointer_sizes.insert(name, ty);
-                            }
+                            Type::Slice(_) => (),
                             Type::Tuple(t) => {
-                                let mut size = 0;
                                 new_structs
                                     .insert(name.clone(), tuple_to_struct(name.clone(), t.clone()));
-                                for e in &t.elems {
-                                    match e {
-                                        Type::Array(a) => {
-                                            size = size + calculate_size_of_array(&a);
-                                        }
-                                        Type::Path(p) => {
-                                            for i in &p.path.segments {
-                                                match i.ident.to_string().as_str() {
-                                                    "usize" => {
-                                                        size = size + std::mem::size_of::<usize>();
-                                                    }
-                                                    "u32" => {
-                                                        size = size + std::mem::size_of::<u32>();
-                                                    }
-                                                    _ => todo!("path size"),
-                                                }
-                                            }
-                                        }
-                                        _ => todo!("element list type"),
-                                    }
-                                }
-                                input_sizes.insert(name.clone(), size);
+                                input_sizes.insert(name, layout_of(&a.elem, &options).0);
+                            }
+                            elem => {
+                                input_sizes.insert(name, layout_of(elem, &options).0);
                             }
-                            _ => todo!("Input Reference Type"),
                         },
-                        Type::Path(p) => {
-                            let ty = p.path.segments[0].ident.to_string();
-                            let size = calculate_size_of(ty);
-                            input_sizes.insert(name, size * 2);
+                        ty => {
+                            input_sizes.insert(name, layout_of(ty, &options).0);
                         }
-                        _ => todo!("Standard Input type {:?}", ty),
                     }
                 }
-                _ => todo!("Untyped args"),
+                FnArg::Receiver(r) => abort!(r, "methods cannot be checked"),
             }
         }
         for i in &attributes.argument_list {
@@ -433,12 +1138,15 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
         args: arguments_to_pass,
     };
 
-    let mut extern_fn = vars.item_fn.clone();
+    let mut extern_fn = item_fn.clone();
     extern_fn.ident = fn_name.clone();
+    extern_fn.output = ReturnType::Default; // written after it below
     if !attributes.argument_list.is_empty() {
         let mut new_args: Punctuated<FnArg, Token![,]> = Punctuated::new();
         let mut span = proc_macro2::Span::call_site();
         for i in attributes.argument_list {
+            let arg_range = SpanRange::from_tokens(&i);
+            let pushed = new_args.len();
             match i {
                 Expr::MethodCall(a) => {
                     let var_name: String;
@@ -447,10 +1155,7 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                             var_name = a.path.segments[0].ident.to_string();
                             span = a.path.segments[0].ident.span();
                         }
-                        Expr::MethodCall(a) => {
-                            todo!("handle nested method calls {:?}", a)
-                        }
-                        _ => todo!("non-path receiver of a method call {:?}", a),
+                        _ => abort!(arg_range, "methods can only be called on variables"),
                     };
                     match a.method.to_string().as_str() {
                         "len" => {
@@ -459,39 +1164,25 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                         }
                         "as_ptr" => {
                             let n = Ident::new(&(var_name.clone() + "_as_ptr"), span.into());
-                            if let Some(size) = pointer_sizes.get(&var_name) {
-                                match size.as_str() {
-                                    "u8" => new_args.push(parse_quote! {#n: *const u8}),
-                                    "u16" => new_args.push(parse_quote! {#n: *const u16}),
-                                    "i16" => new_args.push(parse_quote! {#n: *const i16}),
-                                    "u32" => new_args.push(parse_quote! {#n: *const u32}),
-                                    "u64" => new_args.push(parse_quote! {#n: *const u64}),
-                                    "u128" => new_args.push(parse_quote! {#n: *const u128}),
-                                    "usize" => new_args.push(parse_quote! {#n: *const usize}),
-                                    _ => todo!("ptr array size 1"),
-                                }
+                            if let Some(elem) = element_types.get(&var_name) {
+                                new_args.push(parse_quote! {#n: *const #elem});
                             } else {
                                 new_args.push(parse_quote! {#n: *const usize});
                             }
                         }
                         "as_mut_ptr" => {
                             let n = Ident::new(&(var_name.clone() + "_as_mut_ptr"), span.into());
-                            if let Some(size) = pointer_sizes.get(&var_name) {
-                                match size.as_str() {
-                                    "u8" => new_args.push(parse_quote! {#n: *mut u8}),
-                                    "u16" => new_args.push(parse_quote! {#n: *mut u16}),
-                                    "u32" => new_args.push(parse_quote! {#n: *mut u32}),
-                                    "u64" => new_args.push(parse_quote! {#n: *mut u64}),
-                                    "u128" => new_args.push(parse_quote! {#n: *mut u128}),
-                                    "[u64;5]" => new_args.push(parse_quote! {#n: *mut [u64;5]}), // TODO: automate
-                                    "usize" => new_args.push(parse_quote! {#n: *mut usize}),
-                                    _ => todo!("ptr array size 2"),
-                                }
+                            if let Some(elem) = element_types.get(&var_name) {
+                                new_args.push(parse_quote! {#n: *mut #elem});
                             } else {
                                 new_args.push(parse_quote! {#n: *mut usize});
                             }
                         }
-                        _ => todo!("method call in new args"),
+                        m => abort!(
+                            a.method,
+                            "unsupported method `{}`, expected len, as_ptr or as_mut_ptr",
+                            m
+                        ),
                     };
                 }
                 Expr::Reference(_) => {
@@ -532,12 +1223,12 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                             Expr::Path(p) => {
                                 var_name = p.path.segments[0].ident.to_string();
                             }
-                            _ => todo!("name of cast ref expr types {:?}", r),
+                            _ => abort!(r.expr, "only references to variables can be cast"),
                         },
                         Expr::Path(p) => {
                             var_name = p.path.segments[0].ident.to_string();
                         }
-                        _ => todo!("name of cast expr types {:?}", c.expr),
+                        _ => abort!(c.expr, "only variables can be cast"),
                     }
 
                     let n = Ident::new(&(var_name.clone() + "_as_mut_ptr"), span.into());
@@ -566,21 +1257,17 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                                     var_name = a.path.segments[0].ident.to_string();
                                     span = a.path.segments[0].ident.span();
                                 }
-                                Expr::MethodCall(a) => {
-                                    todo!("handle nested method calls {:?}", a)
-                                }
-                                _ => todo!("non-path receiver of a method call {:?}", m),
+                                _ => abort!(m.receiver, "methods can only be called on variables"),
                             };
                         }
-                        _ => todo!("name of field expr types {:?}", f.base),
+                        _ => abort!(f.base, "fields can only be taken of variables"),
                     }
                     match *f.base {
                         Expr::MethodCall(ref m) => match m.method.to_string().as_str() {
                             "as_ptr_range" => {
-                                let pointer_size = pointer_sizes
+                                let pointer_type = element_types
                                     .get(&var_name)
-                                    .expect("can't find size of  slice");
-                                let pointer_type = Ident::new(&pointer_size.clone(), span.into());
+                                    .unwrap_or_else(|| abort!(m.receiver, "needs to be a slice"));
                                 match f.member {
                                     syn::Member::Named(name) => match name.to_string().as_str() {
                                         "end" => {
@@ -590,7 +1277,7 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                                             );
                                             new_args.push(parse_quote! {#n : *const #pointer_type});
                                         }
-                                        _ => todo!("more subfields of range"),
+                                        _ => abort!(name, "only the end of a range is supported"),
                                     },
                                     syn::Member::Unnamed(i) => match i.index {
                                         0 => {
@@ -607,16 +1294,22 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                                             );
                                             new_args.push(parse_quote! {#n : _});
                                         }
-                                        _ => todo!("irrelevant for this type"),
+                                        _ => abort!(i, "ranges only have fields 0 and 1"),
                                     },
                                 }
                             }
-                            _ => todo!("match on fields of the results of more methods"),
+                            _ => abort!(m.method, "only fields of as_ptr_range are supported"),
                         },
-                        _ => todo!("match on fields of more methods"),
+                        _ => abort!(arg_range, "only fields of as_ptr_range are supported"),
                     }
                 }
-                _ => todo!("Arg list type {:?}", i),
+                i => abort!(i, "unsupported argument expression"),
+            }
+            // remember which argument each region came from to point errors at it
+            if let Some(FnArg::Typed(t)) = new_args.iter().nth(pushed) {
+                if let Pat::Ident(p) = &*t.pat {
+                    arg_spans.insert(p.ident.to_string(), arg_range);
+                }
             }
         }
         for a in &new_args {
@@ -625,6 +1318,80 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
         extern_fn = parse_quote! {fn #fn_name(#new_args)};
     }
 
+    // the proof takes the rows for granted, the slice has to hold them
+    for (slice, [stride, width, height]) in &options.rows {
+        let Some(element) = element_types.get(slice) else {
+            abort_call_site!("rows are taken from slices, `{}` is not one", slice)
+        };
+        let size = layout_of(element, &options).0 as i128;
+        let slice = Ident::new(slice, proc_macro2::Span::call_site());
+        let overlap = format!("rows of {} overlap", slice);
+        let fit = format!("{} does not hold its rows", slice);
+        doc.push(format!(
+            "- `{}` holds rows that do not overlap, going forward from its start",
+            slice
+        ));
+        // the slice starts at the first row, rows before it only fit when there are none
+        let (overlaps, fits) = (
+            quote!(width >= 0 && height >= 0 && stride.abs() >= width),
+            quote!(
+                height == 0
+                    || ((height == 1 || stride >= 0)
+                        && (height - 1) * stride + width <= #slice.len() as i128 * #size)
+            ),
+        );
+        let checks = match result {
+            true => quote! {
+                if !(#overlaps) { return Err(PreconditionError(#overlap)); }
+                if !(#fits) { return Err(PreconditionError(#fit)); }
+            },
+            false => quote! {
+                assert!(#overlaps, #overlap);
+                assert!(#fits, #fit);
+            },
+        };
+        asserts = quote! { #asserts
+            {
+                let (stride, width, height) =
+                    ((#stride) as i128, (#width) as i128 * #size, (#height) as i128);
+                #checks
+            }
+        };
+    }
+
+    // the contract shows up in the documentation of the wrapper
+    if doc.is_empty() {
+        doc.push("- none".to_string());
+    }
+    doc.insert(0, "# Preconditions".to_string());
+    if !options.ensures.is_empty() {
+        doc.push(String::new());
+        doc.push("# Postconditions".to_string());
+        doc.extend(
+            options
+                .ensures
+                .iter()
+                .map(|e| format!("- `{}`", source_text(e))),
+        );
+    }
+    if result {
+        doc.push(String::new());
+        doc.push("# Errors".to_string());
+        doc.push("A `PreconditionError` when a precondition does not hold.".to_string());
+    }
+    let checked = match (&options.mode, &attributes.filename) {
+        (Mode::Skip, _) => "not checked for memory safety".to_string(),
+        (_, Some(file)) => format!("checked for memory safety against `{}`", file.value()),
+        (_, None) => "checked for memory safety".to_string(),
+    };
+    // after a blank line, the wrapper's own documentation comes first
+    doc.insert(0, String::new());
+    doc.insert(
+        1,
+        format!("Calls the assembly of `{}`, {}.", fn_name, checked),
+    );
+    doc.insert(2, String::new());
+
     let mut struct_decs = quote! {};
     for i in new_structs.values() {
         struct_decs = quote! {
@@ -635,53 +1402,114 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
         };
     }
 
-    let original_fn_call = vars.item_fn.clone();
-    let unsafe_block: Stmt = parse_quote! {
-        #original_fn_call {
+    let mut original_fn_call = item_fn.clone();
+    let mut invocation = quote!(#invocation);
+    if result {
+        let ty = match output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ty) => quote!(#ty),
+        };
+        original_fn_call.output = parse_quote!(-> ::core::result::Result<#ty, PreconditionError>);
+        invocation = quote!(Ok(#invocation));
+    }
+
+    // the stack the assembly uses is documented once it has been checked, in bytes if it is known
+    let expand = |stack: Option<Option<i64>>| {
+        let mut doc = doc.clone();
+        if let Some(stack) = stack {
+            doc.push(String::new());
+            doc.push("# Stack".to_string());
+            doc.push(match stack {
+                Some(bytes) => format!("Uses at most {} bytes of stack.", bytes),
+                None => "Uses as much stack as its inputs ask for.".to_string(),
+            });
+        }
+        let doc = doc.iter().map(|line| format!(" {}", line));
+        match &kind {
+            Output::Wrapper(attrs, vis) => quote! {
+                #(#attrs)*
+                #(#[doc = #doc])*
+                #[inline]
+                #vis #original_fn_call {
+
+                    #asserts;
 
-            #asserts;
+                    #layout_checks
 
-            #struct_decs;
+                    #struct_decs;
 
-            extern "C" {
-                #extern_fn #output;
-            }
-            unsafe {
-                return #invocation;
-            }
+                    extern "C" {
+                        #extern_fn #output;
+                    }
+                    unsafe {
+                        return #invocation;
+                    }
+                }
+            },
+            Output::Declaration => quote!(#layout_checks),
+            Output::Checks => quote!(#asserts; #layout_checks),
         }
     };
-
-    let token_stream = quote!(#unsafe_block).into();
+    let token_stream = expand(None);
+    if options.mode == Mode::Skip {
+        return token_stream;
+    }
 
     // compile file
     // make this path
-    let filename = attributes.filename.value();
-    let assembly_file: std::path::PathBuf =
-        [std::env::var("OUT_DIR").expect("OUT_DIR"), filename.clone()]
-            .iter()
-            .collect();
-    let res = File::open(assembly_file);
-    let file: File;
-    match res {
-        Ok(opened) => {
-            file = opened;
-        }
-        Err(error) => {
-            // make more specific using span
-            abort_call_site!(error);
+    let out_dir = std::env::var_os("OUT_DIR").map(std::path::PathBuf::from);
+    let (filename, assembly) = match (attributes.filename, source) {
+        (Some(file), None) => {
+            let Some(out_dir) = &out_dir else {
+                abort!(
+                    file,
+                    "assembly files are read from OUT_DIR, the crate needs a build script"
+                )
+            };
+            match std::fs::read(out_dir.join(file.value())) {
+                Ok(b) => (file.value(), b),
+                // make more specific using span
+                Err(error) => abort_call_site!(error),
+            }
         }
+        (None, Some((name, text))) => (name.to_string(), text.into_bytes()),
+        (Some(file), Some((name, _))) => abort!(file, "the assembly is taken from {}", name),
+        (None, None) => abort_call_site!("the name of the assembly file is missing"),
     };
 
-    let reader = BufReader::new(file);
-    let mut program = Vec::new();
-    for line in reader.lines() {
-        program.push(line.unwrap_or(String::from("")));
+    // BUMS_REVERIFY ignores earlier results, i.e. after changing the checker
+    let cached_proof = out_dir.map(|out_dir| proof_cache(&out_dir, &assembly, &annotation));
+    if std::env::var_os("BUMS_REVERIFY").is_none() {
+        // the label and the stack the proof found, in bytes or "dynamic"
+        if let Some(proof) = cached_proof
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+        {
+            let stack = proof.lines().nth(1).map(|line| line.parse::<i64>().ok());
+            return expand(stack);
+        }
     }
 
     let cfg = Config::new();
     let ctx = Context::new(&cfg);
-    let mut engine = bums::engine::ExecutionEngine::new(program, &ctx);
+
+    // assembled objects and static archives (i.e. liblinkedasms.a) are disassembled directly
+    let mut engine = if filename.ends_with(".o") || filename.ends_with(".a") {
+        match bums::engine::ExecutionEngine::new_from_object(&assembly, &ctx) {
+            Ok(e) => e,
+            Err(error) => abort_call_site!(error),
+        }
+    } else {
+        let program = String::from_utf8_lossy(&assembly)
+            .lines()
+            .map(String::from)
+            .collect();
+
+        bums::engine::ExecutionEngine::new(program, &ctx)
+    };
+
+    // the root argument and the region of each buffer passed
+    let mut buffers: Vec<(String, String)> = Vec::new();
 
     // add memory safe regions
     for i in 0..arguments_to_memory_safe_regions.len() {
@@ -698,9 +1526,49 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                     Pat::Lit(l) => match &l.lit {
                         Lit::Str(s) => name = s.value(),
                         Lit::Int(i) => name = i.base10_digits().to_string(),
-                        _ => todo!("Regions literal pattern {:?}", l),
+                        _ => abort!(l, "unsupported literal argument"),
                     },
-                    _ => todo!("Regions pattern pattern"),
+                    p => abort!(p, "unsupported argument pattern"),
+                }
+                // a secret slice is secret through its pointers, its length is public
+                let root = [
+                    "_as_mut_ptr",
+                    "_end_ptr_range",
+                    "_start_ptr_range",
+                    "_as_ptr",
+                ]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix))
+                .unwrap_or(&name);
+                if matches!(
+                    &*pat_type.ty,
+                    Type::Ptr(_) | Type::Reference(_) | Type::Array(_)
+                ) {
+                    let region = match name.ends_with("_end_ptr_range") {
+                        true => root.to_owned() + "_as_ptr",
+                        false => name.clone(),
+                    };
+                    let buffer = (root.to_string(), region);
+                    if !buffers.contains(&buffer) {
+                        buffers.push(buffer);
+                    }
+                }
+                if options.secrets.iter().any(|s| *s == root) {
+                    match &*pat_type.ty {
+                        Type::Path(_) => engine.add_secret_argument(i),
+                        _ if name.ends_with("_end_ptr_range") => {
+                            engine.add_secret_region(root.to_owned() + "_as_ptr")
+                        }
+                        _ => engine.add_secret_region(name.clone()),
+                    }
+                }
+                if options.initialises.iter().any(|s| *s == root) {
+                    match &*pat_type.ty {
+                        Type::Ptr(p) if p.mutability.is_some() => {
+                            engine.add_output_region(name.clone())
+                        }
+                        ty => abort!(ty, "only buffers passed as as_mut_ptr() are initialised"),
+                    }
                 }
                 //get type to get size
                 match &*pat_type.ty {
@@ -724,180 +1592,859 @@ pub fn check_mem_safe(attr: TokenStream, item: TokenStream) -> TokenStream {
                             engine.add_abstract_from(i, name.clone());
                         }
                     }
-                    Type::Array(a) => {
-                        let size = calculate_size_of_array(a);
+                    Type::Array(_) => {
+                        let (size, align) = layout_of(&pat_type.ty, &options);
                         engine.add_abstract_from(i, name.clone());
                         engine.add_region(
                             RegionType::RW,
                             name.clone(),
                             AbstractExpression::Immediate(size as i64),
                         );
+                        engine.add_region_alignment(name.clone(), align as i64);
                     }
                     Type::Ptr(a) => {
                         // load pointer into register
                         engine.add_abstract_from(i, name.clone());
+                        let kind = buffer_kind(a, root, &options);
 
                         //derive memory safe region based on length
                         let no_mut_name = name.strip_suffix("_as_mut_ptr").unwrap_or(&name);
                         let no_range = name.strip_suffix("_end_ptr_range").unwrap_or(&no_mut_name);
                         let no_suffix = no_range.strip_suffix("_as_ptr").unwrap_or(no_range);
 
-                        match &*a.elem {
-                            Type::Path(p) => {
-                                // if pointer to a macro-defined struct
-                                if p.path.segments[0].ident.to_string().contains("struct") {
-                                    // add the whole region covered by the tuple
-                                    if let Some(bound) = input_sizes.get(no_suffix) {
-                                        if a.mutability.is_some() {
-                                            engine.add_region(
-                                                RegionType::WRITE,
-                                                name.clone(),
-                                                AbstractExpression::Immediate(*bound as i64),
-                                            );
-                                        } else {
-                                            engine.add_region(
-                                                RegionType::READ,
-                                                name.clone(),
-                                                AbstractExpression::Immediate(bound.clone() as i64),
-                                            );
-                                        }
-                                    }
-
-                                    let s = new_structs
-                                        .get(no_suffix)
-                                        .expect("Need well defined struct");
-                                    let mut i = 0;
-                                    let mut index = 0;
-                                    for e in &s.fields {
-                                        match e.ty.clone() {
-                                            Type::Path(p) => {
-                                                if p.path.segments.len() == 1 {
-                                                    let abs = p.path.segments[0].ident.to_string();
-                                                    match abs.as_str() {
-                                                        "usize" => {
-                                                            let new_name = e.ident.clone().expect(
-                                                                "need name of variable to input",
-                                                            );
-                                                            engine.add_abstract_to_memory(
-                                                                name.clone(),
-                                                                index,
-                                                                AbstractExpression::Abstract(
-                                                                    new_name.to_string(),
-                                                                ),
-                                                            );
-                                                        }
-                                                        "u32" => {
-                                                            let new_name = e.ident.clone().expect(
-                                                                "need name of variable to input",
-                                                            );
-                                                            engine.add_abstract_to_memory(
-                                                                name.clone(),
-                                                                index,
-                                                                AbstractExpression::Abstract(
-                                                                    new_name.to_string(),
-                                                                ),
-                                                            );
-                                                        }
-                                                        _ => todo!("tuple abstracts"),
-                                                    }
-                                                }
-                                            }
-                                            Type::Array(a) => {
-                                                if let Some(_) = input_sizes.get(no_suffix) {
-                                                    index = index
-                                                        + ((calculate_size_of_array(&a)) as i64);
-                                                }
-                                            }
-                                            _ => todo!("unsupported tuple type {:?}", e),
-                                        }
-                                        i = i + 1;
-                                    }
-                                    continue;
-                                }
+                        // if pointer to a macro-defined struct, its fields hold the tuple
+                        if let Some(s) = new_structs.get(no_suffix) {
+                            let (offsets, size, align) =
+                                c_layout(s.fields.iter().map(|f| &f.ty), &options);
+                            engine.add_region(
+                                kind,
+                                name.clone(),
+                                AbstractExpression::Immediate(size as i64),
+                            );
+                            engine.add_region_alignment(name.clone(), align as i64);
+                            for (f, offset) in s.fields.iter().zip(offsets) {
+                                let field = f.ident.as_ref().expect("need name of field");
+                                add_contents(
+                                    &mut engine,
+                                    &name,
+                                    offset,
+                                    &field.to_string(),
+                                    &f.ty,
+                                    None,
+                                    &options,
+                                );
+                            }
+                            continue;
+                        }
 
-                                // if pointing to end of array
-                                if name.contains("_end_ptr_range") {
-                                    // add the whole region covered by the tuple
-                                    let bound = no_suffix.to_owned() + "_len";
-                                    let pointer_name = no_suffix.to_owned() + "_as_ptr";
+                        // lengths count elements, regions are in bytes
+                        let (element_size, element_align) = element_types
+                            .get(no_suffix)
+                            .map_or((1, 1), |e| layout_of(e, &options));
+                        // the buffer starts where its first element does
+                        let start = match name.contains("_end_ptr_range") {
+                            true => no_suffix.to_owned() + "_as_ptr",
+                            false => name.clone(),
+                        };
+                        engine.add_region_alignment(start, element_align as i64);
+                        let length = |len: String| match element_size {
+                            1 => AbstractExpression::Abstract(len),
+                            size => generate_expression(
+                                "*",
+                                AbstractExpression::Abstract(len),
+                                AbstractExpression::Immediate(size as i64),
+                            ),
+                        };
 
-                                    engine.add_region(
-                                        RegionType::READ,
-                                        pointer_name.clone(),
-                                        AbstractExpression::Abstract(bound.clone()),
-                                    );
+                        // rows of a 2D buffer, the stride is in bytes already
+                        if let Some([stride, width, height]) = options.rows.get(no_suffix) {
+                            let width = match element_size {
+                                1 => syn_expr_to_abstract_expression(width),
+                                size => generate_expression(
+                                    "*",
+                                    syn_expr_to_abstract_expression(width),
+                                    AbstractExpression::Immediate(size as i64),
+                                ),
+                            };
+                            engine.add_region_2d(
+                                kind,
+                                name.clone(),
+                                width,
+                                syn_expr_to_abstract_expression(stride),
+                                syn_expr_to_abstract_expression(height),
+                            );
+                            continue;
+                        }
 
-                                    //overwrite
-                                    engine.add_abstract_expression_from(
-                                        i,
-                                        generate_expression(
-                                            "+",
-                                            AbstractExpression::Abstract(pointer_name),
-                                            AbstractExpression::Abstract(bound),
-                                        ),
-                                    );
+                        // if pointing to end of array
+                        if name.contains("_end_ptr_range") {
+                            // add the whole region covered by the tuple
+                            let bound = length(no_suffix.to_owned() + "_len");
+                            let pointer_name = no_suffix.to_owned() + "_as_ptr";
+                            if let Some(span) = arg_spans.get(&name).copied() {
+                                arg_spans.insert(pointer_name.clone(), span);
+                            }
 
-                                    continue;
-                                }
+                            engine.add_region(
+                                RegionType::READ,
+                                pointer_name.clone(),
+                                bound.clone(),
+                            );
 
-                                // if pointing to an array defined as a function param, no abstract length
-                                if let Some(bound) = input_sizes.get(no_suffix) {
-                                    if a.mutability.is_some() {
-                                        engine.add_region(
-                                            RegionType::WRITE,
-                                            name.clone(),
-                                            AbstractExpression::Immediate(*bound as i64),
-                                        );
-                                    } else {
-                                        engine.add_region(
-                                            RegionType::READ,
-                                            name.clone(),
-                                            AbstractExpression::Immediate(bound.clone() as i64),
-                                        );
-                                    }
-                                    continue;
-                                }
+                            //overwrite
+                            engine.add_abstract_expression_from(
+                                i,
+                                generate_expression(
+                                    "+",
+                                    AbstractExpression::Abstract(pointer_name),
+                                    bound,
+                                ),
+                            );
 
-                                let bound = no_suffix.to_owned() + "_len";
-                                if a.mutability.is_some() {
-                                    engine.add_region(
-                                        RegionType::WRITE,
-                                        name.clone(),
-                                        AbstractExpression::Abstract(bound),
-                                    );
-                                } else {
-                                    engine.add_region(
-                                        RegionType::READ,
-                                        name.clone(),
-                                        AbstractExpression::Abstract(bound),
-                                    );
-                                }
-                            }
-                            _ => todo!("unsupported pointer type to pass to asm {:?}", a.elem),
+                            continue;
                         }
+
+                        // if pointing to an array defined as a function param, no abstract length
+                        if let Some(bound) = input_sizes.get(no_suffix) {
+                            engine.add_region(
+                                kind,
+                                name.clone(),
+                                AbstractExpression::Immediate(*bound as i64),
+                            );
+                            continue;
+                        }
+
+                        let bound = length(no_suffix.to_owned() + "_len");
+                        engine.add_region(kind, name.clone(), bound);
                     }
-                    _ => todo!("yet unsupported type: {:?}", pat_type.ty),
+                    // reference to a value, i.e. a struct with a declared layout
+                    Type::Reference(r) => {
+                        if let Type::Slice(_) = *r.elem {
+                            abort!(r, "slices are passed as as_ptr() and len()");
+                        }
+                        engine.add_abstract_from(i, name.clone());
+                        add_referenced_region(&mut engine, &name, r, &options);
+                    }
+                    ty => abort!(ty, "unsupported argument type"),
+                }
+            }
+            FnArg::Receiver(r) => abort!(r, "methods cannot be checked"),
+        }
+    }
+
+    // buffers declared to overlap, and those the borrow rules keep apart
+    for (n, (a, region_a)) in buffers.iter().enumerate() {
+        for (b, region_b) in &buffers[n + 1..] {
+            let declared = options
+                .overlapping
+                .iter()
+                .find(|(x, y, _)| (x == a && y == b) || (x == b && y == a));
+            let mutability = [a, b].map(|r| reference_mutability(&item_fn.inputs, r));
+            match (declared, mutability) {
+                (Some((_, _, aliasing)), _) => {
+                    engine.add_alias(region_a.clone(), region_b.clone(), *aliasing)
+                }
+                // the same buffer, i.e. buf.as_ptr() and buf.as_mut_ptr()
+                _ if a == b => {
+                    engine.add_alias(region_a.clone(), region_b.clone(), Aliasing::Exact)
+                }
+                (_, [Some(x), Some(y)]) if x || y => {
+                    engine.add_disjoint_regions(region_a.clone(), region_b.clone())
                 }
+                _ => (),
             }
-            _ => todo!("lib"),
         }
     }
 
     for i in invariants {
         engine.add_invariant(i);
     }
-    let label = vars.item_fn.ident.to_string();
-    let res = engine.start(label);
+    if !options.zeroise.is_empty() {
+        engine.require_zeroised(bums::computer::Zeroise {
+            stack: options.zeroise.iter().any(|p| p == "stack"),
+            registers: options.zeroise.iter().any(|p| p == "registers"),
+            returns: !matches!(item_fn.output, ReturnType::Default),
+        });
+    }
+    for e in &options.ensures {
+        engine.add_postcondition(binary_to_abstract_comparison(e));
+    }
+    engine.check_alignment(options.alignment);
+    let label = item_fn.ident.to_string();
+    let res = engine.start(label.clone());
 
-    match res {
-        Ok(_) => return token_stream,
+    let havocs: proc_macro2::TokenStream = engine
+        .get_havocs()
+        .iter()
+        .map(|havoc| {
+            deprecated_warning(
+                SpanRange::call_site(),
+                "result_not_modelled",
+                format!(
+                    "{} is not modelled, its result is treated as an unknown number",
+                    havoc
+                ),
+            )
+        })
+        .collect();
+
+    let output = match res {
+        Ok(_) => {
+            let stack = engine.get_stack_depth();
+            // the cache is only an optimisation, a proof that is not stored is redone next time
+            if let Some(cached_proof) = cached_proof {
+                if let Some(dir) = cached_proof.parent() {
+                    let proof = match stack {
+                        Some(bytes) => format!("{}\n{}", label, bytes),
+                        None => format!("{}\ndynamic", label),
+                    };
+                    let _ = std::fs::create_dir_all(dir)
+                        .and_then(|_| std::fs::write(&cached_proof, proof));
+                }
+            }
+            expand(Some(stack))
+        }
         Err(error) => {
-            #[cfg(not(debug_assertions))]
-            emit_call_site_error!(error);
+            // point at the argument whose region was violated, or the whole attribute
+            let violation = bums::engine::memory_safety_violation(&error);
+            let span = violation
+                .and_then(|v| v.region.as_ref())
+                .and_then(|r| violated_argument(r, &arg_spans))
+                .copied()
+                .unwrap_or_else(SpanRange::call_site);
+            let mut notes = Vec::new();
+            if let Some(v) = violation {
+                if let Some(instruction) = &v.instruction {
+                    notes.push(format!("in {} at {}", filename, instruction));
+                }
+                if !v.path_condition.is_empty() {
+                    notes.push(format!(
+                        "on the path where {}",
+                        v.path_condition.join(" and ")
+                    ));
+                }
+                if !v.counterexample.is_empty() {
+                    let values: Vec<String> = v
+                        .counterexample
+                        .iter()
+                        .map(|(name, value)| format!("{} = {}", name, value))
+                        .collect();
+                    notes.push(format!("for example {}", values.join(", ")));
+                }
+            }
 
-            #[cfg(debug_assertions)]
-            emit_call_site_warning!(error);
-            return token_stream;
+            // an allowed failure is still reported, with the reason it is accepted
+            if let Some(reason) = &options.allow {
+                notes.insert(0, error.to_string());
+                notes.push(format!("allowed: {}", reason));
+                let warning = not_proven_warning(span, notes.join("\n"));
+                quote!(#token_stream #warning)
+            } else if options.mode == Mode::Warn {
+                notes.insert(0, error.to_string());
+                let warning = not_proven_warning(span, notes.join("\n"));
+                quote!(#token_stream #warning)
+            } else {
+                let mut diagnostic =
+                    Diagnostic::spanned_range(span, Level::Error, error.to_string());
+                for note in notes {
+                    diagnostic = diagnostic.note(note);
+                }
+                diagnostic.emit();
+                token_stream
+            }
         }
     };
+    quote!(#output #havocs)
+}
+
+// #[check_mem_safe("file.S")] on an extern block checks the functions in it that carry the
+// attribute too, with the file of the block unless they name their own, the block is kept as it is
+// and calling them stays unsafe
+fn check_extern_block(
+    attr: proc_macro2::TokenStream,
+    mut block: ItemForeignMod,
+    source: Option<&str>,
+) -> proc_macro2::TokenStream {
+    let filename: Option<LitStr> =
+        parse2(attr.clone()).unwrap_or_else(|error| abort!(attr, "{}", error));
+    let mut checks = quote! {};
+    let mut checked = 0;
+    for item in block.items.iter_mut() {
+        let ForeignItem::Fn(function) = item else {
+            continue;
+        };
+        let Some(i) = function.attrs.iter().position(|a| {
+            a.path()
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "check_mem_safe")
+        }) else {
+            continue;
+        };
+        let attribute = function.attrs.remove(i);
+        let tokens = match &attribute.meta {
+            Meta::Path(_) => quote! {},
+            Meta::List(list) => list.tokens.clone(),
+            Meta::NameValue(n) => abort!(n, "the arguments are written in parentheses"),
+        };
+        let mut attributes: AttributeList =
+            parse2(tokens.clone()).unwrap_or_else(|error| abort!(attribute, "{}", error));
+        if attributes.filename.is_none() && source.is_none() {
+            attributes.filename = filename.clone();
+        }
+        let annotation = attr.to_string() + &tokens.to_string() + &quote!(#function).to_string();
+        checks.extend(check_signature(
+            annotation,
+            function.sig.clone(),
+            attributes,
+            source.map(|text| ("global_asm!", text.to_string())),
+            Output::Declaration,
+        ));
+        checked += 1;
+    }
+    if checked == 0 {
+        abort!(
+            block.abi, "no function in this block is checked";
+            help = "put #[check_mem_safe(..)] on the functions to check, with their arguments";
+        );
+    }
+    quote!(#block #checks)
+}
+
+// #[check_mem_safe] on a module checks the functions declared in its extern blocks against the
+// assembly in its global_asm!
+fn check_global_asm(
+    attr: proc_macro2::TokenStream,
+    mut module: ItemMod,
+) -> proc_macro2::TokenStream {
+    if !attr.is_empty() {
+        abort!(
+            attr,
+            "the assembly is taken from global_asm!, there is no file"
+        );
+    }
+    let Some((_, items)) = &mut module.content else {
+        abort!(module, "only modules written inline can be checked")
+    };
+    let mut text = String::new();
+    for item in items.iter() {
+        let Item::Macro(m) = item else { continue };
+        if m.mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "global_asm")
+        {
+            let asm: AsmArguments = m
+                .mac
+                .parse_body()
+                .unwrap_or_else(|error| abort!(m.mac, "{}", error));
+            text = text + &asm.substitute(&[]) + "\n";
+        }
+    }
+    if text.is_empty() {
+        abort!(module.ident, "there is no global_asm! in this module");
+    }
+    for item in items.iter_mut() {
+        if let Item::ForeignMod(block) = item {
+            *item = Item::Verbatim(check_extern_block(quote! {}, block.clone(), Some(&text)));
+        }
+    }
+    quote!(#module)
+}
+
+// #[check_mem_safe([..])] on a function checks each asm! block in its body, the operands are passed
+// like arguments in registers, inputs from x0 and outputs from x9
+// operands are taken as the parameters were on entry, where the preconditions are checked
+fn check_inline_asm(
+    attr: proc_macro2::TokenStream,
+    mut function: ItemFn,
+) -> proc_macro2::TokenStream {
+    let mut blocks = Vec::new();
+    asm_blocks(quote!(#function).into_iter(), &mut blocks);
+    if blocks.is_empty() {
+        abort!(
+            function.sig.ident,
+            "there is no asm! block in this function"
+        );
+    }
+    let mutable: Vec<String> = function
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|i| match i {
+            FnArg::Typed(t) => match &*t.pat {
+                Pat::Ident(p) if p.mutability.is_some() => Some(p.ident.to_string()),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    let mut checks = quote! {};
+    for (i, block) in blocks.iter().enumerate() {
+        let asm: AsmArguments =
+            parse2(block.stream()).unwrap_or_else(|error| abort!(block.span(), "{}", error));
+        let (registers, inputs) = asm.allocate();
+        for input in inputs.iter() {
+            if mutable.contains(&place_root(input)) {
+                abort!(input, "operands are checked with the values the parameters have on entry, they cannot be mut");
+            }
+        }
+        let program = format!(
+            "{}:\n{}\nret",
+            function.sig.ident,
+            asm.substitute(&registers)
+        );
+
+        let mut attributes: AttributeList =
+            parse2(attr.clone()).unwrap_or_else(|error| abort!(attr, "{}", error));
+        for argument in attributes.argument_list.iter() {
+            if !matches!(argument, Expr::Array(_) | Expr::Assign(_)) {
+                abort!(argument, "the arguments of asm! blocks are their operands");
+            }
+        }
+        let rest = std::mem::take(&mut attributes.argument_list);
+        attributes.argument_list = inputs.into_iter().chain(rest).collect();
+
+        let annotation = attr.to_string() + &block.to_string() + &quote!(#function).to_string();
+        // the preconditions are checked once, before the function body
+        let kind = if i == 0 {
+            Output::Checks
+        } else {
+            Output::Declaration
+        };
+        checks.extend(check_signature(
+            annotation,
+            function.sig.clone(),
+            attributes,
+            Some(("asm!", program)),
+            kind,
+        ));
+    }
+    let body = &function.block;
+    function.block = parse_quote!({ #checks #body });
+    quote!(#function)
+}
+
+// the arguments of every asm! invocation, i.e. in unsafe blocks
+fn asm_blocks(tokens: proc_macro2::token_stream::IntoIter, blocks: &mut Vec<proc_macro2::Group>) {
+    let tokens: Vec<proc_macro2::TokenTree> = tokens.collect();
+    for (i, token) in tokens.iter().enumerate() {
+        let proc_macro2::TokenTree::Group(group) = token else {
+            continue;
+        };
+        match &tokens[..i] {
+            [.., proc_macro2::TokenTree::Ident(name), proc_macro2::TokenTree::Punct(bang)]
+                if name == "asm" && bang.as_char() == '!' =>
+            {
+                blocks.push(group.clone())
+            }
+            _ => asm_blocks(group.stream().into_iter(), blocks),
+        }
+    }
+}
+
+// the variable an operand is computed from, i.e. buf for buf.as_ptr()
+fn place_root(input: &Expr) -> String {
+    match input {
+        Expr::Path(p) => p.path.segments[0].ident.to_string(),
+        Expr::MethodCall(m) => place_root(&m.receiver),
+        Expr::Field(f) => place_root(&f.base),
+        Expr::Binary(b) => place_root(&b.left),
+        Expr::Cast(c) => place_root(&c.expr),
+        Expr::Reference(r) => place_root(&r.expr),
+        Expr::Paren(p) => place_root(&p.expr),
+        _ => String::new(),
+    }
+}
+
+// asm! and global_asm! take template strings, then operands and options
+struct AsmArguments {
+    template: Vec<LitStr>,
+    operands: Vec<AsmOperand>,
+}
+
+// name = direction(register) expression, or const and sym with only an expression
+struct AsmOperand {
+    name: Option<Ident>,
+    direction: Ident,
+    register: Option<Expr>,
+    expression: Expr,
+}
+
+impl Parse for AsmArguments {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut template = Vec::new();
+        let mut operands = Vec::new();
+        while !input.is_empty() {
+            if input.peek(LitStr) {
+                template.push(input.parse()?);
+            } else {
+                let name = match input.peek2(Token![=]) {
+                    true => {
+                        let name = input.call(ext::IdentExt::parse_any)?;
+                        input.parse::<Token![=]>()?;
+                        Some(name)
+                    }
+                    false => None,
+                };
+                let direction: Ident = input.call(ext::IdentExt::parse_any)?;
+                let register = match direction.to_string().as_str() {
+                    "const" | "sym" => None,
+                    _ => {
+                        let content;
+                        parenthesized!(content in input);
+                        Some(content.parse::<Expr>()?)
+                    }
+                };
+                if direction == "options" || direction == "clobber_abi" {
+                    // options do not change what is checked, clobbers are not inputs
+                } else {
+                    let expression = input.parse()?;
+                    // inout(reg) a => b reads a
+                    if input.peek(Token![=>]) {
+                        input.parse::<Token![=>]>()?;
+                        input.parse::<Expr>()?;
+                    }
+                    operands.push(AsmOperand {
+                        name,
+                        direction,
+                        register,
+                        expression,
+                    });
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Self { template, operands })
+    }
+}
+
+impl AsmArguments {
+    // inputs are passed like arguments, outputs go to temporaries, registers written out stay
+    // returns the register of each operand and the expression passed in each argument register
+    fn allocate(&self) -> (Vec<Option<usize>>, Vec<Expr>) {
+        let explicit = |operand: &AsmOperand| match &operand.register {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            })) => {
+                let name = s.value();
+                match name
+                    .strip_prefix(['x', 'w'])
+                    .and_then(|n| n.parse::<usize>().ok())
+                {
+                    Some(n) if n < 31 => Some(n),
+                    _ => abort!(s, "only general purpose registers x0 to x30 are supported"),
+                }
+            }
+            Some(Expr::Path(p)) if p.path.is_ident("reg") => None,
+            Some(register) => abort!(
+                register,
+                "only reg and general purpose registers are supported"
+            ),
+            None => None,
+        };
+        let mut used = [false; 31];
+        let mut registers: Vec<Option<usize>> = self.operands.iter().map(explicit).collect();
+        for n in registers.iter().flatten() {
+            used[*n] = true;
+        }
+        for (operand, register) in self.operands.iter().zip(registers.iter_mut()) {
+            if register.is_some() || operand.register.is_none() {
+                continue;
+            }
+            let free = match is_asm_input(operand) {
+                true => 0..8,
+                false => 9..16,
+            };
+            let Some(n) = free.clone().find(|n| !used[*n]) else {
+                abort!(
+                    operand.direction,
+                    "only registers x{} to x{} are free for this operand",
+                    free.start,
+                    free.end - 1
+                )
+            };
+            used[n] = true;
+            *register = Some(n);
+        }
+
+        let mut inputs: Vec<Expr> = Vec::new();
+        for (operand, register) in self.operands.iter().zip(registers.iter()) {
+            let Some(n) = *register else { continue };
+            if !is_asm_input(operand) {
+                continue;
+            }
+            if n >= 8 {
+                abort!(operand.expression, "inputs are passed in x0 to x7");
+            }
+            // registers no input is passed in hold zero
+            while inputs.len() <= n {
+                inputs.push(parse_quote!(0));
+            }
+            inputs[n] = operand.expression.clone();
+        }
+        (registers, inputs)
+    }
+
+    // the assembly with the registers and constants of the operands in place of {..}
+    fn substitute(&self, registers: &[Option<usize>]) -> String {
+        let template: Vec<String> = self.template.iter().map(|t| t.value()).collect();
+        let template = template.join("\n");
+        let mut text = String::new();
+        let mut next = 0;
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    text.push(c);
+                    chars.next();
+                }
+                ('{', _) => {
+                    let placeholder: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let (name, modifier) =
+                        placeholder.split_once(':').unwrap_or((&placeholder, ""));
+                    let index = match name {
+                        "" => {
+                            next += 1;
+                            next - 1
+                        }
+                        name => match name.parse::<usize>() {
+                            Ok(index) => index,
+                            Err(_) => self
+                                .operands
+                                .iter()
+                                .position(|o| o.name.as_ref().is_some_and(|n| n == name))
+                                .unwrap_or_else(|| {
+                                    abort!(self.template[0], "no operand is named {}", name)
+                                }),
+                        },
+                    };
+                    let Some(operand) = self.operands.get(index) else {
+                        abort!(self.template[0], "there is no operand {}", index)
+                    };
+                    text += &match (operand.direction.to_string().as_str(), registers.get(index)) {
+                        ("const", _) => match &operand.expression {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Int(i), ..
+                            }) => i.base10_digits().to_string(),
+                            e => abort!(e, "only integer literals are supported as const operands"),
+                        },
+                        (_, Some(Some(n))) if modifier == "w" => format!("w{}", n),
+                        (_, Some(Some(n))) => format!("x{}", n),
+                        _ => abort!(operand.expression, "unsupported operand"),
+                    };
+                }
+                _ => text.push(c),
+            }
+        }
+        text
+    }
+}
+
+fn is_asm_input(operand: &AsmOperand) -> bool {
+    matches!(
+        operand.direction.to_string().as_str(),
+        "in" | "inout" | "inlateout"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(attribute: &str) -> Options {
+        let mut attributes: AttributeList = parse_str(attribute).expect("attribute");
+        take_options(&mut attributes)
+    }
+
+    #[test]
+    fn test_mutable_buffer_read_modify_write() {
+        let options = options("\"inc.S\", buf.as_mut_ptr(), buf.len()");
+        let pointer: TypePtr = parse_quote!(*mut u8);
+        let kind = buffer_kind(&pointer, "buf", &options);
+        assert_eq!(kind, RegionType::RW);
+
+        // increments the first byte in place
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let program = [
+            "start:",
+            "ldrb w1, [x0]",
+            "add w1, w1, #1",
+            "strb w1, [x0]",
+            "ret",
+        ];
+        let mut engine =
+            bums::engine::ExecutionEngine::new(program.map(String::from).to_vec(), &ctx);
+        engine.add_abstract_from(0, "buf_as_mut_ptr".to_string());
+        engine.add_region(
+            kind,
+            "buf_as_mut_ptr".to_string(),
+            AbstractExpression::Immediate(16),
+        );
+        let res = engine.start("start".to_string());
+        assert!(res.is_ok(), "{:?}", res);
+    }
+
+    #[test]
+    fn test_outputs_start_uninitialised() {
+        let options =
+            options("\"x25519.S\", out.as_mut_ptr(), key.as_mut_ptr(), initialises = [key]");
+        let maybe_uninit: TypePtr = parse_quote!(*mut std::mem::MaybeUninit<u8>);
+        assert_eq!(
+            buffer_kind(&maybe_uninit, "out", &options),
+            RegionType::WRITE
+        );
+        let bytes: TypePtr = parse_quote!(*mut u8);
+        assert_eq!(buffer_kind(&bytes, "key", &options), RegionType::WRITE);
+        let input: TypePtr = parse_quote!(*const u8);
+        assert_eq!(buffer_kind(&input, "key", &options), RegionType::READ);
+    }
+
+    #[test]
+    fn test_violation_points_at_argument() {
+        let arguments: HashMap<String, &str> = [("key", "key"), ("key_schedule", "schedule")]
+            .map(|(arg, span)| (arg.to_string(), span))
+            .into();
+        assert_eq!(violated_argument("key", &arguments), Some(&"key"));
+        assert_eq!(violated_argument("key_as_ptr", &arguments), Some(&"key"));
+        assert_eq!(
+            violated_argument("key_schedule_rounds", &arguments),
+            Some(&"schedule")
+        );
+        assert_eq!(violated_argument("keys", &arguments), None);
+        assert_eq!(violated_argument("input", &arguments), None);
+    }
+
+    #[test]
+    fn test_options_are_taken_from_arguments() {
+        let mut attributes: AttributeList = parse_str(
+            "\"f.S\", out.as_mut_ptr(), mode = \"warn\", out.len(), allow = \"checked by hand\"",
+        )
+        .expect("attribute");
+        let taken = take_options(&mut attributes);
+        assert_eq!(taken.mode, Mode::Warn);
+        assert_eq!(taken.allow.as_deref(), Some("checked by hand"));
+        let arguments: Vec<String> = attributes.argument_list.iter().map(source_text).collect();
+        assert_eq!(arguments, ["out.as_mut_ptr()", "out.len()"]);
+
+        let options = options("\"f.S\", out.as_mut_ptr()");
+        assert_eq!(options.mode, Mode::Deny);
+        assert!(options.allow.is_none());
+        assert_eq!(parse_mode("skip"), Some(Mode::Skip));
+        assert_eq!(parse_mode("error"), None);
+    }
+
+    #[test]
+    fn test_proof_cache_key() {
+        let out_dir = std::path::Path::new("/work/target/debug/build/app-0123/out");
+        let cached = proof_cache(out_dir, b"f:\n    ret\n", "\"f.S\" fn f();");
+        assert_eq!(
+            cached.parent(),
+            Some(std::path::Path::new("/work/target/debug/bums-cache"))
+        );
+        let key = cached.file_name().and_then(|k| k.to_str()).expect("key");
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // a change to any part is a different proof
+        assert_eq!(
+            proof_cache(out_dir, b"f:\n    ret\n", "\"f.S\" fn f();"),
+            cached
+        );
+        assert_ne!(
+            proof_cache(out_dir, b"f:\n    ret\n", "\"f.S\" fn g();"),
+            cached
+        );
+        assert_ne!(
+            proof_cache(out_dir, b"f:\n    ret \n", "\"f.S\" fn f();"),
+            cached
+        );
+        // parts cannot run together
+        assert_ne!(
+            proof_cache(out_dir, b"f:\n    ret\n\"f.S\"", " fn f();"),
+            cached
+        );
+    }
+
+    #[test]
+    fn test_scalar_and_array_layouts() {
+        assert_eq!(scalar_layout("u8"), Some((1, 1)));
+        assert_eq!(scalar_layout("c_int"), Some((4, 4)));
+        assert_eq!(scalar_layout("usize"), Some((8, 8)));
+        assert_eq!(scalar_layout("u128"), Some((16, 16)));
+        assert_eq!(scalar_layout("String"), None);
+
+        let options = options("\"f.S\", alias = \"Limb = u64\", alias = \"Elem = [Limb; 4]\"");
+        let layout = |ty: &str| layout_of(&parse_str(ty).expect("type"), &options);
+        assert_eq!(layout("u32"), (4, 4));
+        assert_eq!(layout("Limb"), (8, 8));
+        assert_eq!(layout("[u16; 3]"), (6, 2));
+        assert_eq!(layout("[[u32; 3]; 2]"), (24, 4));
+        assert_eq!(layout("[Elem; 2]"), (64, 8));
+        assert_eq!(layout("*const u8"), (8, 8));
+        assert_eq!(layout("&[u8]"), (16, 8));
+    }
+
+    #[test]
+    fn test_repr_c_layouts() {
+        let options = options(
+            "\"f.S\", layout = \"Header { tag: u8, len: u32, flags: u16 }\", layout = \"Packet { header: Header, body: [u8; 5] }\"",
+        );
+        let fields: Vec<Type> = ["u8", "u32", "u16"]
+            .iter()
+            .map(|ty| parse_str(ty).expect("type"))
+            .collect();
+        // padded to the alignment of the next field, then of the largest one
+        assert_eq!(c_layout(fields.iter(), &options), (vec![0, 4, 8], 12, 4));
+
+        let layout = |ty: &str| layout_of(&parse_str(ty).expect("type"), &options);
+        assert_eq!(layout("Header"), (12, 4));
+        assert_eq!(layout("Packet"), (20, 4));
+        assert_eq!(layout("[Header; 2]"), (24, 4));
+        assert_eq!(layout("(u8, u64)"), (16, 8));
+    }
+
+    #[test]
+    fn test_asm_operands() {
+        let asm: AsmArguments = parse_str(
+            r#""ldr {out:w}, [{}, #{}]", "add {{x}}, {2}", in(reg) data.as_ptr(), const 8, in("x3") n, out = out(reg) value, options(nostack)"#,
+        )
+        .expect("asm arguments");
+        assert_eq!(asm.operands.len(), 4);
+
+        // inputs are passed in the first free argument registers, outputs from x9
+        let (registers, inputs) = asm.allocate();
+        assert_eq!(registers, [Some(0), None, Some(3), Some(9)]);
+        let inputs: Vec<String> = inputs.iter().map(source_text).collect();
+        assert_eq!(inputs, ["data.as_ptr()", "0", "0", "n"]);
+
+        assert_eq!(asm.substitute(&registers), "ldr w9, [x0, #8]\nadd {x}, x3");
+    }
+
+    #[test]
+    fn test_preconditions_implied_by_types() {
+        let options = options("\"f.S\", alias = \"Key = [u8; 32]\"");
+        let signature: Signature = parse_quote!(fn f(key: &Key, input: &[u8], n: usize, d: i64));
+        let facts = type_facts(&signature.inputs, &options);
+        assert_eq!(facts.len(), 3);
+
+        let implied = |condition: Expr| {
+            let Expr::Binary(b) = condition else {
+                panic!("a comparison")
+            };
+            implied_by_types(&binary_to_abstract_comparison(&b), &facts)
+        };
+        assert!(implied(parse_quote!(key.len() == 32)));
+        assert!(implied(parse_quote!(key.len() >= 16)));
+        assert!(implied(parse_quote!(input.len() >= 0)));
+        assert!(implied(parse_quote!(n >= 0)));
+        assert!(!implied(parse_quote!(input.len() >= 1)));
+        assert!(!implied(parse_quote!(d >= 0)));
+        assert!(!implied(parse_quote!(n < 8)));
+    }
 }
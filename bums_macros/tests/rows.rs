@@ -0,0 +1,27 @@
+// the checks the wrapper makes before the call, the assembly itself is not checked here
+#[bums_macros::check_mem_safe("rows.S", dst.as_mut_ptr(), stride, w, h, rows = "dst: stride, w, h", mode = "skip")]
+fn put_rows(dst: &mut [u8], stride: isize, w: usize, h: usize);
+
+// stands in for the assembly
+#[export_name = "put_rows"]
+extern "C" fn put_rows_asm(_dst: *mut u8, _stride: isize, _w: usize, _h: usize) {}
+
+#[test]
+fn rows_forward_from_the_slice() {
+    let mut buf = [0u8; 40];
+    put_rows(&mut buf, 16, 8, 3);
+    put_rows(&mut buf, -16, 8, 1);
+    put_rows(&mut buf, -16, 8, 0);
+}
+
+#[test]
+#[should_panic(expected = "dst does not hold its rows")]
+fn rows_past_the_end() {
+    put_rows(&mut [0u8; 40], 16, 16, 3);
+}
+
+#[test]
+#[should_panic(expected = "dst does not hold its rows")]
+fn rows_before_the_slice() {
+    put_rows(&mut [0u8; 40], -16, 8, 2);
+}
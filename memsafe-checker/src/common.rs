@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::fmt;
 use z3::*;
 
-use crate::instruction_parser::{self, Arrangement};
+use crate::instruction_parser::{self, Arrangement, Opcode};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RegisterKind {
@@ -46,7 +46,7 @@ impl RegisterValue {
     }
 }
 
-// TODO: add way to mark endianess if necessary
+// lanes are little endian like the hardware, so byte lanes line up with wider ones
 #[derive(Debug, Clone, PartialEq)]
 pub struct SimdRegister {
     pub kind: RegisterKind,
@@ -188,43 +188,88 @@ impl SimdRegister {
                 let offset = offset as u16;
                 let new_bases = [BASE_INIT; 2];
                 for i in 0..8 {
-                    self.set_halfword(i, new_bases.clone(), offset.to_be_bytes());
+                    self.set_halfword(i, new_bases.clone(), offset.to_le_bytes());
                 }
             }
             Arrangement::S4 => {
                 let offset = offset as u32;
                 let new_bases = [BASE_INIT; 4];
                 for i in 0..4 {
-                    self.set_word(i, new_bases.clone(), offset.to_be_bytes());
+                    self.set_word(i, new_bases.clone(), offset.to_le_bytes());
                 }
             }
             Arrangement::D2 => {
                 let offset = offset as u64;
                 let new_bases = [BASE_INIT; 8];
                 for i in 0..2 {
-                    self.set_double(i, new_bases.clone(), offset.to_be_bytes());
+                    self.set_double(i, new_bases.clone(), offset.to_le_bytes());
                 }
             }
             Arrangement::S => {
                 let offset = offset as u32;
                 let new_bases = [BASE_INIT; 4];
                 // FIX to take in index
-                self.set_word(0, new_bases.clone(), offset.to_be_bytes());
+                self.set_word(0, new_bases.clone(), offset.to_le_bytes());
             }
             Arrangement::D => {
                 let offset = offset as u64;
                 let new_bases = [BASE_INIT; 8];
                 // FIX to take in index
-                self.set_double(0, new_bases.clone(), offset.to_be_bytes());
+                self.set_double(0, new_bases.clone(), offset.to_le_bytes());
             }
             a => todo!("support setting from register across {:?} channels", a),
         }
     }
 
+    // a single element as a scalar, i.e. v3.s[2]
+    // a lane holding the same abstract base in every byte is that base plus its offset
+    pub fn get_lane(&self, element: Arrangement, index: usize) -> RegisterValue {
+        let size = element.element_size();
+        let lane = index * size..(index + 1) * size;
+
+        let mut offset_buf: [u8; 8] = Default::default();
+        offset_buf[..size].clone_from_slice(&self.offset[lane.clone()]);
+        let offset = u64::from_le_bytes(offset_buf) as i64;
+
+        if let Some(base) = &self.base[lane.start] {
+            if self.base[lane].iter().all(|b| b.as_ref() == Some(base)) {
+                // offsets from a base are small and may be negative
+                let shift = 64 - 8 * size as u32;
+                return RegisterValue::new(
+                    RegisterKind::RegisterBase,
+                    Some(base.clone()),
+                    (offset << shift) >> shift,
+                );
+            }
+            return RegisterValue::new(RegisterKind::Number, None, 0);
+        }
+        if self.base[lane].iter().any(|b| b.is_some()) {
+            return RegisterValue::new(RegisterKind::Number, None, 0);
+        }
+
+        let kind = match self.kind {
+            RegisterKind::Immediate => RegisterKind::Immediate,
+            _ => RegisterKind::Number,
+        };
+        RegisterValue::new(kind, None, offset)
+    }
+
+    pub fn set_lane(&mut self, element: Arrangement, index: usize, value: &RegisterValue) {
+        let size = element.element_size();
+        let bytes = (value.offset as u64).to_le_bytes();
+        for (i, byte) in bytes.iter().take(size).enumerate() {
+            self.base[index * size + i] = value.base.clone();
+            self.offset[index * size + i] = *byte;
+        }
+        if value.kind == RegisterKind::Number {
+            self.kind = RegisterKind::Number;
+        }
+    }
+
     pub fn get_as_register(&self) -> RegisterValue {
         let mut offset_buf: [u8; 8] = Default::default();
         offset_buf.clone_from_slice(&self.offset[0..8]);
-        let offset: i64 = i64::from_be_bytes(offset_buf);
+        let offset: i64 = i64::from_le_bytes(offset_buf);
 
         let base = generate_expression_from_options(
             ",",
@@ -335,9 +380,11 @@ impl fmt::Display for AbstractExpression {
             AbstractExpression::Empty => write!(f, "Empty"),
             AbstractExpression::Immediate(value) => write!(f, "{}", value),
             AbstractExpression::Abstract(name) => write!(f, "{}", name),
-            AbstractExpression::Register(reg) => {
-                write!(f, "({:?})", reg)
-            }
+            AbstractExpression::Register(reg) => match &reg.base {
+                Some(base) if reg.offset == 0 => write!(f, "{}", base),
+                Some(base) => write!(f, "({} + {})", base, reg.offset),
+                None => write!(f, "{}", reg.offset),
+            },
             AbstractExpression::Expression(func, arg1, arg2) => {
                 write!(f, "({} {} {})", arg1, func, arg2)
             }
@@ -396,6 +443,21 @@ impl AbstractExpression {
         }
     }
 
+    // replaces every occurence of from, i.e. an induction variable by its general form
+    pub fn substitute(&self, from: &AbstractExpression, to: &AbstractExpression) -> Self {
+        if self == from {
+            return to.clone();
+        }
+        match self {
+            AbstractExpression::Expression(op, arg1, arg2) => AbstractExpression::Expression(
+                op.clone(),
+                Box::new(arg1.substitute(from, to)),
+                Box::new(arg2.substitute(from, to)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
     pub fn contains_expression(&self, expr: &AbstractExpression) -> bool {
         if self == expr {
             return true;
@@ -477,6 +539,14 @@ impl AbstractComparison {
     pub fn contains(&self, token: &str) -> bool {
         return self.left.contains(token) || self.right.contains(token);
     }
+
+    pub fn substitute(&self, from: &AbstractExpression, to: &AbstractExpression) -> Self {
+        Self::new(
+            &self.op,
+            self.left.substitute(from, to),
+            self.right.substitute(from, to),
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -500,6 +570,69 @@ impl PartialEq for MemoryAccess {
 
 impl Eq for MemoryAccess {}
 
+// an instruction whose result is not modelled, its destination was set to an unknown number
+#[derive(Debug, Clone, PartialEq)]
+pub struct Havoc {
+    pub pc: usize,
+    pub opcode: Opcode,
+}
+
+impl fmt::Display for Havoc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.opcode, self.pc)
+    }
+}
+
+// the active lanes of an sve predicate register, as far as memory accesses need them
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Unknown, // any lanes may be active
+    All,
+    None,
+    // lanes i of the element size where index + i < limit, like after whilelo p0.b, x1, x2
+    While(usize, AbstractExpression, AbstractExpression),
+}
+
+impl Predicate {
+    // nzcv as set by whilelo or ptest: first lane active, no lane active, last lane inactive
+    pub fn flags(&self) -> Option<(FlagValue, FlagValue, FlagValue)> {
+        match self {
+            Predicate::Unknown => None,
+            Predicate::All => Some((
+                FlagValue::Real(true),
+                FlagValue::Real(false),
+                FlagValue::Real(false),
+            )),
+            Predicate::None => Some((
+                FlagValue::Real(false),
+                FlagValue::Real(true),
+                FlagValue::Real(true),
+            )),
+            Predicate::While(size, index, limit) => Some((
+                FlagValue::Abstract(AbstractComparison::new("<", index.clone(), limit.clone())),
+                FlagValue::Abstract(AbstractComparison::new(">=", index.clone(), limit.clone())),
+                FlagValue::Abstract(AbstractComparison::new(
+                    "<",
+                    limit.clone(),
+                    generate_expression("+", index.clone(), vector_lanes(*size)),
+                )),
+            )),
+        }
+    }
+}
+
+// sve vector length in bytes, the solver knows it is a multiple of 16 from 16 to 256
+pub const VECTOR_LENGTH: &str = "VL";
+
+// lanes of size bytes in an sve vector
+pub fn vector_lanes(size: usize) -> AbstractExpression {
+    let length = AbstractExpression::Abstract(VECTOR_LENGTH.to_string());
+    if size == 1 {
+        return length;
+    }
+    generate_expression("/", length, AbstractExpression::Immediate(size as i64))
+}
+
 #[derive(Debug, Clone)]
 pub enum FlagValue {
     Abstract(AbstractComparison),
@@ -527,6 +660,13 @@ impl FlagValue {
             Self::Real(r) => return Self::Real(!r),
         }
     }
+
+    pub fn substitute(&self, from: &AbstractExpression, to: &AbstractExpression) -> Self {
+        match self {
+            Self::Abstract(a) => Self::Abstract(a.substitute(from, to)),
+            Self::Real(r) => Self::Real(*r),
+        }
+    }
 }
 
 impl PartialEq for FlagValue {
@@ -556,30 +696,47 @@ impl fmt::Display for RegionType {
     }
 }
 
+// how two regions passed in may overlap
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Aliasing {
+    May,   // somewhere, or not at all
+    Exact, // they start at the same address
+}
+
 #[derive(Debug, Clone)]
 pub struct MemorySafeRegion {
     pub kind: RegionType,
     length: AbstractExpression, // length of region in BYTES
     pub content: HashMap<i64, RegisterValue>,
+    // stride and number of rows of a 2D region, the length is the width of each row
+    pub rows: Option<(AbstractExpression, AbstractExpression)>,
 }
 
 impl MemorySafeRegion {
     pub fn new(length: AbstractExpression, kind: RegionType) -> Self {
-        let mut content = HashMap::new();
-        match length {
-            AbstractExpression::Immediate(l) => {
-                for i in 0..(l) {
-                    content.insert(i * 4, RegisterValue::new(RegisterKind::Number, None, 0));
-                }
-            }
-            _ => (),
-        }
         Self {
             kind,
             length,
-            content,
+            content: HashMap::new(),
+            rows: None,
+        }
+    }
+
+    // height rows of width bytes, each stride bytes after the one before, stride can be negative
+    pub fn new_rows(
+        width: AbstractExpression,
+        stride: AbstractExpression,
+        height: AbstractExpression,
+        kind: RegionType,
+    ) -> Self {
+        Self {
+            kind,
+            length: width,
+            content: HashMap::new(),
+            rows: Some((stride, height)),
         }
     }
+
     pub fn insert(&mut self, address: i64, value: RegisterValue) {
         self.content.insert(address, value);
     }
@@ -592,25 +749,65 @@ impl MemorySafeRegion {
         }
     }
 
-    pub fn get_length(&self) -> AbstractExpression {
-        match self.length {
-            AbstractExpression::Immediate(_) => {
-                return AbstractExpression::Immediate((self.content.len() * 8) as i64)
-            }
-            _ => self.length.clone(),
+    // inputs that decide how large the region is
+    pub fn get_dimensions(&self) -> Vec<String> {
+        let mut dimensions = self.length.get_abstracts();
+        if let Some((stride, height)) = &self.rows {
+            dimensions.extend(stride.get_abstracts());
+            dimensions.extend(height.get_abstracts());
+        }
+        dimensions
+    }
+
+    // length in bytes when it is a constant, a 2D region has none
+    pub fn get_size(&self) -> Option<i64> {
+        match (&self.length, &self.rows) {
+            (AbstractExpression::Immediate(l), None) => Some(*l),
+            _ => None,
         }
     }
+
+    // length in bytes as declared, the width of each row of a 2D region
+    pub fn get_length(&self) -> AbstractExpression {
+        self.length.clone()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MemorySafetyError {
     details: String,
+    // the region the failing access was checked against
+    pub region: Option<String>,
+    // the failing instruction, as written in the program
+    pub instruction: Option<String>,
+    // branch conditions on the way to the failing instruction
+    pub path_condition: Vec<String>,
+    // inputs that make the access fail
+    pub counterexample: Vec<(String, i64)>,
 }
 
 impl MemorySafetyError {
     pub fn new(msg: &str) -> MemorySafetyError {
         MemorySafetyError {
             details: msg.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // errors travel up through the branches that led to them, the innermost instruction is kept
+    pub fn at(mut self, instruction: String, path_condition: Vec<String>) -> MemorySafetyError {
+        if self.instruction.is_none() {
+            self.instruction = Some(instruction);
+            self.path_condition = path_condition;
+        }
+        self
+    }
+
+    // the same violation under another message
+    pub fn reword(&self, msg: &str) -> MemorySafetyError {
+        MemorySafetyError {
+            details: msg.to_string(),
+            ..self.clone()
         }
     }
 
@@ -624,6 +821,8 @@ impl fmt::Display for MemorySafetyError {
     }
 }
 
+impl std::error::Error for MemorySafetyError {}
+
 pub fn get_register_name_string(r: String) -> String {
     let a: Vec<&str> = r.split(",").collect();
     for i in a {
@@ -722,6 +921,7 @@ pub enum ExecuteReturnType {
     Next,
     JumpLabel(String),
     JumpAddress(u128),
+    JumpRegister(RegisterValue), // br and blr, to wherever the register can point
     ConditionalJumpLabel(AbstractComparison, String),
     ConditionalJumpAddress(AbstractComparison, u128),
     Select(
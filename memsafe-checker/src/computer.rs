@@ -2,25 +2,56 @@ use crate::common::*;
 use crate::instruction_parser::*;
 use std::collections::HashMap;
 use std::fmt;
+use z3::ast::Ast;
 use z3::*;
 
+mod abi;
+mod align;
+mod atomic;
+mod init;
 mod instruction_aux;
 mod instructions;
 mod memory;
 mod simd;
+mod sve;
+mod taint;
+
+pub use abi::CallingConvention;
+pub use align::AlignmentCheck;
+pub use init::Initialisation;
+pub use taint::{Secrets, Zeroise};
 
 #[derive(Clone)]
 pub struct ARMCORTEXA<'ctx> {
     pub registers: [RegisterValue; 33],
     pub simd_registers: [SimdRegister; 32],
+    pub predicates: [Predicate; 16],
     zero: Option<FlagValue>,
     neg: Option<FlagValue>,
     pub carry: Option<FlagValue>,
     overflow: Option<FlagValue>,
     pub memory: HashMap<String, MemorySafeRegion>,
     pub memory_labels: HashMap<String, i64>,
+    // addresses in memory from read-only sections and code, [start, end)
+    constant_data: Vec<(i64, i64)>,
     rw_queue: Vec<MemoryAccess>,
+    // values only the solver knows so far, table entries and store exclusive results, each named
+    fresh_values: usize,
+    havocs: Vec<Havoc>,
+    // alignment assumed for the start of regions passed in without one of their own
     alignment: i64,
+    alignments: HashMap<String, i64>,
+    alignment_check: AlignmentCheck,
+    // followed only when some input is secret
+    pub secrets: Option<Secrets>,
+    // what the caller expects back, from the entry of the program
+    calling_convention: Option<CallingConvention>,
+    // the most bytes sp has been below its entry value, unknown once it moves by an unknown amount
+    stack_depth: Option<i64>,
+    // which bytes of write-only regions and the stack have been written
+    initialisation: Initialisation,
+    // regions passed in that overlap, a store to one changes what the other holds
+    aliases: Vec<(String, String, Aliasing)>,
     pub context: &'ctx Context,
     pub solver: Solver<'ctx>,
 }
@@ -117,6 +148,17 @@ impl<'ctx> ARMCORTEXA<'_> {
         let stack_max = ast::Int::new_const(context, "MAX");
         solver.assert(&stack_max.ge(&max));
 
+        // any sve implementation, 128 to 2048 bits in steps of 128
+        let vector_length = ast::Int::new_const(context, VECTOR_LENGTH);
+        let granule = ast::Int::from_i64(context, 16);
+        solver.assert(&vector_length.ge(&granule));
+        solver.assert(&vector_length.le(&ast::Int::from_i64(context, 256)));
+        solver.assert(
+            &vector_length
+                .modulo(&granule)
+                ._eq(&ast::Int::from_i64(context, 0)),
+        );
+
         memory.insert(
             "sp".to_string(),
             MemorySafeRegion::new(
@@ -128,14 +170,25 @@ impl<'ctx> ARMCORTEXA<'_> {
         ARMCORTEXA {
             registers,
             simd_registers,
+            predicates: std::array::from_fn(|_| Predicate::Unknown),
             zero: None,
             neg: None,
             carry: None,
             overflow: None,
             memory,
             memory_labels: HashMap::new(),
+            constant_data: Vec::new(),
             rw_queue: Vec::new(),
-            alignment: 4,
+            fresh_values: 0,
+            havocs: Vec::new(),
+            alignment: 1,
+            alignments: HashMap::new(),
+            alignment_check: AlignmentCheck::default(),
+            secrets: None,
+            calling_convention: None,
+            stack_depth: Some(0),
+            initialisation: Initialisation::new(),
+            aliases: Vec::new(),
             context,
             solver,
         }
@@ -215,7 +268,10 @@ impl<'ctx> ARMCORTEXA<'_> {
                 }
                 RePrefix::Fp => self.registers[29].set(kind, base, offset),
                 RePrefix::Ra => self.registers[30].set(kind, base, offset),
-                RePrefix::Sp => self.registers[31].set(kind, base, offset),
+                RePrefix::Sp => {
+                    self.registers[31].set(kind, base, offset);
+                    self.follow_stack_depth();
+                }
                 RePrefix::Ze => self.registers[32].set(kind, base, offset),
                 _ => todo!("set register for other prefix"),
             },
@@ -300,6 +356,7 @@ impl<'ctx> ARMCORTEXA<'_> {
         match reg {
             Operand::VectorRegister(_, index) => self.simd_registers[*index] = src,
             Operand::Vector(_, index, _) => self.simd_registers[*index] = src,
+            Operand::VectorAccess(_, index, _, _) => self.simd_registers[*index] = src,
             _ => panic!("cannot get register {:?} as a simd register", reg),
         }
     }
@@ -319,6 +376,23 @@ impl<'ctx> ARMCORTEXA<'_> {
         }
     }
 
+    // a pointer stored in the region to another address in it, i.e. .quad table
+    pub fn add_memory_pointer(&mut self, region: String, address: i64, target: i64) {
+        let base = AbstractExpression::Abstract(region.clone());
+        let reg_value = RegisterValue::new(RegisterKind::RegisterBase, Some(base), target);
+        match self.memory.get_mut(&region) {
+            Some(r) => {
+                r.insert(address, reg_value);
+            }
+            None => {
+                let mut region_map =
+                    MemorySafeRegion::new(AbstractExpression::Immediate(0), RegionType::RW);
+                region_map.insert(address, reg_value);
+                self.memory.insert(region, region_map);
+            }
+        }
+    }
+
     pub fn add_memory_value_abstract(
         &mut self,
         region: String,
@@ -340,29 +414,89 @@ impl<'ctx> ARMCORTEXA<'_> {
     }
 
     pub fn add_memory_region(&mut self, name: String, ty: RegionType, length: AbstractExpression) {
+        if ty == RegionType::WRITE {
+            self.add_uninitialised_region(name.clone());
+        }
         let new_region = MemorySafeRegion::new(length, ty);
         self.memory.insert(name, new_region);
     }
 
-    fn label_to_memory_index(&self, _label: String) -> (String, i64) {
-        // FIX: use label
-        return ("memory".to_string(), 0);
+    pub fn add_memory_rows(
+        &mut self,
+        name: String,
+        ty: RegionType,
+        width: AbstractExpression,
+        stride: AbstractExpression,
+        height: AbstractExpression,
+    ) {
+        if ty == RegionType::WRITE {
+            self.add_uninitialised_region(name.clone());
+        }
+        let new_region = MemorySafeRegion::new_rows(width, stride, height, ty);
+        self.memory.insert(name, new_region);
+    }
+
+    pub fn add_constant_data(&mut self, start: i64, end: i64) {
+        self.constant_data.push((start, end));
+    }
+
+    // the address of a label in memory, i.e. K256@PAGE or :lo12:table, 0 if it is not defined here
+    fn label_to_memory_index(&self, label: String) -> (String, i64) {
+        let name = label.split('@').next().unwrap_or(&label);
+        let name = name.rsplit(':').next().unwrap_or(name);
+        let address = self
+            .memory_labels
+            .get(name)
+            .or_else(|| self.memory_labels.get(name.trim_start_matches('_')))
+            .copied()
+            .unwrap_or(0);
+        return ("memory".to_string(), address);
     }
 
-    pub fn check_stack_pointer_restored(&self) {
+    pub fn check_stack_pointer_restored(&self) -> Result<(), MemorySafetyError> {
         let s = &self.registers[31];
-        match &s.base {
-            Some(b) => {
-                if b == &AbstractExpression::Abstract("sp".to_string()) && s.offset == 0 {
-                    log::info!("Stack pointer restored to start");
-                } else {
-                    log::error!("Stack pointer offset not restored");
-                }
-            }
-            None => {
-                log::error!("Stack pointer not restored {:?}", s.base);
-            }
+        let Some(b) = &s.base else {
+            return Err(MemorySafetyError::new("Stack pointer not restored"));
+        };
+        if b == &AbstractExpression::Abstract("sp".to_string()) && s.offset == 0 {
+            log::info!("Stack pointer restored to start");
+            return Ok(());
+        }
+        // moved by amounts that cancel out, i.e. sub sp, sp, x1 and add sp, sp, x1
+        let value = AbstractExpression::Register(Box::new(s.clone()));
+        let moved = expression_to_ast(self.context, value)
+            .expect("computer32")
+            ._eq(&ast::Int::new_const(self.context, "sp"))
+            .not();
+        if self.solver.check_assumptions(&[moved]) == SatResult::Unsat {
+            return Ok(());
         }
+        Err(MemorySafetyError::new(&format!(
+            "Stack pointer not restored, it is {}{:+}",
+            b, s.offset
+        )))
+    }
+
+    fn follow_stack_depth(&mut self) {
+        let sp = &self.registers[31];
+        self.stack_depth = match (&sp.base, self.stack_depth) {
+            (Some(AbstractExpression::Abstract(b)), Some(depth)) if b == "sp" => {
+                Some(depth.max(-sp.offset))
+            }
+            _ => None,
+        };
+    }
+
+    pub fn get_stack_depth(&self) -> Option<i64> {
+        self.stack_depth
+    }
+
+    // the deepest any of two paths goes
+    pub fn join_stack_depth(&mut self, other: &ARMCORTEXA) {
+        self.stack_depth = match (self.stack_depth, other.stack_depth) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
     }
 
     pub fn clear_rw_queue(&mut self) {
@@ -373,6 +507,51 @@ impl<'ctx> ARMCORTEXA<'_> {
         self.rw_queue.clone()
     }
 
+    pub fn read_havocs(&self) -> Vec<Havoc> {
+        self.havocs.clone()
+    }
+
+    // whether a branch condition comes from the flags of a while predicate, i.e. b.first
+    pub fn is_predicated(&self, condition: &AbstractComparison) -> bool {
+        self.predicates.iter().any(|p| match p {
+            Predicate::While(_, index @ AbstractExpression::Register(..), _) => {
+                condition.left.contains_expression(index)
+                    || condition.right.contains_expression(index)
+            }
+            _ => false,
+        })
+    }
+
+    // rewrites predicates and flags in terms of to, for the general iteration of a loop
+    pub fn substitute(&mut self, from: &AbstractExpression, to: &AbstractExpression) {
+        for p in self.predicates.iter_mut() {
+            if let Predicate::While(size, index, limit) = p {
+                *p = Predicate::While(
+                    *size,
+                    index.substitute(from, to),
+                    limit.substitute(from, to),
+                );
+            }
+        }
+        for flag in [
+            &mut self.zero,
+            &mut self.neg,
+            &mut self.carry,
+            &mut self.overflow,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *flag = flag.substitute(from, to);
+        }
+    }
+
+    // a name no other value has, for a value only the solver knows
+    pub fn fresh_value(&mut self, name: &str) -> String {
+        self.fresh_values += 1;
+        format!("{}_{}", name, self.fresh_values)
+    }
+
     pub fn change_alignment(&mut self, value: i64) {
         self.alignment = value;
     }
@@ -530,4 +709,206 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_simd_dup_umov_abstract_lane() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        computer.set_register(
+            &Operand::Register(RePrefix::X, 1),
+            RegisterKind::RegisterBase,
+            Some(AbstractExpression::Abstract("len".to_string())),
+            0,
+        );
+        let _ = computer.execute(0, &Instruction::new("dup v0.4s, w1".to_string()));
+        let _ = computer.execute(1, &Instruction::new("shl v1.4s, v0.4s, #2".to_string()));
+        let _ = computer.execute(2, &Instruction::new("umov w9, v1.s[3]".to_string()));
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 9));
+        assert_eq!(
+            result,
+            RegisterValue {
+                kind: RegisterKind::RegisterBase,
+                base: Some(generate_expression(
+                    "lsl",
+                    AbstractExpression::Abstract("len".to_string()),
+                    AbstractExpression::Immediate(2)
+                )),
+                offset: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_simd_movi_ext_zip_lanes() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        let program = [
+            "movi v0.4s, #1, lsl #8",
+            "movi v1.16b, #0x2a",
+            "ext v2.16b, v0.16b, v1.16b, #14",
+            "zip1 v3.4s, v0.4s, v2.4s",
+            "umov w4, v3.s[1]",
+            "umov w5, v3.s[2]",
+        ];
+        for (pc, line) in program.iter().enumerate() {
+            let _ = computer.execute(pc, &Instruction::new(line.to_string()));
+        }
+        // the two upper bytes of the last word of v0 followed by the bytes of v1
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 4));
+        assert_eq!(result, RegisterValue::new_imm(0x2a2a0000));
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 5));
+        assert_eq!(result, RegisterValue::new_imm(256));
+    }
+
+    #[test]
+    fn test_simd_long_and_narrow_lanes() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        let program = [
+            "movi v0.8h, #255",
+            "ushll v1.4s, v0.4h, #4",
+            "uaddl v2.2d, v1.2s, v1.2s",
+            "rshrn v3.2s, v2.2d, #3",
+            "sqxtun v4.8b, v1.8h",
+            "umov w6, v3.s[1]",
+            "umov w7, v4.b[0]",
+        ];
+        for (pc, line) in program.iter().enumerate() {
+            let _ = computer.execute(pc, &Instruction::new(line.to_string()));
+        }
+        // (4080 + 4080 + 4) >> 3
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 6));
+        assert_eq!(result, RegisterValue::new_imm(1020));
+        // the halfwords of v1.8h are 4080 and 0, saturated to a byte
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 7));
+        assert_eq!(result, RegisterValue::new_imm(255));
+    }
+
+    #[test]
+    fn test_simd_unmodelled_instruction_is_recorded() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        let _ = computer.execute(0, &Instruction::new("movi v0.2d, #0".to_string()));
+        let fadd = Instruction::new("fadd v0.4s, v1.4s, v2.4s".to_string());
+        let _ = computer.execute(1, &fadd);
+        let _ = computer.execute(1, &fadd);
+        let _ = computer.execute(2, &Instruction::new("umov w0, v0.s[0]".to_string()));
+        let _ = computer.execute(3, &Instruction::new("aese v3.16b, v0.16b".to_string()));
+        let _ = computer.execute(4, &Instruction::new("aesmc v3.16b, v3.16b".to_string()));
+
+        assert_eq!(
+            computer.read_havocs(),
+            vec![
+                Havoc {
+                    pc: 1,
+                    opcode: Opcode::Unknown("fadd".to_string()),
+                },
+                Havoc {
+                    pc: 3,
+                    opcode: Opcode::Aese,
+                },
+                Havoc {
+                    pc: 4,
+                    opcode: Opcode::Aesmc,
+                },
+            ]
+        );
+        let result = computer.get_register(&Operand::Register(RePrefix::X, 0));
+        assert_eq!(result.kind, RegisterKind::Number);
+    }
+
+    #[test]
+    fn test_sve_load_checks_active_lanes() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        let length = AbstractExpression::Abstract("len".to_string());
+        computer.add_memory_region("buf".to_string(), RegionType::READ, length.clone());
+        computer.set_abstract(
+            "x0".to_string(),
+            AbstractExpression::Abstract("buf".to_string()),
+        );
+        computer.set_abstract("x1".to_string(), length);
+        computer.set_immediate("x2".to_string(), 0);
+
+        // the lanes past len are inactive, for any vector length
+        let _ = computer.execute(0, &Instruction::new("whilelo p0.b, x2, x1".to_string()));
+        let res = computer.execute(
+            1,
+            &Instruction::new("ld1b {z0.b}, p0/z, [x0, x2]".to_string()),
+        );
+        assert!(res.is_ok());
+
+        // but not when every lane is active and len may be shorter than a vector
+        let _ = computer.execute(2, &Instruction::new("ptrue p1.b".to_string()));
+        let res = computer.execute(
+            3,
+            &Instruction::new("ld1b {z0.b}, p1/z, [x0, x2]".to_string()),
+        );
+        assert!(res.is_err());
+        let res = computer.execute(
+            4,
+            &Instruction::new("st1b {z0.b}, p0, [x0, x2]".to_string()),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_sve_vector_length_arithmetic() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut computer = ARMCORTEXA::new(&ctx);
+
+        computer.set_immediate("x2".to_string(), 8);
+        let _ = computer.execute(0, &Instruction::new("incw x2, all, mul #2".to_string()));
+        let _ = computer.execute(1, &Instruction::new("cntd x3".to_string()));
+        let _ = computer.execute(2, &Instruction::new("rdvl x4, #-1".to_string()));
+
+        let vl = AbstractExpression::Abstract(VECTOR_LENGTH.to_string());
+        assert_eq!(
+            computer.get_register(&Operand::Register(RePrefix::X, 2)),
+            RegisterValue::new(
+                RegisterKind::RegisterBase,
+                Some(generate_expression(
+                    "+",
+                    AbstractExpression::Immediate(0),
+                    generate_expression(
+                        "*",
+                        generate_expression("/", vl.clone(), AbstractExpression::Immediate(4)),
+                        AbstractExpression::Immediate(2)
+                    )
+                )),
+                8
+            )
+        );
+        assert_eq!(
+            computer
+                .get_register(&Operand::Register(RePrefix::X, 3))
+                .base,
+            Some(generate_expression(
+                "/",
+                vl.clone(),
+                AbstractExpression::Immediate(8)
+            ))
+        );
+        assert_eq!(
+            computer
+                .get_register(&Operand::Register(RePrefix::X, 4))
+                .base,
+            Some(generate_expression(
+                "*",
+                vl,
+                AbstractExpression::Immediate(-1)
+            ))
+        );
+    }
 }
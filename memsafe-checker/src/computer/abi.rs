@@ -0,0 +1,239 @@
+use crate::computer::*;
+
+/*
+ * the procedure call standard (aapcs64) as far as callers rely on it: x19 to x30 and the low 64
+ * bits of v8 to v15 hold their entry values on return, sp is restored and 16-byte aligned at calls
+ * simd registers are not followed exactly enough to compare their values, so the low halves of
+ * v8 to v15 are followed by name instead, through spills to memory and back
+ */
+#[derive(Debug, Clone)]
+pub struct CallingConvention {
+    entry: Vec<RegisterValue>, // x19 to x30
+    simd: [Option<usize>; 32], // the entry low half each simd register holds, if any
+    spills: HashMap<(String, i64), usize>,
+    calls: Vec<usize>, // bl instructions not returned from yet
+}
+
+// bytes a ldr, str, ldp or stp moves for a simd register, d8 is a Vector with arrangement D2
+pub fn simd_size(register: &Operand) -> i64 {
+    match register {
+        Operand::Vector(_, _, Arrangement::D2) => 8,
+        Operand::Vector(_, _, Arrangement::S4) => 4,
+        Operand::Vector(_, _, Arrangement::H8) => 2,
+        Operand::Vector(_, _, Arrangement::B16) => 1,
+        _ => 16,
+    }
+}
+
+pub fn lane_size(arrangement: &Arrangement) -> i64 {
+    match arrangement {
+        Arrangement::D | Arrangement::D2 => 8,
+        Arrangement::S | Arrangement::S2 | Arrangement::S4 => 4,
+        Arrangement::H | Arrangement::H4 | Arrangement::H8 => 2,
+        Arrangement::B | Arrangement::B8 | Arrangement::B16 => 1,
+    }
+}
+
+// narrowing into the upper half, i.e. xtn2, keeps the low half of the destination
+fn keeps_low_half(opcode: &Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::Shrn2
+            | Opcode::Rshrn2
+            | Opcode::Sqshrun2
+            | Opcode::Sqrshrun2
+            | Opcode::Uqshrn2
+            | Opcode::Uqrshrn2
+            | Opcode::Xtn2
+            | Opcode::Sqxtun2
+    )
+}
+
+fn simd_index(operand: &Operand) -> Option<usize> {
+    match operand {
+        Operand::VectorRegister(_, n)
+        | Operand::Vector(_, n, _)
+        | Operand::VectorAccess(_, n, ..) => Some(*n),
+        _ => None,
+    }
+}
+
+impl ARMCORTEXA<'_> {
+    // the registers as they are now are what the caller expects back
+    pub fn enter(&mut self) {
+        let mut simd = [None; 32];
+        for (n, value) in simd.iter_mut().enumerate().take(16).skip(8) {
+            *value = Some(n);
+        }
+        self.calling_convention = Some(CallingConvention {
+            entry: self.registers[19..=30].to_vec(),
+            simd,
+            spills: HashMap::new(),
+            calls: Vec::new(),
+        });
+    }
+
+    // a bl inside the program, sp has to be aligned for the callee
+    pub fn call(&mut self, pc: usize) -> Result<(), MemorySafetyError> {
+        let Some(convention) = &mut self.calling_convention else {
+            return Ok(());
+        };
+        let sp = &self.registers[31];
+        if sp.base != Some(AbstractExpression::Abstract("sp".to_string())) {
+            return Err(MemorySafetyError::new(
+                "Stack pointer is not known to be 16-byte aligned at call",
+            ));
+        }
+        if sp.offset % 16 != 0 {
+            return Err(MemorySafetyError::new(&format!(
+                "Stack pointer is not 16-byte aligned at call, it is sp{:+}",
+                sp.offset
+            )));
+        }
+        convention.calls.push(pc);
+        Ok(())
+    }
+
+    // where a ret to the address in x30 goes back to, if it is a bl inside the program
+    pub fn return_from_call(&mut self, link: &RegisterValue) -> Option<usize> {
+        let convention = self.calling_convention.as_mut()?;
+        let pc = usize::try_from(link.offset).ok()?;
+        if link.kind == RegisterKind::Immediate && convention.calls.last() == Some(&pc) {
+            convention.calls.pop();
+            return Some(pc + 1);
+        }
+        None
+    }
+
+    // on the way out of the program
+    pub fn check_calling_convention(&self) -> Result<(), MemorySafetyError> {
+        let Some(convention) = &self.calling_convention else {
+            return Ok(());
+        };
+        self.check_stack_pointer_restored()?;
+        for (i, entry) in convention.entry.iter().enumerate() {
+            let n = i + 19;
+            if self.registers[n] != *entry {
+                let name = match n {
+                    29 => "x29 (frame pointer)".to_string(),
+                    30 => "x30 (link register)".to_string(),
+                    n => format!("x{}", n),
+                };
+                return Err(MemorySafetyError::new(&format!(
+                    "Callee-saved register {} does not hold its entry value on return",
+                    name
+                )));
+            }
+        }
+        for n in 8..16 {
+            if convention.simd[n] != Some(n) {
+                return Err(MemorySafetyError::new(&format!(
+                    "Low 64 bits of callee-saved register v{} do not hold their entry value on return",
+                    n
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * runs before the instruction, follows which simd registers hold the entry low halves of v8 to
+     * v15 and where they are spilled
+     */
+    pub fn follow_callee_saved(&mut self, instruction: &Instruction) {
+        let Some(mut convention) = self.calling_convention.take() else {
+            return;
+        };
+        self.follow_callee_saved_with(&mut convention, instruction);
+        self.calling_convention = Some(convention);
+    }
+
+    fn follow_callee_saved_with(
+        &mut self,
+        convention: &mut CallingConvention,
+        instruction: &Instruction,
+    ) {
+        let operands = &instruction.operands;
+        let opcode = &instruction.opcode;
+        let Some(m) = operands
+            .iter()
+            .position(|o| matches!(o, Operand::Memory(..)))
+        else {
+            let Some(n) = operands.first().and_then(simd_index) else {
+                return;
+            };
+            convention.simd[n] = match (opcode, &operands[0], operands.get(1)) {
+                (_, _, _) if keeps_low_half(opcode) => convention.simd[n],
+                (_, Operand::VectorAccess(_, _, arrangement, lane), _)
+                    if lane * lane_size(arrangement) >= 8 =>
+                {
+                    convention.simd[n]
+                }
+                // whole register copies
+                (
+                    Opcode::Mov | Opcode::Fmov,
+                    Operand::VectorRegister(..) | Operand::Vector(..),
+                    Some(source @ (Operand::VectorRegister(..) | Operand::Vector(..))),
+                ) => simd_index(source).and_then(|s| convention.simd[s]),
+                _ => None,
+            };
+            return;
+        };
+        if opcode.structure().is_some() || opcode.is_sve() {
+            // structure loads and stores interleave lanes, only the registers they load are known
+            if !opcode.is_store() {
+                for r in operands[..m].iter().filter_map(simd_index) {
+                    convention.simd[r] = None;
+                }
+            }
+            return;
+        }
+
+        let (region, offset) = self.accessed_location(&operands[m]);
+        let registers = &operands[..m];
+        let sizes: Vec<i64> = registers
+            .iter()
+            .map(|r| match r {
+                Operand::Register(RePrefix::W, _) => 4,
+                Operand::Register(..) => opcode.access_size().unwrap_or(8) as i64,
+                r => simd_size(r),
+            })
+            .collect();
+
+        if opcode.is_store() {
+            // whatever was spilled where this store writes is overwritten
+            match (&region, offset) {
+                (Some(region), Some(offset)) => {
+                    let end = offset + sizes.iter().sum::<i64>();
+                    convention
+                        .spills
+                        .retain(|(r, o), _| r != region || *o + 8 <= offset || *o >= end);
+                    let mut start = offset;
+                    for (r, size) in registers.iter().zip(sizes) {
+                        if let Some(saved) = simd_index(r).and_then(|n| convention.simd[n]) {
+                            if size >= 8 {
+                                convention.spills.insert((region.clone(), start), saved);
+                            }
+                        }
+                        start += size;
+                    }
+                }
+                (Some(region), None) => convention.spills.retain(|(r, _), _| r != region),
+                (None, _) => convention.spills.clear(),
+            }
+        } else {
+            let mut start = offset;
+            for (r, size) in registers.iter().zip(sizes) {
+                if let Some(n) = simd_index(r) {
+                    convention.simd[n] = match (&region, start) {
+                        (Some(region), Some(start)) if size >= 8 => {
+                            convention.spills.get(&(region.clone(), start)).copied()
+                        }
+                        _ => None,
+                    };
+                }
+                start = start.map(|s| s + size);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::computer::abi::lane_size;
+use crate::computer::init::transfer_size;
+use crate::computer::*;
+
+/*
+ * alignment: exclusive, acquire-release and atomic accesses fault unless their address is a
+ * multiple of their size, and with strict checking every access has to be aligned to the size of
+ * its elements. the solver shows an address is aligned from the alignment of the region's start,
+ * which is what the type passed in guarantees, 16 for the stack, or else what the engine assumes
+ */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum AlignmentCheck {
+    Off,
+    #[default]
+    Required, // only the accesses that fault when they are not aligned
+    Natural, // every access, as with alignment checking enabled in SCTLR_EL1.A
+}
+
+// ldxp, stlxp or casp, which access both registers at once
+fn is_pair(opcode: &Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::Ldxp
+            | Opcode::Ldaxp
+            | Opcode::Stxp
+            | Opcode::Stlxp
+            | Opcode::Casp
+            | Opcode::Caspa
+            | Opcode::Caspl
+            | Opcode::Caspal
+    )
+}
+
+impl ARMCORTEXA<'_> {
+    // the region starts at a multiple of alignment bytes, i.e. the alignment of its element type
+    pub fn add_region_alignment(&mut self, region: String, alignment: i64) {
+        self.alignments.insert(region, alignment);
+    }
+
+    pub fn check_alignment(&mut self, check: AlignmentCheck) {
+        self.alignment_check = check;
+    }
+
+    fn region_alignment(&self, region: &str) -> i64 {
+        match self.alignments.get(region) {
+            Some(alignment) => *alignment,
+            None if region == "sp" => 16,
+            None => self.alignment,
+        }
+    }
+
+    // runs before the instruction, the address is known from the registers as they are
+    pub fn follow_alignment(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let Some(m) = operands
+            .iter()
+            .position(|o| matches!(o, Operand::Memory(..)))
+        else {
+            return Ok(());
+        };
+        if m == 0 || instruction.opcode == Opcode::Prfm || instruction.is_sve() {
+            return Ok(());
+        }
+
+        // the register next to the address holds one element, a pair of them moves as one
+        let element = match &operands[m - 1] {
+            Operand::Vector(_, _, arrangement) | Operand::VectorAccess(_, _, arrangement, _) => {
+                lane_size(arrangement)
+            }
+            register => transfer_size(&instruction.opcode, register),
+        };
+        let size = match (
+            self.alignment_check,
+            instruction.opcode.requires_alignment(),
+        ) {
+            (AlignmentCheck::Off, _) | (AlignmentCheck::Required, false) => return Ok(()),
+            (_, true) if is_pair(&instruction.opcode) => 2 * element,
+            _ => element,
+        };
+
+        let (region, _) = self.accessed_location(&operands[m]);
+        // addresses of static data are placeholders, their alignment is not known
+        let Some(region) = region.filter(|r| r != "memory") else {
+            return Ok(());
+        };
+        let Operand::Memory(prefix, n, immediate, _, post, _) = &operands[m] else {
+            return Ok(());
+        };
+        let mut address = self.get_register(&Operand::Register(prefix.clone(), *n));
+        if *post != Some(true) {
+            address.offset += immediate.unwrap_or(0);
+        }
+        let mut address = AbstractExpression::Register(Box::new(address));
+        if let Some(index) = self.scaled_index(&operands[m]) {
+            address =
+                generate_expression("+", address, AbstractExpression::Register(Box::new(index)));
+        }
+
+        let alignment = self.region_alignment(&region);
+        let zero = ast::Int::from_i64(self.context, 0);
+        let start_aligned = ast::Int::new_const(self.context, region.clone())
+            .modulo(&ast::Int::from_i64(self.context, alignment))
+            ._eq(&zero);
+        let misaligned = expression_to_ast(self.context, address.clone())
+            .expect("computer31")
+            .modulo(&ast::Int::from_i64(self.context, size))
+            ._eq(&zero)
+            .not();
+        if self.solver.check_assumptions(&[start_aligned, misaligned]) == SatResult::Unsat {
+            return Ok(());
+        }
+
+        let mut error = MemorySafetyError::new(&format!(
+            "Cannot show that {} at {} is aligned to {} bytes, {} starts at a multiple of {}",
+            instruction.opcode, address, size, region, alignment
+        ));
+        error.region = Some(region);
+        Err(error)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::computer::init::transfer_size;
+use crate::computer::*;
+
+/*
+ * exclusive and atomic accesses: a load exclusive reads like a load, a store exclusive that
+ * succeeds writes like a store and gives 0 in its status register, one that fails writes nothing
+ * and gives 1. the engine follows both. cas, ldadd, ldclr, ldeor, ldset and swp read the old value
+ * into one register, casp into a pair, and write memory in the same access. acquire and release
+ * only order accesses against other threads, one path through the program does not see it
+ */
+impl ARMCORTEXA<'_> {
+    // ldxr, ldaxr, ldxp, ldar and ldapr
+    pub fn load_exclusive(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let m = operands.len() - 1;
+        let registers = &operands[..m];
+        let size = transfer_size(&instruction.opcode, &registers[0]);
+
+        let (mut address, _) = self.indexed_address(&operands[m]);
+        self.check_footprint(&address, size * registers.len() as i64, RegionType::READ)?;
+        for register in registers {
+            self.load(register.clone(), address.clone())?;
+            address.offset += size;
+        }
+        Ok(())
+    }
+
+    // stxr, stlxr, stxp and stlr, which has no status register
+    pub fn store_exclusive(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let m = operands.len() - 1;
+        let registers = instruction.opcode.transferred(&operands[..m]);
+        let size = transfer_size(&instruction.opcode, &registers[0]);
+
+        let (mut address, _) = self.indexed_address(&operands[m]);
+        self.check_footprint(&address, size * registers.len() as i64, RegionType::WRITE)?;
+        for register in registers {
+            self.store(register.clone(), address.clone())?;
+            address.offset += size;
+        }
+
+        if instruction.opcode.is_store_exclusive() {
+            self.set_register(&operands[0], RegisterKind::Number, None, 0);
+        }
+        Ok(())
+    }
+
+    // the path where a store exclusive fails, memory is left as it was and the status is 1
+    pub fn fail_store_exclusive(&mut self, instruction: &Instruction) {
+        let status = &instruction.operands[0];
+        if let Some(secrets) = self.secrets.as_mut() {
+            secrets.set_operand(status, false);
+        }
+        self.set_register(status, RegisterKind::Number, None, 1);
+    }
+
+    // cas, casp, ldadd, ldclr, ldeor, ldset and swp
+    pub fn atomic(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let m = operands.len() - 1;
+        let Some((source, destination)) = instruction.opcode.atomic_operands() else {
+            unreachable!("atomic operation");
+        };
+        // casp compares and swaps a pair of registers
+        let count = m / 2;
+        let size = transfer_size(&instruction.opcode, &operands[destination]);
+
+        let (mut address, _) = self.indexed_address(&operands[m]);
+        self.check_footprint(&address, size * count as i64, RegionType::READ)?;
+        self.check_footprint(&address, size * count as i64, RegionType::WRITE)?;
+        for i in 0..count {
+            let old = self.read_memory(address.clone())?;
+            // a cas may or may not store, the others combine the two with an operation not tracked
+            let new = match instruction.opcode.is_swap() {
+                true => self.get_register(&operands[source + i]),
+                false => RegisterValue {
+                    kind: RegisterKind::Number,
+                    base: None,
+                    offset: 0,
+                },
+            };
+            self.write_memory(address.clone(), new)?;
+            self.set_register(&operands[destination + i], old.kind, old.base, old.offset);
+            address.offset += size;
+        }
+        Ok(())
+    }
+}
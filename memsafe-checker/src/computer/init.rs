@@ -0,0 +1,181 @@
+use crate::computer::abi::{lane_size, simd_size};
+use crate::computer::*;
+use std::collections::HashSet;
+
+/*
+ * uninitialised reads: write-only regions and the stack below sp at entry start out uninitialised,
+ * and the bytes a store writes are initialised from then on. a load from bytes no store has
+ * written is reported. where a store's offset into the region is not known, i.e. in a loop over a
+ * buffer, the whole region counts as written, and a load at an unknown offset is only reported
+ * when nothing in the region has been written
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Initialisation {
+    regions: HashSet<String>, // regions that start uninitialised
+    bytes: HashSet<(String, i64)>,
+    written: HashSet<String>,
+    outputs: Vec<String>, // regions the program has to fill before it returns
+}
+
+impl Initialisation {
+    pub fn new() -> Self {
+        Self {
+            regions: HashSet::from(["sp".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    fn initialised(&self, region: &str, byte: i64) -> bool {
+        !self.regions.contains(region)
+            // the caller's stack holds the arguments passed there
+            || (region == "sp" && byte >= 0)
+            || self.written.contains(region)
+            || self.bytes.contains(&(region.to_string(), byte))
+    }
+}
+
+// bytes moved for each register of a load or store
+pub fn transfer_size(opcode: &Opcode, register: &Operand) -> i64 {
+    match (register, opcode.structure()) {
+        (Operand::Register(RePrefix::W, _), _) => opcode.access_size().unwrap_or(4) as i64,
+        (Operand::Register(..), _) => opcode.access_size().unwrap_or(8) as i64,
+        (Operand::VectorAccess(_, _, arrangement, _), _) => lane_size(arrangement),
+        (Operand::Vector(_, _, arrangement), Some((_, true))) => lane_size(arrangement),
+        (
+            Operand::Vector(
+                _,
+                _,
+                Arrangement::B8 | Arrangement::H4 | Arrangement::S2 | Arrangement::D,
+            ),
+            Some(_),
+        ) => 8,
+        (register, None) => simd_size(register),
+        _ => 16,
+    }
+}
+
+fn uninitialised_read(message: String, region: &str) -> MemorySafetyError {
+    let mut error = MemorySafetyError::new(&message);
+    error.region = Some(region.to_string());
+    error
+}
+
+impl ARMCORTEXA<'_> {
+    // the region starts out uninitialised, i.e. a buffer the program only writes
+    pub fn add_uninitialised_region(&mut self, region: String) {
+        self.initialisation.regions.insert(region);
+    }
+
+    // what one of two overlapping regions holds at entry, the other may hold too
+    pub fn share_initialisation(&mut self, a: &str, b: &str) {
+        let regions = &mut self.initialisation.regions;
+        if !regions.contains(a) || !regions.contains(b) {
+            regions.remove(a);
+            regions.remove(b);
+        }
+    }
+
+    // every byte of the region has to be written before the program returns
+    pub fn add_output(&mut self, region: String) {
+        self.initialisation.outputs.push(region);
+    }
+
+    /*
+     * runs before the instruction, the address is known from the registers as they are
+     * errors if a load reads bytes no store has written, otherwise marks what a store writes
+     */
+    pub fn follow_initialisation(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let Some(m) = operands
+            .iter()
+            .position(|o| matches!(o, Operand::Memory(..)))
+        else {
+            return Ok(());
+        };
+        if instruction.opcode == Opcode::Prfm {
+            return Ok(());
+        }
+
+        let (region, offset) = self.accessed_location(&operands[m]);
+        let Some(region) = region else {
+            return Ok(());
+        };
+        // scalable vectors move a number of bytes that is not known
+        let offset = offset.filter(|_| !instruction.is_sve());
+        let size: i64 = instruction
+            .opcode
+            .transferred(&operands[..m])
+            .iter()
+            .filter(|o| !matches!(o, Operand::Predicate(..)))
+            .map(|r| transfer_size(&instruction.opcode, r))
+            .sum();
+
+        let init = &mut self.initialisation;
+        if instruction.opcode.is_store() {
+            match offset {
+                Some(offset) => {
+                    for byte in offset..offset + size {
+                        init.bytes.insert((region.clone(), byte));
+                    }
+                }
+                None => {
+                    init.written.insert(region);
+                }
+            }
+            return Ok(());
+        }
+
+        match offset {
+            Some(offset) => {
+                match (offset..offset + size).find(|b| !init.initialised(&region, *b)) {
+                    Some(byte) => Err(uninitialised_read(
+                        format!("Reads uninitialised memory at {} + {}", region, byte),
+                        &region,
+                    )),
+                    None => Ok(()),
+                }
+            }
+            None if region != "sp"
+                && init.regions.contains(&region)
+                && !init.written.contains(&region)
+                && !init.bytes.iter().any(|(r, _)| *r == region) =>
+            {
+                Err(uninitialised_read(
+                    format!("Reads {}, which nothing has been written to", region),
+                    &region,
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    // on the way out of the program
+    pub fn check_outputs_initialised(&self) -> Result<(), MemorySafetyError> {
+        let init = &self.initialisation;
+        for output in &init.outputs {
+            let length = self.memory.get(output).and_then(|r| r.get_size());
+            let Some(length) = length else {
+                return Err(uninitialised_read(
+                    format!(
+                        "Cannot show that all of {} is written, its length is not a constant",
+                        output
+                    ),
+                    output,
+                ));
+            };
+            if let Some(byte) = (0..length).find(|b| !init.bytes.contains(&(output.clone(), *b))) {
+                return Err(uninitialised_read(
+                    format!(
+                        "Does not initialise all of {}, byte {} is not known to be written",
+                        output, byte
+                    ),
+                    output,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
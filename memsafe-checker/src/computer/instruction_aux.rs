@@ -17,7 +17,7 @@ impl<'ctx> ARMCORTEXA<'_> {
         let mut r2 = self.get_register(reg2);
 
         if let Some(Operand::Bitwise(op, num)) = &reg_iter.next() {
-            r2 = shift_imm(op.to_string(), r2.clone(), *num);
+            r2 = shift_imm(op, r2.clone(), *num);
         }
 
         if r1.kind == r2.kind {
@@ -103,10 +103,10 @@ impl<'ctx> ARMCORTEXA<'_> {
         let shifted_reg;
         match reg3 {
             Operand::Bitwise(op, shift) => {
-                shifted_reg = shift_imm(op.to_string(), r2, *shift);
+                shifted_reg = shift_imm(op, r2, *shift);
             }
             Operand::Immediate(shift) => {
-                shifted_reg = shift_imm("lsl".to_string(), r2, *shift);
+                shifted_reg = shift_imm(&Shift::Lsl, r2, *shift);
             }
             _ => {
                 log::error!("Cannot shift with this operand: {:?}", reg3);
@@ -364,53 +364,45 @@ impl<'ctx> ARMCORTEXA<'_> {
     }
 }
 
-pub fn shift_imm(op: String, register: RegisterValue, shift: i64) -> RegisterValue {
-    match op.as_str() {
-        "lsl" => {
+pub fn shift_imm(op: &Shift, register: RegisterValue, shift: i64) -> RegisterValue {
+    match op {
+        Shift::Lsl => {
             let new_offset = register.offset << shift;
             RegisterValue {
                 kind: register.kind,
                 base: Some(generate_expression(
-                    &op,
+                    &op.to_string(),
                     register.base.unwrap_or(AbstractExpression::Empty),
                     AbstractExpression::Immediate(shift),
                 )),
                 offset: new_offset,
             }
         }
-        "lsr" => {
+        Shift::Lsr => {
             let new_offset = register.offset << shift;
             RegisterValue {
                 kind: register.kind,
                 base: Some(generate_expression(
-                    &op,
+                    &op.to_string(),
                     register.base.unwrap_or(AbstractExpression::Empty),
                     AbstractExpression::Immediate(shift),
                 )),
                 offset: new_offset,
             }
         }
-        "ror" => {
+        Shift::Ror => {
             let new_offset = register.offset >> shift;
             RegisterValue {
                 kind: register.kind,
                 base: Some(generate_expression(
-                    &op,
+                    &op.to_string(),
                     register.base.unwrap_or(AbstractExpression::Empty),
                     AbstractExpression::Immediate(shift),
                 )),
                 offset: new_offset,
             }
         }
-        "" => {
-            let new_offset = register.offset + shift;
-            RegisterValue {
-                kind: register.kind,
-                base: register.base,
-                offset: new_offset,
-            }
-        }
-        "uxtw" => {
+        Shift::Uxtw => {
             // TODO
             RegisterValue {
                 kind: register.kind,
@@ -1,3 +1,5 @@
+use crate::computer::init::transfer_size;
+use crate::computer::simd::{binary_operation, narrow_operation, shift_operation, unary_operation};
 use crate::computer::*;
 
 impl<'ctx> ARMCORTEXA<'_> {
@@ -5,470 +7,544 @@ impl<'ctx> ARMCORTEXA<'_> {
         &mut self,
         pc: usize,
         instruction: &Instruction,
-    ) -> Result<ExecuteReturnType, String> {
-        match instruction.ty {
-            InstructionType::Arithmetic => match instruction.opcode.as_str() {
-                "add" => {
-                    self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
-                }
-                "sub" => {
-                    self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
-                }
-                "mul" | "umulh" => {
-                    self.arithmetic("*", &|x, y| x * y, instruction.operands.clone());
-                }
-                "and" => {
-                    self.arithmetic("&", &|x, y| x & y, instruction.operands.clone());
-                }
-                "orr" => {
-                    self.arithmetic("|", &|x, y| x | y, instruction.operands.clone());
-                }
-                "orn" => {
-                    self.arithmetic("!|", &|x, y: i64| x | !y, instruction.operands.clone());
-                }
-                "eor" => {
-                    // potential TODO: deal with case where registers are the same but not imm
-                    self.arithmetic("^", &|x, y| x ^ y, instruction.operands.clone());
-                }
-                "bic" => {
-                    self.arithmetic("!&", &|x, y: i64| x & !y, instruction.operands.clone());
-                }
-                "adds" => {
-                    self.cmn(&instruction.operands[0], &instruction.operands[1]);
+    ) -> Result<ExecuteReturnType, MemorySafetyError> {
+        self.follow_secrets(instruction)?;
+        self.follow_callee_saved(instruction);
+        self.follow_initialisation(instruction)?;
+        self.follow_alignment(instruction)?;
+        match &instruction.opcode {
+            // labels and data (i.e. an undecodable word in an object file) are not executable
+            Opcode::Label(_) | Opcode::Directive(_) => {
+                return Err(MemorySafetyError::new(&format!(
+                    "Cannot execute {:?}",
+                    instruction
+                )));
+            }
+            // vector forms share mnemonics with scalar ones, i.e. add v0.4s vs add x0
+            _ if instruction.is_sve() => return self.execute_sve(pc, instruction),
+            _ if instruction.is_simd() => return self.execute_simd(pc, instruction),
+            Opcode::Add => {
+                // from sha256: add	x9, x9, _BORINGSSL_function_hit@PAGEOFF
+                if !instruction
+                    .operands
+                    .iter()
+                    .any(|o| matches!(o, Operand::Address(..) | Operand::Label(..)))
+                {
                     self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
                 }
-                "subs" => {
-                    self.cmp(&instruction.operands[0], &instruction.operands[1]);
-                    self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
-                }
-                "ror" | "lsl" | "lsr" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let reg0 = reg_iter.next().expect("Need destination register");
-                    let reg1 = reg_iter.next().expect("Need first source register");
-                    let reg2 = reg_iter.next().expect("Need second source register");
-
-                    self.shift_reg(reg0, reg1, reg2);
-                }
-                "ands" => {
-                    self.cmp(&instruction.operands[0], &instruction.operands[1]);
-                    self.arithmetic("&", &|x, y| x & y, instruction.operands.clone());
-                }
-                "adc" => {
-                    match &self.carry {
-                        Some(FlagValue::Real(b)) => {
-                            if *b == true {
-                                self.arithmetic(
-                                    "+",
-                                    &|x, y| x + y + 1,
-                                    instruction.operands.clone(),
-                                );
-                            } else {
-                                self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
-                            }
+            }
+            Opcode::Sub => {
+                self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
+            }
+            Opcode::Mul | Opcode::Umulh => {
+                self.arithmetic("*", &|x, y| x * y, instruction.operands.clone());
+            }
+            // dividing by zero gives zero
+            Opcode::Udiv | Opcode::Sdiv => {
+                self.arithmetic(
+                    "/",
+                    |x: i64, y| x.checked_div(y).unwrap_or(0),
+                    instruction.operands.clone(),
+                );
+            }
+            Opcode::And => {
+                self.arithmetic("&", &|x, y| x & y, instruction.operands.clone());
+            }
+            Opcode::Orr => {
+                self.arithmetic("|", &|x, y| x | y, instruction.operands.clone());
+            }
+            Opcode::Orn => {
+                self.arithmetic("!|", &|x, y: i64| x | !y, instruction.operands.clone());
+            }
+            Opcode::Eor => {
+                // potential TODO: deal with case where registers are the same but not imm
+                self.arithmetic("^", &|x, y| x ^ y, instruction.operands.clone());
+            }
+            Opcode::Bic => {
+                self.arithmetic("!&", &|x, y: i64| x & !y, instruction.operands.clone());
+            }
+            Opcode::Adds => {
+                self.cmn(&instruction.operands[0], &instruction.operands[1]);
+                self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
+            }
+            Opcode::Subs => {
+                self.cmp(&instruction.operands[0], &instruction.operands[1]);
+                self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
+            }
+            Opcode::Ror | Opcode::Lsl | Opcode::Lsr => {
+                let mut reg_iter = instruction.operands.iter();
+
+                let reg0 = reg_iter.next().expect("Need destination register");
+                let reg1 = reg_iter.next().expect("Need first source register");
+                let reg2 = reg_iter.next().expect("Need second source register");
+
+                self.shift_reg(reg0, reg1, reg2);
+            }
+            Opcode::Ands => {
+                self.cmp(&instruction.operands[0], &instruction.operands[1]);
+                self.arithmetic("&", &|x, y| x & y, instruction.operands.clone());
+            }
+            Opcode::Adc => {
+                match &self.carry {
+                    Some(FlagValue::Real(b)) => {
+                        if *b == true {
+                            self.arithmetic("+", &|x, y| x + y + 1, instruction.operands.clone());
+                        } else {
+                            self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
+                        }
+                    }
+                    _ => todo!("adcs carry is not real not supported yet"),
+                    // Some(FlagValue::Abstract(c)) => {
+                    //     let opt0 = self.arithmetic("+", &|x, y| x + y, instruction.operands);
+                    //     let opt1 = self.arithmetic("+", &|x, y| x + y + 1, instruction.operands);
+
+                    //     return Ok(ExecuteReturnType::Select(c.clone(), reg0.clone(), opt0, opt1));
+                    // }
+                    // None => {
+                    //     let opt0 = self.arithmetic("+", &|x, y| x + y, instruction.operands);
+                    //     let opt1 = self.arithmetic("+", &|x, y| x + y + 1, instruction.operands);
+
+                    //     return Ok(ExecuteReturnType::Select(
+                    //         AbstractComparison::new(
+                    //             "==",
+                    //             AbstractExpression::Abstract("carry".to_string()),
+                    //             AbstractExpression::Immediate(1),
+                    //         ),
+                    //         reg0.clone(),
+                    //         opt0,
+                    //         opt1,
+                    //     ));
+                    // }
+                }
+            }
+            Opcode::Adcs => {
+                let mut reg_iter = instruction.operands.iter().clone();
+                let _ = reg_iter.next().expect("Need destination register");
+                let reg1 = reg_iter.next().expect("Need first choice register");
+                let reg2 = reg_iter.next().expect("Need second choice source register");
+
+                match &self.carry {
+                    Some(FlagValue::Real(b)) => {
+                        if *b == true {
+                            self.arithmetic("+", &|x, y| x + y + 1, instruction.operands.clone());
+                            self.cmn(reg1, reg2);
+                        } else {
+                            self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
+                            self.cmn(reg1, reg2);
                         }
-                        _ => todo!("adcs carry is not real not supported yet"),
-                        // Some(FlagValue::Abstract(c)) => {
-                        //     let opt0 = self.arithmetic("+", &|x, y| x + y, instruction.operands);
-                        //     let opt1 = self.arithmetic("+", &|x, y| x + y + 1, instruction.operands);
-
-                        //     return Ok(ExecuteReturnType::Select(c.clone(), reg0.clone(), opt0, opt1));
-                        // }
-                        // None => {
-                        //     let opt0 = self.arithmetic("+", &|x, y| x + y, instruction.operands);
-                        //     let opt1 = self.arithmetic("+", &|x, y| x + y + 1, instruction.operands);
-
-                        //     return Ok(ExecuteReturnType::Select(
-                        //         AbstractComparison::new(
-                        //             "==",
-                        //             AbstractExpression::Abstract("carry".to_string()),
-                        //             AbstractExpression::Immediate(1),
-                        //         ),
-                        //         reg0.clone(),
-                        //         opt0,
-                        //         opt1,
-                        //     ));
-                        // }
                     }
+                    _ => todo!("adcs carry not real 2"),
                 }
-                "adcs" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let _ = reg_iter.next().expect("Need destination register");
-                    let reg1 = reg_iter.next().expect("Need first choice register");
-                    let reg2 = reg_iter.next().expect("Need second choice source register");
-
-                    match &self.carry {
-                        Some(FlagValue::Real(b)) => {
-                            if *b == true {
-                                self.arithmetic(
-                                    "+",
-                                    &|x, y| x + y + 1,
-                                    instruction.operands.clone(),
-                                );
-                                self.cmn(reg1, reg2);
-                            } else {
-                                self.arithmetic("+", &|x, y| x + y, instruction.operands.clone());
-                                self.cmn(reg1, reg2);
-                            }
-                        }
-                        _ => todo!("adcs carry not real 2"),
+            }
+            Opcode::Sbc => match &self.carry {
+                Some(FlagValue::Real(b)) => {
+                    if *b == true {
+                        self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
+                    } else {
+                        self.arithmetic("+", &|x, y| x - y - 1, instruction.operands.clone());
                     }
                 }
-                "sbc" => match &self.carry {
+                _ => todo!("sbc todo"),
+            },
+            Opcode::Sbcs => {
+                let mut reg_iter = instruction.operands.iter().clone();
+                let _ = reg_iter.next().expect("Need destination register");
+                let reg1 = reg_iter.next().expect("Need first choice register");
+                let reg2 = reg_iter.next().expect("Need second choice source register");
+
+                match &self.carry {
                     Some(FlagValue::Real(b)) => {
                         if *b == true {
                             self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
+                            self.cmp(reg1, reg2);
                         } else {
-                            self.arithmetic("+", &|x, y| x - y - 1, instruction.operands.clone());
+                            self.arithmetic("-", &|x, y| x - y - 1, instruction.operands.clone());
+                            self.cmp(reg1, reg2);
                         }
                     }
-                    _ => todo!("sbc todo"),
-                },
-                "sbcs" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let _ = reg_iter.next().expect("Need destination register");
-                    let reg1 = reg_iter.next().expect("Need first choice register");
-                    let reg2 = reg_iter.next().expect("Need second choice source register");
-
-                    match &self.carry {
-                        Some(FlagValue::Real(b)) => {
-                            if *b == true {
-                                self.arithmetic("-", &|x, y| x - y, instruction.operands.clone());
-                                self.cmp(reg1, reg2);
-                            } else {
-                                self.arithmetic(
-                                    "-",
-                                    &|x, y| x - y - 1,
-                                    instruction.operands.clone(),
-                                );
-                                self.cmp(reg1, reg2);
-                            }
-                        }
-                        _ => todo!("sbcs todo"),
-                    }
+                    _ => todo!("sbcs todo"),
                 }
-                "clz" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let reg0 = reg_iter.next().expect("Need register output for clz");
-                    let reg1 = reg_iter.next().expect("Need register output for clz");
+            }
+            Opcode::Clz => {
+                let mut reg_iter = instruction.operands.iter().clone();
+                let reg0 = reg_iter.next().expect("Need register output for clz");
+                let reg1 = reg_iter.next().expect("Need register output for clz");
 
-                    let r1 = self.get_register(reg1);
-                    match r1.kind {
-                        RegisterKind::Immediate => {
-                            self.set_register(
-                                reg0,
-                                RegisterKind::Number,
-                                None,
-                                r1.offset.leading_zeros() as i64,
-                            );
-                        }
-                        _ => {
-                            self.set_register(reg0, RegisterKind::Number, None, 0);
-                        }
-                    }
-                }
-                _ => todo!("Unsupported arithmetic instruction: {:?}", instruction),
-            },
-            InstructionType::ControlFlow => match instruction.opcode.as_str() {
-                // from sha256: add	x9, x9, _BORINGSSL_function_hit@PAGEOFF
-                "add" => (), // FIX
-                "adr" => {
-                    if let Operand::Address(label, _) = &instruction.operands[1] {
-                        let (region, index) = self.label_to_memory_index(label.clone());
+                let r1 = self.get_register(reg1);
+                match r1.kind {
+                    RegisterKind::Immediate => {
                         self.set_register(
-                            &instruction.operands[0],
-                            RegisterKind::RegisterBase,
-                            Some(AbstractExpression::Abstract(region)),
-                            index,
-                        );
-                    } else if let Operand::Label(label) = &instruction.operands[1] {
-                        let (region, index) = self.label_to_memory_index(label.clone());
-                        self.set_register(
-                            &instruction.operands[0],
-                            RegisterKind::RegisterBase,
-                            Some(AbstractExpression::Abstract(region)),
-                            index,
-                        );
-                    } else {
-                        panic!("adr not invoked correctly with register and label")
-                    }
-                }
-                "adrp" | "ldr" => {
-                    // Form PC-relative address to 4KB page adds an immediate value that is shifted left by 12 bits, to the PC value to form a PC-relative address, with the bottom 12 bits masked out, and writes the result to the destination register.
-                    // TODO: handle case for sha256 where we need @OFF page address
-                    if let Operand::Address(label, _) = &instruction.operands[1] {
-                        let (region, index) = self.label_to_memory_index(label.clone());
-                        self.set_register(
-                            &instruction.operands[0],
-                            RegisterKind::RegisterBase,
-                            Some(AbstractExpression::Abstract(region)),
-                            index << 12,
+                            reg0,
+                            RegisterKind::Number,
+                            None,
+                            r1.offset.leading_zeros() as i64,
                         );
-                    } else {
-                        panic!("adrp not invoked correctly with register and label")
                     }
-                }
-                "cbz" => {
-                    // Compare and Branch on Zero compares the value in a register with zero, and conditionally branches to a label at a PC-relative offset if the comparison is equal. It provides a hint that this is not a subroutine call or return. This instruction does not affect condition flags.
-                    let register = self.get_register(&instruction.operands[0]);
-                    if let Operand::Label(label) = &instruction.operands[1] {
-                        if (register.base.is_none()
-                            || register.base == Some(AbstractExpression::Empty))
-                            && register.offset == 0
-                        {
-                            return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
-                        } else if register.kind == RegisterKind::RegisterBase {
-                            return Ok(ExecuteReturnType::ConditionalJumpLabel(
-                                AbstractComparison::new(
-                                    "==",
-                                    AbstractExpression::Immediate(0),
-                                    AbstractExpression::Register(Box::new(register)),
-                                ),
-                                label.to_string(),
-                            ));
-                        } else {
-                            return Ok(ExecuteReturnType::Next);
-                        }
-                    } else {
-                        panic!("cbz not invoked correctly with register and label");
-                    }
-                }
-                "cbnz" => {
-                    let register = self.get_register(&instruction.operands[0]);
-                    if let Operand::Label(label) = &instruction.operands[1] {
-                        if (register.base.is_none()
-                            || register.base == Some(AbstractExpression::Empty))
-                            && register.offset == 0
-                        {
-                            return Ok(ExecuteReturnType::Next);
-                        } else if register.kind == RegisterKind::RegisterBase {
-                            return Ok(ExecuteReturnType::ConditionalJumpLabel(
-                                AbstractComparison::new(
-                                    "!=",
-                                    AbstractExpression::Immediate(0),
-                                    AbstractExpression::Register(Box::new(register)),
-                                ),
-                                label.to_string(),
-                            ));
-                        } else {
-                            return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
-                        }
-                    } else {
-                        panic!("cbnz not invoked correctly with register and label");
+                    _ => {
+                        self.set_register(reg0, RegisterKind::Number, None, 0);
                     }
                 }
-                "b" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
+            }
+            Opcode::Adr => {
+                if let Operand::Address(label, _) = &instruction.operands[1] {
+                    let (region, index) = self.label_to_memory_index(label.clone());
+                    self.set_register(
+                        &instruction.operands[0],
+                        RegisterKind::RegisterBase,
+                        Some(AbstractExpression::Abstract(region)),
+                        index,
+                    );
+                } else if let Operand::Label(label) = &instruction.operands[1] {
+                    let (region, index) = self.label_to_memory_index(label.clone());
+                    self.set_register(
+                        &instruction.operands[0],
+                        RegisterKind::RegisterBase,
+                        Some(AbstractExpression::Abstract(region)),
+                        index,
+                    );
+                } else {
+                    panic!("adr not invoked correctly with register and label")
+                }
+            }
+            Opcode::Adrp | Opcode::Ldr
+                if !matches!(instruction.operands.get(1), Some(Operand::Memory(..))) =>
+            {
+                // Form PC-relative address to 4KB page adds an immediate value that is shifted left by 12 bits, to the PC value to form a PC-relative address, with the bottom 12 bits masked out, and writes the result to the destination register.
+                // TODO: handle case for sha256 where we need @OFF page address
+                if let Operand::Address(label, _) = &instruction.operands[1] {
+                    let (region, index) = self.label_to_memory_index(label.clone());
+                    self.set_register(
+                        &instruction.operands[0],
+                        RegisterKind::RegisterBase,
+                        Some(AbstractExpression::Abstract(region)),
+                        // the add of the page offset is skipped, so this is the whole address
+                        index,
+                    );
+                } else {
+                    panic!("adrp not invoked correctly with register and label")
+                }
+            }
+            Opcode::Cbz => {
+                // Compare and Branch on Zero compares the value in a register with zero, and conditionally branches to a label at a PC-relative offset if the comparison is equal. It provides a hint that this is not a subroutine call or return. This instruction does not affect condition flags.
+                let register = self.get_register(&instruction.operands[0]);
+                if let Operand::Label(label) = &instruction.operands[1] {
+                    if (register.base.is_none() || register.base == Some(AbstractExpression::Empty))
+                        && register.offset == 0
+                    {
                         return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
+                    } else if register.kind == RegisterKind::RegisterBase {
+                        return Ok(ExecuteReturnType::ConditionalJumpLabel(
+                            AbstractComparison::new(
+                                "==",
+                                AbstractExpression::Immediate(0),
+                                AbstractExpression::Register(Box::new(register)),
+                            ),
+                            label.to_string(),
+                        ));
                     } else {
-                        panic!("b not invoked correctly");
+                        return Ok(ExecuteReturnType::Next);
                     }
+                } else {
+                    panic!("cbz not invoked correctly with register and label");
                 }
-                "bl" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        self.set_register(
-                            &Operand::Register(RePrefix::X, 30),
-                            RegisterKind::Immediate,
-                            None,
-                            pc as i64,
-                        );
-                        return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
+            }
+            Opcode::Cbnz => {
+                let register = self.get_register(&instruction.operands[0]);
+                if let Operand::Label(label) = &instruction.operands[1] {
+                    if (register.base.is_none() || register.base == Some(AbstractExpression::Empty))
+                        && register.offset == 0
+                    {
+                        return Ok(ExecuteReturnType::Next);
+                    } else if register.kind == RegisterKind::RegisterBase {
+                        return Ok(ExecuteReturnType::ConditionalJumpLabel(
+                            AbstractComparison::new(
+                                "!=",
+                                AbstractExpression::Immediate(0),
+                                AbstractExpression::Register(Box::new(register)),
+                            ),
+                            label.to_string(),
+                        ));
                     } else {
-                        panic!("b not invoked correctly");
+                        return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
                     }
-                }
-                "b.ne" | "bne" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match &self.zero {
-                            // if zero is set to false, then cmp -> not equal and we branch
-                            Some(flag) => match flag {
-                                FlagValue::Real(b) => {
-                                    if !b {
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()));
-                                    } else {
-                                        return Ok(ExecuteReturnType::Next);
-                                    }
+                } else {
+                    panic!("cbnz not invoked correctly with register and label");
+                }
+            }
+            Opcode::B => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
+                } else {
+                    panic!("b not invoked correctly");
+                }
+            }
+            Opcode::Bl => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    self.call(pc)?;
+                    self.set_register(
+                        &Operand::Register(RePrefix::X, 30),
+                        RegisterKind::Immediate,
+                        None,
+                        pc as i64,
+                    );
+                    return Ok(ExecuteReturnType::JumpLabel(label.to_string()));
+                } else {
+                    panic!("b not invoked correctly");
+                }
+            }
+            Opcode::Br => {
+                let target = self.get_register(&instruction.operands[0]);
+                // a return through another register than x30
+                if target.base == Some(AbstractExpression::Abstract("return".to_string()))
+                    && target.offset == 0
+                {
+                    return Ok(ExecuteReturnType::JumpLabel("return".to_string()));
+                }
+                return Ok(ExecuteReturnType::JumpRegister(target));
+            }
+            Opcode::Blr => {
+                let target = self.get_register(&instruction.operands[0]);
+                self.call(pc)?;
+                self.set_register(
+                    &Operand::Register(RePrefix::X, 30),
+                    RegisterKind::Immediate,
+                    None,
+                    pc as i64,
+                );
+                return Ok(ExecuteReturnType::JumpRegister(target));
+            }
+            Opcode::BCond(Condition::Ne) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match &self.zero {
+                        // if zero is set to false, then cmp -> not equal and we branch
+                        Some(flag) => match flag {
+                            FlagValue::Real(b) => {
+                                if !b {
+                                    return Ok(ExecuteReturnType::JumpLabel(label.clone()));
+                                } else {
+                                    return Ok(ExecuteReturnType::Next);
                                 }
-                                FlagValue::Abstract(s) => {
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel(s.clone().not(), label.clone()));
+                            }
+                            FlagValue::Abstract(s) => {
+                                return Ok(ExecuteReturnType::ConditionalJumpLabel(s.clone().not(), label.clone()));
+                            }
+                        },
+                        None => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
+                    }
+                }
+                panic!("bne not invoked correctly with label");
+            }
+            Opcode::BCond(Condition::Eq) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match &self.zero {
+                        // if zero is set to false, then cmp -> not equal and we branch
+                        Some(flag) => match flag {
+                            FlagValue::Real(b) => {
+                                if *b {
+                                    return Ok(ExecuteReturnType::JumpLabel(label.clone()));
+                                } else {
+                                    return Ok(ExecuteReturnType::Next);
                                 }
-                            },
-                            None => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
-                        }
+                            }
+                            FlagValue::Abstract(s) => {
+                                return Ok(ExecuteReturnType::ConditionalJumpLabel(s.clone(), label.clone()));
+                            }
+                        },
+                        None => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
                     }
-                    panic!("bne not invoked correctly with label");
                 }
-                "b.eq" | "beq" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match &self.zero {
-                            // if zero is set to false, then cmp -> not equal and we branch
-                            Some(flag) => match flag {
-                                FlagValue::Real(b) => {
-                                    if *b {
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()));
-                                    } else {
-                                        return Ok(ExecuteReturnType::Next);
-                                    }
-                                }
-                                FlagValue::Abstract(s) => {
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel(s.clone(), label.clone()));
-                                }
+                panic!("beq not invoked correctly with label");
+            }
+            Opcode::BCond(cond @ (Condition::Mi | Condition::Pl)) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    let branch = if *cond == Condition::Mi {
+                        self.neg.clone()
+                    } else {
+                        self.neg.as_ref().map(|n| n.not())
+                    };
+                    match branch {
+                        Some(FlagValue::Real(true)) => {
+                            return Ok(ExecuteReturnType::JumpLabel(label.clone()))
+                        }
+                        Some(FlagValue::Real(false)) => return Ok(ExecuteReturnType::Next),
+                        Some(FlagValue::Abstract(s)) => {
+                            return Ok(ExecuteReturnType::ConditionalJumpLabel(s, label.clone()))
+                        }
+                        None => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
+                    }
+                }
+                panic!("b.mi/b.pl not invoked correctly with label");
+            }
+            Opcode::BCond(Condition::Gt) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match (&self.zero, &self.neg, &self.overflow) {
+                        (Some(zero), Some(neg), Some(ove)) => {
+                            match  (zero, neg, ove) {
+                            (FlagValue::Real(z), FlagValue::Real(n), FlagValue::Real(v)) => {
+                               if !z && n == v {  // Z = 0 AND N = V
+                                    return Ok(ExecuteReturnType::JumpLabel(label.clone()))
+                               } else {
+                                    return Ok(ExecuteReturnType::Next)
+                               }
                             },
-                            None => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
-                        }
-                    }
-                    panic!("beq not invoked correctly with label");
-                }
-                "bgt" | "bt" | "b.gt" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match (&self.zero, &self.neg, &self.overflow) {
-                            (Some(zero), Some(neg), Some(ove)) => {
-                                match  (zero, neg, ove) {
-                                (FlagValue::Real(z), FlagValue::Real(n), FlagValue::Real(v)) => {
-                                   if !z && n == v {  // Z = 0 AND N = V
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()))
-                                   } else {
-                                        return Ok(ExecuteReturnType::Next)
-                                   }
-                                },
-                                (FlagValue::Abstract(z) , _, _ ) =>  {
-                                    let expression = generate_comparison(">", *z.left.clone(), *z.right.clone());
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
-                                },
-                                (_,_,_) => todo!("match on undefined flags!")
-                                }
+                            (FlagValue::Abstract(z) , _, _ ) =>  {
+                                let expression = generate_comparison(">", *z.left.clone(), *z.right.clone());
+                                return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
                             },
-                            (_, _, _) => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
-                        }
-                    }
-                    panic!("b.gt not invoked correctly with label");
-                }
-                "b.lt" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match (&self.zero, &self.neg, &self.overflow) {
-                            (Some(zero), Some(neg), Some(ove)) => {
-                                match  (zero, neg, ove) {
-                                (FlagValue::Real(z), FlagValue::Real(n), FlagValue::Real(v)) => {
-                                   if !z && n != v {  // Z = 0 AND N = V
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()))
-                                   } else {
-                                        return Ok(ExecuteReturnType::Next)
-                                   }
-                                },
-                                (FlagValue::Abstract(z) , _, _ ) =>  {
-                                    let expression = generate_comparison("<", *z.left.clone(), *z.right.clone());
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel( expression, label.clone()));
-                                },
-                                (_,_,_) => todo!("match on undefined flags!")
-                                }
+                            (_,_,_) => todo!("match on undefined flags!")
+                            }
+                        },
+                        (_, _, _) => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
+                    }
+                }
+                panic!("b.gt not invoked correctly with label");
+            }
+            Opcode::BCond(Condition::Lt) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match (&self.zero, &self.neg, &self.overflow) {
+                        (Some(zero), Some(neg), Some(ove)) => {
+                            match  (zero, neg, ove) {
+                            (FlagValue::Real(z), FlagValue::Real(n), FlagValue::Real(v)) => {
+                               if !z && n != v {  // Z = 0 AND N = V
+                                    return Ok(ExecuteReturnType::JumpLabel(label.clone()))
+                               } else {
+                                    return Ok(ExecuteReturnType::Next)
+                               }
                             },
-                            (_, _, _) => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
+                            (FlagValue::Abstract(z) , _, _ ) =>  {
+                                let expression = generate_comparison("<", *z.left.clone(), *z.right.clone());
+                                return Ok(ExecuteReturnType::ConditionalJumpLabel( expression, label.clone()));
+                            },
+                            (_,_,_) => todo!("match on undefined flags!")
+                            }
+                        },
+                        (_, _, _) => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
+                    }
+                }
+            }
+            Opcode::BCond(Condition::Ls | Condition::Le) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match (&self.zero, &self.carry) {
+                    (Some(zero), Some(carry)) => {
+                        match  (zero, carry) {
+                        (FlagValue::Real(z), FlagValue::Real(c)) => {
+                           if !z && *c {
+                                return Ok(ExecuteReturnType::JumpLabel(label.clone()));
+                           } else {
+                                return Ok(ExecuteReturnType::Next)
+                           }
+                        },
+                        (FlagValue::Abstract(z) , _ ) | (_, FlagValue::Abstract(z) ) =>  {
+                            let expression = generate_comparison("<=", *z.left.clone(), *z.right.clone());
+                            return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
+                        },
                         }
-                    }
-                }
-                "b.ls" | "b.le" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match (&self.zero, &self.carry) {
-                        (Some(zero), Some(carry)) => {
-                            match  (zero, carry) {
-                            (FlagValue::Real(z), FlagValue::Real(c)) => {
-                               if !z && *c {
+                    },
+                    (_, _) => return Err(MemorySafetyError::new(
+                        "Flag cannot be branched on since it has not been set within the program yet",
+                    )),
+                }
+                }
+                panic!("b.ls not invoked correctly with label");
+            }
+            Opcode::BCond(Condition::Hs) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match&self.carry{
+                        Some(carry) => {
+                            match  carry {
+                            FlagValue::Real(c) => {
+                               if *c {
                                     return Ok(ExecuteReturnType::JumpLabel(label.clone()));
                                } else {
                                     return Ok(ExecuteReturnType::Next)
                                }
                             },
-                            (FlagValue::Abstract(z) , _ ) | (_, FlagValue::Abstract(z) ) =>  {
-                                let expression = generate_comparison("<=", *z.left.clone(), *z.right.clone());
+                            FlagValue::Abstract(c) =>  {
+                                let expression = generate_comparison("<", *c.left.clone(), *c.right.clone());
                                 return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
                             },
                             }
                         },
-                        (_, _) => return Err(
-                            "Flag cannot be branched on since it has not been set within the program yet"
-                                .to_string(),
-                        ),
-                    }
-                    }
-                    panic!("b.ls not invoked correctly with label");
-                }
-                "b.cs" | "b.hs" | "bcs" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match&self.carry{
-                            Some(carry) => {
-                                match  carry {
-                                FlagValue::Real(c) => {
-                                   if *c {
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()));
-                                   } else {
-                                        return Ok(ExecuteReturnType::Next)
-                                   }
-                                },
-                                FlagValue::Abstract(c) =>  {
-                                    let expression = generate_comparison("<", *c.left.clone(), *c.right.clone());
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
-                                },
-                                }
+                        None => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
+                    }
+                }
+                panic!("b.cs not invoked correctly");
+            }
+            Opcode::BCond(Condition::Lo) => {
+                if let Operand::Label(label) = &instruction.operands[0] {
+                    match&self.carry{
+                        Some(carry) => {
+                            match  carry {
+                            FlagValue::Real(c) => {
+                               if !*c {
+                                    return Ok(ExecuteReturnType::JumpLabel(label.clone()));
+                               } else {
+                                    return Ok(ExecuteReturnType::Next)
+                               }
                             },
-                            None => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
-                        }
-                    }
-                    panic!("b.cs not invoked correctly");
-                }
-                "b.cc" | "b.lo" | "blo" => {
-                    if let Operand::Label(label) = &instruction.operands[0] {
-                        match&self.carry{
-                            Some(carry) => {
-                                match  carry {
-                                FlagValue::Real(c) => {
-                                   if !*c {
-                                        return Ok(ExecuteReturnType::JumpLabel(label.clone()));
-                                   } else {
-                                        return Ok(ExecuteReturnType::Next)
-                                   }
-                                },
-                                FlagValue::Abstract(c) =>  {
-                                    let expression = generate_comparison(">=", *c.left.clone(), *c.right.clone());
-                                    return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
-                                },
-                                }
+                            FlagValue::Abstract(c) =>  {
+                                let expression = generate_comparison(">=", *c.left.clone(), *c.right.clone());
+                                return Ok(ExecuteReturnType::ConditionalJumpLabel(expression, label.clone()));
                             },
-                            None => return Err(
-                                "Flag cannot be branched on since it has not been set within the program yet"
-                                    .to_string(),
-                            ),
-                        }
+                            }
+                        },
+                        None => return Err(MemorySafetyError::new(
+                            "Flag cannot be branched on since it has not been set within the program yet",
+                        )),
                     }
-                    panic!("b.cc/lo/blo not invoked correctly");
                 }
-                "cset" => {
-                    // match on condition based on flags
-                    let mut reg_iter = instruction.operands.iter();
+                panic!("b.cc/lo/blo not invoked correctly");
+            }
+            Opcode::Cset => {
+                // match on condition based on flags
+                let mut reg_iter = instruction.operands.iter();
 
-                    let register = register_to_tuple(reg_iter.next().expect("cset register"));
-                    let Operand::Label(cond) = reg_iter.next().expect("cset condition code") else {
-                        panic!("not a valid condition code")
-                    };
+                let register = register_to_tuple(reg_iter.next().expect("cset register"));
+                let Operand::Condition(cond) = reg_iter.next().expect("cset condition code") else {
+                    panic!("not a valid condition code")
+                };
 
-                    match cond.as_str() {
-                        "cs" => match self.carry.clone().expect("Need carry flag set cset cs") {
+                match cond {
+                    Condition::Hs => match self.carry.clone().expect("Need carry flag set cset cs")
+                    {
+                        FlagValue::Real(b) => {
+                            if b == true {
+                                self.set_register_from_tuple(
+                                    register,
+                                    RegisterKind::Immediate,
+                                    None,
+                                    1,
+                                );
+                            } else {
+                                self.set_register_from_tuple(
+                                    register,
+                                    RegisterKind::Immediate,
+                                    None,
+                                    0,
+                                );
+                            }
+                        }
+                        FlagValue::Abstract(_) => {
+                            log::error!("Can't support this yet :)");
+                            todo!("Abstract Flag Expression3");
+                        }
+                    },
+                    Condition::Lo => {
+                        match self.carry.clone().expect("Need carry flag set cset cc") {
                             FlagValue::Real(b) => {
-                                if b == true {
+                                if b == false {
                                     self.set_register_from_tuple(
                                         register,
                                         RegisterKind::Immediate,
@@ -488,1239 +564,436 @@ impl<'ctx> ARMCORTEXA<'_> {
                                 log::error!("Can't support this yet :)");
                                 todo!("Abstract Flag Expression3");
                             }
-                        },
-                        "cc" | "lo" => {
-                            match self.carry.clone().expect("Need carry flag set cset cc") {
-                                FlagValue::Real(b) => {
-                                    if b == false {
-                                        self.set_register_from_tuple(
-                                            register,
-                                            RegisterKind::Immediate,
-                                            None,
-                                            1,
-                                        );
-                                    } else {
-                                        self.set_register_from_tuple(
-                                            register,
-                                            RegisterKind::Immediate,
-                                            None,
-                                            0,
-                                        );
-                                    }
-                                }
-                                FlagValue::Abstract(_) => {
-                                    log::error!("Can't support this yet :)");
-                                    todo!("Abstract Flag Expression3");
-                                }
-                            }
                         }
-                        a => todo!("unimplemented condition code for set {}", a),
                     }
+                    a => todo!("unimplemented condition code for set {}", a),
                 }
-                "csel" => {
-                    // match on condition based on flags
-                    let mut reg_iter = instruction.operands.iter();
+            }
+            Opcode::Csel => {
+                // match on condition based on flags
+                let mut reg_iter = instruction.operands.iter();
 
-                    let dest = reg_iter.next().expect("cset register");
-                    let opt1 = self.get_register(reg_iter.next().expect("csel register"));
-                    let opt2 = self.get_register(reg_iter.next().expect("csel register"));
-                    let Operand::Label(cond) = reg_iter.next().expect("csel condition code") else {
-                        panic!("not a valid condition code")
-                    };
+                let dest = reg_iter.next().expect("cset register");
+                let opt1 = self.get_register(reg_iter.next().expect("csel register"));
+                let opt2 = self.get_register(reg_iter.next().expect("csel register"));
+                let Operand::Condition(cond) = reg_iter.next().expect("csel condition code") else {
+                    panic!("not a valid condition code")
+                };
 
-                    match cond.as_str() {
-                        "cc" | "lo" => {
-                            match self.carry.clone().expect("Need carry flag set csel cc") {
-                                FlagValue::Real(b) => {
-                                    if b == true {
-                                        self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
-                                    } else {
-                                        self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
-                                    }
-                                }
-                                FlagValue::Abstract(a) => {
-                                    return Ok(ExecuteReturnType::Select(
-                                        a,
-                                        dest.clone(),
-                                        opt1,
-                                        opt2,
-                                    ));
-                                }
-                            }
-                        }
-                        "cs" => match self.carry.clone() {
-                            Some(FlagValue::Real(b)) => {
-                                if b == false {
-                                    self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
-                                } else {
-                                    self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
-                                }
-                            }
-                            Some(FlagValue::Abstract(a)) => {
-                                return Ok(ExecuteReturnType::Select(
-                                    a.not(),
-                                    dest.clone(),
-                                    opt1,
-                                    opt2,
-                                ));
-                            }
-                            None => {
-                                return Ok(ExecuteReturnType::Select(
-                                    AbstractComparison::new(
-                                        "==",
-                                        AbstractExpression::Abstract("c_flag".to_string()),
-                                        AbstractExpression::Immediate(1),
-                                    ),
-                                    dest.clone(),
-                                    opt1,
-                                    opt2,
-                                ));
-                            }
-                        },
-                        "eq" => {
-                            match self.zero.clone().expect("Need zero flag set") {
-                                FlagValue::Real(z) => {
-                                    if z == true {
-                                        self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
-                                    } else {
-                                        self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
-                                    }
-                                }
-                                FlagValue::Abstract(z) => {
-                                    return Ok(ExecuteReturnType::Select(
-                                        z,
-                                        dest.clone(),
-                                        opt1,
-                                        opt2,
-                                    ));
-                                }
-                            };
-                        }
-                        a => todo!("csel with condition code not yet implemented {}", a),
-                    }
-                }
-                "csetm" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dest = reg_iter.next().expect("cset register");
-                    let Operand::Label(cond) = reg_iter.next().expect("cset condition code") else {
-                        panic!("not a valid condition code")
-                    };
-
-                    match cond.as_str() {
-                        "eq" => match self.zero.clone().expect("Need zero flag set") {
+                match cond {
+                    Condition::Lo => {
+                        match self.carry.clone().expect("Need carry flag set csel cc") {
                             FlagValue::Real(b) => {
                                 if b == true {
-                                    self.set_register(&dest, RegisterKind::Immediate, None, 1);
+                                    self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
                                 } else {
-                                    self.set_register(&dest, RegisterKind::Immediate, None, 0);
+                                    self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
                                 }
                             }
                             FlagValue::Abstract(a) => {
-                                let opt1 = RegisterValue {
-                                    kind: RegisterKind::Immediate,
-                                    base: None,
-                                    offset: 1,
-                                };
-                                let opt2 = RegisterValue {
-                                    kind: RegisterKind::Immediate,
-                                    base: None,
-                                    offset: 1,
-                                };
-
                                 return Ok(ExecuteReturnType::Select(a, dest.clone(), opt1, opt2));
                             }
-                        },
-                        _ => todo!("unsupported condition code for csetm {:?}", cond),
-                    }
-                }
-                // BIG TODO: reimplement these
-                "tst" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst = reg_iter.next().expect("Need destination register");
-                    self.set_register(dst, RegisterKind::Number, None, 0);
-                }
-                "mov" | "movz" | "movk" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let reg0 = reg_iter.next().expect("Need register output for mov");
-                    let reg1 = reg_iter.next().expect("Need register output for mov");
-
-                    let r1 = self.get_register(reg1);
-
-                    self.set_register(reg0, r1.kind, r1.base, r1.offset);
-                }
-                _ => todo!(
-                    "control flow instruction not yet implemented {:?}",
-                    instruction
-                ),
-            },
-            InstructionType::Memory => match instruction.opcode.as_str() {
-                "ldr" | "ldrb" => {
-                    // TODO: split, have to rewrite load or do post-processing after load to extract meaningful byte
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst = reg_iter.next().expect("ldr dst");
-                    let src_addr = reg_iter.next().expect("ldr src");
-
-                    match src_addr {
-                        Operand::Memory(w, reg_num, offset, _, mode) => {
-                            let mut address = self
-                                .get_register(&Operand::Register(w.clone(), *reg_num))
-                                .clone();
-                            address.offset = address.offset + offset.unwrap_or(0);
-
-                            let res = self.load(dst.clone(), address.clone());
-                            match res {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-
-                            match mode {
-                                // pre
-                                Some(false) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset - offset.unwrap_or(0),
-                                    );
-                                }
-                                // post
-                                Some(true) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset,
-                                    );
-                                }
-                                None => {}
-                            }
-                        }
-                        _ => {
-                            panic!("ldr not with correct syntax for memory operand");
-                        }
-                    }
-                }
-                "ldp" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst1 = reg_iter.next().expect("ldr dst");
-                    let dst2 = reg_iter.next().expect("ldr src");
-                    let src_addr = reg_iter.next().expect("ldr src");
-
-                    match src_addr {
-                        Operand::Memory(w, reg_num, offset, _, mode) => {
-                            let mut address = self
-                                .get_register(&Operand::Register(w.clone(), *reg_num))
-                                .clone();
-                            address.offset = address.offset + offset.unwrap_or(0);
-
-                            let res1 = self.load(dst1.clone(), address.clone());
-                            address.offset += 8;
-                            let res2 = self.load(dst2.clone(), address.clone());
-                            match res1 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-                            match res2 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-
-                            match mode {
-                                // pre
-                                Some(false) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset - offset.unwrap_or(0) - 8,
-                                    );
-                                }
-                                // post
-                                Some(true) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset,
-                                    );
-                                }
-                                None => {}
-                            }
-                        }
-                        _ => {
-                            panic!("ldr not with correct syntax for memory operand");
                         }
                     }
-                }
-                "str" | "strb" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst = reg_iter.next().expect("ldr dst");
-                    let src_addr = reg_iter.next().expect("ldr src");
-
-                    match src_addr {
-                        Operand::Memory(w, reg_num, offset, _, mode) => {
-                            let mut address = self
-                                .get_register(&Operand::Register(w.clone(), *reg_num))
-                                .clone();
-                            address.offset = address.offset + offset.unwrap_or(0);
-
-                            let res = self.store(dst.clone(), address.clone());
-                            match res {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-
-                            match mode {
-                                // pre
-                                Some(false) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset - offset.unwrap_or(0),
-                                    );
-                                }
-                                // post
-                                Some(true) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset,
-                                    );
-                                }
-                                None => {}
+                    Condition::Hs => match self.carry.clone() {
+                        Some(FlagValue::Real(b)) => {
+                            if b == false {
+                                self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
+                            } else {
+                                self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
                             }
                         }
-                        _ => {
-                            panic!("ldr not with correct syntax for memory operand");
-                        }
-                    }
-                }
-                "stp" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst1 = reg_iter.next().expect("ldr dst");
-                    let dst2 = reg_iter.next().expect("ldr src");
-                    let src_addr = reg_iter.next().expect("ldr src");
-
-                    match src_addr {
-                        Operand::Memory(w, reg_num, offset, _, mode) => {
-                            let mut address = self
-                                .get_register(&Operand::Register(w.clone(), *reg_num))
-                                .clone();
-                            address.offset = address.offset + offset.unwrap_or(0);
-
-                            let res1 = self.store(dst1.clone(), address.clone());
-                            address.offset += 8;
-                            let res2 = self.store(dst2.clone(), address.clone());
-                            match res1 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-                            match res2 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-
-                            match mode {
-                                // pre
-                                Some(false) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset - offset.unwrap_or(0) - 8,
-                                    );
-                                }
-                                // post
-                                Some(true) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset,
-                                    );
-                                }
-                                None => {}
-                            }
+                        Some(FlagValue::Abstract(a)) => {
+                            return Ok(ExecuteReturnType::Select(
+                                a.not(),
+                                dest.clone(),
+                                opt1,
+                                opt2,
+                            ));
                         }
-                        _ => {
-                            panic!("ldr not with correct syntax for memory operand");
+                        None => {
+                            return Ok(ExecuteReturnType::Select(
+                                AbstractComparison::new(
+                                    "==",
+                                    AbstractExpression::Abstract("c_flag".to_string()),
+                                    AbstractExpression::Immediate(1),
+                                ),
+                                dest.clone(),
+                                opt1,
+                                opt2,
+                            ));
                         }
-                    }
-                }
-                _ => todo!("memory instruction not supported yet {:?}", instruction),
-            },
-            InstructionType::SIMDArithmetic => match instruction.opcode.as_str() {
-                "rev64" | "rev64.16b" | "rev64.8h" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dest_op = reg_iter.next().expect("rev64 dst");
-                    let dest = &mut self.get_simd_register(dest_op);
-                    let src = self.get_simd_register(reg_iter.next().expect("rev64 src"));
-
-                    match dest_op {
-                        Operand::Vector(_, _, arr) => match arr {
-                            Arrangement::H8 => {
-                                for i in 0..8 {
-                                    let (base, offset) = src.get_halfword(7 - i);
-                                    dest.set_halfword(i, base, offset);
+                    },
+                    Condition::Eq => {
+                        match self.zero.clone().expect("Need zero flag set") {
+                            FlagValue::Real(z) => {
+                                if z == true {
+                                    self.set_register(dest, opt1.kind, opt1.base, opt1.offset);
+                                } else {
+                                    self.set_register(dest, opt2.kind, opt2.base, opt2.offset);
                                 }
                             }
-                            Arrangement::B16 => {
-                                for i in 0..16 {
-                                    let (base, offset) = src.get_byte(15 - i);
-                                    dest.set_byte(i, base, offset);
-                                }
+                            FlagValue::Abstract(z) => {
+                                return Ok(ExecuteReturnType::Select(z, dest.clone(), opt1, opt2));
                             }
-                            _ => todo!("rev64 support more arrangement modes"),
-                        },
-                        Operand::VectorRegister(_, _) => {
-                            let arr = instruction.opcode.split(".").collect::<Vec<&str>>()[1];
-                            match arr {
-                                "16b" => {
-                                    dest.kind = src.kind.clone();
-                                    for i in 0..16 {
-                                        let (base, offset) = src.get_byte(15 - i);
-                                        dest.set_byte(i, base, offset);
-                                    }
-                                }
-                                "8h" => {
-                                    for i in 0..8 {
-                                        let (base, offset) = src.get_halfword(7 - i);
-                                        dest.set_halfword(i, base, offset);
-                                    }
-                                }
-                                _ => todo!("support arr in rav1d for rev64"),
-                            }
-                        }
-                        a => todo!("unsupported operand in simd rev64 {:?}", a),
+                        };
                     }
+                    a => todo!("csel with condition code not yet implemented {}", a),
                 }
-                "and" => {
-                    self.vector_arithmetic(
-                        "&",
-                        &|x, y| x & y,
-                        &|x, y| x & y,
-                        &|x, y| x & y,
-                        &|x, y| x & y,
-                        instruction,
-                    );
-                }
-                "add" => {
-                    self.vector_arithmetic(
-                        "+",
-                        &|x, y| x + y,
-                        &|x, y| x + y,
-                        &|x, y| x + y,
-                        &|x, y| x + y,
-                        instruction,
-                    );
-                }
-                "orr" => {
-                    self.vector_arithmetic(
-                        "|",
-                        &|x, y| x | y,
-                        &|x, y| x | y,
-                        &|x, y| x | y,
-                        &|x, y| x | y,
-                        instruction,
-                    );
-                }
-                "eor" => {
-                    self.vector_arithmetic(
-                        "^",
-                        &|x, y| x ^ y,
-                        &|x, y| x ^ y,
-                        &|x, y| x ^ y,
-                        &|x, y| x ^ y,
-                        instruction,
-                    );
-                }
-                "mul" => {
-                    self.vector_arithmetic(
-                        "*",
-                        &|x, y| x * y,
-                        &|x, y| x * y,
-                        &|x, y| x * y,
-                        &|x, y| x * y,
-                        instruction,
-                    );
-                }
-                "sub" => {
-                    self.vector_arithmetic(
-                        "-",
-                        &|x, y| x - y,
-                        &|x, y| x - y,
-                        &|x, y| x - y,
-                        &|x, y| x - y,
-                        instruction,
-                    );
-                }
-                "shl" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst = reg_iter.next().expect("Need destination register");
-                    let mut dst_reg = self.get_simd_register(dst);
-                    let src_name = reg_iter.next().expect("need src register shl");
-                    let src_reg = self.get_simd_register(src_name);
+            }
+            Opcode::Csetm => {
+                let mut reg_iter = instruction.operands.iter();
 
-                    let Operand::Vector(_, _, arr) = src_name else {
-                        panic!("need appropriate vector notation for shl")
-                    };
-                    let Operand::Immediate(shift) = reg_iter.next().expect("Need shift amount")
-                    else {
-                        panic!("cannot call shl without a shift amount")
-                    };
-                    let small_shift = u32::try_from(*shift).expect("shift shoudl fit into u32");
+                let dest = reg_iter.next().expect("cset register");
+                let Operand::Condition(cond) = reg_iter.next().expect("cset condition code") else {
+                    panic!("not a valid condition code")
+                };
 
-                    match arr {
-                        Arrangement::D2 => {
-                            for i in 0..2 {
-                                let (bases, offsets) = src_reg.get_double(i);
-                                let mut offset = u64::from_be_bytes(offsets);
-                                (offset, _) = offset.overflowing_shl(small_shift);
-                                dst_reg.set_double(i, bases, offset.to_be_bytes());
+                match cond {
+                    Condition::Eq => match self.zero.clone().expect("Need zero flag set") {
+                        FlagValue::Real(b) => {
+                            if b == true {
+                                self.set_register(&dest, RegisterKind::Immediate, None, 1);
+                            } else {
+                                self.set_register(&dest, RegisterKind::Immediate, None, 0);
                             }
                         }
-                        _ => todo!("unsupported shl vector type"),
-                    }
+                        FlagValue::Abstract(a) => {
+                            let opt1 = RegisterValue {
+                                kind: RegisterKind::Immediate,
+                                base: None,
+                                offset: 1,
+                            };
+                            let opt2 = RegisterValue {
+                                kind: RegisterKind::Immediate,
+                                base: None,
+                                offset: 1,
+                            };
 
-                    self.set_simd_register(dst, dst_reg);
+                            return Ok(ExecuteReturnType::Select(a, dest.clone(), opt1, opt2));
+                        }
+                    },
+                    _ => todo!("unsupported condition code for csetm {:?}", cond),
                 }
-                "aese" | "aesmc" | "movi" | "ext" | "ushr" | "dup" | "sshr" | "pmull"
-                | "pmull2" | "zip1" | "zip2" | "trn1" | "trn2" | "bit" | "uaddl" | "uaddl2"
-                | "sqrshrun" | "sqrshrun2" | "umull" | "umull2" | "umlal" | "umlal2" | "rshrn"
-                | "rshrn2" => {
-                    let mut reg_iter = instruction.operands.iter();
+            }
+            Opcode::Ldr | Opcode::Ldrb | Opcode::Ldrh => {
+                // TODO: split, have to rewrite load or do post-processing after load to extract meaningful byte
+                let mut reg_iter = instruction.operands.iter();
 
-                    let dst = reg_iter.next().expect("Need destination register");
-                    self.set_register(dst, RegisterKind::Number, None, 0);
-                }
-                a => todo!("simd arithmetic instruction not supported yet {:?}", a),
-            },
-            InstructionType::SIMDManagement => match instruction.opcode.as_str() {
-                "ld1r" => {
-                    // ld1r {v1.16b}, [x5]
-                    // load value from register and replicate it across all channels
-                    let mut reg_iter = instruction.operands.iter();
+                let dst = reg_iter.next().expect("ldr dst");
+                let src_addr = reg_iter.next().expect("ldr src");
 
-                    let dst = reg_iter.next().expect("Need destination register");
-                    if let Some(Operand::Memory(prefix, num, _, _, _)) = reg_iter.next() {
-                        let a = self.get_register(&Operand::Register(prefix.clone(), *num));
+                let (address, writeback) = self.indexed_address(src_addr);
 
-                        match self.load_vector(dst.clone(), a) {
-                            Err(e) => return Err(e.to_string()),
-                            _ => (),
-                        }
+                let res = match src_addr {
+                    Operand::Memory(prefix, num, _, Some(_), ..) => {
+                        let table = self.get_register(&Operand::Register(prefix.clone(), *num));
+                        let size = transfer_size(&instruction.opcode, dst);
+                        self.load_table_entry(dst, table, address.clone(), size)
                     }
-                    // let new_value = self.get_register(dst);
-                }
-                // FIX: extract arrangement from opcode
-                "ld1" | "ld1.16b" | "ld1.8h" => {
-                    // multiple versions:
-                    // ld1	{v24.2d,v25.2d,v26.2d,v27.2d}, [x10]
-                    // ld1 {v0.16b}, [x2], #16
-                    let mut destinations = Vec::new();
-                    let mut addr = None;
-                    for o in instruction.operands.iter() {
-                        match o {
-                            Operand::Vector(..) => destinations.push(o),
-                            Operand::VectorRegister(..) => addr = Some(o),
-                            Operand::Memory(..) => addr = Some(o),
-                            _ => panic!("not a valid operand for instruction ld1"),
+                    _ => self.load(dst.clone(), address.clone()),
+                };
+                match res {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+
+                if let Some(base) = writeback {
+                    self.write_back(src_addr, base);
+                }
+            }
+            Opcode::Ldp => {
+                let mut reg_iter = instruction.operands.iter();
+
+                let dst1 = reg_iter.next().expect("ldr dst");
+                let dst2 = reg_iter.next().expect("ldr src");
+                let src_addr = reg_iter.next().expect("ldr src");
+
+                let (mut address, writeback) = self.indexed_address(src_addr);
+
+                // a pair of w registers is 8 bytes, of x registers 16
+                let size = transfer_size(&instruction.opcode, dst1);
+                self.check_footprint(&address, 2 * size, RegionType::READ)?;
+                let res1 = self.load(dst1.clone(), address.clone());
+                address.offset += size;
+                let res2 = self.load(dst2.clone(), address.clone());
+                match res1 {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+                match res2 {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+
+                if let Some(base) = writeback {
+                    self.write_back(src_addr, base);
+                }
+            }
+            Opcode::Str | Opcode::Strb => {
+                let mut reg_iter = instruction.operands.iter();
+
+                let dst = reg_iter.next().expect("ldr dst");
+                let src_addr = reg_iter.next().expect("ldr src");
+
+                let (address, writeback) = self.indexed_address(src_addr);
+
+                let res = self.store(dst.clone(), address.clone());
+                match res {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+
+                if let Some(base) = writeback {
+                    self.write_back(src_addr, base);
+                }
+            }
+            Opcode::Stp => {
+                let mut reg_iter = instruction.operands.iter();
+
+                let dst1 = reg_iter.next().expect("ldr dst");
+                let dst2 = reg_iter.next().expect("ldr src");
+                let src_addr = reg_iter.next().expect("ldr src");
+
+                let (mut address, writeback) = self.indexed_address(src_addr);
+
+                // a pair of w registers is 8 bytes, of x registers 16
+                let size = transfer_size(&instruction.opcode, dst1);
+                self.check_footprint(&address, 2 * size, RegionType::WRITE)?;
+                let res1 = self.store(dst1.clone(), address.clone());
+                address.offset += size;
+                let res2 = self.store(dst2.clone(), address.clone());
+                match res1 {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+                match res2 {
+                    Err(e) => return Err(e),
+                    _ => (),
+                }
+
+                if let Some(base) = writeback {
+                    self.write_back(src_addr, base);
+                }
+            }
+            Opcode::Ldxr
+            | Opcode::Ldxrb
+            | Opcode::Ldxrh
+            | Opcode::Ldxp
+            | Opcode::Ldaxr
+            | Opcode::Ldaxrb
+            | Opcode::Ldaxrh
+            | Opcode::Ldaxp
+            | Opcode::Ldar
+            | Opcode::Ldarb
+            | Opcode::Ldarh
+            | Opcode::Ldapr
+            | Opcode::Ldaprb
+            | Opcode::Ldaprh => self.load_exclusive(instruction)?,
+            Opcode::Stxr
+            | Opcode::Stxrb
+            | Opcode::Stxrh
+            | Opcode::Stxp
+            | Opcode::Stlxr
+            | Opcode::Stlxrb
+            | Opcode::Stlxrh
+            | Opcode::Stlxp
+            | Opcode::Stlr
+            | Opcode::Stlrb
+            | Opcode::Stlrh => self.store_exclusive(instruction)?,
+            opcode if opcode.atomic_operands().is_some() => self.atomic(instruction)?,
+            Opcode::Cmp => {
+                self.cmp(&instruction.operands[0], &instruction.operands[1]);
+            }
+            Opcode::Cmn => {
+                self.cmn(&instruction.operands[0], &instruction.operands[1]);
+            }
+            Opcode::Ret => {
+                let x30 = self.get_register(&Operand::Register(RePrefix::X, 30));
+                if x30.kind == RegisterKind::RegisterBase {
+                    if let Some(AbstractExpression::Abstract(address)) = x30.base {
+                        if address == "return" && x30.offset == 0 {
+                            return Ok(ExecuteReturnType::JumpLabel("return".to_string()));
+                        } else {
+                            return Ok(ExecuteReturnType::JumpLabel(address.to_string()));
                         }
                     }
-
-                    if let Some(Operand::Memory(prefix, num, offset, _, index)) = addr {
-                        let a = self.get_register(&Operand::Register(prefix.clone(), *num));
-                        for d in destinations {
-                            match self.load_vector(d.clone(), a.clone()) {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-                        }
-
-                        if *index == Some(true) {
-                            if let Some(off) = offset {
-                                self.set_register(
-                                    &Operand::Register(prefix.clone(), *num),
-                                    a.kind,
-                                    a.base,
-                                    a.offset + off,
-                                );
-                            }
-                        }
-                    } else {
-                        panic!("ld1 does not include address {:?}", instruction)
-                    };
-                }
-                "st1" | "st1.d" => {
-                    // multiple versions:
-                    // st1  {v30.8h, v31.8h}, [x0], #32
-                    // st1	{v3.4s},[x2],#16
-                    let mut sources = Vec::new();
-                    let mut addr = None;
-                    for o in instruction.operands.iter() {
-                        match o {
-                            Operand::Vector(..) => sources.push(o),
-                            Operand::VectorAccess(..) => addr = Some(o),
-                            Operand::Memory(..) => addr = Some(o),
-                            _ => panic!("not a valid operand for instruction st1"),
-                        }
+                    return Ok(ExecuteReturnType::JumpAddress(
+                        x30.offset.try_into().expect("computer4"),
+                    ));
+                } else {
+                    // back from a bl in the program
+                    if let Some(next) = self.return_from_call(&x30) {
+                        return Ok(ExecuteReturnType::JumpAddress(next as u128));
                     }
-
-                    if let Some(Operand::Memory(prefix, num, num_offset, reg_offset, indexing)) =
-                        addr
-                    {
-                        let a = self.get_register(&Operand::Register(prefix.clone(), *num));
-                        for d in sources.clone() {
-                            match self.store_vector(d.clone(), a.clone()) {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-                        }
-
-                        if *indexing == Some(true) {
-                            if let Some(i) = num_offset {
-                                self.set_register(
-                                    &Operand::Register(prefix.clone(), *num),
-                                    a.kind.clone(),
-                                    a.base.clone(),
-                                    a.offset + i,
-                                );
-                            }
-                        };
-
-                        if let Some(reg) = reg_offset {
-                            let reg = self.get_register(reg);
-                            self.set_register(
-                                &Operand::Register(prefix.clone(), *num),
-                                a.kind,
-                                a.base,
-                                a.offset + reg.offset,
-                            );
-                        };
-                    } else {
-                        panic!("str1 does not include address {:?}", instruction);
-                    };
+                    // FIX: why would this happen?
+                    return Ok(ExecuteReturnType::JumpLabel("return".to_string()));
+                    // panic!("return register not set before calling ret {:?}", x30)
                 }
-                "stp" => {
-                    let mut reg_iter = instruction.operands.iter();
-
-                    let dst1 = reg_iter.next().expect("ldr dst");
-                    let dst2 = reg_iter.next().expect("ldr src");
-                    let src_addr = reg_iter.next().expect("ldr src");
+            }
+            Opcode::Mov | Opcode::Movz | Opcode::Movk => {
+                let mut reg_iter = instruction.operands.iter().clone();
+                let reg0 = reg_iter.next().expect("Need register output for mov");
+                let reg1 = reg_iter.next().expect("Need register output for mov");
 
-                    match src_addr {
-                        Operand::Memory(w, reg_num, offset, _, mode) => {
-                            let mut address = self
-                                .get_register(&Operand::Register(w.clone(), *reg_num))
-                                .clone();
-                            address.offset = address.offset + offset.unwrap_or(0);
-
-                            let res1 = self.store_vector(dst1.clone(), address.clone());
-                            address.offset += 8;
-                            let res2 = self.store_vector(dst2.clone(), address.clone());
-                            match res1 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
-                            match res2 {
-                                Err(e) => return Err(e.to_string()),
-                                _ => (),
-                            }
+                let mut r1 = self.get_register(reg1);
 
-                            match mode {
-                                // pre
-                                Some(false) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset - offset.unwrap_or(0) - 8,
-                                    );
-                                }
-                                // post
-                                Some(true) => {
-                                    self.set_register(
-                                        &Operand::Register(w.clone(), *reg_num),
-                                        address.kind,
-                                        address.base,
-                                        address.offset,
-                                    );
-                                }
-                                None => {}
-                            }
-                        }
-                        _ => {
-                            panic!("ldr not with correct syntax for memory operand");
+                if let Some(a) = reg_iter.next() {
+                    match a {
+                        Operand::Bitwise(op, shift) => {
+                            r1 = instruction_aux::shift_imm(op, r1, *shift);
                         }
+                        _ => todo!("not sure what else can show up here"),
                     }
                 }
-                // BIG TODO: reimplement these
-                "movi" | "mov" | "fmov" | "aese" | "dup" | "ins" => {
-                    let mut reg_iter = instruction.operands.iter();
 
-                    let dst = reg_iter.next().expect("Need destination register");
-                    self.set_register(dst, RegisterKind::Number, None, 0);
-                }
-                a => todo!("simd instruction {} not supported yet", a),
-            },
-            InstructionType::Other => match instruction.opcode.as_str() {
-                "cmp" => {
-                    self.cmp(&instruction.operands[0], &instruction.operands[1]);
-                }
-                "cmn" => {
-                    self.cmn(&instruction.operands[0], &instruction.operands[1]);
-                }
-                "ret" => {
-                    let x30 = self.get_register(&Operand::Register(RePrefix::X, 30));
-                    if x30.kind == RegisterKind::RegisterBase {
-                        if let Some(AbstractExpression::Abstract(address)) = x30.base {
-                            if address == "return" && x30.offset == 0 {
-                                return Ok(ExecuteReturnType::JumpLabel("return".to_string()));
-                            } else {
-                                return Ok(ExecuteReturnType::JumpLabel(address.to_string()));
-                            }
-                        }
-                        return Ok(ExecuteReturnType::JumpAddress(
-                            x30.offset.try_into().expect("computer4"),
-                        ));
-                    } else {
-                        // FIX: why would this happen?
-                        return Ok(ExecuteReturnType::JumpLabel("return".to_string()));
-                        // panic!("return register not set before calling ret {:?}", x30)
-                    }
-                }
-                "mov" | "movz" | "movk" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let reg0 = reg_iter.next().expect("Need register output for mov");
-                    let reg1 = reg_iter.next().expect("Need register output for mov");
+                self.set_register(reg0, r1.kind, r1.base, r1.offset);
+            }
+            Opcode::Rev | Opcode::Rev32 | Opcode::Rbit => {
+                let mut reg_iter = instruction.operands.iter().clone();
+                let reg0 = reg_iter.next().expect("Need register output for rev");
+                let reg1 = reg_iter.next().expect("Need register output for rev");
 
-                    let mut r1 = self.get_register(reg1);
+                let mut r1 = self.get_register(reg1);
 
-                    if let Some(a) = reg_iter.next() {
-                        match a {
-                            Operand::Bitwise(op, shift) => {
-                                r1 = instruction_aux::shift_imm(op.to_string(), r1, *shift);
-                            }
-                            _ => todo!("not sure what else can show up here"),
-                        }
-                    }
-
-                    self.set_register(reg0, r1.kind, r1.base, r1.offset);
+                if let Some(base) = r1.base {
+                    r1.base = Some(generate_expression("rev", base, AbstractExpression::Empty));
                 }
-                "rev" | "rev32" | "rbit" => {
-                    let mut reg_iter = instruction.operands.iter().clone();
-                    let reg0 = reg_iter.next().expect("Need register output for rev");
-                    let reg1 = reg_iter.next().expect("Need register output for rev");
 
-                    let mut r1 = self.get_register(reg1);
+                r1.offset = r1.offset.swap_bytes();
+                self.set_register(reg0, r1.kind, r1.base, r1.offset);
+            }
+            // hints and barriers do not change tracked state
+            Opcode::Nop | Opcode::Hint | Opcode::Bti | Opcode::Dmb | Opcode::Dsb | Opcode::Isb => {}
+            _ => todo!("instruction not implemented yet {:?}", instruction),
+        }
 
-                    if let Some(base) = r1.base {
-                        r1.base = Some(generate_expression("rev", base, AbstractExpression::Empty));
-                    }
+        Ok(ExecuteReturnType::Next)
+    }
 
-                    r1.offset = r1.offset.swap_bytes();
-                    self.set_register(reg0, r1.kind, r1.base, r1.offset);
-                }
-                _ => todo!("other instruction not implemented yet {:?}", instruction),
-            },
-            _ => panic!(),
+    fn execute_simd(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+    ) -> Result<ExecuteReturnType, MemorySafetyError> {
+        match &instruction.opcode {
+            Opcode::Mov
+            | Opcode::Ins
+            | Opcode::Umov
+            | Opcode::Smov
+            | Opcode::Dup
+            | Opcode::Fmov => {
+                if !self.simd_move(instruction) {
+                    self.havoc(pc, instruction);
+                }
+            }
+            Opcode::Movi | Opcode::Mvni => self.vector_immediate(instruction),
+            Opcode::Ext
+            | Opcode::Zip1
+            | Opcode::Zip2
+            | Opcode::Uzp1
+            | Opcode::Uzp2
+            | Opcode::Trn1
+            | Opcode::Trn2
+            | Opcode::Rev16
+            | Opcode::Rev32
+            | Opcode::Rev64 => self.permute(instruction),
+            Opcode::Tbl | Opcode::Tbx => {
+                if !self.table_lookup(instruction) {
+                    self.havoc(pc, instruction);
+                }
+            }
+            Opcode::Addp => self.pairwise_add(instruction),
+            Opcode::Bit | Opcode::Bif | Opcode::Bsl => self.bitwise_select(instruction),
+            Opcode::Ushll
+            | Opcode::Ushll2
+            | Opcode::Sshll
+            | Opcode::Sshll2
+            | Opcode::Uxtl
+            | Opcode::Uxtl2
+            | Opcode::Sxtl
+            | Opcode::Sxtl2
+            | Opcode::Uaddl
+            | Opcode::Uaddl2
+            | Opcode::Saddl
+            | Opcode::Saddl2
+            | Opcode::Usubl
+            | Opcode::Usubl2
+            | Opcode::Ssubl
+            | Opcode::Ssubl2
+            | Opcode::Umull
+            | Opcode::Umull2
+            | Opcode::Smull
+            | Opcode::Smull2
+            | Opcode::Umlal
+            | Opcode::Umlal2
+            | Opcode::Smlal
+            | Opcode::Smlal2
+            | Opcode::Pmull
+            | Opcode::Pmull2 => self.long_lanes(instruction),
+            opcode if narrow_operation(opcode).is_some() => self.narrow_lanes(instruction),
+            opcode if binary_operation(opcode).is_some() => self.lanewise(instruction),
+            opcode if unary_operation(opcode).is_some() => self.unary_lanes(instruction),
+            opcode if shift_operation(opcode).is_some() => self.shift_lanes(instruction),
+            // the rounds mix every input bit, the result is reported and treated as unknown
+            Opcode::Aese
+            | Opcode::Aesd
+            | Opcode::Aesmc
+            | Opcode::Aesimc
+            | Opcode::Sha1c
+            | Opcode::Sha1p
+            | Opcode::Sha1m
+            | Opcode::Sha1h
+            | Opcode::Sha1su0
+            | Opcode::Sha1su1
+            | Opcode::Sha256h
+            | Opcode::Sha256h2
+            | Opcode::Sha256su0
+            | Opcode::Sha256su1
+            | Opcode::Sha512h
+            | Opcode::Sha512h2
+            | Opcode::Sha512su0
+            | Opcode::Sha512su1 => self.havoc(pc, instruction),
+            Opcode::Ld1
+            | Opcode::Ld2
+            | Opcode::Ld3
+            | Opcode::Ld4
+            | Opcode::Ld1r
+            | Opcode::Ld2r
+            | Opcode::Ld3r
+            | Opcode::Ld4r => {
+                // ld1 {v0.16b-v3.16b}, [x0], #64
+                // ld4 {v0.s, v1.s, v2.s, v3.s}[1], [x1], x2
+                // ld1r {v1.16b}, [x5]
+                if let Err(e) = self.load_structure(instruction) {
+                    return Err(e);
+                }
+            }
+            Opcode::St1 | Opcode::St2 | Opcode::St3 | Opcode::St4 => {
+                // st1 {v30.8h, v31.8h}, [x0], #32
+                // st1 {v0.d}[1], [x0], x4
+                if let Err(e) = self.store_structure(instruction) {
+                    return Err(e);
+                }
+            }
+            Opcode::Ldr | Opcode::Ldur | Opcode::Ldp => {
+                // ldr q0, [x5, #16]
+                self.transfer_vectors(instruction, RegionType::READ)?;
+            }
+            Opcode::Str | Opcode::Stur | Opcode::Stp => {
+                // str d1, [x0], #8
+                self.transfer_vectors(instruction, RegionType::WRITE)?;
+            }
+            _ if !instruction
+                .operands
+                .iter()
+                .any(|o| matches!(o, Operand::Memory(..))) =>
+            {
+                self.havoc(pc, instruction)
+            }
+            _ => todo!("simd instruction not supported yet {:?}", instruction),
         }
 
         Ok(ExecuteReturnType::Next)
     }
 }
-
-// "ushr" | "sshr" => {
-//     // FIX figure out how to do sshr over byte strings
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need source register");
-//     let reg3 = instruction.r3.clone().expect("Need immediate");
-//     let imm = self.operand(reg3);
-
-//     if let Some((_, arrange)) = reg1.split_once(".") {
-//         match arrange {
-//             "2d" => {
-//                 for i in 0..2 {
-//                     let (bases, offsets) = self.simd_registers
-//                         [get_register_index(reg2.clone())]
-//                     .get_double(i);
-//                     let mut offset = u64::from_be_bytes(offsets);
-//                     (offset, _) = offset.overflowing_shr(
-//                         imm.offset.try_into().expect("computer22"),
-//                     );
-//                     // TODO: figure out best way to modify bases
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_double(i, bases, offset.to_be_bytes());
-//                 }
-//             }
-//             "4s" => {
-//                 for i in 0..4 {
-//                     let (bases, offsets) = self.simd_registers
-//                         [get_register_index(reg2.clone())]
-//                     .get_word(i);
-//                     let mut offset = u32::from_be_bytes(offsets);
-//                     (offset, _) = offset.overflowing_shr(
-//                         imm.offset.try_into().expect("computer23"),
-//                     );
-//                     // TODO: figure out best way to modify bases
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_word(i, bases, offset.to_be_bytes());
-//                 }
-//             }
-//             _ => todo!("unsupported ushr vector type"),
-//         }
-//     }
-// }
-// "ext" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need source register");
-//     let reg3 = instruction.r3.clone().expect("Need immediate");
-//     let reg4 = instruction.r4.clone().expect("Need immediate");
-//     let imm = self.operand(reg4);
-
-//     if let Some((_, arrange)) = reg1.split_once(".") {
-//         match arrange {
-//             "8b" => {
-//                 let amt = imm.offset as usize;
-//                 assert!(amt < 8);
-//                 for i in 0..amt {
-//                     let (base, offset) = self.simd_registers
-//                         [get_register_index(reg2.clone())]
-//                     .get_byte(i);
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_byte(i, base.clone(), offset);
-//                 }
-//                 for i in amt..8 {
-//                     let (base, offset) = self.simd_registers
-//                         [get_register_index(reg3.clone())]
-//                     .get_byte(i);
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_byte(i, base.clone(), offset);
-//                 }
-//             }
-//             "16b" => {
-//                 let amt = imm.offset as usize;
-//                 assert!(amt < 16);
-//                 for i in 0..amt {
-//                     let (base, offset) = self.simd_registers
-//                         [get_register_index(reg2.clone())]
-//                     .get_byte(i);
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_byte(i, base.clone(), offset);
-//                 }
-//                 for i in amt..16 {
-//                     let (base, offset) = self.simd_registers
-//                         [get_register_index(reg3.clone())]
-//                     .get_byte(i);
-//                     let dest =
-//                         &mut self.simd_registers[get_register_index(reg1.clone())];
-//                     dest.set_byte(i, base.clone(), offset);
-//                 }
-//             }
-//             _ => todo!("unsupported ext vector type"),
-//         }
-//     }
-// }
-// "dup" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let mut reg2 = instruction.r2.clone().expect("Need source register");
-//     if reg2.contains("[") {
-//         let left_brac = reg2.find("[").expect("need left bracket");
-//         let right_brac = reg2.find("]").expect("need right bracket");
-//         let index_string = reg2
-//             .get((left_brac + 1)..right_brac)
-//             .expect("need brackets");
-//         let index = index_string
-//             .parse::<usize>()
-//             .expect("index into vector must be an integer");
-
-//         reg2 = reg2.split_at(left_brac).0.to_string();
-//         if let Some((vector1, arrangement1)) = reg1.split_once(".") {
-//             if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//                 assert!(arrangement1.contains(arrangement2));
-//                 let src = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "b" => {
-//                         let elem = src.get_byte(index);
-//                         for i in 0..16 {
-//                             dest.set_byte(i, elem.0.clone(), elem.1);
-//                         }
-//                     }
-//                     "h" => {
-//                         let elem = src.get_halfword(index);
-//                         for i in 0..8 {
-//                             dest.set_halfword(i, elem.0.clone(), elem.1);
-//                         }
-//                     }
-//                     "s" => {
-//                         let elem = src.get_word(index);
-//                         for i in 0..4 {
-//                             dest.set_word(i, elem.0.clone(), elem.1);
-//                         }
-//                     }
-//                     "d" => {
-//                         let elem = src.get_double(index);
-//                         for i in 0..2 {
-//                             dest.set_double(i, elem.0.clone(), elem.1);
-//                         }
-//                     }
-//                     _ => log::error!(
-//                         "Not a valid vector arrangement {:?}",
-//                         arrangement1
-//                     ),
-//                 }
-//             }
-//         };
-//     } else {
-//         if let Some((vector1, arrangement1)) = reg1.split_once(".") {
-//             let dest =
-//                 &mut self.simd_registers[get_register_index(vector1.to_string())];
-//             let src = &mut self.registers[get_register_index(reg2)];
-
-//             dest.set_register(
-//                 arrangement1.to_string(),
-//                 src.kind.clone(),
-//                 src.base.clone(),
-//                 src.offset as u128,
-//             );
-//         };
-//     }
-// }
-// "rev32" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need source register");
-
-//     let src = &self.simd_registers[get_register_index(reg2.clone())].clone();
-//     let dest = &mut self.simd_registers[get_register_index(reg1.clone())];
-
-//     dest.kind = src.kind.clone();
-//     for i in 0..8 {
-//         let (base, offset) = src.get_byte(7 - i);
-//         dest.set_byte(i, base, offset);
-//     }
-
-//     for i in 8..16 {
-//         let (base, offset) = src.get_byte(15 - i);
-//         dest.set_byte(i, base, offset);
-//     }
-// }
-// "ins" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let mut reg2 = instruction.r2.clone().expect("Need source register");
-
-//     // vector, element
-//     if reg2.contains("v") {
-//         let left_brac = reg2.find("[").expect("need left bracket");
-//         let right_brac = reg2.find("]").expect("need right bracket");
-//         let index_string = reg2
-//             .get((left_brac + 1)..right_brac)
-//             .expect("need brackets");
-//         let index = index_string
-//             .parse::<usize>()
-//             .expect("index into vector must be an integer");
-
-//         reg2 = reg2.split_at(left_brac).0.to_string();
-//         if let Some((vector1, arrangement1)) = reg1.split_once(".") {
-//             if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//                 assert!(arrangement1.contains(arrangement2));
-//                 let src = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "b" => {
-//                         let (base, offset) = src.get_byte(index);
-//                         dest.set_byte(index, base, offset);
-//                     }
-//                     "h" => {
-//                         let (base, offset) = src.get_halfword(index);
-//                         dest.set_halfword(index, base, offset);
-//                     }
-//                     "s" => {
-//                         let (base, offset) = src.get_word(index);
-//                         dest.set_word(index, base, offset);
-//                     }
-//                     "d" => {
-//                         let (base, offset) = src.get_double(index);
-//                         dest.set_double(index, base, offset);
-//                     }
-//                     _ => log::error!(
-//                         "Not a valid vector arrangement {:?}",
-//                         arrangement1
-//                     ),
-//                 }
-//             }
-//         }
-//     // vector, general
-//     } else {
-//         todo!("vector general ins unsupported");
-//     }
-// }
-// "pmull" | "pmull2" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-//     let reg3 = instruction.r3.clone().expect("Need second source register");
-
-//     if let Some((vector1, _)) = reg1.split_once(".") {
-//         if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//             if let Some((vector3, arrangement3)) = reg3.split_once(".") {
-//                 assert_eq!(arrangement2, arrangement3);
-
-//                 let src1 = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let src2 = self.simd_registers
-//                     [get_register_index(vector3.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "8b" => {
-//                         for i in 0..8 {
-//                             let base = generate_expression_from_options(
-//                                 "*",
-//                                 src1.get_byte(i).0,
-//                                 src2.get_byte(i).0,
-//                             );
-//                             let offset = src1.get_byte(i).1 * src2.get_byte(i).1;
-
-//                             dest.set_byte(i, base, offset);
-//                         }
-//                     }
-//                     "4h" => {
-//                         for i in 0..8 {
-//                             let (bases1, offsets1) = self.simd_registers
-//                                 [get_register_index(reg2.clone())]
-//                             .get_halfword(i);
-
-//                             let (bases2, offsets2) = self.simd_registers
-//                                 [get_register_index(reg3.clone())]
-//                             .get_halfword(i);
-//                             let a = u16::from_be_bytes(offsets1);
-//                             let b = u16::from_be_bytes(offsets2);
-//                             let offset = a * b;
-
-//                             let mut new_bases = [BASE_INIT; 2];
-//                             for i in 0..2 {
-//                                 new_bases[i] = generate_expression_from_options(
-//                                     "*",
-//                                     bases1[i].clone(),
-//                                     bases2[i].clone(),
-//                                 );
-//                             }
-
-//                             let dest = &mut self.simd_registers
-//                                 [get_register_index(reg1.clone())];
-//                             dest.set_halfword(i, new_bases, offset.to_be_bytes());
-//                         }
-//                     }
-//                     _ => todo!("pmull unsupported vector access"),
-//                 }
-//             }
-//         }
-//     }
-// }
-// "zip1" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-//     let reg3 = instruction.r3.clone().expect("Need second source register");
-
-//     if let Some((vector1, _)) = reg1.split_once(".") {
-//         if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//             if let Some((vector3, arrangement3)) = reg3.split_once(".") {
-//                 assert_eq!(arrangement2, arrangement3);
-
-//                 let src1 = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let src2 = self.simd_registers
-//                     [get_register_index(vector3.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "2d" => {
-//                         let elem = src1.get_double(0);
-//                         dest.set_double(0, elem.0, elem.1);
-
-//                         let elem = src2.get_double(0);
-//                         dest.set_double(1, elem.0, elem.1);
-//                     }
-//                     "16b" => {
-//                         for i in 0..16 {
-//                             if i % 2 == 0 {
-//                                 let elem = src1.get_byte(0);
-//                                 dest.set_byte(i, elem.0, elem.1);
-//                             } else {
-//                                 let elem = src2.get_byte(0);
-//                                 dest.set_byte(i, elem.0, elem.1);
-//                             }
-//                         }
-//                     }
-//                     _ => todo!("zip1 unsupported vector access"),
-//                 }
-//             }
-//         }
-//     }
-// }
-// "zip2" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-//     let reg3 = instruction.r3.clone().expect("Need second source register");
-
-//     if let Some((vector1, _)) = reg1.split_once(".") {
-//         if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//             if let Some((vector3, arrangement3)) = reg3.split_once(".") {
-//                 assert_eq!(arrangement2, arrangement3);
-
-//                 let src1 = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let src2 = self.simd_registers
-//                     [get_register_index(vector3.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "2d" => {
-//                         let elem = src1.get_double(1);
-//                         dest.set_double(0, elem.0, elem.1);
-
-//                         let elem = src2.get_double(1);
-//                         dest.set_double(1, elem.0, elem.1);
-//                     }
-//                     "16b" => {
-//                         //FIX
-//                         for i in 0..16 {
-//                             if i % 2 == 1 {
-//                                 let elem = src1.get_byte(0);
-//                                 dest.set_byte(i, elem.0, elem.1);
-//                             } else {
-//                                 let elem = src2.get_byte(0);
-//                                 dest.set_byte(i, elem.0, elem.1);
-//                             }
-//                         }
-//                     }
-//                     _ => todo!("zip2 unsupported vector access"),
-//                 }
-//             }
-//         }
-//     }
-// }
-// "aese" | "aesmc" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-
-//     let src = &self.simd_registers[get_register_index(reg2.clone())].clone();
-//     let dest = &mut self.simd_registers[get_register_index(reg1.clone())];
-
-//     match (src.kind.clone(), dest.kind.clone()) {
-//         (RegisterKind::Number, RegisterKind::Number) => {
-//             // don't need to do anything
-//             ()
-//         }
-//         _ => todo!(),
-//     }
-// }
-// "trn1" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-//     let reg3 = instruction.r3.clone().expect("Need second source register");
-
-//     if let Some((vector1, _)) = reg1.split_once(".") {
-//         if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//             if let Some((vector3, arrangement3)) = reg3.split_once(".") {
-//                 assert_eq!(arrangement2, arrangement3);
-
-//                 let src1 = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let src2 = self.simd_registers
-//                     [get_register_index(vector3.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "2d" => {
-//                         let elem = src1.get_double(0);
-//                         dest.set_double(0, elem.0, elem.1);
-
-//                         let elem = src2.get_double(0);
-//                         dest.set_double(1, elem.0, elem.1);
-//                     }
-//                     _ => todo!("trn1 unsupported vector access"),
-//                 }
-//             }
-//         }
-//     }
-// }
-// "trn2" => {
-//     let reg1 = instruction.r1.clone().expect("Need dst register");
-//     let reg2 = instruction.r2.clone().expect("Need first source register");
-//     let reg3 = instruction.r3.clone().expect("Need second source register");
-
-//     if let Some((vector1, _)) = reg1.split_once(".") {
-//         if let Some((vector2, arrangement2)) = reg2.split_once(".") {
-//             if let Some((vector3, arrangement3)) = reg3.split_once(".") {
-//                 assert_eq!(arrangement2, arrangement3);
-
-//                 let src1 = self.simd_registers
-//                     [get_register_index(vector2.to_string())]
-//                 .clone();
-//                 let src2 = self.simd_registers
-//                     [get_register_index(vector3.to_string())]
-//                 .clone();
-//                 let dest = &mut self.simd_registers
-//                     [get_register_index(vector1.to_string())];
-
-//                 match arrangement2 {
-//                     "2d" => {
-//                         let elem = src1.get_double(1);
-//                         dest.set_double(0, elem.0, elem.1);
-
-//                         let elem = src2.get_double(1);
-//                         dest.set_double(1, elem.0, elem.1);
-//                     }
-//                     _ => todo!("trn2 unsupported vector access"),
-//                 }
-//             }
-//         }
-//     }
-// }
-// "bit" | "uaddl" | "uaddl2" | "sqrshrun" | "sqrshrun2" | "umull" | "umull2"
-// | "umlal" | "umlal2" | "rshrn" | "rshrn2" => {
-// let dest = instruction.r1.clone().expect("need dest");
-// let reg = &mut self.simd_registers[get_register_index(dest.to_string())];
-// reg.set(
-//     "16b".to_string(),
-//     RegisterKind::Number,
-//     [BASE_INIT; 16],
-//     [0; 16],
-// );
-// },
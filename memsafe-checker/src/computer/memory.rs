@@ -6,57 +6,9 @@ impl<'ctx> ARMCORTEXA<'_> {
      * address: register with address as value
      */
     pub fn load(&mut self, t: Operand, address: RegisterValue) -> Result<(), MemorySafetyError> {
-        let res = self.mem_safe_access(
-            address.base.clone().expect("Need a name for region"),
-            address.offset,
-            RegionType::READ,
-        );
-
-        if res.is_ok() {
-            if let Some(AbstractExpression::Abstract(base)) = address.base {
-                let (region_name, offset) = self.get_memory_pointer(base.clone(), address.offset);
-                let region = self
-                    .memory
-                    .get(&region_name)
-                    .expect(format!("Need memory region to load from {:?}", region_name).as_str());
-                match region.get(offset) {
-                    Some(v) => {
-                        self.set_register(&t, v.kind.clone(), v.base.clone(), v.offset);
-                        self.rw_queue.push(MemoryAccess {
-                            kind: RegionType::READ,
-                            base: base.clone(),
-                            offset: address.offset,
-                        });
-                        log::info!(
-                            "Load from address {:?} + {}",
-                            base.clone(),
-                            address.offset.clone()
-                        );
-                        return Ok(());
-                    }
-                    None => {
-                        log::error!("No element at this address in region {:?}", region);
-                        return Err(MemorySafetyError::new(
-                            "Cannot read element at this address from region",
-                        ));
-                    }
-                }
-            } else {
-                log::info!(
-                    "Loading from an abstract but safe region of memory {:?}",
-                    address
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::READ,
-                    base: address.base.expect("Need base").to_string(),
-                    offset: address.offset,
-                });
-                self.set_register(&t, RegisterKind::Number, None, 0);
-                Ok(())
-            }
-        } else {
-            return res;
-        }
+        let v = self.read_memory(address)?;
+        self.set_register(&t, v.kind, v.base, v.offset);
+        Ok(())
     }
 
     pub fn load_vector(
@@ -64,57 +16,71 @@ impl<'ctx> ARMCORTEXA<'_> {
         t: Operand,
         address: RegisterValue,
     ) -> Result<(), MemorySafetyError> {
-        let res = self.mem_safe_access(
-            address.base.clone().expect("Need a name for region"),
-            address.offset,
-            RegionType::READ,
-        );
+        self.load(t, address)
+    }
 
-        if res.is_ok() {
-            if let Some(AbstractExpression::Abstract(base)) = address.base {
-                let (region_name, offset) = self.get_memory_pointer(base.clone(), address.offset);
-                let region = self
-                    .memory
-                    .get(&region_name)
-                    .expect(format!("Need memory region to load from {:?}", region_name).as_str());
-                match region.get(offset) {
-                    Some(v) => {
-                        self.set_register(&t, v.kind.clone(), v.base, v.offset);
-                        self.rw_queue.push(MemoryAccess {
-                            kind: RegionType::READ,
-                            base: base.clone(),
-                            offset: address.offset,
-                        });
-                        log::info!(
-                            "Load from address {:?} + {}",
-                            base.clone(),
-                            address.offset.clone()
-                        );
-                        return Ok(());
-                    }
-                    None => {
-                        log::error!("No element at this address in region {:?}", region);
-                        return Err(MemorySafetyError::new(
-                            "Cannot read element at this address from region",
-                        ));
-                    }
-                }
-            } else {
-                log::info!(
-                    "Loading from an abstract but safe region of memory {:?}",
-                    address
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::READ,
-                    base: address.base.expect("Need base").to_string(),
-                    offset: address.offset,
-                });
-                self.set_register(&t, RegisterKind::Number, None, 0);
-                Ok(())
-            }
-        } else {
-            return res;
+    /*
+     * a load at an index only the solver knows from a table of static data, i.e. a jump table,
+     * gives any of the table's entries, which run from the label table is at to the next
+     * address: the indexed entry, all size bytes of it have to be in bounds
+     */
+    pub fn load_table_entry(
+        &mut self,
+        t: &Operand,
+        table: RegisterValue,
+        address: RegisterValue,
+        size: i64,
+    ) -> Result<(), MemorySafetyError> {
+        self.check_footprint(&address, size, RegionType::READ)?;
+        if table.base != Some(AbstractExpression::Abstract("memory".to_string())) {
+            return self.load(t.clone(), address);
+        }
+        let start = table.offset;
+        let end = self
+            .memory_labels
+            .values()
+            .filter(|a| **a > start)
+            .min()
+            .copied()
+            .unwrap_or(i64::MAX);
+        let memory = self.memory.get("memory").expect("memory should exist");
+        let mut entries: Vec<(i64, RegisterValue)> = memory
+            .content
+            .iter()
+            .filter(|(a, _)| start <= **a && **a < end)
+            .map(|(a, v)| (*a, v.clone()))
+            .collect();
+        if entries.is_empty() {
+            return self.load(t.clone(), address);
         }
+        entries.sort_by_key(|(a, _)| *a);
+
+        let entry = self.fresh_value(&format!("table_{}_entry", start));
+        let value = ast::Int::new_const(self.context, entry.clone());
+        let choices: Vec<ast::Bool> = entries
+            .into_iter()
+            .map(|(_, v)| {
+                let v = expression_to_ast(self.context, AbstractExpression::Register(Box::new(v)))
+                    .expect("computer32");
+                value._eq(&v)
+            })
+            .collect();
+        self.solver.assert(&ast::Bool::or(
+            self.context,
+            &choices.iter().collect::<Vec<_>>(),
+        ));
+        self.rw_queue.push(MemoryAccess {
+            kind: RegionType::READ,
+            base: "memory".to_string(),
+            offset: start,
+        });
+        self.set_register(
+            t,
+            RegisterKind::RegisterBase,
+            Some(AbstractExpression::Abstract(entry)),
+            0,
+        );
+        Ok(())
     }
 
     /*
@@ -126,95 +92,269 @@ impl<'ctx> ARMCORTEXA<'_> {
         register: Operand,
         address: RegisterValue,
     ) -> Result<(), MemorySafetyError> {
-        let region = address.base.clone();
-        let res = self.mem_safe_access(
-            region.clone().expect("Need region base"),
-            address.offset,
-            RegionType::WRITE,
-        );
+        let value = self.get_register(&register);
+        self.write_memory(address, value)
+    }
 
-        if res.is_ok() {
-            if let Some(AbstractExpression::Abstract(base)) = region.clone() {
-                let (region, offset) = self.get_memory_pointer(base.clone(), address.offset);
+    pub fn store_vector(
+        &mut self,
+        register: Operand,
+        address: RegisterValue,
+    ) -> Result<(), MemorySafetyError> {
+        self.store(register, address)
+    }
 
-                let register = &self.get_register(&register);
-                let region = self.memory.get_mut(&region).expect("No region");
-                region.insert(offset.clone(), register.clone());
+    /*
+     * address: register with address as value
+     * returns the value at that address, an unknown number if the region is abstract
+     */
+    pub fn read_memory(
+        &mut self,
+        address: RegisterValue,
+    ) -> Result<RegisterValue, MemorySafetyError> {
+        self.mem_safe_access(
+            address.base.clone().expect("Need a name for region"),
+            address.offset,
+            RegionType::READ,
+        )?;
 
-                log::info!(
-                    "Store to address {:?} + {}",
-                    base.clone(),
-                    address.offset.clone()
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::WRITE,
-                    base,
-                    offset: address.offset,
-                });
-                return Ok(());
-            } else {
-                log::info!(
-                    "Storing from an abstract but safe region of memory {:?}",
-                    address
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::WRITE,
-                    base: address.base.expect("Need base").to_string(),
-                    offset: address.offset,
-                });
-                Ok(())
+        if let Some(AbstractExpression::Abstract(base)) = address.base {
+            let (region_name, offset) = self.get_memory_pointer(base.clone(), address.offset);
+            let region = self
+                .memory
+                .get(&region_name)
+                .expect(format!("Need memory region to load from {:?}", region_name).as_str());
+            match region.get(offset) {
+                Some(v) => {
+                    self.rw_queue.push(MemoryAccess {
+                        kind: RegionType::READ,
+                        base: base.clone(),
+                        offset: address.offset,
+                    });
+                    log::info!(
+                        "Load from address {:?} + {}",
+                        base.clone(),
+                        address.offset.clone()
+                    );
+                    Ok(v)
+                }
+                None => {
+                    log::error!("No element at this address in region {:?}", region);
+                    Err(MemorySafetyError::new(
+                        "Cannot read element at this address from region",
+                    ))
+                }
             }
         } else {
-            return res;
+            log::info!(
+                "Loading from an abstract but safe region of memory {:?}",
+                address
+            );
+            self.rw_queue.push(MemoryAccess {
+                kind: RegionType::READ,
+                base: address.base.expect("Need base").to_string(),
+                offset: address.offset,
+            });
+            Ok(RegisterValue::new(RegisterKind::Number, None, 0))
         }
     }
 
-    pub fn store_vector(
+    /*
+     * address: where to store
+     * value: what to store there
+     */
+    pub fn write_memory(
         &mut self,
-        register: Operand,
         address: RegisterValue,
+        value: RegisterValue,
     ) -> Result<(), MemorySafetyError> {
         let region = address.base.clone();
-        let res = self.mem_safe_access(
+        self.mem_safe_access(
             region.clone().expect("Need region base"),
             address.offset,
             RegionType::WRITE,
-        );
-
-        if res.is_ok() {
-            if let Some(AbstractExpression::Abstract(base)) = region.clone() {
-                let (region, offset) = self.get_memory_pointer(base.clone(), address.offset);
+        )?;
 
-                let register = self.get_register(&register);
-                let region = self.memory.get_mut(&region).expect("No region");
-                region.insert(offset.clone(), register.clone());
+        if let Some(AbstractExpression::Abstract(base)) = region {
+            let (region, offset) = self.get_memory_pointer(base.clone(), address.offset);
 
-                log::info!(
-                    "Store to address {:?} + {}",
-                    base.clone(),
-                    address.offset.clone()
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::WRITE,
-                    base,
-                    offset: address.offset,
-                });
-                return Ok(());
-            } else {
-                log::info!(
-                    "Storing from an abstract but safe region of memory {:?}",
-                    address
-                );
-                self.rw_queue.push(MemoryAccess {
-                    kind: RegionType::WRITE,
-                    base: address.base.expect("Need base").to_string(),
-                    offset: address.offset,
-                });
-                Ok(())
+            for (alias, aliasing) in self.aliases_of(&region) {
+                let alias = self.memory.get_mut(&alias).expect("No region");
+                match aliasing {
+                    Aliasing::Exact => alias.insert(offset, value.clone()),
+                    Aliasing::May => alias.content.clear(),
+                }
             }
+            let region = self.memory.get_mut(&region).expect("No region");
+            region.insert(offset, value);
+
+            log::info!(
+                "Store to address {:?} + {}",
+                base.clone(),
+                address.offset.clone()
+            );
+            self.rw_queue.push(MemoryAccess {
+                kind: RegionType::WRITE,
+                base,
+                offset: address.offset,
+            });
         } else {
-            return res;
+            log::info!(
+                "Storing from an abstract but safe region of memory {:?}",
+                address
+            );
+            // where in the region is not known, nor what the regions overlapping it hold
+            let written = region
+                .iter()
+                .flat_map(|r| r.get_abstracts())
+                .collect::<Vec<_>>();
+            for r in written {
+                for (alias, _) in self.aliases_of(&r) {
+                    if let Some(alias) = self.memory.get_mut(&alias) {
+                        alias.content.clear();
+                    }
+                }
+            }
+            self.rw_queue.push(MemoryAccess {
+                kind: RegionType::WRITE,
+                base: address.base.expect("Need base").to_string(),
+                offset: address.offset,
+            });
         }
+        Ok(())
+    }
+
+    /*
+     * checks every byte of a size byte access starting at address, i.e. the 64 bytes of
+     * ld1 {v0.16b-v3.16b}, [x0]
+     */
+    pub fn check_footprint(
+        &self,
+        address: &RegisterValue,
+        size: i64,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        let base = address.base.clone().expect("Need a name for region");
+        self.mem_safe_access_sized(base.clone(), address.offset, size, ty)?;
+        self.mem_safe_access(base, address.offset + size - 1, ty)
+    }
+
+    /*
+     * checks the bytes from start up to end when only the solver knows how many there are,
+     * i.e. the active lanes of ld1b {z0.b}, p0/z, [x0, x1], the byte at end is not accessed
+     */
+    pub fn check_range(
+        &self,
+        start: AbstractExpression,
+        end: AbstractExpression,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        self.mem_safe_access(start, 0, ty)?;
+        self.mem_safe_access(end, -1, ty)
+    }
+
+    // the address a load or store accesses, and the base register after post indexing
+    pub fn indexed_address(&mut self, memory: &Operand) -> (RegisterValue, Option<RegisterValue>) {
+        let Operand::Memory(prefix, num, offset, _, mode, _) = memory else {
+            panic!("load or store needs an address {:?}", memory)
+        };
+        let start = self.get_register(&Operand::Register(prefix.clone(), *num));
+        let mut moved = RegisterValue {
+            offset: start.offset + offset.unwrap_or(0),
+            ..start.clone()
+        };
+        // a register offset is added to the address, a post-index register only to the base
+        if let (None, Some(index)) = (mode, self.scaled_index(memory)) {
+            moved.offset += index.offset;
+            if index.base.is_some() {
+                moved.base = generate_expression_from_options("+", moved.base, index.base);
+            }
+        }
+        match mode {
+            Some(true) => (start, Some(moved)),
+            // the base moves first, so a push to the stack allocates what it writes
+            Some(false) => {
+                self.write_back(memory, moved.clone());
+                (moved, None)
+            }
+            None => (moved, None),
+        }
+    }
+
+    // the index of a register offset after its extend and shift, the x1 * 8 of [x0, x1, lsl #3]
+    pub fn scaled_index(&mut self, memory: &Operand) -> Option<RegisterValue> {
+        let Operand::Memory(_, _, _, Some(index), _, extend) = memory else {
+            return None;
+        };
+        let index = self.get_register(index);
+        let (shift, amount) = extend.unwrap_or((Shift::Lsl, 0));
+        if index.base.is_none() {
+            let value = match shift {
+                Shift::Uxtw => index.offset as u32 as i64,
+                Shift::Sxtw => index.offset as i32 as i64,
+                _ => index.offset,
+            };
+            return Some(RegisterValue::new(index.kind, None, value << amount));
+        }
+        let scaled = generate_expression(
+            "*",
+            AbstractExpression::Register(Box::new(index)),
+            AbstractExpression::Immediate(1 << amount),
+        );
+        Some(RegisterValue::new(RegisterKind::Number, Some(scaled), 0))
+    }
+
+    pub fn write_back(&mut self, memory: &Operand, base: RegisterValue) {
+        if let Operand::Memory(prefix, num, ..) = memory {
+            self.set_register(
+                &Operand::Register(prefix.clone(), *num),
+                base.kind,
+                base.base,
+                base.offset,
+            );
+        }
+    }
+
+    // the region an address operand points into, and the offset there if it is known exactly
+    pub fn accessed_location(&mut self, address: &Operand) -> (Option<String>, Option<i64>) {
+        let Operand::Memory(prefix, n, immediate, index, post, _) = address else {
+            return (None, None);
+        };
+        let value = self.get_register(&Operand::Register(prefix.clone(), *n));
+        let region = value.base.as_ref().and_then(|b| {
+            b.get_abstracts()
+                .into_iter()
+                .find(|a| self.memory.contains_key(a))
+        });
+        let offset = match (&value.base, index) {
+            (Some(AbstractExpression::Abstract(b)), None) if Some(b) == region.as_ref() => {
+                match post {
+                    Some(true) => Some(value.offset),
+                    _ => Some(value.offset + immediate.unwrap_or(0)),
+                }
+            }
+            _ => None,
+        };
+        (region, offset)
+    }
+
+    // the regions may overlap, both are named by the abstract base they start at
+    pub fn add_alias(&mut self, a: String, b: String, aliasing: Aliasing) {
+        self.share_initialisation(&a, &b);
+        self.share_secrets(&a, &b);
+        self.aliases.push((a, b, aliasing));
+    }
+
+    // regions overlapping the region, and how
+    pub fn aliases_of(&self, region: &str) -> Vec<(String, Aliasing)> {
+        self.aliases
+            .iter()
+            .filter_map(|(a, b, aliasing)| match region {
+                r if r == a => Some((b.clone(), *aliasing)),
+                r if r == b => Some((a.clone(), *aliasing)),
+                _ => None,
+            })
+            .collect()
     }
 
     fn get_memory_pointer(&self, base: String, offset: i64) -> (String, i64) {
@@ -231,16 +371,27 @@ impl<'ctx> ARMCORTEXA<'_> {
 
     // SAFETY CHECKS
     fn mem_safe_access(
+        &self,
+        base_expr: AbstractExpression,
+        offset: i64,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        self.mem_safe_access_sized(base_expr, offset, 1, ty)
+    }
+
+    // size bytes are accessed, they have to be in the same row of a 2D region
+    fn mem_safe_access_sized(
         &self,
         base_expr: AbstractExpression,
         mut offset: i64,
+        size: i64,
         ty: RegionType,
     ) -> Result<(), MemorySafetyError> {
-        let mut symbolic_base = false;
-        let (region, base, base_access) = match base_expr.clone() {
+        let (name, region, base, base_access) = match base_expr.clone() {
             AbstractExpression::Abstract(regbase) => {
                 if let Some(region) = self.memory.get(&regbase.clone()) {
                     (
+                        regbase.clone(),
                         region,
                         ast::Int::new_const(self.context, regbase.clone()),
                         ast::Int::new_const(self.context, regbase),
@@ -249,6 +400,7 @@ impl<'ctx> ARMCORTEXA<'_> {
                     if let Some(address) = self.memory_labels.get(&regbase.clone()) {
                         offset = offset + address;
                         (
+                            regbase.clone(),
                             self.memory
                                 .get(&"memory".to_string())
                                 .expect("memory should exist"),
@@ -261,13 +413,17 @@ impl<'ctx> ARMCORTEXA<'_> {
                 }
             }
             _ => {
-                symbolic_base = true;
                 let abstracts = base_expr.get_abstracts();
-                let mut result: Option<(&MemorySafeRegion, z3::ast::Int<'_>, z3::ast::Int<'_>)> =
-                    None;
+                let mut result: Option<(
+                    String,
+                    &MemorySafeRegion,
+                    z3::ast::Int<'_>,
+                    z3::ast::Int<'_>,
+                )> = None;
                 for r in self.memory.keys() {
                     if abstracts.contains(r) {
                         result = Some((
+                            r.clone(),
                             self.memory.get(r).expect("Region not in memory 2"),
                             expression_to_ast(
                                 self.context,
@@ -284,6 +440,7 @@ impl<'ctx> ARMCORTEXA<'_> {
                             if let Some(address) = self.memory_labels.get(&r.0.clone()) {
                                 offset = *address;
                                 result = Some((
+                                    r.0.clone(),
                                     self.memory.get("memory").expect("Region not in memory 2"),
                                     expression_to_ast(
                                         self.context,
@@ -312,38 +469,73 @@ impl<'ctx> ARMCORTEXA<'_> {
             }
         };
 
+        if name == "sp" {
+            return self.stack_access(base_expr, offset, size);
+        }
+
+        // static data and code are laid out by address in memory
+        let static_data = self
+            .memory
+            .get("memory")
+            .is_some_and(|m| std::ptr::eq(m, region));
+        if static_data && ty == RegionType::WRITE {
+            self.constant_data_access(&base_expr, offset, size)?;
+        }
+
         if ty == RegionType::WRITE && region.kind == RegionType::READ {
-            return Err(MemorySafetyError::new(&format!(
+            let mut error = MemorySafetyError::new(&format!(
                 "Access does not match region type {:#?} {:?} {:?}",
                 region.kind, ty, base_expr
-            )));
+            ));
+            error.region = Some(name);
+            return Err(error);
         }
 
-        let mut abs_offset = ast::Int::from_i64(self.context, offset);
-        if base_expr.contains("sp") {
-            abs_offset = ast::Int::from_i64(self.context, offset.abs());
-        }
-        let access = ast::Int::add(self.context, &[&base_access, &abs_offset]);
-
-        // let width = ast::Int::from_i64(self.context, 2);    // how wide is memory access, two bytes
-        let lowerbound_value = ast::Int::from_i64(self.context, 0);
-        let low_access = ast::Int::add(self.context, &[&base, &lowerbound_value]);
-        let upperbound_value =
-            expression_to_ast(self.context, region.get_length()).expect("computer26");
-        let up_access = ast::Int::add(self.context, &[&base, &upperbound_value]);
-        let l = access.lt(&low_access);
-        let u = {
-            if offset == 0 && !symbolic_base {
-                access.ge(&up_access)
-            } else {
-                access.gt(&up_access)
-            }
+        let offset_ast = ast::Int::from_i64(self.context, offset);
+        let access = ast::Int::add(self.context, &[&base_access, &offset_ast]);
+
+        let (l, u) = if let Some((stride, height)) = &region.rows {
+            // the access is in row distance / stride, and in bounds if it is in a row's first bytes
+            let stride = expression_to_ast(self.context, stride.clone()).expect("computer27");
+            let height = expression_to_ast(self.context, height.clone()).expect("computer28");
+            let width = expression_to_ast(self.context, region.get_length()).expect("computer29");
+            let distance = ast::Int::sub(self.context, &[&access, &base]);
+            let row = distance.div(&stride);
+            let column = distance.modulo(&stride);
+            let zero = ast::Int::from_i64(self.context, 0);
+            let end = ast::Int::add(
+                self.context,
+                &[&column, &ast::Int::from_i64(self.context, size)],
+            );
+            (
+                ast::Bool::or(self.context, &[&row.lt(&zero), &row.ge(&height)]),
+                end.gt(&width),
+            )
+        } else {
+            // let width = ast::Int::from_i64(self.context, 2);    // how wide is memory access, two bytes
+            let lowerbound_value = ast::Int::from_i64(self.context, 0);
+            let low_access = ast::Int::add(self.context, &[&base, &lowerbound_value]);
+            let length = match static_data {
+                true => AbstractExpression::Immediate(
+                    region.content.keys().max().map_or(0, |end| end + 8),
+                ),
+                false => region.get_length(),
+            };
+            let upperbound_value = expression_to_ast(self.context, length).expect("computer26");
+            let up_access = ast::Int::add(self.context, &[&base, &upperbound_value]);
+            // the last byte accessed is still in the region
+            let end = ast::Int::add(
+                self.context,
+                &[&access, &ast::Int::from_i64(self.context, size)],
+            );
+            (access.lt(&low_access), end.gt(&up_access))
         };
 
-        match (
+        let (lower, upper) = (
             self.solver.check_assumptions(&[l.clone()]),
             self.solver.check_assumptions(&[u.clone()]),
-        ) {
+        );
+        match (lower, upper) {
             (SatResult::Unsat, SatResult::Unsat) => {
                 log::info!("Memory safe with solver's check!");
                 log::info!("Unsat core {:?}", self.solver.get_unsat_core());
@@ -360,12 +552,147 @@ impl<'ctx> ARMCORTEXA<'_> {
                 log::info!("Memory unsafe with solver's check!");
             }
         }
-        return Err(MemorySafetyError::new(
+        let mut error = MemorySafetyError::new(
             format!(
                 "Accessing address outside allowable memory regions {:?}, {:?}",
                 base_expr, offset
             )
             .as_str(),
+        );
+        error.region = Some(name.clone());
+
+        // rerun the bound that can be broken so the model shows how
+        let broken = if lower == SatResult::Sat { l } else { u };
+        if self.solver.check_assumptions(&[broken]) == SatResult::Sat {
+            if let Some(model) = self.solver.get_model() {
+                let mut inputs = base_expr.get_abstracts();
+                inputs.extend(region.get_length().get_abstracts());
+                if let Some((stride, height)) = &region.rows {
+                    inputs.extend(stride.get_abstracts());
+                    inputs.extend(height.get_abstracts());
+                }
+                inputs.sort();
+                inputs.dedup();
+                inputs.retain(|input| *input != name);
+                for input in inputs {
+                    let value = model.eval(&ast::Int::new_const(self.context, input.clone()), true);
+                    if let Some(value) = value.and_then(|v| v.as_i64()) {
+                        error.counterexample.push((input, value));
+                    }
+                }
+            }
+        }
+        return Err(error);
+    }
+
+    // a write to data from a read-only section, or to code, at an address in memory
+    fn constant_data_access(
+        &self,
+        base_expr: &AbstractExpression,
+        address: i64,
+        size: i64,
+    ) -> Result<(), MemorySafetyError> {
+        let Some(_) = self
+            .constant_data
+            .iter()
+            .find(|(start, end)| address < *end && address + size > *start)
+        else {
+            return Ok(());
+        };
+        // named by the closest label before it
+        let (label, label_address) = self
+            .memory_labels
+            .iter()
+            .filter(|(_, a)| **a <= address)
+            .max_by(|x, y| x.1.cmp(y.1).then(y.0.cmp(x.0)))
+            .map_or(("memory".to_string(), 0), |(l, a)| (l.clone(), *a));
+        let mut error = MemorySafetyError::new(&format!(
+            "Writes to constant data at {} + {}, through {}",
+            label,
+            address - label_address,
+            base_expr
         ));
+        error.region = Some(label);
+        Err(error)
+    }
+
+    /*
+     * the stack grows down from sp on entry, the frame is what has been allocated below it by
+     * moving sp, and above it are only the arguments the caller passed on the stack
+     */
+    fn stack_access(
+        &self,
+        base_expr: AbstractExpression,
+        offset: i64,
+        size: i64,
+    ) -> Result<(), MemorySafetyError> {
+        let stack = self.memory.get("sp").expect("Stack not found");
+        let arguments = stack
+            .content
+            .keys()
+            .filter(|k| **k >= 0)
+            .map(|k| k + 8)
+            .max()
+            .unwrap_or(0);
+
+        let access = ast::Int::add(
+            self.context,
+            &[
+                &expression_to_ast(self.context, base_expr.clone()).expect("computer30"),
+                &ast::Int::from_i64(self.context, offset),
+            ],
+        );
+        let end = ast::Int::add(
+            self.context,
+            &[&access, &ast::Int::from_i64(self.context, size)],
+        );
+        let sp = AbstractExpression::Register(Box::new(self.registers[31].clone()));
+        let bottom = expression_to_ast(self.context, sp.clone()).expect("computer31");
+        let top = ast::Int::add(
+            self.context,
+            &[
+                &ast::Int::new_const(self.context, "sp"),
+                &ast::Int::from_i64(self.context, arguments),
+            ],
+        );
+
+        let bounds = [
+            (
+                access.lt(&bottom),
+                format!(
+                    "Accesses the stack below sp, which is not allocated, at {} + {}",
+                    base_expr, offset
+                ),
+            ),
+            (
+                end.gt(&top),
+                format!(
+                    "Accesses the caller's stack past the arguments passed there, at {} + {}",
+                    base_expr, offset
+                ),
+            ),
+        ];
+        for (broken, message) in bounds {
+            if self.solver.check_assumptions(std::slice::from_ref(&broken)) == SatResult::Unsat {
+                continue;
+            }
+            let mut error = MemorySafetyError::new(&message);
+            error.region = Some("sp".to_string());
+            if let Some(model) = self.solver.get_model() {
+                let mut inputs = base_expr.get_abstracts();
+                inputs.extend(sp.get_abstracts());
+                inputs.sort();
+                inputs.dedup();
+                inputs.retain(|input| input != "sp");
+                for input in inputs {
+                    let value = model.eval(&ast::Int::new_const(self.context, input.clone()), true);
+                    if let Some(value) = value.and_then(|v| v.as_i64()) {
+                        error.counterexample.push((input, value));
+                    }
+                }
+            }
+            return Err(error);
+        }
+        Ok(())
     }
 }
@@ -1,152 +1,938 @@
+use crate::computer::init::transfer_size;
 use crate::computer::*;
 
 impl<'ctx> ARMCORTEXA<'_> {
-    //TODO: generics instead
-    pub fn vector_arithmetic(
+    /*
+     * every lane is an immediate, an abstract base plus offset or an unknown number
+     * abstract lanes only survive operations the solver can express, see combine
+     */
+
+    // lane by lane, i.e. umax v0.4s, v1.4s, v2.4s or mul v0.8h, v1.8h, v2.h[3] or bic v0.4s, #0xff
+    pub fn lanewise(&mut self, instruction: &Instruction) {
+        let (op_string, op) =
+            binary_operation(&instruction.opcode).expect("need a lane by lane operation");
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let first = reg_iter.next().expect("Need first source register");
+        let arrangement = lane_arrangement(dst);
+        let (size, count) = (arrangement.element_size(), lane_count(&arrangement));
+
+        let (a, b) = match first {
+            // the immediate forms modify the destination in place
+            Operand::Immediate(imm) => {
+                let imm = shifted_immediate(*imm, reg_iter.next());
+                (
+                    self.source_lanes(dst, count),
+                    vec![RegisterValue::new_imm(imm); count],
+                )
+            }
+            _ => {
+                let second = reg_iter.next().expect("Need second source register");
+                (
+                    self.source_lanes(first, count),
+                    self.source_lanes(second, count),
+                )
+            }
+        };
+
+        let lanes = a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| combine(op_string, x, y, size, |x, y| op(x, y, size)))
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    // not, neg, abs and cnt
+    pub fn unary_lanes(&mut self, instruction: &Instruction) {
+        let (op_string, op) =
+            unary_operation(&instruction.opcode).expect("need a unary lane operation");
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let src = reg_iter.next().expect("Need source register");
+        let arrangement = lane_arrangement(dst);
+        let (size, count) = (arrangement.element_size(), lane_count(&arrangement));
+
+        let zero = RegisterValue::new_imm(0);
+        let lanes = self
+            .source_lanes(src, count)
+            .iter()
+            .map(|v| combine(op_string, &zero, v, size, |_, v| op(v, size)))
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    // shl, ushr, sshr and their rounding, accumulating and inserting forms, i.e. usra v0.2d, v1.2d, #7
+    pub fn shift_lanes(&mut self, instruction: &Instruction) {
+        let (op_string, op) =
+            shift_operation(&instruction.opcode).expect("need a shift by immediate");
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let src = reg_iter.next().expect("Need source register");
+        let Some(Operand::Immediate(shift)) = reg_iter.next() else {
+            panic!("cannot call {} without a shift amount", instruction.opcode)
+        };
+        let shift = u32::try_from(*shift).expect("shift should fit into u32");
+        let arrangement = lane_arrangement(dst);
+        let (size, count) = (arrangement.element_size(), lane_count(&arrangement));
+
+        // only the accumulating and inserting forms read the destination
+        let accumulates = matches!(
+            instruction.opcode,
+            Opcode::Usra | Opcode::Ssra | Opcode::Sri | Opcode::Sli
+        );
+        let d = self.source_lanes(dst, count);
+        let lanes = self
+            .source_lanes(src, count)
+            .iter()
+            .zip(d.iter())
+            .map(|(v, d)| {
+                if accumulates {
+                    combine("", d, v, size, |d, v| op(d, v, shift, size))
+                } else {
+                    let amount = RegisterValue::new_imm(shift as i64);
+                    combine(op_string, v, &amount, size, |v, _| op(0, v, shift, size))
+                }
+            })
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    // widening operations, the 2 forms read the upper half of the sources, i.e. umlal2 v0.4s, v1.8h, v2.8h
+    pub fn long_lanes(&mut self, instruction: &Instruction) {
+        let opcode = &instruction.opcode;
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let first = reg_iter.next().expect("Need source register");
+        let second = reg_iter.next();
+
+        let source = lane_arrangement(first).element();
+        let size = source.element_size() * 2;
+        let count = 8 / source.element_size();
+        let half = |lanes: Vec<RegisterValue>| -> Vec<RegisterValue> {
+            let start = if opcode.upper_half() { count } else { 0 };
+            lanes.into_iter().skip(start).take(count).collect()
+        };
+        let is_signed = matches!(
+            opcode,
+            Opcode::Sshll
+                | Opcode::Sshll2
+                | Opcode::Sxtl
+                | Opcode::Sxtl2
+                | Opcode::Saddl
+                | Opcode::Saddl2
+                | Opcode::Ssubl
+                | Opcode::Ssubl2
+                | Opcode::Smull
+                | Opcode::Smull2
+                | Opcode::Smlal
+                | Opcode::Smlal2
+        );
+
+        let a = half(self.source_lanes(first, 2 * count))
+            .iter()
+            .map(|v| widen(v, source.element_size(), is_signed))
+            .collect::<Vec<_>>();
+        let b = match second {
+            Some(Operand::Immediate(n)) => vec![RegisterValue::new_imm(*n); count],
+            Some(o) => half(self.source_lanes(o, 2 * count))
+                .iter()
+                .map(|v| widen(v, source.element_size(), is_signed))
+                .collect::<Vec<_>>(),
+            None => vec![RegisterValue::new_imm(0); count],
+        };
+
+        // pmull v0.1q, v1.1d, v2.1d multiplies into the whole register
+        if source == Arrangement::D {
+            let product = match (&a[0].kind, &b[0].kind) {
+                (RegisterKind::Immediate, RegisterKind::Immediate) => {
+                    let product = carryless(unsigned(&a[0], 8), unsigned(&b[0], 8)) as u128;
+                    vec![
+                        RegisterValue::new_imm(product as u64 as i64),
+                        RegisterValue::new_imm((product >> 64) as u64 as i64),
+                    ]
+                }
+                _ => vec![unknown(); 2],
+            };
+            self.write_lanes(dst, &Arrangement::D, 0, &product);
+            return;
+        }
+
+        let d = self.source_lanes(dst, count);
+        let lanes = (0..count)
+            .map(|i| match opcode {
+                Opcode::Uaddl | Opcode::Uaddl2 | Opcode::Saddl | Opcode::Saddl2 => {
+                    combine("+", &a[i], &b[i], size, |x, y| x + y)
+                }
+                Opcode::Usubl | Opcode::Usubl2 | Opcode::Ssubl | Opcode::Ssubl2 => {
+                    combine("-", &a[i], &b[i], size, |x, y| x - y)
+                }
+                Opcode::Umull | Opcode::Umull2 | Opcode::Smull | Opcode::Smull2 => {
+                    combine("*", &a[i], &b[i], size, |x, y| x.wrapping_mul(y))
+                }
+                Opcode::Umlal | Opcode::Umlal2 | Opcode::Smlal | Opcode::Smlal2 => {
+                    let product = combine("*", &a[i], &b[i], size, |x, y| x.wrapping_mul(y));
+                    combine("+", &d[i], &product, size, |x, y| x + y)
+                }
+                Opcode::Ushll | Opcode::Ushll2 | Opcode::Sshll | Opcode::Sshll2 => {
+                    combine("lsl", &a[i], &b[i], size, |x, y| x << y)
+                }
+                Opcode::Uxtl | Opcode::Uxtl2 | Opcode::Sxtl | Opcode::Sxtl2 => a[i].clone(),
+                Opcode::Pmull | Opcode::Pmull2 => combine("", &a[i], &b[i], size, carryless),
+                _ => panic!("{} is not a widening operation", opcode),
+            })
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &wider(&source), 0, &lanes);
+    }
+
+    // narrowing operations, the 2 forms write the upper half and keep the lower, i.e. rshrn2 v0.16b, v1.8h, #4
+    pub fn narrow_lanes(&mut self, instruction: &Instruction) {
+        let op = narrow_operation(&instruction.opcode).expect("need a narrowing operation");
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let src = reg_iter.next().expect("Need source register");
+        let shift = match reg_iter.next() {
+            Some(Operand::Immediate(n)) => u32::try_from(*n).expect("shift should fit into u32"),
+            _ => 0,
+        };
+
+        // the source is read as twice the destination element, it may not name an arrangement
+        let element = lane_arrangement(dst).element();
+        let source = wider(&element);
+        let (size, count) = (source.element_size(), 8 / element.element_size());
+        let register = self.get_simd_register(src);
+        let zero = RegisterValue::new_imm(0);
+        let lanes = (0..count)
+            .map(|i| register.get_lane(source.clone(), i))
+            .map(|v| combine("", &v, &zero, size, |v, _| op(v, shift, size)))
+            .collect::<Vec<_>>();
+
+        let first = if instruction.opcode.upper_half() {
+            count
+        } else {
+            0
+        };
+        self.write_lanes(dst, &element, first, &lanes);
+    }
+
+    // addp v0.4s, v1.4s, v2.4s adds neighbouring lanes of both sources, addp d0, v1.2d the two lanes of one
+    pub fn pairwise_add(&mut self, instruction: &Instruction) {
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let first = reg_iter.next().expect("Need source register");
+        let arrangement = lane_arrangement(first);
+        let (size, count) = (arrangement.element_size(), lane_count(&arrangement));
+
+        let mut concat = self.source_lanes(first, count);
+        if let Some(second) = reg_iter.next() {
+            concat.append(&mut self.source_lanes(second, count));
+        }
+        let lanes = concat
+            .chunks(2)
+            .map(|pair| combine("+", &pair[0], &pair[1], size, |x, y| x + y))
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    // bit, bif and bsl pick bits from two registers with a mask
+    pub fn bitwise_select(&mut self, instruction: &Instruction) {
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let first = reg_iter.next().expect("Need first source register");
+        let second = reg_iter.next().expect("Need second source register");
+        let arrangement = lane_arrangement(dst);
+        let count = lane_count(&arrangement);
+
+        let d = self.source_lanes(dst, count);
+        let n = self.source_lanes(first, count);
+        let m = self.source_lanes(second, count);
+        let lanes = (0..count)
+            .map(|i| {
+                if [&d[i], &n[i], &m[i]]
+                    .iter()
+                    .any(|v| v.kind != RegisterKind::Immediate)
+                {
+                    return unknown();
+                }
+                let (d, n, m) = (d[i].offset, n[i].offset, m[i].offset);
+                RegisterValue::new_imm(match instruction.opcode {
+                    Opcode::Bit => (d & !m) | (n & m),
+                    Opcode::Bif => (d & m) | (n & !m),
+                    _ => (d & n) | (!d & m),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    /*
+     * moves between lanes and general registers:
+     * dup v0.4s, w1 | dup v0.4s, v1.s[1] | ins v0.s[1], w1 | mov v0.s[1], v1.s[0] | umov w0, v1.s[1]
+     * smov x0, v1.h[2] | mov v0.16b, v1.16b | fmov d0, x1 | fmov x0, d1
+     * returns false for forms without a model, i.e. fmov with a floating point immediate
+     */
+    pub fn simd_move(&mut self, instruction: &Instruction) -> bool {
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let src = reg_iter.next().expect("Need source register");
+
+        match (&instruction.opcode, dst, src) {
+            (_, _, Operand::Immediate(_)) => return false,
+            (Opcode::Dup, Operand::Vector(_, _, a), _) => {
+                let value = self.source_lanes(src, 1).remove(0);
+                self.write_lanes(dst, &a.element(), 0, &vec![value; lane_count(a)]);
+            }
+            (_, Operand::VectorAccess(_, _, element, index), _) => {
+                let value = self.source_lanes(src, 1).remove(0);
+                let mut register = self.get_simd_register(dst);
+                register.set_lane(element.clone(), *index as usize, &value);
+                self.set_simd_register(dst, register);
+            }
+            (_, Operand::Register(..), Operand::VectorAccess(..) | Operand::Vector(..)) => {
+                let element = lane_arrangement(src).element();
+                let mut value = self.source_lanes(src, 1).remove(0);
+                if instruction.opcode == Opcode::Smov && value.kind == RegisterKind::Immediate {
+                    let size = element.element_size();
+                    value.offset = signed(unsigned(&value, size), size) as i64;
+                }
+                self.set_register(dst, value.kind, value.base, value.offset);
+            }
+            (Opcode::Mov, Operand::Vector(_, _, a), Operand::Vector(..)) => {
+                let lanes = self.source_lanes(src, lane_count(a));
+                self.write_lanes(dst, &a.element(), 0, &lanes);
+            }
+            // scalar moves write the lowest lane and clear the rest, i.e. fmov s0, w1 or mov s0, v1.s[1]
+            (_, Operand::Vector(_, _, a), _) => {
+                let value = self.source_lanes(src, 1).remove(0);
+                self.write_lanes(dst, &a.element(), 0, &[value]);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // movi v0.4s, #1, lsl #8 | mvni v0.4s, #3, msl #16 | movi v0.2d, #0xff00ff00ff00ff00
+    pub fn vector_immediate(&mut self, instruction: &Instruction) {
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let Some(Operand::Immediate(imm)) = reg_iter.next() else {
+            panic!("{} needs an immediate", instruction.opcode)
+        };
+        let mut value = shifted_immediate(*imm, reg_iter.next());
+        if instruction.opcode == Opcode::Mvni {
+            value = !value;
+        }
+
+        let arrangement = lane_arrangement(dst);
+        let lanes = vec![RegisterValue::new_imm(value); lane_count(&arrangement)];
+        self.write_lanes(dst, &arrangement.element(), 0, &lanes);
+    }
+
+    // ext, zip, uzp, trn and rev only move lanes around
+    pub fn permute(&mut self, instruction: &Instruction) {
+        let mut reg_iter = instruction.operands.iter();
+
+        let dst = reg_iter.next().expect("Need destination register");
+        let first = reg_iter.next().expect("Need source register");
+        let arrangement = lane_arrangement(dst);
+        let count = lane_count(&arrangement);
+
+        let mut lanes = self.source_lanes(first, count);
+        let mut second = Vec::new();
+        let mut amount = 0;
+        for o in reg_iter {
+            match o {
+                Operand::Immediate(n) => amount = *n as usize,
+                _ => second = self.source_lanes(o, count),
+            }
+        }
+        lanes.append(&mut second);
+
+        // index into the lanes of the first source followed by those of the second
+        let half = count / 2;
+        let per_container = match instruction.opcode {
+            Opcode::Rev16 => 2,
+            Opcode::Rev32 => 4,
+            _ => 8,
+        } / arrangement.element_size();
+        let select = |i: usize| -> usize {
+            match instruction.opcode {
+                Opcode::Ext => amount + i,
+                Opcode::Zip1 => (i % 2) * count + i / 2,
+                Opcode::Zip2 => (i % 2) * count + half + i / 2,
+                Opcode::Uzp1 => 2 * i,
+                Opcode::Uzp2 => 2 * i + 1,
+                Opcode::Trn1 if i.is_multiple_of(2) => i,
+                Opcode::Trn1 => count + i - 1,
+                Opcode::Trn2 if i.is_multiple_of(2) => i + 1,
+                Opcode::Trn2 => count + i,
+                _ => (i / per_container) * per_container + per_container - 1 - i % per_container,
+            }
+        };
+        let result = (0..count)
+            .map(|i| lanes[select(i)].clone())
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &arrangement.element(), 0, &result);
+    }
+
+    // tbl v0.16b, {v1.16b, v2.16b}, v3.16b, out of range indices give 0 or keep the lane for tbx
+    // returns false when an index is not known
+    pub fn table_lookup(&mut self, instruction: &Instruction) -> bool {
+        let dst = instruction
+            .operands
+            .first()
+            .expect("Need destination register");
+        let (indices, tables) = instruction.operands[1..]
+            .split_last()
+            .expect("Need table and index registers");
+        let count = lane_count(&lane_arrangement(dst));
+
+        let mut table = Vec::new();
+        for t in tables {
+            let register = self.get_simd_register(t);
+            table.extend((0..16).map(|i| register.get_lane(Arrangement::B, i)));
+        }
+        let indices = self.source_lanes(indices, count);
+        if indices.iter().any(|i| i.kind != RegisterKind::Immediate) {
+            return false;
+        }
+
+        let d = self.source_lanes(dst, count);
+        let lanes = indices
+            .iter()
+            .zip(d)
+            .map(|(i, d)| match table.get(i.offset as usize) {
+                Some(v) => v.clone(),
+                None if instruction.opcode == Opcode::Tbx => d,
+                None => RegisterValue::new_imm(0),
+            })
+            .collect::<Vec<_>>();
+        self.write_lanes(dst, &Arrangement::B, 0, &lanes);
+        true
+    }
+
+    // the result of an instruction without a model is an unknown number, recorded once per pc
+    pub fn havoc(&mut self, pc: usize, instruction: &Instruction) {
+        log::warn!(
+            "Result of {:?} is not modelled, assuming an unknown number",
+            instruction
+        );
+        match instruction.operands.first() {
+            Some(
+                dst @ (Operand::Vector(..)
+                | Operand::VectorRegister(..)
+                | Operand::VectorAccess(..)),
+            ) => self.set_simd_register(dst, SimdRegister::new("")),
+            Some(dst @ Operand::Register(..)) => {
+                self.set_register(dst, RegisterKind::Number, None, 0)
+            }
+            _ => (),
+        }
+        if !self.havocs.iter().any(|h| h.pc == pc) {
+            self.havocs.push(Havoc {
+                pc,
+                opcode: instruction.opcode.clone(),
+            });
+        }
+    }
+
+    // lanes of a source, lowest first; a single element, general register or immediate is repeated
+    fn source_lanes(&mut self, operand: &Operand, count: usize) -> Vec<RegisterValue> {
+        match operand {
+            Operand::Vector(..) | Operand::VectorRegister(..) => {
+                let arrangement = lane_arrangement(operand);
+                let register = self.get_simd_register(operand);
+                (0..lane_count(&arrangement))
+                    .map(|i| register.get_lane(arrangement.element(), i))
+                    .collect()
+            }
+            Operand::VectorAccess(_, _, element, index) => {
+                let value = self
+                    .get_simd_register(operand)
+                    .get_lane(element.clone(), *index as usize);
+                vec![value; count]
+            }
+            Operand::Register(..) | Operand::Immediate(_) => {
+                vec![self.get_register(operand); count]
+            }
+            _ => panic!("not a valid source for a lane operation {:?}", operand),
+        }
+    }
+
+    // writes lanes from `first` on, writing from the lowest lane clears the rest of the register
+    fn write_lanes(
         &mut self,
-        op_string: &str,
-        op_byte: impl Fn(u8, u8) -> u8,
-        op_half: impl Fn(u16, u16) -> u16,
-        op_word: impl Fn(u32, u32) -> u32,
-        op_double: impl Fn(u64, u64) -> u64,
-        instruction: &Instruction,
+        operand: &Operand,
+        element: &Arrangement,
+        first: usize,
+        lanes: &[RegisterValue],
     ) {
-        let mut reg_iter = instruction.operands.iter();
+        let mut register = if first == 0 {
+            let mut r = SimdRegister::new("");
+            r.kind = RegisterKind::Immediate;
+            r
+        } else {
+            self.get_simd_register(operand)
+        };
+        for (i, lane) in lanes.iter().enumerate() {
+            register.set_lane(element.clone(), first + i, lane);
+        }
+        self.set_simd_register(operand, register);
+    }
+
+    /*
+     * ld1-4 and ld1r-4r, i.e. ld1 {v0.16b-v3.16b}, [x0], #64 or ld2 {v0.s, v1.s}[1], [x1], x2
+     * the whole footprint of the instruction is checked before any register is written
+     */
+    pub fn load_structure(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let (elements, replicate) = instruction
+            .opcode
+            .structure()
+            .expect("need a structure load");
+        let (registers, memory) = structure_operands(instruction);
+        let address = self.structure_address(memory);
+
+        let size = structure_size(&registers, replicate);
+        self.check_footprint(&address, size, RegionType::READ)?;
+
+        for (i, register) in registers.iter().enumerate() {
+            match register {
+                Operand::VectorAccess(_, _, element, index) => {
+                    let value =
+                        self.read_memory(offset_address(&address, i * element.element_size()))?;
+                    let mut r = self.get_simd_register(register);
+                    r.set_lane(element.clone(), *index as usize, &value);
+                    self.set_simd_register(register, r);
+                }
+                Operand::Vector(_, _, a) if replicate => {
+                    self.load_vector(
+                        (*register).clone(),
+                        offset_address(&address, i * a.element_size()),
+                    )?;
+                }
+                _ if elements == 1 => {
+                    self.load_vector(
+                        (*register).clone(),
+                        offset_address(&address, i * register_size(register)),
+                    )?;
+                }
+                _ => {
+                    // elements are interleaved across registers, which memory values cannot express
+                    log::warn!("De-interleaving load {:?} into {:?}", instruction, register);
+                    self.set_register(register, RegisterKind::Number, None, 0);
+                }
+            }
+        }
+
+        self.structure_writeback(memory, address);
+        Ok(())
+    }
+
+    /*
+     * st1-4, i.e. st1 {v30.8h, v31.8h}, [x0], #32 or st1 {v0.d}[1], [x0], x4
+     */
+    pub fn store_structure(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let (elements, _) = instruction
+            .opcode
+            .structure()
+            .expect("need a structure store");
+        let (registers, memory) = structure_operands(instruction);
+        let address = self.structure_address(memory);
+
+        let size = structure_size(&registers, false);
+        self.check_footprint(&address, size, RegionType::WRITE)?;
+
+        for (i, register) in registers.iter().enumerate() {
+            match register {
+                Operand::VectorAccess(_, _, element, index) => {
+                    let value = self
+                        .get_simd_register(register)
+                        .get_lane(element.clone(), *index as usize);
+                    self.write_memory(offset_address(&address, i * element.element_size()), value)?;
+                }
+                _ if elements == 1 => {
+                    self.store_vector(
+                        (*register).clone(),
+                        offset_address(&address, i * register_size(register)),
+                    )?;
+                }
+                _ => {
+                    log::warn!("Interleaving store {:?} from {:?}", instruction, register);
+                    self.write_memory(
+                        offset_address(&address, i * register_size(register)),
+                        RegisterValue::new(RegisterKind::Number, None, 0),
+                    )?;
+                }
+            }
+        }
+
+        self.structure_writeback(memory, address);
+        Ok(())
+    }
+
+    /*
+     * ldr, ldur, str, stur and ldp of q, d, s, h and b registers, i.e. ldr q0, [x5, #16]
+     * d0 and the like are parsed as whole vectors, only their first lane is transferred
+     */
+    pub fn transfer_vectors(
+        &mut self,
+        instruction: &Instruction,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        let (registers, memory) = structure_operands(instruction);
+        let (address, writeback) = self.indexed_address(memory);
+
+        let width = match registers.first() {
+            Some(register) => transfer_size(&instruction.opcode, register) as usize,
+            None => 16,
+        };
+        self.check_footprint(&address, (width * registers.len()) as i64, ty)?;
+        for (i, register) in registers.iter().enumerate() {
+            let at = offset_address(&address, i * width);
+            if ty == RegionType::READ {
+                self.load_vector((*register).clone(), at)?;
+            } else {
+                self.store_vector((*register).clone(), at)?;
+            }
+        }
+
+        // post-index moves the base past the address
+        if let Some(base) = writeback {
+            self.write_back(memory, base);
+        }
+        Ok(())
+    }
+
+    fn structure_address(&mut self, memory: &Operand) -> RegisterValue {
+        let Operand::Memory(prefix, num, offset, _, mode, _) = memory else {
+            panic!("structure load or store needs an address {:?}", memory)
+        };
+        let mut address = self.get_register(&Operand::Register(prefix.clone(), *num));
+        if mode.is_none() {
+            address.offset += offset.unwrap_or(0);
+        }
+        address
+    }
 
-        let reg0 = reg_iter.next().expect("Need destination register");
-        let reg1 = reg_iter.next().expect("Need first source register");
-        let reg2 = reg_iter.next().expect("Need second source register");
-
-        let r0 = &mut self.get_simd_register(reg0);
-        let r1 = self.get_simd_register(reg1);
-        let r2 = self.get_simd_register(reg2);
-
-        match (reg0, reg1, reg2) {
-            (Operand::Vector(_, _, a), Operand::Vector(..), Operand::Vector(..)) => match a {
-                Arrangement::D2 => {
-                    for i in 0..2 {
-                        let (bases1, offsets1) = r1.get_double(i);
-
-                        let (bases2, offsets2) = r2.get_double(i);
-
-                        let a = u64::from_be_bytes(offsets1);
-                        let b = u64::from_be_bytes(offsets2);
-                        let offset = op_double(a, b);
-
-                        let mut new_bases = [BASE_INIT; 8];
-                        for i in 0..8 {
-                            if bases1[i].is_some() && bases2[i].is_some() {
-                                new_bases[i] = generate_expression_from_options(
-                                    op_string,
-                                    bases1[i].clone(),
-                                    bases2[i].clone(),
-                                );
-                            }
-                        }
-
-                        r0.set_double(i, new_bases, offset.to_be_bytes());
-                    }
-                }
-                Arrangement::S4 => {
-                    for i in 0..4 {
-                        let (bases1, offsets1) = r1.get_word(i);
-
-                        let (bases2, offsets2) = r2.get_word(i);
-                        let a = u32::from_be_bytes(offsets1);
-                        let b = u32::from_be_bytes(offsets2);
-                        let offset = op_word(a, b);
-
-                        let mut new_bases = [BASE_INIT; 4];
-                        for i in 0..4 {
-                            new_bases[i] = generate_expression_from_options(
-                                op_string,
-                                bases1[i].clone(),
-                                bases2[i].clone(),
-                            );
-                        }
-
-                        r0.set_word(i, new_bases, offset.to_be_bytes());
-                    }
-                }
-                Arrangement::H4 => {
-                    for i in 0..4 {
-                        let (bases1, offsets1) = r1.get_halfword(i);
-
-                        let (bases2, offsets2) = r2.get_halfword(i);
-                        let a = u16::from_be_bytes(offsets1);
-                        let b = u16::from_be_bytes(offsets2);
-                        let offset = op_half(a, b);
-
-                        let mut new_bases = [BASE_INIT; 2];
-                        for i in 0..4 {
-                            new_bases[i] = generate_expression_from_options(
-                                op_string,
-                                bases1[i].clone(),
-                                bases2[i].clone(),
-                            );
-                        }
-
-                        r0.set_halfword(i, new_bases, offset.to_be_bytes());
-                    }
-                }
-                Arrangement::H8 => {
-                    for i in 0..8 {
-                        let (bases1, offsets1) = r1.get_halfword(i);
-
-                        let (bases2, offsets2) = r2.get_halfword(i);
-                        let a = u16::from_be_bytes(offsets1);
-                        let b = u16::from_be_bytes(offsets2);
-                        let offset = op_half(a, b);
-
-                        let mut new_bases = [BASE_INIT; 2];
-                        for i in 0..2 {
-                            new_bases[i] = generate_expression_from_options(
-                                op_string,
-                                bases1[i].clone(),
-                                bases2[i].clone(),
-                            );
-                        }
-
-                        r0.set_halfword(i, new_bases, offset.to_be_bytes());
-                    }
-                }
-                Arrangement::B8 => {
-                    for i in 0..8 {
-                        let (bases1, a) = r1.get_byte(i);
-
-                        let (bases2, b) = r2.get_byte(i);
-
-                        let offset = op_byte(a, b);
-
-                        let new_base = generate_expression_from_options(
-                            op_string,
-                            bases1.clone(),
-                            bases2.clone(),
-                        );
-
-                        r0.set_byte(i, new_base, offset);
-                    }
-                }
-                Arrangement::B16 => {
-                    for i in 0..16 {
-                        let (bases1, a) = r1.get_byte(i);
-
-                        let (bases2, b) = r2.get_byte(i);
-
-                        let offset = op_byte(a, b);
-
-                        let new_base = generate_expression_from_options(
-                            op_string,
-                            bases1.clone(),
-                            bases2.clone(),
-                        );
-
-                        r0.set_byte(i, new_base, offset);
-                    }
-                }
-                _ => todo!("unsupported vector arithmetic access"),
-            },
-            (_, _, _) => todo!("more simd arithmetic support"),
+    // post-index by an immediate (always the footprint) or by a register
+    fn structure_writeback(&mut self, memory: &Operand, address: RegisterValue) {
+        let Operand::Memory(prefix, num, offset, reg_offset, Some(true), _) = memory else {
+            return;
+        };
+        let base_register = Operand::Register(prefix.clone(), *num);
+        if let Some(i) = offset {
+            self.set_register(
+                &base_register,
+                address.kind,
+                address.base,
+                address.offset + i,
+            );
+        } else if let Some(reg) = reg_offset {
+            let step = self.get_register(reg);
+            let base = match step.base {
+                Some(b) => generate_expression_from_options("+", address.base, Some(b)),
+                None => address.base,
+            };
+            self.set_register(
+                &base_register,
+                address.kind,
+                base,
+                address.offset + step.offset,
+            );
         }
     }
 }
+
+fn structure_operands(instruction: &Instruction) -> (Vec<&Operand>, &Operand) {
+    let registers = instruction
+        .operands
+        .iter()
+        .filter(|o| {
+            matches!(
+                o,
+                Operand::Vector(..) | Operand::VectorRegister(..) | Operand::VectorAccess(..)
+            )
+        })
+        .collect();
+    let memory = instruction
+        .operands
+        .iter()
+        .find(|o| matches!(o, Operand::Memory(..)))
+        .unwrap_or_else(|| {
+            panic!(
+                "{} does not include address {:?}",
+                instruction.opcode, instruction
+            )
+        });
+    (registers, memory)
+}
+
+// bytes of a register in a structure list, a q register when no arrangement is given
+fn register_size(register: &Operand) -> usize {
+    match register {
+        Operand::Vector(_, _, a) => a.size(),
+        Operand::VectorAccess(_, _, a, _) => a.element_size(),
+        _ => 16,
+    }
+}
+
+// total bytes transferred, one element per register for lane and replicate forms
+fn structure_size(registers: &[&Operand], replicate: bool) -> i64 {
+    registers
+        .iter()
+        .map(|r| match r {
+            Operand::Vector(_, _, a) if replicate => a.element_size(),
+            _ => register_size(r),
+        })
+        .sum::<usize>() as i64
+}
+
+fn offset_address(address: &RegisterValue, offset: usize) -> RegisterValue {
+    RegisterValue {
+        offset: address.offset + offset as i64,
+        ..address.clone()
+    }
+}
+
+type LaneOp = fn(i128, i128, usize) -> i128;
+type UnaryOp = fn(i128, usize) -> i128;
+type ShiftOp = fn(i128, i128, u32, usize) -> i128;
+type NarrowOp = fn(i128, u32, usize) -> i128;
+
+// operator kept for abstract lanes ("" if the solver cannot express it) and the value of the lanes
+pub fn binary_operation(opcode: &Opcode) -> Option<(&'static str, LaneOp)> {
+    let op: (&str, LaneOp) = match opcode {
+        Opcode::Add => ("+", |a, b, _| a + b),
+        Opcode::Sub => ("-", |a, b, _| a - b),
+        Opcode::Mul => ("*", |a, b, _| a.wrapping_mul(b)),
+        Opcode::Pmul => ("", |a, b, _| carryless(a, b)),
+        Opcode::And => ("", |a, b, _| a & b),
+        Opcode::Orr => ("", |a, b, _| a | b),
+        Opcode::Eor => ("", |a, b, _| a ^ b),
+        Opcode::Bic => ("", |a, b, _| a & !b),
+        Opcode::Orn => ("", |a, b, _| a | !b),
+        Opcode::Umax => ("", |a, b, _| a.max(b)),
+        Opcode::Umin => ("", |a, b, _| a.min(b)),
+        Opcode::Smax => ("", |a, b, s| signed(a, s).max(signed(b, s))),
+        Opcode::Smin => ("", |a, b, s| signed(a, s).min(signed(b, s))),
+        Opcode::Cmeq => ("", |a, b, _| all_ones(a == b)),
+        Opcode::Cmtst => ("", |a, b, _| all_ones(a & b != 0)),
+        Opcode::Cmhi => ("", |a, b, _| all_ones(a > b)),
+        Opcode::Cmhs => ("", |a, b, _| all_ones(a >= b)),
+        Opcode::Cmgt => ("", |a, b, s| all_ones(signed(a, s) > signed(b, s))),
+        Opcode::Cmge => ("", |a, b, s| all_ones(signed(a, s) >= signed(b, s))),
+        Opcode::Uqadd => ("", |a, b, s| (a + b).min(lane_mask(s))),
+        Opcode::Uqsub => ("", |a, b, _| (a - b).max(0)),
+        Opcode::Sqadd => ("", |a, b, s| saturate(signed(a, s) + signed(b, s), s)),
+        Opcode::Sqsub => ("", |a, b, s| saturate(signed(a, s) - signed(b, s), s)),
+        Opcode::Urhadd => ("", |a, b, _| (a + b + 1) >> 1),
+        Opcode::Srhadd => ("", |a, b, s| (signed(a, s) + signed(b, s) + 1) >> 1),
+        Opcode::Ushl => ("", |a, b, s| shift_by(a, signed(b & 0xff, 1), s)),
+        Opcode::Sshl => ("", |a, b, s| shift_by(signed(a, s), signed(b & 0xff, 1), s)),
+        _ => return None,
+    };
+    Some(op)
+}
+
+pub fn unary_operation(opcode: &Opcode) -> Option<(&'static str, UnaryOp)> {
+    let op: (&str, UnaryOp) = match opcode {
+        // 0 - v for abstract lanes
+        Opcode::Neg => ("-", |v, s| -signed(v, s)),
+        Opcode::Not | Opcode::Mvn => ("", |v, _| !v),
+        Opcode::Abs => ("", |v, s| signed(v, s).abs()),
+        Opcode::Cnt => ("", |v, _| v.count_ones() as i128),
+        _ => return None,
+    };
+    Some(op)
+}
+
+// (destination, source, shift, lane size)
+pub fn shift_operation(opcode: &Opcode) -> Option<(&'static str, ShiftOp)> {
+    let op: (&str, ShiftOp) = match opcode {
+        Opcode::Shl => ("lsl", |_, v, n, _| v << n),
+        Opcode::Ushr => ("", |_, v, n, _| v >> n),
+        Opcode::Sshr => ("", |_, v, n, s| signed(v, s) >> n),
+        Opcode::Urshr => ("", |_, v, n, _| (v + (1 << (n - 1))) >> n),
+        Opcode::Srshr => ("", |_, v, n, s| (signed(v, s) + (1 << (n - 1))) >> n),
+        Opcode::Usra => ("", |d, v, n, _| d + (v >> n)),
+        Opcode::Ssra => ("", |d, v, n, s| d + (signed(v, s) >> n)),
+        Opcode::Sri => ("", |d, v, n, s| (d & !(lane_mask(s) >> n)) | (v >> n)),
+        Opcode::Sli => ("", |d, v, n, _| (d & ((1 << n) - 1)) | (v << n)),
+        _ => return None,
+    };
+    Some(op)
+}
+
+// (wide source, shift, wide lane size), the result is truncated to the narrow lane
+pub fn narrow_operation(opcode: &Opcode) -> Option<NarrowOp> {
+    let op: NarrowOp = match opcode {
+        Opcode::Xtn | Opcode::Xtn2 => |v, _, _| v,
+        Opcode::Shrn | Opcode::Shrn2 => |v, n, _| v >> n,
+        Opcode::Rshrn | Opcode::Rshrn2 => |v, n, _| round_shift(v, n),
+        Opcode::Sqxtun | Opcode::Sqxtun2 => |v, _, s| signed(v, s).clamp(0, lane_mask(s / 2)),
+        Opcode::Sqshrun | Opcode::Sqshrun2 => {
+            |v, n, s| (signed(v, s) >> n).clamp(0, lane_mask(s / 2))
+        }
+        Opcode::Sqrshrun | Opcode::Sqrshrun2 => {
+            |v, n, s| round_shift(signed(v, s), n).clamp(0, lane_mask(s / 2))
+        }
+        Opcode::Uqshrn | Opcode::Uqshrn2 => |v, n, s| (v >> n).min(lane_mask(s / 2)),
+        Opcode::Uqrshrn | Opcode::Uqrshrn2 => |v, n, s| round_shift(v, n).min(lane_mask(s / 2)),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/*
+ * one lane of an operation
+ * immediates are computed, abstract lanes become an expression when op_string is one the solver
+ * understands and anything involving an unknown number is an unknown number
+ */
+fn combine(
+    op_string: &str,
+    a: &RegisterValue,
+    b: &RegisterValue,
+    size: usize,
+    op: impl Fn(i128, i128) -> i128,
+) -> RegisterValue {
+    match (&a.kind, &b.kind) {
+        (RegisterKind::Immediate, RegisterKind::Immediate) => {
+            let value = op(unsigned(a, size), unsigned(b, size)) & lane_mask(size);
+            RegisterValue::new_imm(value as u64 as i64)
+        }
+        (RegisterKind::Number, _) | (_, RegisterKind::Number) => unknown(),
+        _ if !matches!(op_string, "+" | "-" | "*" | "lsl") => unknown(),
+        // like scalar arithmetic, an offset from the same base
+        _ if matches!(op_string, "+" | "-") && b.base.is_none() => {
+            let b = signed(unsigned(b, size), size) as i64;
+            let offset = if op_string == "+" {
+                a.offset + b
+            } else {
+                a.offset - b
+            };
+            RegisterValue::new(RegisterKind::RegisterBase, a.base.clone(), offset)
+        }
+        _ => RegisterValue::new(
+            RegisterKind::RegisterBase,
+            Some(generate_expression(
+                op_string,
+                lane_expression(a),
+                lane_expression(b),
+            )),
+            0,
+        ),
+    }
+}
+
+fn lane_expression(value: &RegisterValue) -> AbstractExpression {
+    match &value.base {
+        Some(base) if value.offset == 0 => base.clone(),
+        Some(base) => generate_expression(
+            "+",
+            base.clone(),
+            AbstractExpression::Immediate(value.offset),
+        ),
+        None => AbstractExpression::Immediate(value.offset),
+    }
+}
+
+// sign or zero extend a lane to twice its size, abstract lanes are unchanged
+fn widen(value: &RegisterValue, size: usize, is_signed: bool) -> RegisterValue {
+    match value.kind {
+        RegisterKind::Immediate if is_signed => {
+            RegisterValue::new_imm(signed(unsigned(value, size), size) as i64)
+        }
+        _ => value.clone(),
+    }
+}
+
+fn unknown() -> RegisterValue {
+    RegisterValue::new(RegisterKind::Number, None, 0)
+}
+
+fn unsigned(value: &RegisterValue, size: usize) -> i128 {
+    value.offset as u64 as i128 & lane_mask(size)
+}
+
+fn signed(value: i128, size: usize) -> i128 {
+    let shift = 128 - 8 * size as u32;
+    (value << shift) >> shift
+}
+
+fn lane_mask(size: usize) -> i128 {
+    (1 << (8 * size)) - 1
+}
+
+fn all_ones(condition: bool) -> i128 {
+    if condition {
+        -1
+    } else {
+        0
+    }
+}
+
+fn saturate(value: i128, size: usize) -> i128 {
+    let max = lane_mask(size) >> 1;
+    value.clamp(-max - 1, max)
+}
+
+fn round_shift(value: i128, shift: u32) -> i128 {
+    (value + (1 << shift >> 1)) >> shift
+}
+
+// shift left by a signed amount, right for negative amounts
+fn shift_by(value: i128, shift: i128, size: usize) -> i128 {
+    let bits = 8 * size as i128;
+    match shift {
+        s if s >= bits => 0,
+        s if s >= 0 => value << s,
+        s if -s >= bits => all_ones(value < 0),
+        s => value >> -s,
+    }
+}
+
+// polynomial multiplication over GF(2), as in pmull
+fn carryless(a: i128, b: i128) -> i128 {
+    (0..64)
+        .filter(|i| b >> i & 1 == 1)
+        .fold(0, |product, i| product ^ (a << i))
+}
+
+// the arrangement a lane operation works on, a register without one is 16 bytes
+fn lane_arrangement(operand: &Operand) -> Arrangement {
+    match operand {
+        Operand::Vector(_, _, a) | Operand::VectorAccess(_, _, a, _) => a.clone(),
+        _ => Arrangement::B16,
+    }
+}
+
+// the element of twice the size, i.e. the destination of ushll v0.8h, v1.8b, #0
+fn wider(element: &Arrangement) -> Arrangement {
+    match element.element_size() {
+        1 => Arrangement::H,
+        2 => Arrangement::S,
+        _ => Arrangement::D,
+    }
+}
+
+fn lane_count(arrangement: &Arrangement) -> usize {
+    arrangement.size() / arrangement.element_size()
+}
+
+// an immediate with an optional shift, msl shifts in ones
+fn shifted_immediate(imm: i64, shift: Option<&Operand>) -> i64 {
+    match shift {
+        Some(Operand::Bitwise(Shift::Lsl, n)) => imm << n,
+        Some(Operand::Bitwise(Shift::Msl, n)) => (imm << n) | ((1 << n) - 1),
+        _ => imm,
+    }
+}
@@ -0,0 +1,412 @@
+use crate::computer::*;
+
+// sve lanes are not tracked, a z register only ever holds unknown numbers. what matters for
+// memory safety is the vector length VL, which is left to the solver, and which lanes of a
+// load or store are active
+impl ARMCORTEXA<'_> {
+    pub fn execute_sve(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+    ) -> Result<ExecuteReturnType, MemorySafetyError> {
+        let operands = &instruction.operands;
+        match &instruction.opcode {
+            Opcode::Whilelo | Opcode::Whilelt => self.while_predicate(operands, false),
+            Opcode::Whilels | Opcode::Whilele => self.while_predicate(operands, true),
+            Opcode::Ptrue | Opcode::Ptrues => {
+                let (n, size) = predicate_register(&operands[0]);
+                let predicate = match operands.get(1) {
+                    None => Predicate::All,
+                    Some(Operand::Label(pattern)) if pattern == "all" => Predicate::All,
+                    // vlN is N lanes, or none if the vector is shorter. N is an upper bound either way
+                    Some(Operand::Label(pattern)) => match pattern
+                        .strip_prefix("vl")
+                        .and_then(|n| n.parse::<i64>().ok())
+                    {
+                        Some(count) => Predicate::While(
+                            size,
+                            AbstractExpression::Immediate(0),
+                            AbstractExpression::Immediate(count),
+                        ),
+                        None => Predicate::Unknown,
+                    },
+                    Some(_) => Predicate::Unknown,
+                };
+                if instruction.opcode == Opcode::Ptrues {
+                    self.set_predicate_flags(&predicate);
+                }
+                self.predicates[n] = predicate;
+            }
+            Opcode::Pfalse => self.predicates[predicate_register(&operands[0]).0] = Predicate::None,
+            Opcode::Ptest => {
+                let (n, _) = predicate_register(&operands[1]);
+                self.set_predicate_flags(&self.predicates[n].clone());
+            }
+            Opcode::Cntb | Opcode::Cnth | Opcode::Cntw | Opcode::Cntd => {
+                match element_count(instruction) {
+                    Some(count) => {
+                        self.set_register(&operands[0], RegisterKind::RegisterBase, Some(count), 0)
+                    }
+                    None => self.havoc(pc, instruction),
+                }
+            }
+            Opcode::Incb
+            | Opcode::Inch
+            | Opcode::Incw
+            | Opcode::Incd
+            | Opcode::Decb
+            | Opcode::Dech
+            | Opcode::Decw
+            | Opcode::Decd
+                if matches!(operands[0], Operand::Register(..)) =>
+            {
+                let op = match instruction.opcode {
+                    Opcode::Incb | Opcode::Inch | Opcode::Incw | Opcode::Incd => "+",
+                    _ => "-",
+                };
+                match element_count(instruction) {
+                    Some(count) => self.add_vector_multiple(&operands[0], &operands[0], op, count),
+                    None => self.havoc(pc, instruction),
+                }
+            }
+            Opcode::Addvl | Opcode::Addpl => {
+                let Operand::Immediate(imm) = operands[2] else {
+                    return Err(MemorySafetyError::new(&format!(
+                        "{} needs an immediate {:?}",
+                        instruction.opcode, operands
+                    )));
+                };
+                let size = if instruction.opcode == Opcode::Addvl {
+                    1
+                } else {
+                    8
+                };
+                let count = generate_expression(
+                    "*",
+                    vector_lanes(size),
+                    AbstractExpression::Immediate(imm),
+                );
+                self.add_vector_multiple(&operands[0], &operands[1], "+", count);
+            }
+            Opcode::Rdvl => {
+                let Operand::Immediate(imm) = operands[1] else {
+                    return Err(MemorySafetyError::new(&format!(
+                        "rdvl needs an immediate {:?}",
+                        operands
+                    )));
+                };
+                let length =
+                    generate_expression("*", vector_lanes(1), AbstractExpression::Immediate(imm));
+                self.set_register(&operands[0], RegisterKind::RegisterBase, Some(length), 0);
+            }
+            Opcode::Ld1b
+            | Opcode::Ld1h
+            | Opcode::Ld1w
+            | Opcode::Ld1d
+            | Opcode::Ld1sb
+            | Opcode::Ld1sh
+            | Opcode::Ld1sw => {
+                self.contiguous_access(instruction, RegionType::READ)?;
+                self.set_simd_register(&operands[0], SimdRegister::new(""));
+            }
+            Opcode::St1b | Opcode::St1h | Opcode::St1w | Opcode::St1d => {
+                self.contiguous_access(instruction, RegionType::WRITE)?
+            }
+            // fills and spills of a whole z or p register, the offset is in multiples of it
+            Opcode::Ldr | Opcode::Str => {
+                let ty = match instruction.opcode {
+                    Opcode::Ldr => RegionType::READ,
+                    _ => RegionType::WRITE,
+                };
+                let size = match operands[0] {
+                    Operand::Predicate(..) => 8,
+                    _ => 1,
+                };
+                let start = self.sve_address(&operands[1], vector_lanes(size), 1)?;
+                self.check_active_lanes(start, None, vector_lanes(size), 1, ty)?;
+                match &operands[0] {
+                    Operand::Predicate(n, ..) if ty == RegionType::READ => {
+                        self.predicates[*n] = Predicate::Unknown
+                    }
+                    register if ty == RegionType::READ => {
+                        self.set_simd_register(register, SimdRegister::new(""))
+                    }
+                    _ => (),
+                }
+            }
+            Opcode::Mov if operands.iter().all(|o| matches!(o, Operand::Predicate(..))) => {
+                let (n, _) = predicate_register(&operands[0]);
+                let (m, _) = predicate_register(&operands[operands.len() - 1]);
+                self.predicates[n] = self.predicates[m].clone();
+            }
+            // prefetches never fault
+            Opcode::Unknown(s) if s.starts_with("prf") => (),
+            _ if operands.iter().any(|o| matches!(o, Operand::Memory(..))) => {
+                return Err(MemorySafetyError::new(&format!(
+                    "{} is not modelled, cannot check its accesses {:?}",
+                    instruction.opcode, instruction
+                )));
+            }
+            _ => match &operands[0] {
+                // compares and predicate logic under pg/z only leave lanes of pg active
+                Operand::Predicate(n, ..) => {
+                    self.predicates[*n] = operands[1..]
+                        .iter()
+                        .find_map(|o| match o {
+                            Operand::Predicate(g, _, Some(PredicateMode::Zeroing)) => {
+                                Some(self.predicates[*g].clone())
+                            }
+                            _ => None,
+                        })
+                        .unwrap_or(Predicate::Unknown);
+                    self.set_predicate_flags(&Predicate::Unknown);
+                }
+                register @ (Operand::Vector(..)
+                | Operand::VectorRegister(..)
+                | Operand::VectorAccess(..)) => {
+                    self.set_simd_register(register, SimdRegister::new(""))
+                }
+                _ => self.havoc(pc, instruction),
+            },
+        }
+
+        Ok(ExecuteReturnType::Next)
+    }
+
+    // whilelo p0.b, x1, x2 and friends; inclusive for whilels and whilele
+    fn while_predicate(&mut self, operands: &[Operand], inclusive: bool) {
+        let (n, size) = predicate_register(&operands[0]);
+        let index = self.get_register(&operands[1]);
+        let limit = self.get_register(&operands[2]);
+        if index.kind == RegisterKind::Number || limit.kind == RegisterKind::Number {
+            self.predicates[n] = Predicate::Unknown;
+            self.set_predicate_flags(&Predicate::Unknown);
+            return;
+        }
+
+        let mut limit = AbstractExpression::Register(Box::new(limit));
+        if inclusive {
+            limit = generate_expression("+", limit, AbstractExpression::Immediate(1));
+        }
+        let predicate =
+            Predicate::While(size, AbstractExpression::Register(Box::new(index)), limit);
+        self.set_predicate_flags(&predicate);
+        self.predicates[n] = predicate;
+    }
+
+    fn set_predicate_flags(&mut self, predicate: &Predicate) {
+        match predicate.flags() {
+            Some((neg, zero, carry)) => {
+                self.neg = Some(neg);
+                self.zero = Some(zero);
+                self.carry = Some(carry);
+                self.overflow = Some(FlagValue::Real(false));
+            }
+            None => {
+                self.neg = None;
+                self.zero = None;
+                self.carry = None;
+                self.overflow = None;
+            }
+        }
+    }
+
+    // dst = src + or - count, where count is some multiple of the vector length
+    fn add_vector_multiple(
+        &mut self,
+        dst: &Operand,
+        src: &Operand,
+        op: &str,
+        count: AbstractExpression,
+    ) {
+        let value = self.get_register(src);
+        if value.kind == RegisterKind::Number {
+            self.set_register(dst, RegisterKind::Number, None, 0);
+            return;
+        }
+        let base = generate_expression_from_options(op, value.base, Some(count));
+        self.set_register(dst, RegisterKind::RegisterBase, base, value.offset);
+    }
+
+    // contiguous ld1 and st1, only the active lanes are accessed
+    fn contiguous_access(
+        &mut self,
+        instruction: &Instruction,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        let size = instruction
+            .opcode
+            .sve_size()
+            .expect("contiguous access has a memory size");
+        let registers: Vec<&Operand> = instruction
+            .operands
+            .iter()
+            .filter(|o| matches!(o, Operand::Vector(..) | Operand::VectorRegister(..)))
+            .collect();
+        if registers.len() != 1 {
+            return Err(MemorySafetyError::new(&format!(
+                "{} of several vectors is not modelled {:?}",
+                instruction.opcode, instruction
+            )));
+        }
+        // the lanes are of the register element, each moves size bytes, i.e. ld1sb {z0.h}
+        let element = match registers[0] {
+            Operand::Vector(_, _, a) => a.element_size(),
+            _ => size,
+        };
+        let Some((governing, _)) = instruction.operands.iter().find_map(|o| match o {
+            Operand::Predicate(..) => Some(predicate_register(o)),
+            _ => None,
+        }) else {
+            return Err(MemorySafetyError::new(&format!(
+                "{} needs a governing predicate",
+                instruction.opcode
+            )));
+        };
+        let memory = instruction
+            .operands
+            .iter()
+            .find(|o| matches!(o, Operand::Memory(..)))
+            .ok_or_else(|| {
+                MemorySafetyError::new(&format!("{} needs an address", instruction.opcode))
+            })?;
+
+        let lanes = vector_lanes(element);
+        let start = self.sve_address(memory, lanes.clone(), size)?;
+        let active = match &self.predicates[governing] {
+            Predicate::None => {
+                log::info!("No active lanes in {:?}", instruction);
+                return Ok(());
+            }
+            Predicate::While(s, index, limit) if *s == element => {
+                Some((index.clone(), limit.clone()))
+            }
+            // at most every lane
+            _ => None,
+        };
+        self.check_active_lanes(start, active, lanes, size, ty)
+    }
+
+    // address of a contiguous access, register offsets count elements and immediates vectors
+    fn sve_address(
+        &mut self,
+        memory: &Operand,
+        lanes: AbstractExpression,
+        size: usize,
+    ) -> Result<AbstractExpression, MemorySafetyError> {
+        let Operand::Memory(prefix, num, offset, reg_offset, _, _) = memory else {
+            return Err(MemorySafetyError::new(&format!(
+                "sve access needs an address {:?}",
+                memory
+            )));
+        };
+        if *prefix == RePrefix::Z {
+            return Err(MemorySafetyError::new(
+                "sve gathers and scatters are not modelled",
+            ));
+        }
+        let base = self.get_register(&Operand::Register(prefix.clone(), *num));
+        let mut address = AbstractExpression::Register(Box::new(base));
+        match reg_offset.as_deref() {
+            Some(index @ Operand::Register(..)) => {
+                let index = AbstractExpression::Register(Box::new(self.get_register(index)));
+                address = generate_expression(
+                    "+",
+                    address,
+                    generate_expression("*", index, AbstractExpression::Immediate(size as i64)),
+                );
+            }
+            Some(_) => {
+                return Err(MemorySafetyError::new(
+                    "sve vector offsets are not modelled",
+                ))
+            }
+            None => (),
+        }
+        if let Some(imm) = offset {
+            let vectors =
+                generate_expression("*", lanes, AbstractExpression::Immediate(imm * size as i64));
+            address = generate_expression("+", address, vectors);
+        }
+        Ok(address)
+    }
+
+    /*
+     * start: address of the first lane
+     * active: index and limit of a while predicate, every lane is active if none
+     * checks the lanes that can be active, none of them if the loop is done
+     */
+    fn check_active_lanes(
+        &mut self,
+        start: AbstractExpression,
+        active: Option<(AbstractExpression, AbstractExpression)>,
+        lanes: AbstractExpression,
+        size: usize,
+        ty: RegionType,
+    ) -> Result<(), MemorySafetyError> {
+        let lanes = expression_to_ast(self.context, lanes).expect("sve lanes");
+        let count = ast::Int::new_const(self.context, "active_lanes");
+
+        self.solver.push();
+        match active {
+            Some((index, limit)) => {
+                let remaining = ast::Int::sub(
+                    self.context,
+                    &[
+                        &expression_to_ast(self.context, limit).expect("sve limit"),
+                        &expression_to_ast(self.context, index).expect("sve index"),
+                    ],
+                );
+                self.solver
+                    .assert(&count._eq(&remaining.lt(&lanes).ite(&remaining, &lanes)));
+            }
+            None => self.solver.assert(&count._eq(&lanes)),
+        }
+        self.solver
+            .assert(&count.gt(&ast::Int::from_i64(self.context, 0)));
+
+        let end = generate_expression(
+            "+",
+            start.clone(),
+            generate_expression(
+                "*",
+                AbstractExpression::Abstract("active_lanes".to_string()),
+                AbstractExpression::Immediate(size as i64),
+            ),
+        );
+        let result = self.check_range(start.clone(), end, ty);
+        self.solver.pop(1);
+
+        self.rw_queue.push(MemoryAccess {
+            kind: ty,
+            base: start.to_string(),
+            offset: 0,
+        });
+        result
+    }
+}
+
+// number and element size of a predicate, bytes if it has none like p0/z
+fn predicate_register(operand: &Operand) -> (usize, usize) {
+    match operand {
+        Operand::Predicate(n, Some(a), _) => (*n, a.element_size()),
+        Operand::Predicate(n, None, _) => (*n, 1),
+        _ => panic!("expected a predicate register {:?}", operand),
+    }
+}
+
+// elements counted by cnt, inc and dec, i.e. incw x0, all, mul #4. none for other patterns
+fn element_count(instruction: &Instruction) -> Option<AbstractExpression> {
+    let size = instruction.opcode.sve_size()?;
+    let mut count = vector_lanes(size);
+    for operand in instruction.operands.iter().skip(1) {
+        match operand {
+            Operand::Label(pattern) if pattern == "all" || pattern == "mul" => (),
+            Operand::Immediate(multiple) => {
+                count = generate_expression("*", count, AbstractExpression::Immediate(*multiple))
+            }
+            _ => return None,
+        }
+    }
+    Some(count)
+}
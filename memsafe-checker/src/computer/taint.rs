@@ -0,0 +1,495 @@
+use crate::computer::init::transfer_size;
+use crate::computer::*;
+use std::collections::HashSet;
+
+/*
+ * constant time checking: secrets are followed from the inputs marked secret through registers,
+ * flags and memory. a branch, a memory address or a division that depends on them takes a time
+ * that tells something about the secret, and is reported like an unsafe access
+ * vector registers and predicates are followed as a whole. memory is followed by the bytes a
+ * store writes when its offset into the region is known, otherwise the whole region is secret
+ */
+#[derive(Debug, Clone)]
+pub struct Secrets {
+    registers: [bool; 33],
+    simd_registers: [bool; 32],
+    predicates: [bool; 16],
+    flags: bool,
+    regions: HashSet<String>,
+    bytes: HashSet<(String, i64)>,
+    zeroise: Zeroise,
+}
+
+// what has to be clear of secrets when the program returns, i.e. for key schedules
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zeroise {
+    pub stack: bool,     // the frame below sp at entry
+    pub registers: bool, // x0 to x17 and v0 to v31
+    pub returns: bool,   // x0 holds the result, which may depend on secrets
+}
+
+impl Secrets {
+    fn new() -> Self {
+        Self {
+            registers: [false; 33],
+            simd_registers: [false; 32],
+            predicates: [false; 16],
+            flags: false,
+            regions: HashSet::new(),
+            bytes: HashSet::new(),
+            zeroise: Zeroise::default(),
+        }
+    }
+
+    fn operand(&self, operand: &Operand) -> bool {
+        match operand {
+            Operand::Register(prefix, n) => self.registers[register_index(prefix, *n)],
+            Operand::VectorRegister(_, n)
+            | Operand::Vector(_, n, _)
+            | Operand::VectorAccess(_, n, _, _) => self.simd_registers[*n],
+            Operand::Predicate(n, _, _) => self.predicates[*n],
+            _ => false,
+        }
+    }
+
+    pub(super) fn set_operand(&mut self, operand: &Operand, secret: bool) {
+        match operand {
+            // writes to xzr are dropped
+            Operand::Register(RePrefix::Ze, _) => (),
+            Operand::Register(prefix, n) => self.registers[register_index(prefix, *n)] = secret,
+            Operand::VectorRegister(_, n)
+            | Operand::Vector(_, n, _)
+            | Operand::VectorAccess(_, n, _, _) => self.simd_registers[*n] = secret,
+            Operand::Predicate(n, _, _) => self.predicates[*n] = secret,
+            _ => (),
+        }
+    }
+}
+
+fn register_index(prefix: &RePrefix, n: usize) -> usize {
+    match prefix {
+        RePrefix::Fp => 29,
+        RePrefix::Ra => 30,
+        RePrefix::Sp => 31,
+        RePrefix::Ze => 32,
+        _ => n,
+    }
+}
+
+// instructions that keep part of their destination, i.e. movk or inserting a lane
+fn reads_destination(instruction: &Instruction) -> bool {
+    matches!(
+        instruction.operands.first(),
+        Some(Operand::VectorAccess(..))
+    ) || matches!(
+        instruction.opcode,
+        Opcode::Movk
+            | Opcode::Bfi
+            | Opcode::Bfxil
+            | Opcode::Ins
+            | Opcode::Bit
+            | Opcode::Bif
+            | Opcode::Bsl
+            | Opcode::Usra
+            | Opcode::Ssra
+            | Opcode::Sri
+            | Opcode::Sli
+            | Opcode::Umlal
+            | Opcode::Umlal2
+            | Opcode::Smlal
+            | Opcode::Smlal2
+            | Opcode::Aese
+            | Opcode::Aesd
+            | Opcode::Sha1c
+            | Opcode::Sha1p
+            | Opcode::Sha1m
+            | Opcode::Sha1su0
+            | Opcode::Sha1su1
+            | Opcode::Sha256h
+            | Opcode::Sha256h2
+            | Opcode::Sha256su0
+            | Opcode::Sha256su1
+            | Opcode::Sha512h
+            | Opcode::Sha512h2
+            | Opcode::Sha512su0
+            | Opcode::Sha512su1
+            | Opcode::Tbx
+            | Opcode::Shrn2
+            | Opcode::Rshrn2
+            | Opcode::Sqshrun2
+            | Opcode::Sqrshrun2
+            | Opcode::Uqshrn2
+            | Opcode::Uqrshrn2
+            | Opcode::Xtn2
+            | Opcode::Sqxtun2
+    )
+}
+
+fn reads_flags(opcode: &Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::BCond(_) | Opcode::Adc | Opcode::Adcs | Opcode::Sbc | Opcode::Sbcs
+    ) || opcode.is_conditional_select()
+}
+
+fn sets_flags(opcode: &Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::Adds
+            | Opcode::Subs
+            | Opcode::Ands
+            | Opcode::Bics
+            | Opcode::Negs
+            | Opcode::Adcs
+            | Opcode::Sbcs
+            | Opcode::Cmp
+            | Opcode::Cmn
+            | Opcode::Tst
+            | Opcode::Ccmp
+            | Opcode::Ccmn
+            | Opcode::Whilelo
+            | Opcode::Whilelt
+            | Opcode::Whilels
+            | Opcode::Whilele
+            | Opcode::Ptest
+            | Opcode::Ptrues
+    )
+}
+
+// the first operand is read, not written
+fn has_destination(opcode: &Opcode) -> bool {
+    !matches!(
+        opcode,
+        Opcode::Cmp
+            | Opcode::Cmn
+            | Opcode::Tst
+            | Opcode::Ccmp
+            | Opcode::Ccmn
+            | Opcode::Ptest
+            | Opcode::Cbz
+            | Opcode::Cbnz
+            | Opcode::Tbz
+            | Opcode::Tbnz
+            | Opcode::Br
+            | Opcode::Blr
+            | Opcode::B
+            | Opcode::Bl
+            | Opcode::BCond(_)
+            | Opcode::Ret
+    )
+}
+
+fn leak(what: &str, instruction: &Instruction) -> MemorySafetyError {
+    MemorySafetyError::new(&format!(
+        "{} of {} depends on secret data",
+        what, instruction.opcode
+    ))
+}
+
+// whether size bytes loaded from the region at offset hold anything secret
+fn reads_secret(
+    secrets: &Secrets,
+    region: &Option<String>,
+    offset: Option<i64>,
+    size: i64,
+) -> bool {
+    match (region, offset) {
+        (Some(region), _) if secrets.regions.contains(region) => true,
+        (Some(region), Some(offset)) => {
+            (offset..offset + size).any(|byte| secrets.bytes.contains(&(region.clone(), byte)))
+        }
+        (Some(region), None) => secrets.bytes.iter().any(|(r, _)| r == region),
+        (None, None) | (None, Some(_)) => !secrets.regions.is_empty() || !secrets.bytes.is_empty(),
+    }
+}
+
+impl ARMCORTEXA<'_> {
+    // the contents of the region are secret
+    pub fn add_secret_region(&mut self, region: String) {
+        self.secrets
+            .get_or_insert_with(Secrets::new)
+            .regions
+            .insert(region);
+    }
+
+    // the value in the register is secret, i.e. an argument
+    pub fn add_secret_register(&mut self, register: usize) {
+        self.secrets.get_or_insert_with(Secrets::new).registers[register] = true;
+    }
+
+    // overlapping regions, a secret in one may be read through the other
+    pub fn share_secrets(&mut self, a: &str, b: &str) {
+        let Some(secrets) = &mut self.secrets else {
+            return;
+        };
+        if secrets.regions.contains(a) || secrets.regions.contains(b) {
+            secrets.regions.insert(a.to_string());
+            secrets.regions.insert(b.to_string());
+        }
+    }
+
+    // bytes of a region are secret, i.e. an argument passed on the stack
+    pub fn add_secret_bytes(&mut self, region: String, offset: i64, size: i64) {
+        let secrets = self.secrets.get_or_insert_with(Secrets::new);
+        for byte in offset..offset + size {
+            secrets.bytes.insert((region.clone(), byte));
+        }
+    }
+
+    pub fn require_zeroised(&mut self, zeroise: Zeroise) {
+        self.secrets.get_or_insert_with(Secrets::new).zeroise = zeroise;
+    }
+
+    // on the way out of the program
+    pub fn check_zeroised(&self) -> Result<(), MemorySafetyError> {
+        let Some(secrets) = &self.secrets else {
+            return Ok(());
+        };
+        let zeroise = secrets.zeroise;
+        if zeroise.stack {
+            let left = |message: String| {
+                let mut error = MemorySafetyError::new(&message);
+                error.region = Some("sp".to_string());
+                Err(error)
+            };
+            if secrets.regions.contains("sp") {
+                return left("Secret data is left on the stack on return".to_string());
+            }
+            let mut bytes: Vec<i64> = secrets
+                .bytes
+                .iter()
+                .filter(|(region, byte)| region == "sp" && *byte < 0)
+                .map(|(_, byte)| *byte)
+                .collect();
+            bytes.sort();
+            if let Some(byte) = bytes.first() {
+                return left(format!(
+                    "Secret data is left on the stack at sp + {} on return",
+                    byte
+                ));
+            }
+        }
+        if zeroise.registers {
+            let first = if zeroise.returns { 1 } else { 0 };
+            let left = (first..18)
+                .find(|n| secrets.registers[*n])
+                .map(|n| format!("x{}", n))
+                .or_else(|| {
+                    (0..32)
+                        .find(|n| secrets.simd_registers[*n])
+                        .map(|n| format!("v{}", n))
+                });
+            if let Some(register) = left {
+                return Err(MemorySafetyError::new(&format!(
+                    "Secret data is left in {} on return",
+                    register
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * runs before the instruction, the secrets it reads are known from the registers as they are
+     * errors if the instruction leaks them, otherwise marks what it writes as secret or not
+     */
+    pub fn follow_secrets(&mut self, instruction: &Instruction) -> Result<(), MemorySafetyError> {
+        let Some(mut secrets) = self.secrets.take() else {
+            return Ok(());
+        };
+        let result = self.follow_secrets_with(&mut secrets, instruction);
+        self.secrets = Some(secrets);
+        result
+    }
+
+    fn follow_secrets_with(
+        &mut self,
+        secrets: &mut Secrets,
+        instruction: &Instruction,
+    ) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        if let Some(m) = operands
+            .iter()
+            .position(|o| matches!(o, Operand::Memory(..)))
+        {
+            return self.follow_transfer(secrets, instruction, m);
+        }
+
+        let opcode = &instruction.opcode;
+        match opcode {
+            Opcode::BCond(_) if secrets.flags => return Err(leak("the branch", instruction)),
+            Opcode::Cbz | Opcode::Cbnz | Opcode::Tbz | Opcode::Tbnz | Opcode::Br | Opcode::Blr
+                if secrets.operand(&operands[0]) =>
+            {
+                return Err(leak("the branch", instruction));
+            }
+            Opcode::Ret => {
+                let target = operands
+                    .first()
+                    .cloned()
+                    .unwrap_or(Operand::Register(RePrefix::X, 30));
+                if secrets.operand(&target) {
+                    return Err(leak("the return address", instruction));
+                }
+            }
+            _ => (),
+        }
+
+        let destination = has_destination(opcode) && !operands.is_empty();
+        let sources = match destination && !reads_destination(instruction) {
+            true => &operands[1..],
+            false => &operands[..],
+        };
+        let mut secret = sources.iter().any(|o| secrets.operand(o));
+        // clearing a register with itself, i.e. eor v0.16b, v0.16b, v0.16b
+        if matches!(opcode, Opcode::Eor | Opcode::Sub | Opcode::Bic)
+            && operands.len() == 3
+            && operands[1] == operands[2]
+        {
+            secret = false;
+        }
+        if reads_flags(opcode) {
+            secret = secret || secrets.flags;
+        }
+        if matches!(opcode, Opcode::Udiv | Opcode::Sdiv) && secret {
+            return Err(leak("the time", instruction));
+        }
+        if sets_flags(opcode) {
+            secrets.flags = secret;
+        }
+        if destination {
+            secrets.set_operand(&operands[0], secret);
+        }
+        // a call leaves the return address in x30
+        if matches!(opcode, Opcode::Bl | Opcode::Blr) {
+            secrets.registers[30] = false;
+        }
+        Ok(())
+    }
+
+    // loads and stores, operand m is the address
+    fn follow_transfer(
+        &mut self,
+        secrets: &mut Secrets,
+        instruction: &Instruction,
+        m: usize,
+    ) -> Result<(), MemorySafetyError> {
+        let operands = &instruction.operands;
+        let Operand::Memory(prefix, n, _, index, _, _) = &operands[m] else {
+            unreachable!("address operand");
+        };
+
+        // the address, the register offset, writeback amounts and governing predicates are public
+        let base = Operand::Register(prefix.clone(), *n);
+        let mut address = vec![&base];
+        address.extend(index.as_deref());
+        address.extend(operands[m + 1..].iter());
+        address.extend(
+            operands[..m]
+                .iter()
+                .filter(|o| matches!(o, Operand::Predicate(..))),
+        );
+        if address.iter().any(|o| secrets.operand(o)) {
+            let mut error = leak("the address", instruction);
+            if let Some(AbstractExpression::Abstract(region)) = self.get_register(&base).base {
+                error.region = Some(region);
+            }
+            return Err(error);
+        }
+        if instruction.opcode == Opcode::Prfm {
+            return Ok(());
+        }
+
+        let (region, offset) = self.accessed_location(&operands[m]);
+        // scalable vectors move a number of bytes that is not known
+        let offset = offset.filter(|_| !instruction.is_sve());
+
+        if let Some((source, destination)) = instruction.opcode.atomic_operands() {
+            // the old value comes back, what is written is made from the other register(s)
+            let count = m / 2;
+            let size = transfer_size(&instruction.opcode, &operands[destination]) * count as i64;
+            let secret = reads_secret(secrets, &region, offset, size);
+            let sources = &operands[source..source + count];
+            if sources.iter().any(|r| secrets.operand(r)) {
+                match (&region, offset) {
+                    (Some(region), Some(offset)) => {
+                        for byte in offset..offset + size {
+                            secrets.bytes.insert((region.clone(), byte));
+                        }
+                    }
+                    (Some(region), None) => {
+                        secrets.regions.insert(region.clone());
+                    }
+                    (None, _) => secrets.regions.extend(self.memory.keys().cloned()),
+                }
+                if let Some(region) = &region {
+                    for (alias, _) in self.aliases_of(region) {
+                        secrets.regions.insert(alias);
+                    }
+                }
+            }
+            for r in &operands[destination..destination + count] {
+                secrets.set_operand(r, secret);
+            }
+            return Ok(());
+        }
+
+        let registers: Vec<&Operand> = instruction
+            .opcode
+            .transferred(&operands[..m])
+            .iter()
+            .filter(|o| !matches!(o, Operand::Predicate(..)))
+            .collect();
+        let sizes: Vec<i64> = registers
+            .iter()
+            .map(|r| transfer_size(&instruction.opcode, r))
+            .collect();
+        let size: i64 = sizes.iter().sum();
+
+        if instruction.opcode.is_store() {
+            let secret = registers.iter().any(|r| secrets.operand(r));
+            // structure stores interleave their registers, any secret one makes all bytes secret
+            let interleaved = instruction.opcode.structure().is_some();
+            match (&region, offset) {
+                (Some(region), Some(offset)) => {
+                    let mut start = offset;
+                    for (r, size) in registers.iter().zip(sizes) {
+                        let secret = match interleaved {
+                            true => secret,
+                            false => secrets.operand(r),
+                        };
+                        for byte in start..start + size {
+                            match secret {
+                                true => secrets.bytes.insert((region.clone(), byte)),
+                                false => secrets.bytes.remove(&(region.clone(), byte)),
+                            };
+                        }
+                        start += size;
+                    }
+                }
+                (Some(region), _) if secret => {
+                    secrets.regions.insert(region.clone());
+                }
+                (None, _) if secret => {
+                    secrets.regions.extend(self.memory.keys().cloned());
+                }
+                _ => (),
+            }
+            if let (Some(region), true) = (&region, secret) {
+                for (alias, _) in self.aliases_of(region) {
+                    secrets.regions.insert(alias);
+                }
+            }
+            // whether a store exclusive happened does not depend on what it stores
+            if instruction.opcode.is_store_exclusive() {
+                secrets.set_operand(&operands[0], false);
+            }
+        } else {
+            let secret = reads_secret(secrets, &region, offset, size);
+            for r in registers {
+                secrets.set_operand(r, secret);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-// use std::collections::HashMap;
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
 use z3::ast::Ast;
 use z3::*;
@@ -12,6 +12,10 @@ struct Program {
     // defs: Vec<String>,
     code: Vec<Instruction>,
     labels: Vec<(String, usize)>,
+    // line number and text of each instruction in the assembly file, empty for objects
+    source: Vec<(usize, String)>,
+    // address of each line of code in memory, where br and blr land
+    addresses: Vec<i64>,
     // ifdefs: Vec<((String, usize), usize)>,
 }
 
@@ -36,9 +40,84 @@ pub struct ExecutionEngine<'ctx> {
             Option<FlagValue>,
         ),
     )>,
+    // predicated loops being proven by induction: the pc of the loop branch and the registers
+    // the next iteration has to arrive with, none for registers holding unknown numbers
+    inductions: Vec<(usize, Vec<Option<RegisterValue>>)>,
+    // what has to hold whenever the program returns, ret stands for x0
+    postconditions: Vec<AbstractComparison>,
+    // store exclusives this path has already followed failing, after that they succeed
+    failed_exclusives: Vec<usize>,
     fail_fast: bool,
 }
 
+// an indirect branch that can go to more places than this is not followed
+const MAX_JUMP_TARGETS: usize = 64;
+
+// directives that lay out static data
+fn is_data_directive(text: &str) -> bool {
+    [".byte", ".hword", ".short", ".quad", ".long"]
+        .iter()
+        .any(|d| text.split_whitespace().next() == Some(*d))
+}
+
+// whether data in the section switched to can be written, none if the directive switches none
+fn section_is_read_only(directive: &[&str]) -> Option<bool> {
+    let name = match directive {
+        [".section", name, ..] => *name,
+        [name, ..] => *name,
+        [] => return None,
+    };
+    match name {
+        ".text" | ".const" | ".const_data" | ".cstring" | ".literal4" | ".literal8"
+        | ".literal16" | "__TEXT" => Some(true),
+        ".data" | ".bss" | "__DATA" => Some(false),
+        name if name.starts_with(".rodata") || name.starts_with(".data.rel.ro") => Some(true),
+        name if name.starts_with(".text.") => Some(true),
+        name if name.starts_with(".data.") || name.starts_with(".bss.") => Some(false),
+        _ => None,
+    }
+}
+
+/*
+ * the value of a sum of numbers and labels in a data directive, i.e. the jump table entry
+ * Ltbl - 64b, where 64b is the closest label 64 before the directive and 64f the closest after
+ */
+fn evaluate_data(
+    expression: &str,
+    line: usize,
+    labels: &[(String, usize)],
+    memory_labels: &HashMap<String, i64>,
+    code_base: i64,
+) -> Option<i64> {
+    let spaced = expression.replace('-', " - ").replace('+', " + ");
+    let (mut value, mut sign) = (0, 1);
+    for term in spaced.split_whitespace() {
+        let term_value = match term {
+            "-" => {
+                sign = -sign;
+                continue;
+            }
+            "+" => continue,
+            hex if hex.starts_with("0x") => i64::from_str_radix(&hex[2..], 16).ok()?,
+            number if number.parse::<i64>().is_ok() => number.parse::<i64>().ok()?,
+            local if local.ends_with('b') && local[..local.len() - 1].parse::<u64>().is_ok() => {
+                let name = &local[..local.len() - 1];
+                let (_, l) = labels.iter().rev().find(|(n, l)| n == name && *l < line)?;
+                code_base + 4 * *l as i64
+            }
+            local if local.ends_with('f') && local[..local.len() - 1].parse::<u64>().is_ok() => {
+                let name = &local[..local.len() - 1];
+                let (_, l) = labels.iter().find(|(n, l)| n == name && *l >= line)?;
+                code_base + 4 * *l as i64
+            }
+            label => *memory_labels.get(label.trim_start_matches('_'))?,
+        };
+        value += sign * term_value;
+        sign = 1;
+    }
+    Some(value)
+}
+
 impl<'ctx> ExecutionEngine<'ctx> {
     pub fn new(lines: Vec<String>, context: &'ctx Context) -> ExecutionEngine<'ctx> {
         // let _ = env_logger::try_init();
@@ -47,9 +126,11 @@ impl<'ctx> ExecutionEngine<'ctx> {
         // log::info!("-------");
 
         // represent code this way, highly unoptimized
-        let mut defs: Vec<String> = Vec::new();
+        // directives, with the number of lines of code before them
+        let mut defs: Vec<(String, usize)> = Vec::new();
         let mut code: Vec<Instruction> = Vec::new();
         let mut labels: Vec<(String, usize)> = Vec::new();
+        let mut source: Vec<(usize, String)> = Vec::new();
         let mut ifdefs: Vec<((String, usize), usize)> = Vec::new();
 
         // grab lines into array
@@ -57,14 +138,17 @@ impl<'ctx> ExecutionEngine<'ctx> {
         let mut inifdef = false;
         let mut lastifdef: (String, usize) = ("Start".to_string(), 0);
 
+        // labels not followed by an instruction yet, they name data if data follows
+        let mut pending: Vec<String> = Vec::new();
+
         // first pass, move text into array
-        for line in lines {
+        for (file_line, line) in lines.iter().enumerate() {
             // trim trailing comments and whitespace
             let trimmed = line.trim();
             let nocomment = trimmed.split_once("//");
             let text: String;
             match nocomment {
-                Some(strings) => text = strings.0.to_string(),
+                Some(strings) => text = strings.0.trim_end().to_string(),
                 None => text = trimmed.to_string(),
             }
 
@@ -73,7 +157,10 @@ impl<'ctx> ExecutionEngine<'ctx> {
             } else if text.starts_with("#") {
                 continue;
             } else if text.starts_with('.') {
-                defs.push(text);
+                if is_data_directive(&text) {
+                    defs.extend(pending.drain(..).map(|label| (label + ":", line_number)));
+                }
+                defs.push((text, line_number));
             } else {
                 // check if ifdef but keep them in the code
                 if text.starts_with('#') {
@@ -87,10 +174,14 @@ impl<'ctx> ExecutionEngine<'ctx> {
                 }
 
                 let i = Instruction::new(text.clone());
-                if i.ty == InstructionType::Label {
-                    labels.push((i.opcode, line_number));
+                if let Opcode::Label(name) = i.opcode {
+                    pending.push(name.clone());
+                    labels.push((name, line_number));
+                } else {
+                    pending.clear();
                 }
-                code.push(Instruction::new(text));
+                code.push(Instruction::new(text.clone()));
+                source.push((file_line + 1, text));
 
                 line_number = line_number + 1;
             }
@@ -98,10 +189,18 @@ impl<'ctx> ExecutionEngine<'ctx> {
 
         let mut computer = ARMCORTEXA::new(context);
 
-        // load computer static memory
+        // load computer static memory, what the read-only sections hold is constant
         let mut address = 4;
-        for def in defs.iter() {
+        let mut constant = true;
+        let mut data_labels: Vec<String> = Vec::new();
+        // entries of jump tables are differences of labels, known once the code is laid out
+        let mut tables: Vec<(i64, String, usize)> = Vec::new();
+        for (def, line) in defs.iter() {
             let v: Vec<&str> = def.split(|c| c == '\t' || c == ',' || c == ' ').collect();
+            if let Some(read_only) = section_is_read_only(&v) {
+                constant = read_only;
+            }
+            let start = address;
             if v[0] == ".align" {
                 //let alignment = v[1].parse::<usize>().expect("engine");
                 // do nothing for now
@@ -118,11 +217,18 @@ impl<'ctx> ExecutionEngine<'ctx> {
                         num = i.parse::<i64>().expect("engine4");
                     }
                     computer.add_memory_value("memory".to_string(), address, num);
-                    // address = address + (alignment as i64);
-                    // heap grows down
-                    address = address + 4;
+                    address = address + 1;
+                }
+            } else if v[0] == ".hword" || v[0] == ".short" {
+                for entry in def[v[0].len()..].split(',') {
+                    if !entry.trim().is_empty() {
+                        tables.push((address, entry.trim().to_string(), *line));
+                        address += 2;
+                    }
                 }
             } else if v[0] == ".quad" || v[0] == ".long" {
+                // a long is a 32 bit word on AArch64
+                let size = if v[0] == ".quad" { 8 } else { 4 };
                 for i in v.iter().skip(1) {
                     let num: i64;
                     if i.contains("x") {
@@ -135,30 +241,114 @@ impl<'ctx> ExecutionEngine<'ctx> {
                         num = i.parse::<i64>().expect("engine4");
                     }
                     computer.add_memory_value("memory".to_string(), address, num);
-                    address = address + 8;
+                    address = address + size;
                 }
             } else if v[0] == ".globl" || v[0] == ".private_extern" {
                 computer.memory_labels.insert(v[1].to_string(), address);
             } else if def.contains(":") {
+                let label = def.strip_suffix(":").unwrap_or(def).to_string();
+                data_labels.push(label.clone());
+                computer.memory_labels.insert(label, address);
+            }
+            if constant && address > start {
+                computer.add_constant_data(start, address);
+            }
+        }
+
+        // code is laid out after the data, four bytes an instruction, and cannot be written either
+        let code_base = address;
+        for (name, line) in labels.iter() {
+            if !data_labels.contains(name) {
                 computer
                     .memory_labels
-                    .insert(def.strip_suffix(":").unwrap_or(def).to_string(), address);
+                    .insert(name.clone(), address + 4 * *line as i64);
+            }
+        }
+        computer.add_constant_data(address, address + 4 * code.len() as i64);
+        for (entry_address, entry, line) in tables {
+            match evaluate_data(&entry, line, &labels, &computer.memory_labels, code_base) {
+                Some(value) => {
+                    computer.add_memory_value("memory".to_string(), entry_address, value)
+                }
+                None => log::warn!("Cannot evaluate table entry {}", entry),
+            }
+        }
+        let addresses = (0..code.len())
+            .map(|line| code_base + 4 * line as i64)
+            .collect();
+        if !computer.memory.contains_key("memory") {
+            computer.add_memory_region(
+                "memory".to_string(),
+                RegionType::RW,
+                AbstractExpression::Immediate(0),
+            );
+        }
+
+        return ExecutionEngine::from_program(code, labels, source, addresses, computer);
+    }
+
+    // load an assembled AArch64 ELF object or static archive instead of assembly text
+    pub fn new_from_object(
+        bytes: &[u8],
+        context: &'ctx Context,
+    ) -> std::io::Result<ExecutionEngine<'ctx>> {
+        let object = crate::object_parser::parse_object(bytes)?;
+
+        let mut labels: Vec<(String, usize)> = Vec::new();
+        for (line_number, i) in object.code.iter().enumerate() {
+            if let Opcode::Label(name) = &i.opcode {
+                labels.push((name.clone(), line_number));
             }
         }
 
-        return ExecutionEngine {
+        let mut computer = ARMCORTEXA::new(context);
+        for (address, value) in object.data {
+            computer.add_memory_value("memory".to_string(), address, value);
+        }
+        for (address, target) in object.pointers {
+            computer.add_memory_pointer("memory".to_string(), address, target);
+        }
+        for (start, end) in object.constant {
+            computer.add_constant_data(start, end);
+        }
+        for (name, address) in object.symbols {
+            computer.memory_labels.insert(name, address);
+        }
+
+        Ok(ExecutionEngine::from_program(
+            object.code,
+            labels,
+            Vec::new(),
+            object.addresses,
+            computer,
+        ))
+    }
+
+    fn from_program(
+        code: Vec<Instruction>,
+        labels: Vec<(String, usize)>,
+        source: Vec<(usize, String)>,
+        addresses: Vec<i64>,
+        computer: ARMCORTEXA<'ctx>,
+    ) -> ExecutionEngine<'ctx> {
+        ExecutionEngine {
             program: Program {
                 // defs,
                 code,
                 labels,
+                source,
+                addresses,
                 // ifdefs,
             },
             computer,
             jump_history: Vec::new(),
+            inductions: Vec::new(),
             in_loop: false,
             // abstracts: HashMap::new(),
+            postconditions: Vec::new(),
+            failed_exclusives: Vec::new(),
             fail_fast: true,
-        };
+        }
     }
 
     pub fn add_region(&mut self, ty: RegionType, base: String, length: AbstractExpression) {
@@ -171,6 +361,92 @@ impl<'ctx> ExecutionEngine<'ctx> {
         self.computer.add_memory_region(base.clone(), ty, length);
     }
 
+    /*
+     * a 2D buffer like a block of pixels: height rows of width bytes, stride bytes apart
+     * rows may not overlap, the caller makes sure the stride is at least as wide as a row
+     */
+    pub fn add_region_2d(
+        &mut self,
+        ty: RegionType,
+        base: String,
+        width: AbstractExpression,
+        stride: AbstractExpression,
+        height: AbstractExpression,
+    ) {
+        let context = self.computer.context;
+        let zero = ast::Int::from_i64(context, 0);
+        let (w, s, h) = (
+            expression_to_ast(context, width.clone()).expect("width of rows"),
+            expression_to_ast(context, stride.clone()).expect("stride of rows"),
+            expression_to_ast(context, height.clone()).expect("number of rows"),
+        );
+        self.computer.solver.assert(&w.ge(&zero));
+        self.computer.solver.assert(&h.ge(&zero));
+        self.computer.solver.assert(&ast::Bool::or(
+            context,
+            &[&s.ge(&w), &s.le(&w.unary_minus())],
+        ));
+        self.computer.solver.assert(&s._eq(&zero).not());
+
+        self.computer
+            .add_memory_rows(base, ty, width, stride, height);
+    }
+
+    /*
+     * regions the program is given may overlap, i.e. the input and output of in place encryption
+     * exactly aliased regions start at the same address, others anywhere
+     */
+    pub fn add_alias(&mut self, a: String, b: String, aliasing: Aliasing) {
+        if aliasing == Aliasing::Exact {
+            let context = self.computer.context;
+            let (x, y) = (
+                ast::Int::new_const(context, a.clone()),
+                ast::Int::new_const(context, b.clone()),
+            );
+            self.computer.solver.assert(&x._eq(&y));
+        }
+        self.computer.add_alias(a, b, aliasing);
+    }
+
+    // the regions do not overlap, i.e. a & and a &mut reference, so one ends before the other. the
+    // rows of a 2D region lie in one slice, from its first row to its last whichever way it strides
+    pub fn add_disjoint_regions(&mut self, a: String, b: String) {
+        let context = self.computer.context;
+        let zero = ast::Int::from_i64(context, 0);
+        let one = ast::Int::from_i64(context, 1);
+        let bounds = |name: &String| {
+            // the same length the accesses are checked against
+            let region = self.computer.memory.get(name)?;
+            let length = expression_to_ast(context, region.get_length())?;
+            let (first, end) = match &region.rows {
+                None => (zero.clone(), length),
+                Some((stride, height)) => {
+                    let stride = expression_to_ast(context, stride.clone())?;
+                    let height = expression_to_ast(context, height.clone())?;
+                    let rows = ast::Int::sub(context, &[&height, &one]);
+                    let rows = rows.gt(&zero).ite(&rows, &zero);
+                    let last = ast::Int::mul(context, &[&rows, &stride]);
+                    (
+                        last.lt(&zero).ite(&last, &zero),
+                        ast::Int::add(context, &[&last.gt(&zero).ite(&last, &zero), &length]),
+                    )
+                }
+            };
+            let base = ast::Int::new_const(context, name.clone());
+            Some((
+                ast::Int::add(context, &[&base, &first]),
+                ast::Int::add(context, &[&base, &end]),
+            ))
+        };
+        let (Some((a_start, a_end)), Some((b_start, b_end))) = (bounds(&a), bounds(&b)) else {
+            return;
+        };
+        self.computer.solver.assert(&ast::Bool::or(
+            context,
+            &[&a_end.le(&b_start), &b_end.le(&a_start)],
+        ));
+    }
+
     pub fn add_immediate(&mut self, register: String, value: usize) {
         self.computer.set_immediate(register, value as u64);
     }
@@ -189,12 +465,13 @@ impl<'ctx> ExecutionEngine<'ctx> {
             .add_memory_value_abstract(region, address, value);
     }
 
+    // arguments after the eighth are passed on the stack in 8 byte slots, the ninth at sp
     pub fn add_abstract_expression_from(&mut self, register: usize, value: AbstractExpression) {
         if register < 8 {
             let name = ("x".to_owned() + &register.to_string()).to_string();
             self.computer.set_abstract(name.clone(), value);
         } else {
-            let stack_index = ((register as i64) - 3) * -8;
+            let stack_index = ((register as i64) - 8) * 8;
             self.computer.set_stack_element(stack_index, Some(value), 0);
         }
     }
@@ -205,7 +482,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
             self.computer
                 .set_abstract(name.clone(), AbstractExpression::Abstract(value));
         } else {
-            let stack_index = ((register as i64) - 3) * -8;
+            let stack_index = ((register as i64) - 8) * 8;
             self.computer.set_stack_element(
                 stack_index,
                 Some(AbstractExpression::Abstract(value)),
@@ -214,18 +491,65 @@ impl<'ctx> ExecutionEngine<'ctx> {
         }
     }
 
+    // the run fails if it returns before writing every byte of the region, i.e. a MaybeUninit output
+    pub fn add_output_region(&mut self, base: String) {
+        self.computer.add_output(base);
+    }
+
+    // what the region holds is secret, the run fails if a branch, an address or a division depends on it
+    pub fn add_secret_region(&mut self, base: String) {
+        self.computer.add_secret_region(base);
+    }
+
+    // the run fails if secrets are left where the caller can find them, see Zeroise
+    pub fn require_zeroised(&mut self, zeroise: Zeroise) {
+        self.computer.require_zeroised(zeroise);
+    }
+
+    // the argument in the register, or on the stack like add_abstract_from, is secret
+    pub fn add_secret_argument(&mut self, register: usize) {
+        if register < 8 {
+            self.computer.add_secret_register(register);
+        } else {
+            let stack_index = ((register as i64) - 8) * 8;
+            self.computer
+                .add_secret_bytes("sp".to_string(), stack_index, 8);
+        }
+    }
+
+    // bytes of stack the program uses below sp on entry, on its deepest path, if it is known
+    pub fn get_stack_depth(&self) -> Option<i64> {
+        self.computer.get_stack_depth()
+    }
+
     pub fn get_register_output(&self, register: usize) -> RegisterValue {
         return self.computer.registers[register].clone();
     }
 
+    // instructions whose results were replaced by unknown numbers during the last run
+    pub fn get_havocs(&self) -> Vec<Havoc> {
+        self.computer.read_havocs()
+    }
+
     pub fn dont_fail_fast(&mut self) {
         self.fail_fast = false;
     }
 
+    // alignment assumed for the start of regions without one of their own, 1 unless changed
     pub fn change_alignment(&mut self, value: i64) {
         self.computer.change_alignment(value);
     }
 
+    // the region starts at a multiple of alignment bytes, i.e. a buffer of u32 at a multiple of 4
+    pub fn add_region_alignment(&mut self, base: String, alignment: i64) {
+        self.computer.add_region_alignment(base, alignment);
+    }
+
+    // which accesses the run fails for when their address is not shown to be aligned
+    pub fn check_alignment(&mut self, check: AlignmentCheck) {
+        self.computer.check_alignment(check);
+    }
+
     pub fn start(&mut self, start: String) -> std::io::Result<()> {
         let pc;
         match self.get_linenumber_of_label(start.trim_matches(|c| c == '_' || c == ':').to_string())
@@ -240,6 +564,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
         }
 
         // run is recursive
+        self.computer.enter();
         let res = self.run(pc);
         match res {
             Ok(_) => (),
@@ -255,14 +580,40 @@ impl<'ctx> ExecutionEngine<'ctx> {
         while pc < length {
             let mut instruction = self.program.code[pc].clone();
 
-            // skip instruction if it is a label
+            // skip instruction if it is a label, there can be several at the same spot
             if instruction.is_label() {
                 pc = pc + 1;
+                if pc == length {
+                    break;
+                }
                 instruction = self.program.code[pc].clone();
+                if instruction.is_label() {
+                    continue;
+                }
             }
 
             log::info!("{:?}: {:?}", pc, instruction);
 
+            // a store exclusive fails or succeeds, the path where it fails goes on without storing
+            if instruction.opcode.is_store_exclusive() && !self.failed_exclusives.contains(&pc) {
+                self.failed_exclusives.push(pc);
+                let failed = &mut self.clone();
+                failed.computer.fail_store_exclusive(&instruction);
+                let res1 = failed.run(pc + 1);
+                let res2 = self.run(pc);
+                self.computer.join_stack_depth(&failed.computer);
+                match (res1, res2) {
+                    (Ok(_), Ok(_)) => return Ok(()),
+                    (Err(err), Ok(_)) | (Ok(_), Err(err)) => {
+                        log::error!("{:?}: {:?}", pc, err);
+                        return Err(Error::other(err));
+                    }
+                    (Err(e1), Err(e2)) => {
+                        return Err(join_errors(e1, e2));
+                    }
+                }
+            }
+
             let execute_result = self.computer.execute(pc, &instruction);
 
             match execute_result {
@@ -292,6 +643,67 @@ impl<'ctx> ExecutionEngine<'ctx> {
                     ExecuteReturnType::JumpAddress(address) => {
                         pc = address as usize;
                     }
+                    ExecuteReturnType::JumpRegister(target) => {
+                        let targets = match self.indirect_targets(&target) {
+                            Ok(targets) => targets,
+                            Err(err) => {
+                                let path = self.path_condition();
+                                return Err(Error::other(err.at(self.describe(pc), path)));
+                            }
+                        };
+                        let register = AbstractExpression::Register(Box::new(target));
+                        let landing = |address: i64| {
+                            generate_comparison(
+                                "==",
+                                register.clone(),
+                                generate_expression(
+                                    "+",
+                                    AbstractExpression::Abstract("memory".to_string()),
+                                    AbstractExpression::Immediate(address),
+                                ),
+                            )
+                        };
+                        // one place to go, i.e. a function whose address was taken with adr
+                        if let [(address, line)] = targets[..] {
+                            self.add_constraint(landing(address), true);
+                            pc = line;
+                            continue;
+                        }
+
+                        // each entry of a jump table is explored knowing the branch goes there
+                        let rw_list = self.computer.read_rw_queue();
+                        let mut result: Option<Error> = None;
+                        for (address, line) in targets {
+                            let clone = &mut self.clone();
+                            clone.jump_history.push((
+                                pc,
+                                true,
+                                landing(address),
+                                rw_list.clone(),
+                                self.computer.get_state(),
+                            ));
+                            clone.computer.clear_rw_queue();
+                            log::info!("exploring indirect branch target line: {:?}", line);
+
+                            self.computer.solver.push();
+                            clone.add_constraint(landing(address), true);
+                            let res = clone.run(line);
+                            self.computer.join_stack_depth(&clone.computer);
+                            self.computer.solver.pop(1);
+
+                            if let Err(err) = res {
+                                log::error!("{:?}: {:?}", pc, err);
+                                result = Some(match result {
+                                    Some(e) => join_errors(e, err),
+                                    None => err,
+                                });
+                            }
+                        }
+                        return match result {
+                            Some(err) => Err(err),
+                            None => Ok(()),
+                        };
+                    }
                     ExecuteReturnType::ConditionalJumpLabel(condition, label) => {
                         if self.looping_too_deep() {
                             return Err(Error::new(ErrorKind::Other, "could not resolve loop"));
@@ -332,6 +744,14 @@ impl<'ctx> ExecutionEngine<'ctx> {
                             }
                         }
 
+                        if jump_dest <= pc && self.computer.is_predicated(&condition) {
+                            if let Some(res) =
+                                self.predicated_loop(pc, jump_dest, condition.clone())
+                            {
+                                return res;
+                            }
+                        }
+
                         match self.evaluate_branch_condition(
                             pc.clone(),
                             condition.clone(),
@@ -367,6 +787,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
                                 self.computer.solver.push();
                                 clone.add_constraint(condition, false);
                                 let res2 = clone.run(pc + 1);
+                                self.computer.join_stack_depth(&clone.computer);
                                 self.computer.solver.pop(1);
 
                                 match (res1, res2) {
@@ -380,10 +801,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
                                         return Err(Error::new(ErrorKind::Other, err));
                                     }
                                     (Err(e1), Err(e2)) => {
-                                        return Err(Error::new(
-                                            ErrorKind::Other,
-                                            e1.to_string() + &e2.to_string(),
-                                        ));
+                                        return Err(join_errors(e1, e2));
                                     }
                                 }
                             }
@@ -492,6 +910,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
                                 self.computer.solver.push();
                                 clone.add_constraint(condition, false);
                                 let res2 = clone.run(pc + 1);
+                                self.computer.join_stack_depth(&clone.computer);
                                 self.computer.solver.pop(1);
 
                                 match (res1, res2) {
@@ -505,10 +924,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
                                         return Err(Error::new(ErrorKind::Other, err));
                                     }
                                     (Err(e1), Err(e2)) => {
-                                        return Err(Error::new(
-                                            ErrorKind::Other,
-                                            e1.to_string() + &e2.to_string(),
-                                        ));
+                                        return Err(join_errors(e1, e2));
                                     }
                                 }
                             }
@@ -591,13 +1007,14 @@ impl<'ctx> ExecutionEngine<'ctx> {
                             let res1 = self.run(pc + 1);
 
                             clone.add_constraint(condition, false);
-                            self.computer.set_register(
+                            clone.computer.set_register(
                                 &register,
                                 option2.kind,
                                 option2.base,
                                 option2.offset,
                             );
                             let res2 = clone.run(pc + 1);
+                            self.computer.join_stack_depth(&clone.computer);
                             match (res1, res2) {
                                 (Ok(_), Ok(_)) => return Ok(()),
                                 (Err(err), Ok(_)) | (Ok(_), Err(err)) => {
@@ -605,10 +1022,7 @@ impl<'ctx> ExecutionEngine<'ctx> {
                                     return Err(Error::new(ErrorKind::Other, err));
                                 }
                                 (Err(e1), Err(e2)) => {
-                                    return Err(Error::new(
-                                        ErrorKind::Other,
-                                        e1.to_string() + &e2.to_string(),
-                                    ));
+                                    return Err(join_errors(e1, e2));
                                 }
                             }
                         }
@@ -623,13 +1037,247 @@ impl<'ctx> ExecutionEngine<'ctx> {
                         err
                     );
                     if self.fail_fast {
-                        return Err(Error::new(ErrorKind::Other, err));
+                        let path = self.path_condition();
+                        return Err(Error::new(ErrorKind::Other, err.at(self.describe(pc), path)));
                     }
                     pc = pc + 1;
                 }
             }
         }
-        Ok(self.computer.check_stack_pointer_restored())
+        let returned = self
+            .computer
+            .check_calling_convention()
+            .and_then(|_| self.computer.check_outputs_initialised())
+            .and_then(|_| self.computer.check_zeroised());
+        match returned.and_then(|_| self.check_postconditions()) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                let last = pc.min(length - 1);
+                let path = self.path_condition();
+                Err(Error::new(
+                    ErrorKind::Other,
+                    err.at(self.describe(last), path),
+                ))
+            }
+        }
+    }
+
+    // branch decisions taken to get to the current instruction
+    fn path_condition(&self) -> Vec<String> {
+        self.jump_history
+            .iter()
+            .map(|(_, decision, condition, ..)| match decision {
+                true => condition.to_string(),
+                false => condition.not().to_string(),
+            })
+            .collect()
+    }
+
+    // every path out of the program has to establish each postcondition for any input
+    fn check_postconditions(&self) -> Result<(), MemorySafetyError> {
+        if self.postconditions.is_empty() {
+            return Ok(());
+        }
+        let ret = self.computer.registers[0].clone();
+        if ret.kind == RegisterKind::Number {
+            return Err(MemorySafetyError::new(
+                "Return value is not known, postconditions cannot be proven",
+            ));
+        }
+        let context = self.computer.context;
+        let value = AbstractExpression::Register(Box::new(ret));
+        let returned = ast::Int::new_const(context, "ret")
+            ._eq(&expression_to_ast(context, value.clone()).expect("engine13"));
+        for postcondition in self.postconditions.iter() {
+            let holds = comparison_to_ast(context, postcondition.clone()).expect("engine14");
+            let broken = ast::Bool::and(context, &[&returned, &holds.not()]);
+            match self
+                .computer
+                .solver
+                .check_assumptions(std::slice::from_ref(&broken))
+            {
+                SatResult::Unsat => continue,
+                result => {
+                    log::info!("postcondition {} not proven: {:?}", postcondition, result);
+                    let mut error = MemorySafetyError::new(&format!(
+                        "Postcondition {} does not hold for return value {}",
+                        postcondition, value
+                    ));
+                    if result == SatResult::Sat {
+                        if let Some(model) = self.computer.solver.get_model() {
+                            let mut inputs = postcondition.get_abstracts();
+                            inputs.extend(value.get_abstracts());
+                            inputs.sort();
+                            inputs.dedup();
+                            for input in inputs {
+                                let value =
+                                    model.eval(&ast::Int::new_const(context, input.clone()), true);
+                                if let Some(value) = value.and_then(|v| v.as_i64()) {
+                                    error.counterexample.push((input, value));
+                                }
+                            }
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * loops like
+     *     whilelo p0.b, x2, x1
+     * loop:
+     *     ld1b {z0.b}, p0/z, [x0, x2]
+     *     incb x2
+     *     whilelo p0.b, x2, x1
+     *     b.first loop
+     * run a number of times that depends on VL, so they cannot be unrolled. when the branch is
+     * reached a second time, every register that changed is generalised to any later iteration
+     * k of the loop, both branches are explored from there, and the jump succeeds once the
+     * iteration after k arrives at the values of k + 1
+     * returns none to unroll as usual, i.e. when only one branch is possible
+     */
+    fn predicated_loop(
+        &mut self,
+        pc: usize,
+        jump_dest: usize,
+        mut condition: AbstractComparison,
+    ) -> Option<std::io::Result<()>> {
+        let current = self.computer.registers.clone();
+        if let Some(i) = self.inductions.iter().position(|(p, _)| *p == pc) {
+            let (_, expected) = self.inductions.remove(i);
+            return Some(self.check_induction(pc, &current, &expected));
+        }
+
+        let jump = comparison_to_ast(self.computer.context, condition.clone()).expect("engine12");
+        if self
+            .computer
+            .solver
+            .check_assumptions(std::slice::from_ref(&jump))
+            != SatResult::Sat
+            || self.computer.solver.check_assumptions(&[jump.not()]) != SatResult::Sat
+        {
+            return None;
+        }
+        let (_, _, _, _, (previous, ..)) =
+            self.jump_history.iter().rev().find(|h| h.0 == pc)?.clone();
+
+        let changed: Vec<usize> = (0..current.len())
+            .filter(|i| previous[*i] != current[*i])
+            .collect();
+        // registers that end up equal but got there differently cannot be told apart
+        for i in changed.iter() {
+            if changed
+                .iter()
+                .any(|j| current[*j] == current[*i] && previous[*j] != previous[*i])
+            {
+                return None;
+            }
+        }
+
+        let k =
+            AbstractExpression::Abstract(format!("{}_iteration_{}", pc, self.jump_history.len()));
+        self.add_invariant(generate_comparison(
+            ">=",
+            k.clone(),
+            AbstractExpression::Immediate(0),
+        ));
+        let mut expected: Vec<Option<RegisterValue>> =
+            current.iter().map(|r| Some(r.clone())).collect();
+        for i in changed {
+            if previous[i].kind == RegisterKind::Number || current[i].kind == RegisterKind::Number {
+                self.computer.registers[i] = RegisterValue::new(RegisterKind::Number, None, 0);
+                expected[i] = None;
+                continue;
+            }
+            let last = AbstractExpression::Register(Box::new(previous[i].clone()));
+            let value = AbstractExpression::Register(Box::new(current[i].clone()));
+            let step = generate_expression("-", value.clone(), last);
+            let general = generate_expression(
+                "+",
+                value.clone(),
+                generate_expression("*", k.clone(), step.clone()),
+            );
+            expected[i] = Some(RegisterValue::new(
+                RegisterKind::RegisterBase,
+                Some(generate_expression("+", general.clone(), step)),
+                0,
+            ));
+            self.computer.substitute(&value, &general);
+            condition = condition.substitute(&value, &general);
+            self.computer.registers[i] =
+                RegisterValue::new(RegisterKind::RegisterBase, Some(general), 0);
+        }
+        log::info!("generalised loop at {} to iteration {}", pc, k);
+
+        let mut exit = self.clone();
+        exit.computer.clear_rw_queue();
+        exit.add_constraint(condition.clone(), false);
+        let res1 = exit.run(pc + 1);
+
+        self.inductions.push((pc, expected));
+        self.computer.clear_rw_queue();
+        self.add_constraint(condition, true);
+        let res2 = self.run(jump_dest);
+
+        Some(match (res1, res2) {
+            (Ok(_), Ok(_)) => Ok(()),
+            (Err(err), Ok(_)) | (Ok(_), Err(err)) => Err(err),
+            (Err(e1), Err(e2)) => Err(join_errors(e1, e2)),
+        })
+    }
+
+    // the iteration after the general one has to arrive where iteration k + 1 would
+    fn check_induction(
+        &self,
+        pc: usize,
+        current: &[RegisterValue],
+        expected: &[Option<RegisterValue>],
+    ) -> std::io::Result<()> {
+        for (value, expected) in current.iter().zip(expected) {
+            let Some(expected) = expected else {
+                continue;
+            };
+            if value == expected {
+                continue;
+            }
+            let advanced = value.kind != RegisterKind::Number && {
+                let context = self.computer.context;
+                let value = expression_to_ast(
+                    context,
+                    AbstractExpression::Register(Box::new(value.clone())),
+                )
+                .expect("engine13");
+                let expected = expression_to_ast(
+                    context,
+                    AbstractExpression::Register(Box::new(expected.clone())),
+                )
+                .expect("engine13");
+                self.computer
+                    .solver
+                    .check_assumptions(&[value._eq(&expected).not()])
+                    == SatResult::Unsat
+            };
+            if !advanced {
+                log::error!("loop at {} went from {:?} to {:?}", pc, expected, value);
+                return Err(Error::other(format!(
+                    "could not resolve loop at {}, it does not advance the same way every iteration",
+                    pc
+                )));
+            }
+        }
+        log::info!("loop at {} holds for every iteration", pc);
+        Ok(())
+    }
+
+    // where an instruction is in the program, for error reports
+    fn describe(&self, pc: usize) -> String {
+        match self.program.source.get(pc) {
+            Some((line, text)) => format!("line {}: {}", line, text),
+            None => format!("instruction {}: {}", pc, self.program.code[pc].opcode),
+        }
     }
 
     fn get_linenumber_of_label(&self, label: String) -> Option<usize> {
@@ -653,6 +1301,11 @@ impl<'ctx> ExecutionEngine<'ctx> {
         }
     }
 
+    // a fact about the return value in x0, written in terms of ret and the inputs
+    pub fn add_postcondition(&mut self, constraint: AbstractComparison) {
+        self.postconditions.push(constraint);
+    }
+
     pub fn add_invariant(&self, constraint: AbstractComparison) {
         let c = comparison_to_ast(self.computer.context, constraint)
             .expect("engine6.5")
@@ -660,6 +1313,80 @@ impl<'ctx> ExecutionEngine<'ctx> {
         self.computer.solver.assert(&c);
     }
 
+    /*
+     * where br or blr goes, every address the solver allows the register to hold has to be an
+     * instruction of the program, i.e. the entries of a jump table indexed by an input
+     */
+    fn indirect_targets(
+        &self,
+        target: &RegisterValue,
+    ) -> Result<Vec<(i64, usize)>, MemorySafetyError> {
+        let register = AbstractExpression::Register(Box::new(target.clone()));
+        if !register.get_abstracts().contains(&"memory".to_string()) {
+            return Err(MemorySafetyError::new(&format!(
+                "Cannot tell where the branch to {} goes, it is not an address in the program",
+                register
+            )));
+        }
+        let context = self.computer.context;
+        let solver = &self.computer.solver;
+        let address = ast::Int::sub(
+            context,
+            &[
+                &expression_to_ast(context, register.clone()).expect("engine9"),
+                &ast::Int::new_const(context, "memory"),
+            ],
+        );
+
+        let mut targets = Vec::new();
+        solver.push();
+        let result = loop {
+            match solver.check() {
+                SatResult::Unsat => break Ok(targets),
+                SatResult::Unknown => {
+                    break Err(MemorySafetyError::new(&format!(
+                        "Cannot tell where the branch to {} goes",
+                        register
+                    )))
+                }
+                SatResult::Sat => (),
+            }
+            let model = solver.get_model().expect("engine10");
+            let Some(value) = model.eval(&address, true).and_then(|v| v.as_i64()) else {
+                break Err(MemorySafetyError::new(&format!(
+                    "Cannot tell where the branch to {} goes",
+                    register
+                )));
+            };
+            let Some(line) = self.program.addresses.iter().position(|a| *a == value) else {
+                let mut error = MemorySafetyError::new(&format!(
+                    "Branch to {} can go to address {}, which is not an instruction",
+                    register, value
+                ));
+                for input in register.get_abstracts() {
+                    let input_value =
+                        model.eval(&ast::Int::new_const(context, input.clone()), true);
+                    if let Some(v) = input_value.and_then(|v| v.as_i64()) {
+                        if input != "memory" {
+                            error.counterexample.push((input, v));
+                        }
+                    }
+                }
+                break Err(error);
+            };
+            targets.push((value, line));
+            if targets.len() > MAX_JUMP_TARGETS {
+                break Err(MemorySafetyError::new(&format!(
+                    "Branch to {} can go to more than {} places",
+                    register, MAX_JUMP_TARGETS
+                )));
+            }
+            solver.assert(&address._eq(&ast::Int::from_i64(context, value)).not());
+        };
+        solver.pop(1);
+        result
+    }
+
     fn looping_too_deep(&self) -> bool {
         // jump out if too deep in tree
 
@@ -722,9 +1449,16 @@ impl<'ctx> ExecutionEngine<'ctx> {
 
         //check if loop can be resolved without loop protocol
         // i.e. there are explicit lower and upper bounds
+        // loops count to an input length or to a dimension of a region, like the rows of a block
+        let dimensions: Vec<String> = self
+            .computer
+            .memory
+            .values()
+            .flat_map(|region| region.get_dimensions())
+            .collect();
         let mut is_abstract = false;
         for a in expression.get_abstracts() {
-            if a.contains("len") || a.contains("loop") {
+            if a.contains("len") || a.contains("loop") || dimensions.contains(&a) {
                 is_abstract = true;
             }
         }
@@ -979,6 +1713,24 @@ impl<'ctx> ExecutionEngine<'ctx> {
     }
 }
 
+// the memory safety violation behind an error returned by start, if there is one
+pub fn memory_safety_violation(error: &Error) -> Option<&MemorySafetyError> {
+    let inner = error.get_ref()?;
+    match inner.downcast_ref::<MemorySafetyError>() {
+        Some(violation) => Some(violation),
+        None => memory_safety_violation(inner.downcast_ref::<Error>()?),
+    }
+}
+
+// both sides of a branch failed
+fn join_errors(e1: Error, e2: Error) -> Error {
+    let details = e1.to_string() + &e2.to_string();
+    match memory_safety_violation(&e1).or(memory_safety_violation(&e2)) {
+        Some(violation) => Error::other(violation.reword(&details)),
+        None => Error::other(details),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
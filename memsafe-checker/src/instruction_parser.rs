@@ -1,10 +1,744 @@
 #[derive(Debug, Clone, PartialEq)]
 pub struct Instruction {
     pub ty: InstructionType,
-    pub opcode: String,
+    pub opcode: Opcode,
     pub operands: Vec<Operand>,
 }
 
+// one variant per mnemonic, plus the text form for display and parsing
+macro_rules! opcodes {
+    ($($variant:ident => $text:literal,)*) => {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum Opcode {
+            $($variant,)*
+            BCond(Condition), // like b.ne
+            Label(String),
+            Directive(String), // like .byte or .globl
+            Unknown(String),
+        }
+
+        impl Opcode {
+            fn from_mnemonic(s: &str) -> Option<Opcode> {
+                match s {
+                    $($text => Some(Opcode::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl std::fmt::Display for Opcode {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Opcode::$variant => write!(f, $text),)*
+                    Opcode::BCond(c) => write!(f, "b.{}", c),
+                    Opcode::Label(s) | Opcode::Directive(s) | Opcode::Unknown(s) => write!(f, "{}", s),
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    // integer arithmetic and logic
+    Add => "add", Adds => "adds", Sub => "sub", Subs => "subs",
+    Adc => "adc", Adcs => "adcs", Sbc => "sbc", Sbcs => "sbcs",
+    Neg => "neg", Negs => "negs",
+    Mul => "mul", Madd => "madd", Msub => "msub", Mneg => "mneg",
+    Umulh => "umulh", Smulh => "smulh", Umull => "umull", Smull => "smull",
+    Umaddl => "umaddl", Smaddl => "smaddl", Udiv => "udiv", Sdiv => "sdiv",
+    And => "and", Ands => "ands", Orr => "orr", Orn => "orn",
+    Eor => "eor", Eon => "eon", Bic => "bic", Bics => "bics", Tst => "tst",
+    Mov => "mov", Movz => "movz", Movn => "movn", Movk => "movk", Mvn => "mvn",
+    Lsl => "lsl", Lsr => "lsr", Asr => "asr", Ror => "ror", Extr => "extr",
+    Ubfx => "ubfx", Ubfiz => "ubfiz", Sbfx => "sbfx", Sbfiz => "sbfiz",
+    Bfi => "bfi", Bfxil => "bfxil",
+    Sxtb => "sxtb", Sxth => "sxth", Sxtw => "sxtw", Uxtb => "uxtb", Uxth => "uxth",
+    Cmp => "cmp", Cmn => "cmn", Ccmp => "ccmp", Ccmn => "ccmn",
+    Csel => "csel", Csinc => "csinc", Csinv => "csinv", Csneg => "csneg",
+    Cset => "cset", Csetm => "csetm", Cinc => "cinc", Cneg => "cneg",
+    Clz => "clz", Cls => "cls", Rbit => "rbit", Rev => "rev", Rev16 => "rev16", Rev32 => "rev32",
+    // control flow
+    Adr => "adr", Adrp => "adrp", B => "b", Bl => "bl", Br => "br", Blr => "blr", Ret => "ret",
+    Cbz => "cbz", Cbnz => "cbnz", Tbz => "tbz", Tbnz => "tbnz",
+    Nop => "nop", Hint => "hint", Bti => "bti", Dmb => "dmb", Dsb => "dsb", Isb => "isb",
+    // memory
+    Ldr => "ldr", Ldrb => "ldrb", Ldrh => "ldrh", Ldrsb => "ldrsb", Ldrsh => "ldrsh", Ldrsw => "ldrsw",
+    Ldur => "ldur", Ldurb => "ldurb", Ldurh => "ldurh", Ldursb => "ldursb", Ldursh => "ldursh",
+    Ldursw => "ldursw",
+    Str => "str", Strb => "strb", Strh => "strh", Stur => "stur", Sturb => "sturb", Sturh => "sturh",
+    Ldp => "ldp", Ldpsw => "ldpsw", Stp => "stp", Prfm => "prfm",
+    // exclusive, acquire-release and atomic memory
+    Ldxr => "ldxr", Ldxrb => "ldxrb", Ldxrh => "ldxrh", Ldxp => "ldxp",
+    Ldaxr => "ldaxr", Ldaxrb => "ldaxrb", Ldaxrh => "ldaxrh", Ldaxp => "ldaxp",
+    Stxr => "stxr", Stxrb => "stxrb", Stxrh => "stxrh", Stxp => "stxp",
+    Stlxr => "stlxr", Stlxrb => "stlxrb", Stlxrh => "stlxrh", Stlxp => "stlxp",
+    Ldar => "ldar", Ldarb => "ldarb", Ldarh => "ldarh",
+    Ldapr => "ldapr", Ldaprb => "ldaprb", Ldaprh => "ldaprh",
+    Stlr => "stlr", Stlrb => "stlrb", Stlrh => "stlrh",
+    Cas => "cas", Casa => "casa", Casl => "casl", Casal => "casal",
+    Casb => "casb", Casab => "casab", Caslb => "caslb", Casalb => "casalb",
+    Cash => "cash", Casah => "casah", Caslh => "caslh", Casalh => "casalh",
+    Casp => "casp", Caspa => "caspa", Caspl => "caspl", Caspal => "caspal",
+    Ldadd => "ldadd", Ldadda => "ldadda", Ldaddl => "ldaddl", Ldaddal => "ldaddal",
+    Ldaddb => "ldaddb", Ldaddab => "ldaddab", Ldaddlb => "ldaddlb", Ldaddalb => "ldaddalb",
+    Ldaddh => "ldaddh", Ldaddah => "ldaddah", Ldaddlh => "ldaddlh", Ldaddalh => "ldaddalh",
+    Ldclr => "ldclr", Ldclra => "ldclra", Ldclrl => "ldclrl", Ldclral => "ldclral",
+    Ldclrb => "ldclrb", Ldclrab => "ldclrab", Ldclrlb => "ldclrlb", Ldclralb => "ldclralb",
+    Ldclrh => "ldclrh", Ldclrah => "ldclrah", Ldclrlh => "ldclrlh", Ldclralh => "ldclralh",
+    Ldeor => "ldeor", Ldeora => "ldeora", Ldeorl => "ldeorl", Ldeoral => "ldeoral",
+    Ldeorb => "ldeorb", Ldeorab => "ldeorab", Ldeorlb => "ldeorlb", Ldeoralb => "ldeoralb",
+    Ldeorh => "ldeorh", Ldeorah => "ldeorah", Ldeorlh => "ldeorlh", Ldeoralh => "ldeoralh",
+    Ldset => "ldset", Ldseta => "ldseta", Ldsetl => "ldsetl", Ldsetal => "ldsetal",
+    Ldsetb => "ldsetb", Ldsetab => "ldsetab", Ldsetlb => "ldsetlb", Ldsetalb => "ldsetalb",
+    Ldseth => "ldseth", Ldsetah => "ldsetah", Ldsetlh => "ldsetlh", Ldsetalh => "ldsetalh",
+    Swp => "swp", Swpa => "swpa", Swpl => "swpl", Swpal => "swpal",
+    Swpb => "swpb", Swpab => "swpab", Swplb => "swplb", Swpalb => "swpalb",
+    Swph => "swph", Swpah => "swpah", Swplh => "swplh", Swpalh => "swpalh",
+    // simd
+    Ld1 => "ld1", Ld2 => "ld2", Ld3 => "ld3", Ld4 => "ld4",
+    St1 => "st1", St2 => "st2", St3 => "st3", St4 => "st4",
+    Ld1r => "ld1r", Ld2r => "ld2r", Ld3r => "ld3r", Ld4r => "ld4r",
+    Movi => "movi", Mvni => "mvni", Fmov => "fmov", Umov => "umov", Smov => "smov", Ins => "ins",
+    Dup => "dup", Ext => "ext", Rev64 => "rev64", Cnt => "cnt", Abs => "abs", Not => "not",
+    Shl => "shl", Ushr => "ushr", Sshr => "sshr", Usra => "usra", Ssra => "ssra",
+    Urshr => "urshr", Srshr => "srshr", Sri => "sri", Sli => "sli",
+    Shrn => "shrn", Shrn2 => "shrn2", Rshrn => "rshrn", Rshrn2 => "rshrn2",
+    Sqshrun => "sqshrun", Sqshrun2 => "sqshrun2", Sqrshrun => "sqrshrun", Sqrshrun2 => "sqrshrun2",
+    Uqshrn => "uqshrn", Uqshrn2 => "uqshrn2", Uqrshrn => "uqrshrn", Uqrshrn2 => "uqrshrn2",
+    Ushll => "ushll", Ushll2 => "ushll2", Sshll => "sshll", Sshll2 => "sshll2",
+    Uxtl => "uxtl", Uxtl2 => "uxtl2", Sxtl => "sxtl", Sxtl2 => "sxtl2",
+    Xtn => "xtn", Xtn2 => "xtn2", Sqxtun => "sqxtun", Sqxtun2 => "sqxtun2",
+    Aese => "aese", Aesd => "aesd", Aesmc => "aesmc", Aesimc => "aesimc",
+    Sha1c => "sha1c", Sha1p => "sha1p", Sha1m => "sha1m", Sha1h => "sha1h",
+    Sha1su0 => "sha1su0", Sha1su1 => "sha1su1",
+    Sha256h => "sha256h", Sha256h2 => "sha256h2", Sha256su0 => "sha256su0", Sha256su1 => "sha256su1",
+    Sha512h => "sha512h", Sha512h2 => "sha512h2", Sha512su0 => "sha512su0", Sha512su1 => "sha512su1",
+    Pmull => "pmull", Pmull2 => "pmull2", Pmul => "pmul",
+    Zip1 => "zip1", Zip2 => "zip2", Uzp1 => "uzp1", Uzp2 => "uzp2", Trn1 => "trn1", Trn2 => "trn2",
+    Bit => "bit", Bif => "bif", Bsl => "bsl", Tbl => "tbl", Tbx => "tbx",
+    Uaddl => "uaddl", Uaddl2 => "uaddl2", Saddl => "saddl", Saddl2 => "saddl2",
+    Usubl => "usubl", Usubl2 => "usubl2", Ssubl => "ssubl", Ssubl2 => "ssubl2",
+    Umull2 => "umull2", Smull2 => "smull2",
+    Umlal => "umlal", Umlal2 => "umlal2", Smlal => "smlal", Smlal2 => "smlal2",
+    Cmeq => "cmeq", Cmtst => "cmtst", Cmgt => "cmgt", Cmhi => "cmhi", Cmge => "cmge", Cmhs => "cmhs",
+    Sshl => "sshl", Ushl => "ushl", Smax => "smax", Umax => "umax", Smin => "smin", Umin => "umin",
+    Addp => "addp", Sqadd => "sqadd", Uqadd => "uqadd", Sqsub => "sqsub", Uqsub => "uqsub",
+    Srhadd => "srhadd", Urhadd => "urhadd",
+    // sve
+    Ld1b => "ld1b", Ld1h => "ld1h", Ld1w => "ld1w", Ld1d => "ld1d",
+    Ld1sb => "ld1sb", Ld1sh => "ld1sh", Ld1sw => "ld1sw",
+    St1b => "st1b", St1h => "st1h", St1w => "st1w", St1d => "st1d",
+    Whilelo => "whilelo", Whilelt => "whilelt", Whilels => "whilels", Whilele => "whilele",
+    Ptrue => "ptrue", Ptrues => "ptrues", Pfalse => "pfalse", Ptest => "ptest",
+    Cntb => "cntb", Cnth => "cnth", Cntw => "cntw", Cntd => "cntd",
+    Incb => "incb", Inch => "inch", Incw => "incw", Incd => "incd",
+    Decb => "decb", Dech => "dech", Decw => "decw", Decd => "decd",
+    Addvl => "addvl", Addpl => "addpl", Rdvl => "rdvl",
+}
+
+impl Opcode {
+    pub fn from_string(s: &str) -> Opcode {
+        if s.ends_with(':') {
+            return Opcode::Label(s.trim_matches(|c| c == ':' || c == '_').to_string());
+        }
+        if s.starts_with('.') || s.starts_with('#') {
+            return Opcode::Directive(s.to_string());
+        }
+        if let Some(cond) = s.strip_prefix("b.") {
+            if let Some(c) = Condition::from_string(cond) {
+                return Opcode::BCond(c);
+            }
+        }
+        match s {
+            // the landing pads of arm64 asm.S, a bti or nothing
+            "AARCH64_VALID_JUMP_TARGET" | "AARCH64_VALID_CALL_TARGET" => return Opcode::Bti,
+            "bt" => return Opcode::BCond(Condition::Gt),
+            _ => (),
+        }
+        if let Some(opcode) = Opcode::from_mnemonic(s) {
+            return opcode;
+        }
+        // older notation without the dot, like bne or bhs
+        match s.strip_prefix('b').filter(|cond| cond.len() == 2) {
+            Some(cond) => match Condition::from_string(cond) {
+                Some(c) => Opcode::BCond(c),
+                None => Opcode::Unknown(s.to_string()),
+            },
+            None => Opcode::Unknown(s.to_string()),
+        }
+    }
+
+    // branches, returns and the pc relative adr and adrp
+    pub fn is_control_flow(&self) -> bool {
+        matches!(
+            self,
+            Opcode::B
+                | Opcode::Bl
+                | Opcode::Br
+                | Opcode::Blr
+                | Opcode::Ret
+                | Opcode::Cbz
+                | Opcode::Cbnz
+                | Opcode::Tbz
+                | Opcode::Tbnz
+                | Opcode::BCond(_)
+                | Opcode::Adr
+                | Opcode::Adrp
+        )
+    }
+
+    // bytes moved by a single register load or store, from the size suffix
+    pub fn access_size(&self) -> Option<usize> {
+        match self {
+            Opcode::Ldrb | Opcode::Ldrsb | Opcode::Ldurb | Opcode::Ldursb => Some(1),
+            Opcode::Strb | Opcode::Sturb => Some(1),
+            Opcode::Ldrh | Opcode::Ldrsh | Opcode::Ldurh | Opcode::Ldursh => Some(2),
+            Opcode::Strh | Opcode::Sturh => Some(2),
+            Opcode::Ldrsw | Opcode::Ldursw | Opcode::Ldpsw => Some(4),
+            Opcode::Ldxrb | Opcode::Ldaxrb | Opcode::Ldarb | Opcode::Ldaprb => Some(1),
+            Opcode::Stxrb | Opcode::Stlxrb | Opcode::Stlrb => Some(1),
+            Opcode::Ldxrh | Opcode::Ldaxrh | Opcode::Ldarh | Opcode::Ldaprh => Some(2),
+            Opcode::Stxrh | Opcode::Stlxrh | Opcode::Stlrh => Some(2),
+            Opcode::Casb
+            | Opcode::Casab
+            | Opcode::Caslb
+            | Opcode::Casalb
+            | Opcode::Ldaddb
+            | Opcode::Ldaddab
+            | Opcode::Ldaddlb
+            | Opcode::Ldaddalb
+            | Opcode::Ldclrb
+            | Opcode::Ldclrab
+            | Opcode::Ldclrlb
+            | Opcode::Ldclralb
+            | Opcode::Ldeorb
+            | Opcode::Ldeorab
+            | Opcode::Ldeorlb
+            | Opcode::Ldeoralb
+            | Opcode::Ldsetb
+            | Opcode::Ldsetab
+            | Opcode::Ldsetlb
+            | Opcode::Ldsetalb
+            | Opcode::Swpb
+            | Opcode::Swpab
+            | Opcode::Swplb
+            | Opcode::Swpalb => Some(1),
+            Opcode::Cash
+            | Opcode::Casah
+            | Opcode::Caslh
+            | Opcode::Casalh
+            | Opcode::Ldaddh
+            | Opcode::Ldaddah
+            | Opcode::Ldaddlh
+            | Opcode::Ldaddalh
+            | Opcode::Ldclrh
+            | Opcode::Ldclrah
+            | Opcode::Ldclrlh
+            | Opcode::Ldclralh
+            | Opcode::Ldeorh
+            | Opcode::Ldeorah
+            | Opcode::Ldeorlh
+            | Opcode::Ldeoralh
+            | Opcode::Ldseth
+            | Opcode::Ldsetah
+            | Opcode::Ldsetlh
+            | Opcode::Ldsetalh
+            | Opcode::Swph
+            | Opcode::Swpah
+            | Opcode::Swplh
+            | Opcode::Swpalh => Some(2),
+            _ => None,
+        }
+    }
+
+    // everything that writes memory, atomics write the location they read
+    pub fn is_store(&self) -> bool {
+        self.is_store_exclusive()
+            || self.atomic_operands().is_some()
+            || matches!(
+                self,
+                Opcode::Str
+                    | Opcode::Strb
+                    | Opcode::Strh
+                    | Opcode::Stur
+                    | Opcode::Sturb
+                    | Opcode::Sturh
+                    | Opcode::Stp
+                    | Opcode::Stlr
+                    | Opcode::Stlrb
+                    | Opcode::Stlrh
+                    | Opcode::St1
+                    | Opcode::St2
+                    | Opcode::St3
+                    | Opcode::St4
+                    | Opcode::St1b
+                    | Opcode::St1h
+                    | Opcode::St1w
+                    | Opcode::St1d
+            )
+    }
+
+    // stxr w1, x2, [x0] writes to w1 whether the store failed
+    pub fn is_store_exclusive(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Stxr
+                | Opcode::Stxrb
+                | Opcode::Stxrh
+                | Opcode::Stxp
+                | Opcode::Stlxr
+                | Opcode::Stlxrb
+                | Opcode::Stlxrh
+                | Opcode::Stlxp
+        )
+    }
+
+    // (first register written to memory, first register given the old value) of cas, ldadd and swp
+    pub fn atomic_operands(&self) -> Option<(usize, usize)> {
+        match self {
+            Opcode::Cas
+            | Opcode::Casa
+            | Opcode::Casl
+            | Opcode::Casal
+            | Opcode::Casb
+            | Opcode::Casab
+            | Opcode::Caslb
+            | Opcode::Casalb
+            | Opcode::Cash
+            | Opcode::Casah
+            | Opcode::Caslh
+            | Opcode::Casalh => Some((1, 0)),
+            Opcode::Casp | Opcode::Caspa | Opcode::Caspl | Opcode::Caspal => Some((2, 0)),
+            Opcode::Ldadd
+            | Opcode::Ldadda
+            | Opcode::Ldaddl
+            | Opcode::Ldaddal
+            | Opcode::Ldaddb
+            | Opcode::Ldaddab
+            | Opcode::Ldaddlb
+            | Opcode::Ldaddalb
+            | Opcode::Ldaddh
+            | Opcode::Ldaddah
+            | Opcode::Ldaddlh
+            | Opcode::Ldaddalh
+            | Opcode::Ldclr
+            | Opcode::Ldclra
+            | Opcode::Ldclrl
+            | Opcode::Ldclral
+            | Opcode::Ldclrb
+            | Opcode::Ldclrab
+            | Opcode::Ldclrlb
+            | Opcode::Ldclralb
+            | Opcode::Ldclrh
+            | Opcode::Ldclrah
+            | Opcode::Ldclrlh
+            | Opcode::Ldclralh
+            | Opcode::Ldeor
+            | Opcode::Ldeora
+            | Opcode::Ldeorl
+            | Opcode::Ldeoral
+            | Opcode::Ldeorb
+            | Opcode::Ldeorab
+            | Opcode::Ldeorlb
+            | Opcode::Ldeoralb
+            | Opcode::Ldeorh
+            | Opcode::Ldeorah
+            | Opcode::Ldeorlh
+            | Opcode::Ldeoralh
+            | Opcode::Ldset
+            | Opcode::Ldseta
+            | Opcode::Ldsetl
+            | Opcode::Ldsetal
+            | Opcode::Ldsetb
+            | Opcode::Ldsetab
+            | Opcode::Ldsetlb
+            | Opcode::Ldsetalb
+            | Opcode::Ldseth
+            | Opcode::Ldsetah
+            | Opcode::Ldsetlh
+            | Opcode::Ldsetalh
+            | Opcode::Swp
+            | Opcode::Swpa
+            | Opcode::Swpl
+            | Opcode::Swpal
+            | Opcode::Swpb
+            | Opcode::Swpab
+            | Opcode::Swplb
+            | Opcode::Swpalb
+            | Opcode::Swph
+            | Opcode::Swpah
+            | Opcode::Swplh
+            | Opcode::Swpalh => Some((0, 1)),
+            _ => None,
+        }
+    }
+
+    // swp writes the register as it is, the other atomics combine it with what was there
+    pub fn is_swap(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Swp
+                | Opcode::Swpa
+                | Opcode::Swpl
+                | Opcode::Swpal
+                | Opcode::Swpb
+                | Opcode::Swpab
+                | Opcode::Swplb
+                | Opcode::Swpalb
+                | Opcode::Swph
+                | Opcode::Swpah
+                | Opcode::Swplh
+                | Opcode::Swpalh
+        )
+    }
+
+    // the registers before the address whose bytes move, an atomic moves one register's worth and
+    // casp a pair
+    pub fn transferred<'a>(&self, registers: &'a [Operand]) -> &'a [Operand] {
+        match (self.is_store_exclusive(), self.atomic_operands()) {
+            (true, _) => &registers[1..],
+            (_, Some((source, _))) => &registers[source..source + registers.len() / 2],
+            _ => registers,
+        }
+    }
+
+    // exclusive, acquire-release and atomic accesses fault at addresses not aligned to their size
+    pub fn requires_alignment(&self) -> bool {
+        self.is_store_exclusive()
+            || self.atomic_operands().is_some()
+            || matches!(
+                self,
+                Opcode::Ldxr
+                    | Opcode::Ldxrb
+                    | Opcode::Ldxrh
+                    | Opcode::Ldxp
+                    | Opcode::Ldaxr
+                    | Opcode::Ldaxrb
+                    | Opcode::Ldaxrh
+                    | Opcode::Ldaxp
+                    | Opcode::Ldar
+                    | Opcode::Ldarb
+                    | Opcode::Ldarh
+                    | Opcode::Ldapr
+                    | Opcode::Ldaprb
+                    | Opcode::Ldaprh
+                    | Opcode::Stlr
+                    | Opcode::Stlrb
+                    | Opcode::Stlrh
+            )
+    }
+
+    // (elements in each structure, replicated to all lanes) for ld1-4 and st1-4
+    pub fn structure(&self) -> Option<(usize, bool)> {
+        match self {
+            Opcode::Ld1 | Opcode::St1 => Some((1, false)),
+            Opcode::Ld2 | Opcode::St2 => Some((2, false)),
+            Opcode::Ld3 | Opcode::St3 => Some((3, false)),
+            Opcode::Ld4 | Opcode::St4 => Some((4, false)),
+            Opcode::Ld1r => Some((1, true)),
+            Opcode::Ld2r => Some((2, true)),
+            Opcode::Ld3r => Some((3, true)),
+            Opcode::Ld4r => Some((4, true)),
+            _ => None,
+        }
+    }
+
+    // long and narrow forms, where source and destination arrangements differ
+    pub fn changes_element_size(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Ushll
+                | Opcode::Ushll2
+                | Opcode::Sshll
+                | Opcode::Sshll2
+                | Opcode::Uxtl
+                | Opcode::Uxtl2
+                | Opcode::Sxtl
+                | Opcode::Sxtl2
+                | Opcode::Xtn
+                | Opcode::Xtn2
+                | Opcode::Sqxtun
+                | Opcode::Sqxtun2
+                | Opcode::Shrn
+                | Opcode::Shrn2
+                | Opcode::Rshrn
+                | Opcode::Rshrn2
+                | Opcode::Sqshrun
+                | Opcode::Sqshrun2
+                | Opcode::Sqrshrun
+                | Opcode::Sqrshrun2
+                | Opcode::Uqshrn
+                | Opcode::Uqshrn2
+                | Opcode::Uqrshrn
+                | Opcode::Uqrshrn2
+                | Opcode::Uaddl
+                | Opcode::Uaddl2
+                | Opcode::Saddl
+                | Opcode::Saddl2
+                | Opcode::Usubl
+                | Opcode::Usubl2
+                | Opcode::Ssubl
+                | Opcode::Ssubl2
+                | Opcode::Umull
+                | Opcode::Umull2
+                | Opcode::Smull
+                | Opcode::Smull2
+                | Opcode::Umlal
+                | Opcode::Umlal2
+                | Opcode::Smlal
+                | Opcode::Smlal2
+                | Opcode::Pmull
+                | Opcode::Pmull2
+        )
+    }
+
+    // long and narrow forms that read or write the upper half of a register, i.e. uaddl2
+    pub fn upper_half(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Shrn2
+                | Opcode::Rshrn2
+                | Opcode::Sqshrun2
+                | Opcode::Sqrshrun2
+                | Opcode::Uqshrn2
+                | Opcode::Uqrshrn2
+                | Opcode::Ushll2
+                | Opcode::Sshll2
+                | Opcode::Uxtl2
+                | Opcode::Sxtl2
+                | Opcode::Xtn2
+                | Opcode::Sqxtun2
+                | Opcode::Uaddl2
+                | Opcode::Saddl2
+                | Opcode::Usubl2
+                | Opcode::Ssubl2
+                | Opcode::Umull2
+                | Opcode::Smull2
+                | Opcode::Umlal2
+                | Opcode::Smlal2
+                | Opcode::Pmull2
+        )
+    }
+
+    // bytes per element in memory for sve loads and stores, per counted element for cnt, inc and dec
+    pub fn sve_size(&self) -> Option<usize> {
+        match self {
+            Opcode::Ld1b | Opcode::Ld1sb | Opcode::St1b => Some(1),
+            Opcode::Cntb | Opcode::Incb | Opcode::Decb => Some(1),
+            Opcode::Ld1h | Opcode::Ld1sh | Opcode::St1h => Some(2),
+            Opcode::Cnth | Opcode::Inch | Opcode::Dech => Some(2),
+            Opcode::Ld1w | Opcode::Ld1sw | Opcode::St1w => Some(4),
+            Opcode::Cntw | Opcode::Incw | Opcode::Decw => Some(4),
+            Opcode::Ld1d | Opcode::St1d => Some(8),
+            Opcode::Cntd | Opcode::Incd | Opcode::Decd => Some(8),
+            _ => None,
+        }
+    }
+
+    // only exists in sve, even when no operand is a z or p register, i.e. incb x0
+    pub fn is_sve(&self) -> bool {
+        self.sve_size().is_some()
+            || matches!(
+                self,
+                Opcode::Whilelo
+                    | Opcode::Whilelt
+                    | Opcode::Whilels
+                    | Opcode::Whilele
+                    | Opcode::Ptrue
+                    | Opcode::Ptrues
+                    | Opcode::Pfalse
+                    | Opcode::Ptest
+                    | Opcode::Addvl
+                    | Opcode::Addpl
+                    | Opcode::Rdvl
+            )
+    }
+
+    // takes a condition code as its last operand
+    pub fn is_conditional_select(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Csel
+                | Opcode::Csinc
+                | Opcode::Csinv
+                | Opcode::Csneg
+                | Opcode::Cset
+                | Opcode::Csetm
+                | Opcode::Cinc
+                | Opcode::Cneg
+                | Opcode::Ccmp
+                | Opcode::Ccmn
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Eq,
+    Ne,
+    Hs, // also cs
+    Lo, // also cc
+    Mi,
+    Pl,
+    Vs,
+    Vc,
+    Hi,
+    Ls,
+    Ge,
+    Lt,
+    Gt,
+    Le,
+    Al,
+    Nv,
+}
+
+impl Condition {
+    pub fn from_string(s: &str) -> Option<Condition> {
+        match s {
+            "eq" => Some(Condition::Eq),
+            "ne" => Some(Condition::Ne),
+            "hs" | "cs" => Some(Condition::Hs),
+            "lo" | "cc" => Some(Condition::Lo),
+            "mi" => Some(Condition::Mi),
+            "pl" => Some(Condition::Pl),
+            "vs" => Some(Condition::Vs),
+            "vc" => Some(Condition::Vc),
+            "hi" => Some(Condition::Hi),
+            "ls" => Some(Condition::Ls),
+            "ge" => Some(Condition::Ge),
+            "lt" => Some(Condition::Lt),
+            "gt" => Some(Condition::Gt),
+            "le" => Some(Condition::Le),
+            "al" => Some(Condition::Al),
+            "nv" => Some(Condition::Nv),
+            // sve names for the flags set by predicates, i.e. b.first
+            "first" => Some(Condition::Mi),
+            "nfrst" => Some(Condition::Pl),
+            "none" => Some(Condition::Eq),
+            "any" => Some(Condition::Ne),
+            "last" => Some(Condition::Lo),
+            "nlast" => Some(Condition::Hs),
+            "pmore" => Some(Condition::Hi),
+            "plast" => Some(Condition::Ls),
+            "tcont" => Some(Condition::Ge),
+            "tstop" => Some(Condition::Lt),
+            _ => None,
+        }
+    }
+
+    pub fn invert(&self) -> Condition {
+        match self {
+            Condition::Eq => Condition::Ne,
+            Condition::Ne => Condition::Eq,
+            Condition::Hs => Condition::Lo,
+            Condition::Lo => Condition::Hs,
+            Condition::Mi => Condition::Pl,
+            Condition::Pl => Condition::Mi,
+            Condition::Vs => Condition::Vc,
+            Condition::Vc => Condition::Vs,
+            Condition::Hi => Condition::Ls,
+            Condition::Ls => Condition::Hi,
+            Condition::Ge => Condition::Lt,
+            Condition::Lt => Condition::Ge,
+            Condition::Gt => Condition::Le,
+            Condition::Le => Condition::Gt,
+            Condition::Al => Condition::Nv,
+            Condition::Nv => Condition::Al,
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Condition::Eq => "eq",
+            Condition::Ne => "ne",
+            Condition::Hs => "hs",
+            Condition::Lo => "lo",
+            Condition::Mi => "mi",
+            Condition::Pl => "pl",
+            Condition::Vs => "vs",
+            Condition::Vc => "vc",
+            Condition::Hi => "hi",
+            Condition::Ls => "ls",
+            Condition::Ge => "ge",
+            Condition::Lt => "lt",
+            Condition::Gt => "gt",
+            Condition::Le => "le",
+            Condition::Al => "al",
+            Condition::Nv => "nv",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// shifts and register extends, like the lsl in lsl#2 or the uxtw in [x0, w1, uxtw]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    Msl,
+    Uxtb,
+    Uxth,
+    Uxtw,
+    Uxtx,
+    Sxtb,
+    Sxth,
+    Sxtw,
+    Sxtx,
+}
+
+impl Shift {
+    pub fn from_string(s: &str) -> Option<Shift> {
+        match s {
+            "lsl" => Some(Shift::Lsl),
+            "lsr" => Some(Shift::Lsr),
+            "asr" => Some(Shift::Asr),
+            "ror" => Some(Shift::Ror),
+            "msl" => Some(Shift::Msl),
+            "uxtb" => Some(Shift::Uxtb),
+            "uxth" => Some(Shift::Uxth),
+            "uxtw" => Some(Shift::Uxtw),
+            "uxtx" => Some(Shift::Uxtx),
+            "sxtb" => Some(Shift::Sxtb),
+            "sxth" => Some(Shift::Sxth),
+            "sxtw" => Some(Shift::Sxtw),
+            "sxtx" => Some(Shift::Sxtx),
+            _ => None,
+        }
+    }
+
+    pub fn is_extend(&self) -> bool {
+        !matches!(
+            self,
+            Shift::Lsl | Shift::Lsr | Shift::Asr | Shift::Ror | Shift::Msl
+        )
+    }
+}
+
+impl std::fmt::Display for Shift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Shift::Lsl => "lsl",
+            Shift::Lsr => "lsr",
+            Shift::Asr => "asr",
+            Shift::Ror => "ror",
+            Shift::Msl => "msl",
+            Shift::Uxtb => "uxtb",
+            Shift::Uxth => "uxth",
+            Shift::Uxtw => "uxtw",
+            Shift::Uxtx => "uxtx",
+            Shift::Sxtb => "sxtb",
+            Shift::Sxth => "sxth",
+            Shift::Sxtw => "sxtw",
+            Shift::Sxtx => "sxtx",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Arrangement {
     B8,
@@ -29,6 +763,15 @@ pub enum RePrefix {
     Sp,
     Ra,
     Ze,
+    Z, // sve vector, the lower 128 bits are the v register of the same number
+    P, // sve predicate
+}
+
+// what happens to inactive lanes, the z or m in p0/z and p0/m
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredicateMode {
+    Zeroing,
+    Merging,
 }
 
 impl Arrangement {
@@ -48,9 +791,37 @@ impl Arrangement {
             _ => panic!("Invalid arrangement string {:?}", s),
         }
     }
+
+    // the single element an arrangement is made of, i.e. 4s -> s
+    pub fn element(&self) -> Arrangement {
+        match self {
+            Arrangement::B8 | Arrangement::B16 | Arrangement::B => Arrangement::B,
+            Arrangement::H4 | Arrangement::H8 | Arrangement::H => Arrangement::H,
+            Arrangement::S2 | Arrangement::S4 | Arrangement::S => Arrangement::S,
+            Arrangement::D2 | Arrangement::D => Arrangement::D,
+        }
+    }
+
+    // bytes in one element
+    pub fn element_size(&self) -> usize {
+        match self.element() {
+            Arrangement::B => 1,
+            Arrangement::H => 2,
+            Arrangement::S => 4,
+            _ => 8,
+        }
+    }
+
+    // bytes of the register the arrangement covers, 8 for the lower half only, i.e. 8b
+    pub fn size(&self) -> usize {
+        match self {
+            Arrangement::B8 | Arrangement::H4 | Arrangement::S2 => 8,
+            Arrangement::B16 | Arrangement::H8 | Arrangement::S4 | Arrangement::D2 => 16,
+            a => a.element_size(),
+        }
+    }
 }
 
-// BIG TODO: get rid of all the strings!
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operand {
     Register(RePrefix, usize),
@@ -61,26 +832,74 @@ pub enum Operand {
         Option<i64>,
         Option<Box<Operand>>,
         Option<bool>,
+        Option<(Shift, i64)>,
     ), // like [x0, #16] // bool to represent pre/post index false = pre, true = post
-    Bitwise(String, i64), // like lsl#2, TODO: make enum for shift types
+    // the extend and shift of the index register last, like the lsl #3 of [x0, x1, lsl #3]
+    Bitwise(Shift, i64), // like lsl#2
     // the "string" param is probably always going to be "v"
     VectorRegister(RePrefix, usize),
     Vector(RePrefix, usize, Arrangement),
     VectorAccess(RePrefix, usize, Arrangement, i64), // like v1.d[1] or v2.b[3]
     Label(String),
     Address(String, i64), // for relative addresses, i.e. LK256@PAGEOFF
+    Condition(Condition), // like the eq in cset w0, eq
+    Predicate(usize, Option<Arrangement>, Option<PredicateMode>), // like p0.b or p0/z
     Other,
 }
 pub fn register_to_tuple(r: &Operand) -> (RePrefix, usize) {
     match r.clone() {
         Operand::Register(prefix, index) => (prefix, index),
         Operand::VectorRegister(prefix, index) => (prefix, index),
-        Operand::Memory(prefix, index, _, _, _) => (prefix, index),
+        Operand::Memory(prefix, index, ..) => (prefix, index),
         _ => panic!("Expected a register or vector register operand"),
     }
 }
 
+// z and p registers, like z0.b, z1.d[1], p0/z or p1.s
+fn sve_operand(a: &str) -> Option<Operand> {
+    let (prefix, rest) = a.split_at(1);
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(digits);
+    let n = number.parse::<usize>().ok()?;
+    match (prefix, suffix) {
+        ("z", "") | ("z", ".q") => Some(Operand::VectorRegister(RePrefix::Z, n)),
+        ("z", _) => {
+            let element = suffix.strip_prefix('.')?;
+            match element.split_once('[') {
+                Some((element, index)) => Some(Operand::VectorAccess(
+                    RePrefix::Z,
+                    n,
+                    Arrangement::from_string(element),
+                    string_to_int(index.trim_end_matches(']')),
+                )),
+                None => Some(Operand::Vector(
+                    RePrefix::Z,
+                    n,
+                    Arrangement::from_string(element),
+                )),
+            }
+        }
+        ("p", "") => Some(Operand::Predicate(n, None, None)),
+        ("p", "/z") => Some(Operand::Predicate(n, None, Some(PredicateMode::Zeroing))),
+        ("p", "/m") => Some(Operand::Predicate(n, None, Some(PredicateMode::Merging))),
+        ("p", _) => Some(Operand::Predicate(
+            n,
+            Some(Arrangement::from_string(suffix.strip_prefix('.')?)),
+            None,
+        )),
+        _ => None,
+    }
+}
+
 pub fn operand_from_string(a: String) -> Operand {
+    if a.starts_with('z') || a.starts_with('p') {
+        if let Some(operand) = sve_operand(&a) {
+            return operand;
+        }
+    }
+
     if a.starts_with("x")
         || a.starts_with("z")
         || a.starts_with("w")
@@ -136,16 +955,11 @@ pub fn operand_from_string(a: String) -> Operand {
     // FIX: potential issue with this that can be fixed by checking shift indicator matches expected ones, i.e. lsl, lsr, asr, ror
     if a.contains("#") & !a.contains("[") {
         let mut parts = a.split('#').peekable();
-        if !parts
-            .peek()
-            .expect("need some strings in shift parsing")
-            .is_empty()
+        if let Some(shift) =
+            Shift::from_string(parts.next().expect("need some strings in shift parsing"))
         {
             return Operand::Bitwise(
-                parts
-                    .next()
-                    .expect("need part before # in shift parsing")
-                    .to_string(),
+                shift,
                 parts
                     .next()
                     .and_then(|s| s.parse::<i64>().ok())
@@ -154,8 +968,10 @@ pub fn operand_from_string(a: String) -> Operand {
         }
     }
 
-    if a == "uxtw" || a == "uxtb" || a == "uxth" {
-        return Operand::Bitwise(a, 0);
+    if let Some(shift) = Shift::from_string(&a) {
+        if shift.is_extend() {
+            return Operand::Bitwise(shift, 0);
+        }
     }
 
     if a.starts_with("v") {
@@ -165,6 +981,13 @@ pub fn operand_from_string(a: String) -> Operand {
                 .next()
                 .expect("require base register for simd register");
             let arrangement = parts.next().expect("require a valid simd arrangement");
+            if arrangement == "1q" {
+                // the whole register, i.e. the product of pmull v0.1q, v1.1d, v2.1d
+                let parts = base.split_at(1);
+                if let Ok(value) = parts.1.parse::<usize>() {
+                    return Operand::VectorRegister(RePrefix::V, value);
+                }
+            }
             if arrangement.contains("[") {
                 let mut parts = arrangement.split(&['[', ']']).into_iter();
                 let a = Arrangement::from_string(
@@ -212,6 +1035,13 @@ pub fn operand_from_string(a: String) -> Operand {
         }
     }
 
+    // the whole vector register, i.e. ldr q0, [x5]
+    if a.starts_with('q') || a.starts_with('Q') {
+        if let Ok(value) = a[1..].parse::<usize>() {
+            return Operand::VectorRegister(RePrefix::V, value);
+        }
+    }
+
     // equivalent to vector registers
     if a.starts_with("D")
         || a.starts_with("S")
@@ -252,9 +1082,24 @@ pub fn operand_from_string(a: String) -> Operand {
             base = b.trim_matches(&['[', ']', ',']).to_string();
         }
 
+        let mut extend: Option<(Shift, i64)> = None;
         if let Some(o) = parts.next() {
-            if o.starts_with("x") || o.starts_with("w") {
-                register_offset = Some(Box::new(operand_from_string(o.to_string())));
+            if o.starts_with("x") || o.starts_with("w") || o.starts_with("z") {
+                register_offset = Some(Box::new(operand_from_string(
+                    o.trim_matches([']', '!']).to_string(),
+                )));
+                // lsl #3, lsl#3 or uxtw, the amount is zero when it is not written
+                if let Some(s) = parts.next() {
+                    let (name, amount) = match s.trim_matches(']').split_once('#') {
+                        Some((name, amount)) => (name, Some(amount.to_string())),
+                        None => (s.trim_matches(']'), parts.next().map(|a| a.to_string())),
+                    };
+                    if let Some(shift) = Shift::from_string(name) {
+                        let amount =
+                            amount.map_or(0, |a| string_to_int(a.trim_matches(['#', ']'])));
+                        extend = Some((shift, amount));
+                    }
+                }
             } else {
                 offset = Some(string_to_int(o.trim_matches(&['[', ']', ',', '#', '!'])));
             }
@@ -275,20 +1120,21 @@ pub fn operand_from_string(a: String) -> Operand {
                 "xzr" | "ze" => RePrefix::Ze,
                 _ => {
                     let reg = base.split_at(1);
-                    if let Ok(n) = reg.1.parse::<usize>() {
+                    // vector bases of sve gathers carry an element, i.e. [z1.d, #8]
+                    if let Ok(n) = reg.1.split('.').next().unwrap_or(reg.1).parse::<usize>() {
                         num = n;
                     }
                     match reg.0 {
                         "x" => RePrefix::X,
                         "w" => RePrefix::W,
+                        "z" => RePrefix::Z,
                         _ => panic!("Unknown register prefix: {}", reg.0),
                     }
                 }
             }
         };
 
-        // TODO: include shift
-        return Operand::Memory(prefix, num, offset, register_offset, indexing);
+        return Operand::Memory(prefix, num, offset, register_offset, indexing, extend);
     }
 
     if !a.is_empty() {
@@ -299,6 +1145,60 @@ pub fn operand_from_string(a: String) -> Operand {
     return Operand::Other;
 }
 
+// register lists like {v0.16b-v3.16b} only name the first and last register,
+// the list wraps around after v31 (or z31)
+fn expand_register_lists(parts: Vec<String>) -> Vec<String> {
+    let mut result = Vec::new();
+    for part in parts {
+        let Some((first, last)) = part.split_once('-').filter(|(first, last)| {
+            (first.starts_with('v') || first.starts_with('z')) && last.get(..1) == first.get(..1)
+        }) else {
+            result.push(part);
+            continue;
+        };
+        let prefix = &first[..1];
+
+        let (first, arrangement) = first.split_at(first.find('.').unwrap_or(first.len()));
+        let (last, index) = last.split_at(last.find('[').unwrap_or(last.len()));
+        let last = last
+            .split('.')
+            .next()
+            .expect("need register number in list");
+        let (Ok(first), Ok(last)) = (first[1..].parse::<usize>(), last[1..].parse::<usize>())
+        else {
+            result.push(part);
+            continue;
+        };
+
+        let count = (last + 32 - first) % 32 + 1;
+        for i in 0..count {
+            result.push(format!("{}{}{}", prefix, (first + i) % 32, arrangement));
+        }
+        if let Some(l) = result.last_mut() {
+            l.push_str(index);
+        }
+    }
+    result
+}
+
+// shifts written with a space like movi v0.4s, #1, lsl #8 are one operand, as in lsl#8
+fn join_shift_amounts(parts: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for part in parts {
+        match result.last_mut() {
+            Some(shift)
+                if Shift::from_string(shift).is_some()
+                    && part.starts_with('#')
+                    && !part.contains(']') =>
+            {
+                shift.push_str(&part)
+            }
+            _ => result.push(part),
+        }
+    }
+    result
+}
+
 fn combine_addressing_modes_operands(parts: Vec<String>) -> Vec<String> {
     let mut result = Vec::new();
 
@@ -336,16 +1236,15 @@ pub enum InstructionType {
     Other, // catchall for now, add other subtypes like jumps, comparisons, etc... if necessary
 }
 
-fn match_instruction_type(opcode: &str, operands: &[Operand]) -> InstructionType {
-    if operands.is_empty() {
-        if opcode.ends_with(':') {
-            return InstructionType::Label;
-        }
-        return InstructionType::Other;
+fn match_instruction_type(opcode: &Opcode, operands: &[Operand]) -> InstructionType {
+    match opcode {
+        Opcode::Label(_) => return InstructionType::Label,
+        Opcode::Directive(_) => return InstructionType::Def,
+        opcode if opcode.is_control_flow() => return InstructionType::ControlFlow,
+        _ => (),
+    }
     // considered SIMDManagement when there is a vector access or when a vector is loaded/stored from a regular register
-    } else if opcode.starts_with('.') || opcode.starts_with('#') {
-        return InstructionType::Def;
-    } else if operands
+    if operands
         .iter()
         .any(|op| matches!(op, Operand::VectorAccess(..)))
         || ((operands.iter().any(|op| matches!(op, Operand::Vector(..)))
@@ -375,13 +1274,7 @@ fn match_instruction_type(opcode: &str, operands: &[Operand]) -> InstructionType
         .any(|op| matches!(op, Operand::Vector(..)) || matches!(op, Operand::VectorRegister(..)))
     {
         return InstructionType::SIMDArithmetic;
-    } else if opcode.starts_with("b")
-        || opcode.starts_with("j")
-        || opcode.contains("adr")
-        || opcode.starts_with("adrp")
-        || opcode == "ret"
-        || operands.iter().any(|op| matches!(op, Operand::Label(..)))
-    {
+    } else if operands.iter().any(|op| matches!(op, Operand::Label(..))) {
         return InstructionType::ControlFlow;
     }
     return InstructionType::Other;
@@ -400,25 +1293,77 @@ impl Instruction {
             .collect::<Vec<&str>>()
             .into_iter()
             .filter(|x| !x.is_empty());
-        let mut opcode = parts
+        let opcode = parts
             .next()
             .expect("Require opcode for instruction")
             .to_string();
 
-        let combine_brackets =
-            combine_addressing_modes_operands(parts.into_iter().map(|s| s.to_string()).collect());
+        let combine_brackets = combine_addressing_modes_operands(join_shift_amounts(
+            expand_register_lists(parts.into_iter().map(|s| s.to_string()).collect()),
+        ));
 
         let operands: Vec<Operand> = combine_brackets
             .into_iter()
             .map(|s| operand_from_string(s.to_string()))
             .collect();
 
-        let ty = match_instruction_type(&opcode, &operands);
+        Instruction::from_operands(opcode, operands)
+    }
+
+    // for front-ends that already know the operands, i.e. the object file decoder
+    pub fn from_operands(mnemonic: String, mut operands: Vec<Operand>) -> Self {
+        let opcode = if let Some((base, suffix)) = mnemonic
+            .split_once('.')
+            .filter(|(base, _)| !base.is_empty() && *base != "b" && !mnemonic.ends_with(':'))
+        {
+            // rav1d notation like rev64.16b v0, v1 is the same as rev64 v0.16b, v1.16b
+            let opcode = Opcode::from_string(base);
+            let arrangement = Arrangement::from_string(suffix);
+            // the suffix only names the destination when the element size changes, i.e. ushll.8h
+            for operand in operands
+                .iter_mut()
+                .filter(|_| !opcode.changes_element_size())
+            {
+                match operand {
+                    Operand::VectorRegister(prefix, n) => {
+                        *operand = Operand::Vector(prefix.clone(), *n, arrangement.clone())
+                    }
+                    Operand::VectorAccess(_, _, a, _) => *a = arrangement.element(),
+                    _ => (),
+                }
+            }
+            opcode
+        } else {
+            Opcode::from_string(&mnemonic)
+        };
+
+        if opcode.is_conditional_select() {
+            if let Some(Operand::Label(cond)) = operands.last() {
+                if let Some(c) = Condition::from_string(cond) {
+                    *operands.last_mut().expect("operands are not empty") = Operand::Condition(c);
+                }
+            }
+        }
 
-        if ty == InstructionType::Label {
-            opcode = opcode.trim_matches(|c| c == ':' || c == '_').to_string();
+        // lane lists like {v0.s, v1.s}[1] only write the index after the last register
+        if opcode.structure().is_some() {
+            let lane = operands.iter().find_map(|o| match o {
+                Operand::VectorAccess(_, _, _, index) => Some(*index),
+                _ => None,
+            });
+            if let Some(index) = lane {
+                for operand in operands.iter_mut() {
+                    if let Operand::Vector(prefix, n, a) = operand {
+                        if a.element() == *a {
+                            *operand = Operand::VectorAccess(prefix.clone(), *n, a.clone(), index);
+                        }
+                    }
+                }
+            }
         }
 
+        let ty = match_instruction_type(&opcode, &operands);
+
         Instruction {
             ty,
             opcode,
@@ -435,6 +1380,19 @@ impl Instruction {
         })
     }
 
+    pub fn is_sve(&self) -> bool {
+        self.opcode.is_sve()
+            || self.operands.iter().any(|op| {
+                matches!(
+                    op,
+                    Operand::VectorRegister(RePrefix::Z, _)
+                        | Operand::Vector(RePrefix::Z, ..)
+                        | Operand::VectorAccess(RePrefix::Z, ..)
+                        | Operand::Predicate(..)
+                )
+            })
+    }
+
     pub fn is_label(&self) -> bool {
         self.ty == InstructionType::Label
     }
@@ -448,7 +1406,7 @@ mod tests {
     fn test_parse_add_register() {
         let good_result = Instruction {
             ty: InstructionType::Arithmetic,
-            opcode: String::from("add"),
+            opcode: Opcode::Add,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
                 Operand::Register(RePrefix::X, 0),
@@ -472,7 +1430,7 @@ mod tests {
     fn test_parse_add_immediate() {
         let good_result = Instruction {
             ty: InstructionType::Arithmetic,
-            opcode: String::from("add"),
+            opcode: Opcode::Add,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
                 Operand::Register(RePrefix::X, 0),
@@ -487,12 +1445,12 @@ mod tests {
     fn test_parse_add_shifted_immediate() {
         let good_result = Instruction {
             ty: InstructionType::Arithmetic,
-            opcode: String::from("add"),
+            opcode: Opcode::Add,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
                 Operand::Register(RePrefix::X, 0),
                 Operand::Immediate(2),
-                Operand::Bitwise(String::from("lsl"), 12),
+                Operand::Bitwise(Shift::Lsl, 12),
             ]),
         };
 
@@ -506,7 +1464,7 @@ mod tests {
     fn test_parse_rev_single_src() {
         let good_result = Instruction {
             ty: InstructionType::Other,
-            opcode: String::from("rev"),
+            opcode: Opcode::Rev,
             operands: Vec::from([
                 Operand::Register(RePrefix::W, 3),
                 Operand::Register(RePrefix::W, 3),
@@ -520,12 +1478,12 @@ mod tests {
     // fn test_parse_add_shifted_immediate_with_space() {
     //     let good_result = Instruction {
     //         ty: InstructionType::Arithmetic,
-    //         opcode: String::from("add"),
+    //         opcode: Opcode::Add,
     //         operands: Vec::from([
     //             Operand::Register(RePrefix::X, 0),
     //             Operand::Register(RePrefix::X, 0),
     //             Operand::Register(RePrefix::X, 1),
-    //             Operand::Bitwise(String::from("lsl"), 12),
+    //             Operand::Bitwise(Shift::Lsl, 12),
     //         ]),
     //     };
 
@@ -539,7 +1497,7 @@ mod tests {
     fn test_parse_add_address() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("add"),
+            opcode: Opcode::Add,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 30),
                 Operand::Register(RePrefix::X, 30),
@@ -556,10 +1514,10 @@ mod tests {
     fn test_parse_str() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("str"),
+            opcode: Opcode::Str,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
-                Operand::Memory(RePrefix::X, 29, None, None, None),
+                Operand::Memory(RePrefix::X, 29, None, None, None, None),
             ]),
         };
         assert_eq!(Instruction::new("str x0,[x29]".to_string()), good_result);
@@ -569,10 +1527,10 @@ mod tests {
     fn test_parse_str_immediate() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("str"),
+            opcode: Opcode::Str,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
-                Operand::Memory(RePrefix::X, 29, Some(112), None, None),
+                Operand::Memory(RePrefix::X, 29, Some(112), None, None, None),
             ]),
         };
         assert_eq!(
@@ -581,15 +1539,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_ldr_q_register() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDManagement,
+            opcode: Opcode::Ldr,
+            operands: Vec::from([
+                Operand::VectorRegister(RePrefix::V, 0),
+                Operand::Memory(RePrefix::X, 5, Some(16), None, None, None),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("ldr q0, [x5, #16]".to_string()),
+            good_result
+        );
+    }
+
     #[test]
     fn test_parse_stp_register_address() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 20),
                 Operand::Register(RePrefix::X, 21),
-                Operand::Memory(RePrefix::X, 0, None, None, None),
+                Operand::Memory(RePrefix::X, 0, None, None, None, None),
             ]),
         };
         assert_eq!(
@@ -602,11 +1576,11 @@ mod tests {
     fn test_parse_stp_signed_offset() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 22),
                 Operand::Register(RePrefix::X, 23),
-                Operand::Memory(RePrefix::X, 0, Some(8), None, None),
+                Operand::Memory(RePrefix::X, 0, Some(8), None, None, None),
             ]),
         };
         assert_eq!(
@@ -619,11 +1593,11 @@ mod tests {
     fn test_parse_stp_signed_offset_arithmetic() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 22),
                 Operand::Register(RePrefix::X, 23),
-                Operand::Memory(RePrefix::X, 0, Some(8), None, None),
+                Operand::Memory(RePrefix::X, 0, Some(8), None, None, None),
             ]),
         };
         assert_eq!(
@@ -636,11 +1610,11 @@ mod tests {
     fn test_parse_stp_post_index() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 22),
                 Operand::Register(RePrefix::X, 23),
-                Operand::Memory(RePrefix::X, 0, Some(8), None, Some(true)),
+                Operand::Memory(RePrefix::X, 0, Some(8), None, Some(true), None),
             ]),
         };
         assert_eq!(
@@ -653,11 +1627,11 @@ mod tests {
     fn test_parse_stp_post_index_arithmetic() {
         let good_result = Instruction {
             ty: InstructionType::Memory,
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 22),
                 Operand::Register(RePrefix::X, 23),
-                Operand::Memory(RePrefix::X, 0, Some(8), None, Some(true)),
+                Operand::Memory(RePrefix::X, 0, Some(8), None, Some(true), None),
             ]),
         };
         assert_eq!(
@@ -669,12 +1643,12 @@ mod tests {
     #[test]
     fn test_parse_stp_pre_index() {
         let good_result = Instruction {
-            opcode: String::from("stp"),
+            opcode: Opcode::Stp,
             ty: InstructionType::Memory,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 29),
                 Operand::Register(RePrefix::X, 30),
-                Operand::Memory(RePrefix::X, 0, Some(-128), None, Some(false)),
+                Operand::Memory(RePrefix::X, 0, Some(-128), None, Some(false), None),
             ]),
         };
         assert_eq!(
@@ -689,7 +1663,7 @@ mod tests {
     fn test_parse_cmp_register_immediate() {
         let good_result = Instruction {
             ty: InstructionType::Other,
-            opcode: String::from("cmp"),
+            opcode: Opcode::Cmp,
             operands: Vec::from([Operand::Register(RePrefix::X, 0), Operand::Immediate(2)]),
         };
         assert_eq!(Instruction::new("cmp x0,#2".to_string()), good_result);
@@ -699,7 +1673,7 @@ mod tests {
     fn test_parse_cmp_register() {
         let good_result = Instruction {
             ty: InstructionType::Other,
-            opcode: String::from("cmp"),
+            opcode: Opcode::Cmp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
                 Operand::Register(RePrefix::X, 1),
@@ -712,11 +1686,11 @@ mod tests {
     fn test_parse_cmp_shifted_register() {
         let good_result = Instruction {
             ty: InstructionType::Arithmetic,
-            opcode: String::from("cmp"),
+            opcode: Opcode::Cmp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 0),
                 Operand::Register(RePrefix::X, 1),
-                Operand::Bitwise(String::from("lsr"), 2),
+                Operand::Bitwise(Shift::Lsr, 2),
             ]),
         };
         assert_eq!(Instruction::new("cmp x0,x1,lsr#2".to_string()), good_result);
@@ -726,7 +1700,7 @@ mod tests {
     fn test_parse_adrp() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("adrp"),
+            opcode: Opcode::Adrp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 30),
                 Operand::Address(String::from("LK256@PAGE"), 0),
@@ -742,7 +1716,7 @@ mod tests {
     fn test_parse_adrp_from_sha256() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("adrp"),
+            opcode: Opcode::Adrp,
             operands: Vec::from([
                 Operand::Register(RePrefix::X, 9),
                 Operand::Address(String::from("_BORINGSSL_function_hit@PAGEE"), 0),
@@ -758,17 +1732,36 @@ mod tests {
     fn test_parse_b_condition_bne() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("b.ne"),
+            opcode: Opcode::BCond(Condition::Ne),
             operands: Vec::from([Operand::Label(String::from("Loop"))]),
         };
         assert_eq!(Instruction::new("b.ne Loop".to_string()), good_result);
+        assert_eq!(Instruction::new("bne Loop".to_string()), good_result);
+    }
+
+    #[test]
+    fn test_parse_csel_condition_alias() {
+        let good_result = Instruction {
+            ty: InstructionType::Other,
+            opcode: Opcode::Csel,
+            operands: Vec::from([
+                Operand::Register(RePrefix::X, 0),
+                Operand::Register(RePrefix::X, 1),
+                Operand::Register(RePrefix::X, 2),
+                Operand::Condition(Condition::Lo),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("csel x0, x1, x2, cc".to_string()),
+            good_result
+        );
     }
 
     #[test]
     fn test_parse_b() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("b"),
+            opcode: Opcode::B,
             operands: Vec::from([Operand::Label(String::from("Loop"))]),
         };
         assert_eq!(Instruction::new("b Loop".to_string()), good_result);
@@ -778,7 +1771,7 @@ mod tests {
     fn test_parse_cbnz() {
         let good_result = Instruction {
             ty: InstructionType::ControlFlow,
-            opcode: String::from("cbnz"),
+            opcode: Opcode::Cbnz,
             operands: Vec::from([
                 Operand::Register(RePrefix::W, 19),
                 Operand::Label(String::from("Loop_16_xx")),
@@ -790,11 +1783,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_b_condition_without_dot() {
+        for (mnemonic, condition) in [
+            ("bhi", Condition::Hi),
+            ("bls", Condition::Ls),
+            ("bcc", Condition::Lo),
+            ("bmi", Condition::Mi),
+            ("blt", Condition::Lt),
+            ("ble", Condition::Le),
+        ] {
+            let good_result = Instruction {
+                ty: InstructionType::ControlFlow,
+                opcode: Opcode::BCond(condition),
+                operands: Vec::from([Operand::Label(String::from("Loop"))]),
+            };
+            assert_eq!(Instruction::new(format!("{} Loop", mnemonic)), good_result);
+        }
+        // mnemonics that look like a condition after the b stay what they are
+        assert_eq!(Opcode::from_string("bic"), Opcode::Bic);
+        assert_eq!(Opcode::from_string("blr"), Opcode::Blr);
+        assert_eq!(
+            Opcode::from_string("bxx"),
+            Opcode::Unknown("bxx".to_string())
+        );
+    }
+
     #[test]
     fn test_parse_ret() {
         let good_result = Instruction {
-            ty: InstructionType::Other,
-            opcode: String::from("ret"),
+            ty: InstructionType::ControlFlow,
+            opcode: Opcode::Ret,
             operands: Vec::new(),
         };
         assert_eq!(Instruction::new("ret".to_string()), good_result);
@@ -804,10 +1823,10 @@ mod tests {
     fn test_parse_simd_ld1() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("ld1"),
+            opcode: Opcode::Ld1,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 0, Arrangement::B16),
-                Operand::Memory(RePrefix::X, 16, None, None, None),
+                Operand::Memory(RePrefix::X, 16, None, None, None, None),
             ]),
         };
         assert_eq!(
@@ -819,13 +1838,13 @@ mod tests {
     fn test_parse_simd_ld1_multiple_dst() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("ld1"),
+            opcode: Opcode::Ld1,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 24, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 25, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 26, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 27, Arrangement::D2),
-                Operand::Memory(RePrefix::X, 10, None, None, None),
+                Operand::Memory(RePrefix::X, 10, None, None, None, None),
             ]),
         };
         assert_eq!(
@@ -837,13 +1856,13 @@ mod tests {
     fn test_parse_simd_ld1_multiple_dst_with_offset() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("ld1"),
+            opcode: Opcode::Ld1,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 20, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 21, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 22, Arrangement::D2),
                 Operand::Vector(RePrefix::V, 23, Arrangement::D2),
-                Operand::Memory(RePrefix::X, 10, Some(64), None, Some(true)),
+                Operand::Memory(RePrefix::X, 10, Some(64), None, Some(true), None),
             ]),
         };
         assert_eq!(
@@ -856,10 +1875,10 @@ mod tests {
     fn test_parse_simd_st1() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("st1"),
+            opcode: Opcode::St1,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 5, Arrangement::H8),
-                Operand::Memory(RePrefix::X, 0, None, None, None),
+                Operand::Memory(RePrefix::X, 0, None, None, None, None),
             ]),
         };
         assert_eq!(
@@ -871,7 +1890,7 @@ mod tests {
     fn test_parse_simd_st1_register_offset() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("st1"),
+            opcode: Opcode::St1,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 0, Arrangement::S4),
                 Operand::Memory(
@@ -880,6 +1899,7 @@ mod tests {
                     None,
                     Some(Box::new(Operand::Register(RePrefix::X, 4))),
                     Some(true),
+                    None,
                 ),
             ]),
         };
@@ -893,10 +1913,10 @@ mod tests {
     fn test_parse_simd_st1_indexed() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("st1"),
+            opcode: Opcode::St1,
             operands: Vec::from([
                 Operand::VectorAccess(RePrefix::V, 1, Arrangement::S, 0),
-                Operand::Memory(RePrefix::X, 0, None, None, None),
+                Operand::Memory(RePrefix::X, 0, None, None, None, None),
             ]),
         };
         assert_eq!(
@@ -909,7 +1929,7 @@ mod tests {
     fn test_parse_simd_movi() {
         let good_result = Instruction {
             ty: InstructionType::SIMDArithmetic,
-            opcode: String::from("movi"),
+            opcode: Opcode::Movi,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 19, Arrangement::B16),
                 Operand::Immediate(0xe1),
@@ -925,7 +1945,7 @@ mod tests {
     fn test_parse_simd_aese() {
         let good_result = Instruction {
             ty: InstructionType::SIMDArithmetic,
-            opcode: String::from("aese"),
+            opcode: Opcode::Aese,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 0, Arrangement::B16),
                 Operand::Vector(RePrefix::V, 18, Arrangement::B16),
@@ -941,7 +1961,7 @@ mod tests {
     fn test_parse_simd_fmov() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("fmov"),
+            opcode: Opcode::Fmov,
             operands: Vec::from([
                 Operand::VectorAccess(RePrefix::V, 1, Arrangement::D, 1),
                 Operand::Register(RePrefix::X, 9),
@@ -957,7 +1977,7 @@ mod tests {
     fn test_parse_simd_ext() {
         let good_result = Instruction {
             ty: InstructionType::SIMDArithmetic,
-            opcode: String::from("ext"),
+            opcode: Opcode::Ext,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 14, Arrangement::B16),
                 Operand::Vector(RePrefix::V, 14, Arrangement::B16),
@@ -975,7 +1995,7 @@ mod tests {
     fn test_parse_simd_arithmetic() {
         let good_result = Instruction {
             ty: InstructionType::SIMDArithmetic,
-            opcode: String::from("eor"),
+            opcode: Opcode::Eor,
             operands: Vec::from([
                 Operand::Vector(RePrefix::V, 1, Arrangement::B16),
                 Operand::Vector(RePrefix::V, 1, Arrangement::B16),
@@ -988,16 +2008,160 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_simd_register_list_range() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDManagement,
+            opcode: Opcode::Ld1,
+            operands: Vec::from([
+                Operand::Vector(RePrefix::V, 30, Arrangement::B16),
+                Operand::Vector(RePrefix::V, 31, Arrangement::B16),
+                Operand::Vector(RePrefix::V, 0, Arrangement::B16),
+                Operand::Memory(RePrefix::X, 0, Some(48), None, Some(true), None),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("ld1 {v30.16b-v0.16b}, [x0], #48".to_string()),
+            good_result
+        );
+    }
+
+    #[test]
+    fn test_parse_simd_immediate_with_spaced_shift() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDArithmetic,
+            opcode: Opcode::Movi,
+            operands: Vec::from([
+                Operand::Vector(RePrefix::V, 0, Arrangement::S4),
+                Operand::Immediate(1),
+                Operand::Bitwise(Shift::Lsl, 8),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("movi v0.4s, #1, lsl #8".to_string()),
+            good_result
+        );
+    }
+
+    #[test]
+    fn test_parse_simd_pmull_whole_register() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDArithmetic,
+            opcode: Opcode::Pmull,
+            operands: Vec::from([
+                Operand::VectorRegister(RePrefix::V, 0),
+                Operand::Vector(RePrefix::V, 1, Arrangement::D),
+                Operand::Vector(RePrefix::V, 2, Arrangement::D),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("pmull v0.1q, v1.1d, v2.1d".to_string()),
+            good_result
+        );
+    }
+
+    #[test]
+    fn test_parse_sve_predicated_load() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDManagement,
+            opcode: Opcode::Ld1b,
+            operands: Vec::from([
+                Operand::Vector(RePrefix::Z, 0, Arrangement::B),
+                Operand::Predicate(0, None, Some(PredicateMode::Zeroing)),
+                Operand::Memory(
+                    RePrefix::X,
+                    0,
+                    None,
+                    Some(Box::new(Operand::Register(RePrefix::X, 1))),
+                    None,
+                    None,
+                ),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("ld1b {z0.b}, p0/z, [x0, x1]".to_string()),
+            good_result
+        );
+
+        let vl_offset = Instruction::new("st1w {z1.s}, p1, [x2, #1, mul vl]".to_string());
+        assert_eq!(vl_offset.opcode, Opcode::St1w);
+        assert_eq!(
+            vl_offset.operands,
+            Vec::from([
+                Operand::Vector(RePrefix::Z, 1, Arrangement::S),
+                Operand::Predicate(1, None, None),
+                Operand::Memory(RePrefix::X, 2, Some(1), None, None, None),
+            ])
+        );
+
+        let list = Instruction::new("ld2w {z31.s-z0.s}, p1/z, [x2]".to_string());
+        assert_eq!(
+            list.operands[..2],
+            [
+                Operand::Vector(RePrefix::Z, 31, Arrangement::S),
+                Operand::Vector(RePrefix::Z, 0, Arrangement::S),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sve_while_and_branch() {
+        let whilelo = Instruction::new("whilelo p0.s, x2, x1".to_string());
+        assert_eq!(whilelo.opcode, Opcode::Whilelo);
+        assert_eq!(
+            whilelo.operands,
+            Vec::from([
+                Operand::Predicate(0, Some(Arrangement::S), None),
+                Operand::Register(RePrefix::X, 2),
+                Operand::Register(RePrefix::X, 1),
+            ])
+        );
+        assert!(whilelo.is_sve());
+        assert!(Instruction::new("incb x2".to_string()).is_sve());
+        assert_eq!(
+            Instruction::new("b.first loop".to_string()).opcode,
+            Opcode::BCond(Condition::Mi)
+        );
+    }
+
+    #[test]
+    fn test_parse_simd_lane_list() {
+        let good_result = Instruction {
+            ty: InstructionType::SIMDManagement,
+            opcode: Opcode::Ld2,
+            operands: Vec::from([
+                Operand::VectorAccess(RePrefix::V, 0, Arrangement::S, 1),
+                Operand::VectorAccess(RePrefix::V, 1, Arrangement::S, 1),
+                Operand::Memory(
+                    RePrefix::X,
+                    1,
+                    None,
+                    Some(Box::new(Operand::Register(RePrefix::X, 2))),
+                    Some(true),
+                    None,
+                ),
+            ]),
+        };
+        assert_eq!(
+            Instruction::new("ld2 {v0.s, v1.s}[1], [x1], x2".to_string()),
+            good_result
+        );
+        assert_eq!(
+            Instruction::new("ld2 {v0.s-v1.s}[1], [x1], x2".to_string()),
+            good_result
+        );
+    }
+
     // this is what the SIMD used in rav1d looks like, may change with different decompilation pipeline
     #[test]
     fn test_parse_simd_st1_8h() {
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("st1.8h"),
+            opcode: Opcode::St1,
             operands: Vec::from([
-                Operand::VectorRegister(RePrefix::V, 30),
-                Operand::VectorRegister(RePrefix::V, 31),
-                Operand::Memory(RePrefix::X, 0, Some(32), None, Some(true)),
+                Operand::Vector(RePrefix::V, 30, Arrangement::H8),
+                Operand::Vector(RePrefix::V, 31, Arrangement::H8),
+                Operand::Memory(RePrefix::X, 0, Some(32), None, Some(true), None),
             ]),
         };
         assert_eq!(
@@ -1009,7 +2173,7 @@ mod tests {
     fn test_parse_simd_ushll() {
         let good_result = Instruction {
             ty: InstructionType::SIMDArithmetic,
-            opcode: String::from("ushll.8h"),
+            opcode: Opcode::Ushll,
             operands: Vec::from([
                 Operand::VectorRegister(RePrefix::V, 2),
                 Operand::VectorRegister(RePrefix::V, 2),
@@ -1026,7 +2190,7 @@ mod tests {
     fn test_parse_label() {
         let good_result = Instruction {
             ty: InstructionType::Label,
-            opcode: String::from("Loop"),
+            opcode: Opcode::Label(String::from("Loop")),
             operands: Vec::new(),
         };
         assert_eq!(Instruction::new("Loop:".to_string()), good_result);
@@ -1036,7 +2200,7 @@ mod tests {
     fn test_parse_label_with_underscore() {
         let good_result = Instruction {
             ty: InstructionType::Label,
-            opcode: String::from("Loop"),
+            opcode: Opcode::Label(String::from("Loop")),
             operands: Vec::new(),
         };
         assert_eq!(Instruction::new("_Loop:".to_string()), good_result);
@@ -1045,11 +2209,11 @@ mod tests {
     #[test]
     fn test_parse_cset_with_condition() {
         let good_result = Instruction {
-            ty: InstructionType::ControlFlow,
-            opcode: String::from("cset"),
+            ty: InstructionType::Other,
+            opcode: Opcode::Cset,
             operands: Vec::from([
                 Operand::Register(RePrefix::W, 0),
-                Operand::Label("eq".to_string()), // TODO: make this condition code enum
+                Operand::Condition(Condition::Eq),
             ]),
         };
         assert_eq!(Instruction::new("cset w0, eq".to_string()), good_result);
@@ -1059,7 +2223,7 @@ mod tests {
     fn test_parse_def_for_memory_long() {
         let good_result = Instruction {
             ty: InstructionType::Def,
-            opcode: String::from(".long"),
+            opcode: Opcode::Directive(String::from(".long")),
             operands: Vec::from([
                 Operand::Immediate(0x90befffa),
                 Operand::Immediate(0xa4506ceb),
@@ -1077,7 +2241,7 @@ mod tests {
     fn test_parse_globl_def() {
         let good_result = Instruction {
             ty: InstructionType::Def,
-            opcode: String::from(".globl"),
+            opcode: Opcode::Directive(String::from(".globl")),
             operands: Vec::from([Operand::Label(String::from("sha256_block_data_order"))]),
         };
         assert_eq!(
@@ -1091,15 +2255,16 @@ mod tests {
         //st1.d	{ v0 }[1], [x0], x4
         let good_result = Instruction {
             ty: InstructionType::SIMDManagement,
-            opcode: String::from("st1.d"),
+            opcode: Opcode::St1,
             operands: Vec::from([
-                Operand::VectorAccess(RePrefix::V, 0, Arrangement::S, 1),
+                Operand::VectorAccess(RePrefix::V, 0, Arrangement::D, 1),
                 Operand::Memory(
                     RePrefix::X,
                     0,
                     None,
                     Some(Box::new(Operand::Register(RePrefix::X, 4))),
                     Some(true),
+                    None,
                 ),
             ]),
         };
@@ -1108,6 +2273,36 @@ mod tests {
             good_result
         );
     }
+
+    #[test]
+    fn test_stores_include_atomics() {
+        for store in ["str", "stp", "st1d", "stlr", "stxr", "cas", "ldadd", "swp"] {
+            assert!(Opcode::from_string(store).is_store(), "{}", store);
+        }
+        for load in ["ldr", "ldp", "ld1", "ldar", "ldxr", "prfm"] {
+            assert!(!Opcode::from_string(load).is_store(), "{}", load);
+        }
+    }
+
+    #[test]
+    fn test_alignment_required_by_exclusives_and_atomics() {
+        for aligned in [
+            "ldxr", "ldaxp", "stlxrb", "ldar", "ldaprh", "stlr", "casal", "ldclr",
+        ] {
+            assert!(
+                Opcode::from_string(aligned).requires_alignment(),
+                "{}",
+                aligned
+            );
+        }
+        for unaligned in ["ldr", "str", "ldp", "ld1", "stur"] {
+            assert!(
+                !Opcode::from_string(unaligned).requires_alignment(),
+                "{}",
+                unaligned
+            );
+        }
+    }
 }
 
 // // FIX: try to retire this function since errors are sometimes confusing
@@ -2,6 +2,10 @@ pub mod common;
 pub mod computer;
 pub mod engine;
 pub mod instruction_parser;
+pub mod object_parser;
+
+// part of the key of cached proofs, results of another checker version are not reused
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[macro_export]
 macro_rules! extract_three_registers {
@@ -0,0 +1,450 @@
+// Front-end for assembled AArch64 ELF objects (.o) and static archives (.a).
+//
+// Text sections are disassembled into the same instructions the text parser
+// produces, symbols become labels (so they can be used as entry points) and
+// relocations are turned back into the symbolic operands the assembler saw,
+// i.e. `adrp x3, K256@PAGE` or `bl foo`. Data sections (.bss zeroed) and
+// literal pools are laid out byte-for-byte in the "memory" region, with the
+// pointers they hold resolved. Relocations that cannot be followed are errors.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::Error;
+
+use object::read::archive::ArchiveFile;
+use object::{
+    elf, Architecture, FileKind, Object, ObjectSection, ObjectSymbol, RelocationFlags,
+    RelocationTarget, SectionIndex, SectionKind, SymbolKind,
+};
+
+use crate::instruction_parser::*;
+
+pub mod aarch64;
+use aarch64::Reference;
+
+pub struct ObjectProgram {
+    pub code: Vec<Instruction>,
+    // where each line of code is, a label is at the instruction it names
+    pub addresses: Vec<i64>,
+    // (address, little endian 8 byte chunk) pairs for the "memory" region
+    pub data: Vec<(i64, i64)>,
+    // (address, address pointed to) of the pointers data holds, both in the "memory" region
+    pub pointers: Vec<(i64, i64)>,
+    pub symbols: Vec<(String, i64)>,
+    // [start, end) of the sections that cannot be written, code and read-only data
+    pub constant: Vec<(i64, i64)>,
+}
+
+// a location inside one section of one archive member
+type Location = (usize, SectionIndex, u64);
+
+struct Section {
+    member: usize,
+    index: SectionIndex,
+    bytes: Vec<u8>,
+    address: i64,
+    executable: bool,
+    writable: bool,
+    // byte ranges of an executable section that hold data ($d mapping symbols)
+    data_ranges: Vec<(u64, u64)>,
+    relocations: HashMap<u64, Reference>,
+    // offsets of the 8 byte absolute pointers in the section, and the addresses they hold
+    pointers: Vec<(u64, i64)>,
+}
+
+#[derive(Default)]
+struct Loader {
+    sections: Vec<Section>,
+    // named symbols per location, first name defined wins on conflicts
+    names: HashMap<Location, Vec<String>>,
+    defined: HashMap<String, Location>,
+    address: i64,
+}
+
+fn symbol_name(name: &str) -> String {
+    name.trim_matches(|c| c == ':' || c == '_').to_string()
+}
+
+fn synthesized_name(location: &Location) -> String {
+    format!("L{}_{}_{:x}", location.0, location.1 .0, location.2)
+}
+
+fn other(message: String) -> Error {
+    Error::other(message)
+}
+
+pub fn parse_object(bytes: &[u8]) -> std::io::Result<ObjectProgram> {
+    let mut loader = Loader {
+        address: 4,
+        ..Default::default()
+    };
+
+    match FileKind::parse(bytes).map_err(|e| other(e.to_string()))? {
+        FileKind::Archive => {
+            let archive = ArchiveFile::parse(bytes).map_err(|e| other(e.to_string()))?;
+            for (i, member) in archive.members().enumerate() {
+                let member = member.map_err(|e| other(e.to_string()))?;
+                let data = member.data(bytes).map_err(|e| other(e.to_string()))?;
+                // archives can carry non object members like symbol tables
+                if let Ok(FileKind::Elf64) = FileKind::parse(data) {
+                    // members only start at even offsets, the elf headers have to be aligned
+                    let mut words = vec![0u64; data.len().div_ceil(8)];
+                    object::pod::bytes_of_slice_mut(&mut words)[..data.len()].copy_from_slice(data);
+                    loader.load_member(i, &object::pod::bytes_of_slice(&words)[..data.len()])?;
+                }
+            }
+        }
+        FileKind::Elf64 => loader.load_member(0, bytes)?,
+        kind => return Err(other(format!("Unsupported object file kind: {:?}", kind))),
+    }
+
+    Ok(loader.finish())
+}
+
+impl Loader {
+    fn load_member(&mut self, member: usize, bytes: &[u8]) -> std::io::Result<()> {
+        let file = object::File::parse(bytes).map_err(|e| other(e.to_string()))?;
+        if file.architecture() != Architecture::Aarch64 {
+            return Err(other(format!(
+                "Unsupported architecture: {:?}",
+                file.architecture()
+            )));
+        }
+
+        let mut mapping: HashMap<SectionIndex, Vec<(u64, bool)>> = HashMap::new();
+        let first = self.sections.len();
+        for section in file.sections() {
+            let executable = section.kind() == SectionKind::Text;
+            let data = matches!(
+                section.kind(),
+                SectionKind::Data
+                    | SectionKind::UninitializedData
+                    | SectionKind::ReadOnlyData
+                    | SectionKind::ReadOnlyString
+            );
+            if !executable && !data {
+                continue;
+            }
+            let align = section.align().max(1) as i64;
+            self.address = (self.address + align - 1) / align * align;
+            // .bss takes up space in memory but not in the file, it starts zeroed
+            let bytes = match section.kind() {
+                SectionKind::UninitializedData => vec![0; section.size() as usize],
+                _ => section.data().map_err(|e| other(e.to_string()))?.to_vec(),
+            };
+            self.sections.push(Section {
+                member,
+                index: section.index(),
+                address: self.address,
+                executable,
+                writable: matches!(
+                    section.kind(),
+                    SectionKind::Data | SectionKind::UninitializedData
+                ),
+                data_ranges: Vec::new(),
+                relocations: HashMap::new(),
+                pointers: Vec::new(),
+                bytes,
+            });
+            self.address += section.size() as i64;
+        }
+
+        for symbol in file.symbols() {
+            let Some(index) = symbol.section_index() else {
+                continue;
+            };
+            let Ok(name) = symbol.name() else { continue };
+            if name.starts_with("$x") || name.starts_with("$d") {
+                mapping
+                    .entry(index)
+                    .or_default()
+                    .push((symbol.address(), name.starts_with("$d")));
+                continue;
+            }
+            if name.is_empty() || matches!(symbol.kind(), SymbolKind::Section | SymbolKind::File) {
+                continue;
+            }
+            let name = symbol_name(name);
+            if self.defined.contains_key(&name) {
+                continue;
+            }
+            let location = (member, index, symbol.address());
+            self.defined.insert(name.clone(), location);
+            self.names.entry(location).or_default().push(name);
+        }
+
+        for section in self.sections[first..].iter_mut() {
+            let mut marks = mapping.remove(&section.index).unwrap_or_default();
+            marks.sort();
+            let mut start: Option<u64> = None;
+            for (offset, is_data) in marks {
+                match (start, is_data) {
+                    (None, true) => start = Some(offset),
+                    (Some(s), false) => {
+                        section.data_ranges.push((s, offset));
+                        start = None;
+                    }
+                    _ => (),
+                }
+            }
+            if let Some(s) = start {
+                section.data_ranges.push((s, section.bytes.len() as u64));
+            }
+        }
+
+        for i in first..self.sections.len() {
+            let index = self.sections[i].index;
+            let section = file
+                .section_by_index(index)
+                .map_err(|e| other(e.to_string()))?;
+            for (offset, relocation) in section.relocations() {
+                let RelocationFlags::Elf { r_type } = relocation.flags() else {
+                    return Err(other(format!(
+                        "Unsupported relocation at {}+{:#x}",
+                        section.name().unwrap_or("?"),
+                        offset
+                    )));
+                };
+                let RelocationTarget::Symbol(symbol_index) = relocation.target() else {
+                    return Err(other(format!(
+                        "Unsupported relocation target at {}+{:#x}",
+                        section.name().unwrap_or("?"),
+                        offset
+                    )));
+                };
+                let symbol = file
+                    .symbol_by_index(symbol_index)
+                    .map_err(|e| other(e.to_string()))?;
+
+                // pointers in data are resolved in place, the layout of the member is known
+                if matches!(
+                    r_type,
+                    elf::R_AARCH64_ABS64
+                        | elf::R_AARCH64_ABS32
+                        | elf::R_AARCH64_PREL64
+                        | elf::R_AARCH64_PREL32
+                ) {
+                    let name = symbol.name().unwrap_or("?");
+                    let target = symbol
+                        .section_index()
+                        .and_then(|target| {
+                            self.sections[first..].iter().find(|s| s.index == target)
+                        })
+                        .ok_or_else(|| {
+                            other(format!("Pointer to undefined or unloaded symbol {}", name))
+                        })?;
+                    let value = target.address + symbol.address() as i64 + relocation.addend()
+                        - match r_type {
+                            elf::R_AARCH64_PREL64 | elf::R_AARCH64_PREL32 => {
+                                self.sections[i].address + offset as i64
+                            }
+                            _ => 0,
+                        };
+                    let width = match r_type {
+                        elf::R_AARCH64_ABS64 | elf::R_AARCH64_PREL64 => 8,
+                        _ => 4,
+                    };
+                    let bytes = &mut self.sections[i].bytes;
+                    let Some(field) = bytes.get_mut(offset as usize..offset as usize + width)
+                    else {
+                        return Err(other(format!(
+                            "Relocation past the end of {}",
+                            section.name().unwrap_or("?")
+                        )));
+                    };
+                    field.copy_from_slice(&value.to_le_bytes()[..width]);
+                    if r_type == elf::R_AARCH64_ABS64 {
+                        self.sections[i].pointers.push((offset, value));
+                    }
+                    continue;
+                }
+
+                let name = match symbol.section_index() {
+                    Some(target)
+                        if symbol.kind() == SymbolKind::Section || relocation.addend() != 0 =>
+                    {
+                        let location = (
+                            member,
+                            target,
+                            symbol.address().wrapping_add(relocation.addend() as u64),
+                        );
+                        self.name_for(location)
+                    }
+                    _ => symbol_name(symbol.name().map_err(|e| other(e.to_string()))?),
+                };
+                let reference = match r_type {
+                    _ if !self.sections[i].executable => None,
+                    elf::R_AARCH64_ADR_PREL_PG_HI21 | elf::R_AARCH64_ADR_PREL_PG_HI21_NC => {
+                        Some(Reference::Page(name))
+                    }
+                    elf::R_AARCH64_ADD_ABS_LO12_NC
+                    | elf::R_AARCH64_LDST8_ABS_LO12_NC
+                    | elf::R_AARCH64_LDST16_ABS_LO12_NC
+                    | elf::R_AARCH64_LDST32_ABS_LO12_NC
+                    | elf::R_AARCH64_LDST64_ABS_LO12_NC
+                    | elf::R_AARCH64_LDST128_ABS_LO12_NC => Some(Reference::PageOffset(name)),
+                    elf::R_AARCH64_CALL26
+                    | elf::R_AARCH64_JUMP26
+                    | elf::R_AARCH64_CONDBR19
+                    | elf::R_AARCH64_TSTBR14
+                    | elf::R_AARCH64_LD_PREL_LO19
+                    | elf::R_AARCH64_ADR_PREL_LO21 => Some(Reference::Branch(name)),
+                    _ => None,
+                };
+                // an unresolved operand would be analysed as the placeholder the assembler left
+                let Some(reference) = reference else {
+                    return Err(other(format!(
+                        "Unsupported relocation type {} at {}+{:#x}",
+                        r_type,
+                        section.name().unwrap_or("?"),
+                        offset
+                    )));
+                };
+                self.sections[i].relocations.insert(offset, reference);
+            }
+        }
+        Ok(())
+    }
+
+    // name of the first symbol at a location, or a synthesized one that is defined on demand
+    fn name_for(&mut self, location: Location) -> String {
+        if let Some(names) = self.names.get(&location) {
+            return names[0].clone();
+        }
+        let name = synthesized_name(&location);
+        self.defined.insert(name.clone(), location);
+        self.names.insert(location, vec![name.clone()]);
+        name
+    }
+
+    fn finish(mut self) -> ObjectProgram {
+        let mut decoded: Vec<Vec<(u64, Instruction)>> = Vec::new();
+        for i in 0..self.sections.len() {
+            let mut instructions = Vec::new();
+            if self.sections[i].executable {
+                let (member, index) = (self.sections[i].member, self.sections[i].index);
+                let mut offset = 0;
+                while offset + 4 <= self.sections[i].bytes.len() as u64 {
+                    let section = &self.sections[i];
+                    if section
+                        .data_ranges
+                        .iter()
+                        .any(|(s, e)| *s <= offset && offset < *e)
+                    {
+                        offset += 4;
+                        continue;
+                    }
+                    let o = offset as usize;
+                    let word = u32::from_le_bytes([
+                        section.bytes[o],
+                        section.bytes[o + 1],
+                        section.bytes[o + 2],
+                        section.bytes[o + 3],
+                    ]);
+                    let reference = section.relocations.get(&offset).cloned();
+                    let mut targets = Vec::new();
+                    let instruction =
+                        aarch64::decode(word, offset, reference.as_ref(), &mut |target| {
+                            targets.push(target);
+                            synthesized_name(&(member, index, target))
+                        })
+                        .unwrap_or_else(|| {
+                            Instruction::from_operands(
+                                ".inst".to_string(),
+                                vec![Operand::Immediate(word as i64)],
+                            )
+                        });
+                    // use the real symbol name if the branch target has one
+                    let instruction = match (targets.pop(), instruction) {
+                        (Some(target), mut instruction) => {
+                            let name = self.name_for((member, index, target));
+                            for operand in instruction.operands.iter_mut() {
+                                if let Operand::Label(l) = operand {
+                                    if *l == synthesized_name(&(member, index, target)) {
+                                        *l = name.clone();
+                                    }
+                                }
+                            }
+                            instruction
+                        }
+                        (None, instruction) => instruction,
+                    };
+                    instructions.push((offset, instruction));
+                    offset += 4;
+                }
+            }
+            decoded.push(instructions);
+        }
+
+        let mut code = Vec::new();
+        let mut addresses = Vec::new();
+        let mut data = Vec::new();
+        let mut pointers = Vec::new();
+        let mut symbols = Vec::new();
+        let mut constant = Vec::new();
+        for (section, instructions) in self.sections.iter().zip(decoded) {
+            if !section.writable {
+                let end = section.address + section.bytes.len() as i64;
+                constant.push((section.address, end));
+            }
+            for (offset, target) in section.pointers.iter() {
+                pointers.push((section.address + *offset as i64, *target));
+            }
+            let key = |offset: u64| (section.member, section.index, offset);
+            let labelled: BTreeSet<u64> = self
+                .names
+                .keys()
+                .filter(|l| l.0 == section.member && l.1 == section.index)
+                .map(|l| l.2)
+                .collect();
+            for offset in labelled.iter() {
+                for name in self.names[&key(*offset)].iter() {
+                    symbols.push((name.clone(), section.address + *offset as i64));
+                }
+            }
+
+            let mut labels = labelled.iter().peekable();
+            for (offset, instruction) in instructions {
+                while let Some(l) = labels.next_if(|l| **l <= offset) {
+                    if *l == offset {
+                        for name in self.names[&key(*l)].iter() {
+                            code.push(Instruction::from_operands(name.clone() + ":", vec![]));
+                            addresses.push(section.address + *l as i64);
+                        }
+                    }
+                }
+                code.push(instruction);
+                addresses.push(section.address + offset as i64);
+            }
+
+            let ranges = if section.executable {
+                section.data_ranges.clone()
+            } else {
+                vec![(0, section.bytes.len() as u64)]
+            };
+            for (start, end) in ranges {
+                let mut offset = start;
+                while offset < end {
+                    let mut chunk = [0u8; 8];
+                    for (i, b) in section.bytes[offset as usize..end as usize]
+                        .iter()
+                        .take(8)
+                        .enumerate()
+                    {
+                        chunk[i] = *b;
+                    }
+                    data.push((section.address + offset as i64, i64::from_le_bytes(chunk)));
+                    offset += 8;
+                }
+            }
+        }
+
+        ObjectProgram {
+            code,
+            addresses,
+            data,
+            pointers,
+            symbols,
+            constant,
+        }
+    }
+}
@@ -0,0 +1,1696 @@
+// Decoder for the A64 instruction encodings we can reason about.
+//
+// Every decoded word is turned into the same `Instruction` the text parser
+// produces for the disassembly of that word, so the executor does not need to
+// know where the program came from. Aliases follow the preferred disassembly
+// (cmp, mov, lsl, cset, ...) because that is what the executor matches on.
+
+use crate::instruction_parser::*;
+
+// symbol a relocation at this word points to, already resolved by the caller
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    Page(String),
+    PageOffset(String),
+    Branch(String),
+}
+
+const CONDITIONS: [Condition; 16] = [
+    Condition::Eq,
+    Condition::Ne,
+    Condition::Hs,
+    Condition::Lo,
+    Condition::Mi,
+    Condition::Pl,
+    Condition::Vs,
+    Condition::Vc,
+    Condition::Hi,
+    Condition::Ls,
+    Condition::Ge,
+    Condition::Lt,
+    Condition::Gt,
+    Condition::Le,
+    Condition::Al,
+    Condition::Nv,
+];
+
+fn bits(word: u32, hi: u32, lo: u32) -> u32 {
+    (word >> lo) & ((1u32 << (hi - lo + 1)) - 1)
+}
+
+fn bit(word: u32, b: u32) -> bool {
+    (word >> b) & 1 == 1
+}
+
+fn sign_extend(value: u32, width: u32) -> i64 {
+    let shift = 64 - width;
+    ((value as i64) << shift) >> shift
+}
+
+// general purpose register, 31 is either sp or the zero register depending on the encoding
+fn gpr(n: u32, sf: bool, sp: bool) -> Operand {
+    if n == 31 {
+        if sp {
+            return Operand::Register(RePrefix::Sp, 0);
+        }
+        return Operand::Register(RePrefix::Ze, 0);
+    }
+    Operand::Register(if sf { RePrefix::X } else { RePrefix::W }, n as usize)
+}
+
+fn base(n: u32) -> (RePrefix, usize) {
+    if n == 31 {
+        (RePrefix::Sp, 0)
+    } else {
+        (RePrefix::X, n as usize)
+    }
+}
+
+fn memory(n: u32, offset: Option<i64>, index: Option<bool>) -> Operand {
+    let (prefix, num) = base(n);
+    Operand::Memory(prefix, num, offset, None, index, None)
+}
+
+fn arrangement(size: u32, q: bool) -> Arrangement {
+    match (size, q) {
+        (0, false) => Arrangement::B8,
+        (0, true) => Arrangement::B16,
+        (1, false) => Arrangement::H4,
+        (1, true) => Arrangement::H8,
+        (2, false) => Arrangement::S2,
+        (2, true) => Arrangement::S4,
+        (3, true) => Arrangement::D2,
+        _ => Arrangement::D,
+    }
+}
+
+fn element(size: u32) -> Arrangement {
+    match size {
+        0 => Arrangement::B,
+        1 => Arrangement::H,
+        2 => Arrangement::S,
+        _ => Arrangement::D,
+    }
+}
+
+fn vector(n: u32, a: Arrangement) -> Operand {
+    Operand::Vector(RePrefix::V, n as usize, a)
+}
+
+// scalar view of a simd register (b0, h0, s0, d0, q0) the same way the text parser reads it
+fn scalar(n: u32, scale: u32) -> Operand {
+    match scale {
+        0 => vector(n, Arrangement::B16),
+        1 => vector(n, Arrangement::H8),
+        2 => vector(n, Arrangement::S4),
+        3 => vector(n, Arrangement::D2),
+        _ => Operand::VectorRegister(RePrefix::V, n as usize),
+    }
+}
+
+fn shift(kind: u32, amount: u32) -> Operand {
+    let kind = [Shift::Lsl, Shift::Lsr, Shift::Asr, Shift::Ror][kind as usize];
+    Operand::Bitwise(kind, amount as i64)
+}
+
+fn extend(option: u32, amount: u32) -> Operand {
+    let kind = [
+        Shift::Uxtb,
+        Shift::Uxth,
+        Shift::Uxtw,
+        Shift::Uxtx,
+        Shift::Sxtb,
+        Shift::Sxth,
+        Shift::Sxtw,
+        Shift::Sxtx,
+    ][option as usize];
+    Operand::Bitwise(kind, amount as i64)
+}
+
+fn instruction(opcode: &str, operands: Vec<Operand>) -> Instruction {
+    Instruction::from_operands(opcode.to_string(), operands)
+}
+
+fn target(pc: u64, offset: i64, label: &mut dyn FnMut(u64) -> String) -> Operand {
+    Operand::Label(label(pc.wrapping_add(offset as u64)))
+}
+
+// DecodeBitMasks from the architecture reference, returns the immediate of a logical instruction
+fn decode_bit_masks(n: u32, imms: u32, immr: u32, sf: bool) -> Option<u64> {
+    let combined = (n << 6) | (!imms & 0x3f);
+    if combined == 0 {
+        return None;
+    }
+    let len = 31 - combined.leading_zeros();
+    if len < 1 || (!sf && n == 1) {
+        return None;
+    }
+    let esize = 1u32 << len;
+    let levels = esize - 1;
+    let s = imms & levels;
+    let r = immr & levels;
+    if s == levels {
+        return None;
+    }
+    let emask = if esize == 64 {
+        u64::MAX
+    } else {
+        (1u64 << esize) - 1
+    };
+    let welem = (1u64 << (s + 1)) - 1;
+    let rotated = if r == 0 {
+        welem
+    } else {
+        ((welem >> r) | (welem << (esize - r))) & emask
+    };
+    let mut result = 0u64;
+    let mut i = 0;
+    while i < 64 {
+        result |= rotated << i;
+        i += esize;
+    }
+    if !sf {
+        result &= 0xffff_ffff;
+    }
+    Some(result)
+}
+
+pub fn decode(
+    word: u32,
+    pc: u64,
+    reference: Option<&Reference>,
+    label: &mut dyn FnMut(u64) -> String,
+) -> Option<Instruction> {
+    match bits(word, 28, 25) {
+        0b1000 | 0b1001 => decode_data_immediate(word, pc, reference, label),
+        0b1010 | 0b1011 => decode_branch(word, pc, reference, label),
+        0b0101 | 0b1101 => decode_data_register(word),
+        0b0100 | 0b0110 | 0b1100 | 0b1110 => decode_load_store(word, pc, reference, label),
+        0b0111 | 0b1111 => decode_simd(word),
+        _ => None,
+    }
+}
+
+fn decode_data_immediate(
+    word: u32,
+    pc: u64,
+    reference: Option<&Reference>,
+    label: &mut dyn FnMut(u64) -> String,
+) -> Option<Instruction> {
+    let sf = bit(word, 31);
+    let rd = bits(word, 4, 0);
+    let rn = bits(word, 9, 5);
+    match bits(word, 25, 23) {
+        // pc relative addressing
+        0b000 | 0b001 => {
+            let immlo = bits(word, 30, 29);
+            let immhi = bits(word, 23, 5);
+            let imm = sign_extend((immhi << 2) | immlo, 21);
+            let dst = gpr(rd, true, false);
+            if bit(word, 31) {
+                let page = match reference {
+                    Some(Reference::Page(s)) | Some(Reference::Branch(s)) => {
+                        Operand::Address(s.to_string() + "@PAGE", 0)
+                    }
+                    _ => Operand::Immediate(imm << 12),
+                };
+                Some(instruction("adrp", vec![dst, page]))
+            } else {
+                let address = match reference {
+                    Some(Reference::Branch(s)) | Some(Reference::Page(s)) => {
+                        Operand::Label(s.to_string())
+                    }
+                    _ => target(pc, imm, label),
+                };
+                Some(instruction("adr", vec![dst, address]))
+            }
+        }
+        // add/sub immediate
+        0b010 => {
+            let op = bit(word, 30);
+            let s = bit(word, 29);
+            let sh = bit(word, 22);
+            let imm = bits(word, 21, 10) as i64;
+            let shifted = if sh {
+                vec![Operand::Immediate(imm), shift(0, 12)]
+            } else {
+                vec![Operand::Immediate(imm)]
+            };
+            let immediate = match reference {
+                Some(Reference::PageOffset(sym)) => {
+                    vec![Operand::Address(sym.to_string() + "@PAGEOFF", 0)]
+                }
+                _ => shifted,
+            };
+            let name = match (op, s) {
+                (false, false) => "add",
+                (false, true) => "adds",
+                (true, false) => "sub",
+                (true, true) => "subs",
+            };
+            if s && rd == 31 {
+                let mut operands = vec![gpr(rn, sf, true)];
+                operands.extend(immediate);
+                return Some(instruction(if op { "cmp" } else { "cmn" }, operands));
+            }
+            if !op && !s && imm == 0 && !sh && (rd == 31 || rn == 31) && reference.is_none() {
+                return Some(instruction(
+                    "mov",
+                    vec![gpr(rd, sf, true), gpr(rn, sf, true)],
+                ));
+            }
+            let mut operands = vec![gpr(rd, sf, !s), gpr(rn, sf, true)];
+            operands.extend(immediate);
+            Some(instruction(name, operands))
+        }
+        // logical immediate
+        0b100 => {
+            let opc = bits(word, 30, 29);
+            let imm = decode_bit_masks(
+                bits(word, 22, 22),
+                bits(word, 15, 10),
+                bits(word, 21, 16),
+                sf,
+            )? as i64;
+            let imm = if sf { imm } else { imm as u32 as i64 };
+            match opc {
+                0b11 if rd == 31 => Some(instruction(
+                    "tst",
+                    vec![gpr(rn, sf, false), Operand::Immediate(imm)],
+                )),
+                0b01 if rn == 31 => Some(instruction(
+                    "mov",
+                    vec![gpr(rd, sf, true), Operand::Immediate(imm)],
+                )),
+                _ => {
+                    let name = ["and", "orr", "eor", "ands"][opc as usize];
+                    Some(instruction(
+                        name,
+                        vec![
+                            gpr(rd, sf, opc != 0b11),
+                            gpr(rn, sf, false),
+                            Operand::Immediate(imm),
+                        ],
+                    ))
+                }
+            }
+        }
+        // move wide
+        0b101 => {
+            let opc = bits(word, 30, 29);
+            let hw = bits(word, 22, 21);
+            let imm16 = bits(word, 20, 5) as i64;
+            let dst = gpr(rd, sf, false);
+            match opc {
+                0b00 => {
+                    let mut value = !(imm16 << (hw * 16));
+                    if !sf {
+                        value = value as u32 as i64;
+                    }
+                    Some(instruction("mov", vec![dst, Operand::Immediate(value)]))
+                }
+                0b10 => Some(instruction(
+                    "mov",
+                    vec![dst, Operand::Immediate(imm16 << (hw * 16))],
+                )),
+                0b11 => {
+                    let mut operands = vec![dst, Operand::Immediate(imm16)];
+                    if hw != 0 {
+                        operands.push(shift(0, hw * 16));
+                    }
+                    Some(instruction("movk", operands))
+                }
+                _ => None,
+            }
+        }
+        // bitfield
+        0b110 => {
+            let opc = bits(word, 30, 29);
+            let immr = bits(word, 21, 16);
+            let imms = bits(word, 15, 10);
+            let width = if sf { 64 } else { 32 };
+            let dst = gpr(rd, sf, false);
+            let src = gpr(rn, sf, false);
+            let imm = |v: u32| Operand::Immediate(v as i64);
+            match opc {
+                0b00 => {
+                    if imms == width - 1 {
+                        Some(instruction("asr", vec![dst, src, imm(immr)]))
+                    } else if immr == 0 && imms == 7 {
+                        Some(instruction("sxtb", vec![dst, gpr(rn, false, false)]))
+                    } else if immr == 0 && imms == 15 {
+                        Some(instruction("sxth", vec![dst, gpr(rn, false, false)]))
+                    } else if immr == 0 && imms == 31 {
+                        Some(instruction("sxtw", vec![dst, gpr(rn, false, false)]))
+                    } else if imms < immr {
+                        Some(instruction(
+                            "sbfiz",
+                            vec![dst, src, imm((width - immr) % width), imm(imms + 1)],
+                        ))
+                    } else {
+                        Some(instruction(
+                            "sbfx",
+                            vec![dst, src, imm(immr), imm(imms + 1 - immr)],
+                        ))
+                    }
+                }
+                0b01 => {
+                    if imms < immr {
+                        Some(instruction(
+                            "bfi",
+                            vec![dst, src, imm((width - immr) % width), imm(imms + 1)],
+                        ))
+                    } else {
+                        Some(instruction(
+                            "bfxil",
+                            vec![dst, src, imm(immr), imm(imms + 1 - immr)],
+                        ))
+                    }
+                }
+                0b10 => {
+                    if imms == width - 1 {
+                        Some(instruction("lsr", vec![dst, src, imm(immr)]))
+                    } else if imms + 1 == immr {
+                        Some(instruction("lsl", vec![dst, src, imm(width - 1 - imms)]))
+                    } else if imms < immr {
+                        Some(instruction(
+                            "ubfiz",
+                            vec![dst, src, imm((width - immr) % width), imm(imms + 1)],
+                        ))
+                    } else {
+                        Some(instruction(
+                            "ubfx",
+                            vec![dst, src, imm(immr), imm(imms + 1 - immr)],
+                        ))
+                    }
+                }
+                _ => None,
+            }
+        }
+        // extract
+        0b111 => {
+            let rm = bits(word, 20, 16);
+            let imms = bits(word, 15, 10) as i64;
+            if rn == rm {
+                Some(instruction(
+                    "ror",
+                    vec![
+                        gpr(rd, sf, false),
+                        gpr(rn, sf, false),
+                        Operand::Immediate(imms),
+                    ],
+                ))
+            } else {
+                Some(instruction(
+                    "extr",
+                    vec![
+                        gpr(rd, sf, false),
+                        gpr(rn, sf, false),
+                        gpr(rm, sf, false),
+                        Operand::Immediate(imms),
+                    ],
+                ))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn decode_branch(
+    word: u32,
+    pc: u64,
+    reference: Option<&Reference>,
+    label: &mut dyn FnMut(u64) -> String,
+) -> Option<Instruction> {
+    let mut destination = |offset: i64| match reference {
+        Some(Reference::Branch(s)) => Operand::Label(s.to_string()),
+        _ => target(pc, offset, label),
+    };
+
+    // unconditional branch (immediate)
+    if bits(word, 30, 26) == 0b00101 {
+        let offset = sign_extend(bits(word, 25, 0), 26) * 4;
+        let name = if bit(word, 31) { "bl" } else { "b" };
+        return Some(instruction(name, vec![destination(offset)]));
+    }
+    // compare and branch
+    if bits(word, 30, 25) == 0b011010 {
+        let sf = bit(word, 31);
+        let offset = sign_extend(bits(word, 23, 5), 19) * 4;
+        let name = if bit(word, 24) { "cbnz" } else { "cbz" };
+        return Some(instruction(
+            name,
+            vec![gpr(bits(word, 4, 0), sf, false), destination(offset)],
+        ));
+    }
+    // test and branch
+    if bits(word, 30, 25) == 0b011011 {
+        let b5 = bit(word, 31);
+        let number = (bits(word, 31, 31) << 5) | bits(word, 23, 19);
+        let offset = sign_extend(bits(word, 18, 5), 14) * 4;
+        let name = if bit(word, 24) { "tbnz" } else { "tbz" };
+        return Some(instruction(
+            name,
+            vec![
+                gpr(bits(word, 4, 0), b5, false),
+                Operand::Immediate(number as i64),
+                destination(offset),
+            ],
+        ));
+    }
+    // conditional branch
+    if bits(word, 31, 24) == 0b01010100 && !bit(word, 4) {
+        let offset = sign_extend(bits(word, 23, 5), 19) * 4;
+        let name = format!("b.{}", CONDITIONS[bits(word, 3, 0) as usize]);
+        return Some(Instruction::from_operands(name, vec![destination(offset)]));
+    }
+    // hints and barriers
+    if bits(word, 31, 22) == 0b1101010100 {
+        if word == 0xd503201f {
+            return Some(instruction("nop", vec![]));
+        }
+        if bits(word, 31, 12) == 0xd5033 && bits(word, 4, 0) == 0b11111 {
+            let option = bits(word, 11, 8) as i64;
+            return match bits(word, 7, 5) {
+                0b100 => Some(instruction("dsb", vec![Operand::Immediate(option)])),
+                0b101 => Some(instruction("dmb", vec![Operand::Immediate(option)])),
+                0b110 => Some(instruction("isb", vec![])),
+                _ => None,
+            };
+        }
+        if bits(word, 31, 12) == 0xd5032 && bits(word, 4, 0) == 0b11111 {
+            // paciasp, autiasp, bti and friends do not change anything we track
+            return Some(instruction(
+                "hint",
+                vec![Operand::Immediate(bits(word, 11, 5) as i64)],
+            ));
+        }
+        return None;
+    }
+    // unconditional branch (register)
+    if bits(word, 31, 25) == 0b1101011
+        && bits(word, 20, 10) == 0b11111000000
+        && bits(word, 4, 0) == 0
+    {
+        let rn = bits(word, 9, 5);
+        return match bits(word, 24, 21) {
+            0b0000 => Some(instruction("br", vec![gpr(rn, true, false)])),
+            0b0001 => Some(instruction("blr", vec![gpr(rn, true, false)])),
+            0b0010 if rn == 30 => Some(instruction("ret", vec![])),
+            0b0010 => Some(instruction("ret", vec![gpr(rn, true, false)])),
+            _ => None,
+        };
+    }
+    None
+}
+
+fn decode_data_register(word: u32) -> Option<Instruction> {
+    let sf = bit(word, 31);
+    let rd = bits(word, 4, 0);
+    let rn = bits(word, 9, 5);
+    let rm = bits(word, 20, 16);
+
+    // logical (shifted register)
+    if bits(word, 28, 24) == 0b01010 {
+        let opc = bits(word, 30, 29);
+        let n = bit(word, 21);
+        let kind = bits(word, 23, 22);
+        let amount = bits(word, 15, 10);
+        let mut operands = vec![gpr(rd, sf, false), gpr(rn, sf, false), gpr(rm, sf, false)];
+        if amount != 0 || kind != 0 {
+            operands.push(shift(kind, amount));
+        }
+        if opc == 0b01 && !n && rn == 31 && amount == 0 && kind == 0 {
+            return Some(instruction(
+                "mov",
+                vec![gpr(rd, sf, false), gpr(rm, sf, false)],
+            ));
+        }
+        if opc == 0b01 && n && rn == 31 {
+            operands.remove(1);
+            return Some(instruction("mvn", operands));
+        }
+        if opc == 0b11 && !n && rd == 31 {
+            operands.remove(0);
+            return Some(instruction("tst", operands));
+        }
+        let name = match (opc, n) {
+            (0b00, false) => "and",
+            (0b00, true) => "bic",
+            (0b01, false) => "orr",
+            (0b01, true) => "orn",
+            (0b10, false) => "eor",
+            (0b10, true) => "eon",
+            (0b11, false) => "ands",
+            _ => "bics",
+        };
+        return Some(instruction(name, operands));
+    }
+    // add/sub (shifted or extended register)
+    if bits(word, 28, 24) == 0b01011 {
+        let op = bit(word, 30);
+        let s = bit(word, 29);
+        let extended = bit(word, 21);
+        let name = match (op, s) {
+            (false, false) => "add",
+            (false, true) => "adds",
+            (true, false) => "sub",
+            (true, true) => "subs",
+        };
+        let mut operands;
+        if extended {
+            let option = bits(word, 15, 13);
+            let amount = bits(word, 12, 10);
+            let wide = option & 0b011 == 0b011;
+            operands = vec![
+                gpr(rd, sf, !s),
+                gpr(rn, sf, true),
+                gpr(rm, sf && wide, false),
+            ];
+            // uxtx/uxtw with an sp operand is printed as lsl
+            let lsl = (rd == 31 || rn == 31) && option == if sf { 0b011 } else { 0b010 };
+            if lsl {
+                if amount != 0 {
+                    operands.push(shift(0, amount));
+                }
+            } else {
+                operands.push(extend(option, amount));
+            }
+        } else {
+            let kind = bits(word, 23, 22);
+            let amount = bits(word, 15, 10);
+            operands = vec![gpr(rd, sf, false), gpr(rn, sf, false), gpr(rm, sf, false)];
+            if amount != 0 || kind != 0 {
+                operands.push(shift(kind, amount));
+            }
+            if op && rn == 31 && !(s && rd == 31) {
+                operands.remove(1);
+                return Some(instruction(if s { "negs" } else { "neg" }, operands));
+            }
+        }
+        if s && rd == 31 {
+            operands.remove(0);
+            return Some(instruction(if op { "cmp" } else { "cmn" }, operands));
+        }
+        return Some(instruction(name, operands));
+    }
+    if bits(word, 28, 21) == 0b11010000 {
+        // add/sub with carry
+        let name = match bits(word, 30, 29) {
+            0b00 => "adc",
+            0b01 => "adcs",
+            0b10 => "sbc",
+            _ => "sbcs",
+        };
+        return Some(instruction(
+            name,
+            vec![gpr(rd, sf, false), gpr(rn, sf, false), gpr(rm, sf, false)],
+        ));
+    }
+    if bits(word, 28, 21) == 0b11010010 && !bit(word, 10) && !bit(word, 4) {
+        // conditional compare
+        let name = if bit(word, 30) { "ccmp" } else { "ccmn" };
+        let second = if bit(word, 11) {
+            Operand::Immediate(rm as i64)
+        } else {
+            gpr(rm, sf, false)
+        };
+        return Some(instruction(
+            name,
+            vec![
+                gpr(rn, sf, false),
+                second,
+                Operand::Immediate(bits(word, 3, 0) as i64),
+                Operand::Condition(CONDITIONS[bits(word, 15, 12) as usize]),
+            ],
+        ));
+    }
+    if bits(word, 28, 21) == 0b11010100 && !bit(word, 29) {
+        // conditional select
+        let cond = bits(word, 15, 12);
+        let op = (bits(word, 30, 30) << 1) | bits(word, 10, 10);
+        let inverted = Operand::Condition(CONDITIONS[(cond ^ 1) as usize]);
+        if rm == rn && cond < 0b1110 {
+            match op {
+                0b01 if rn == 31 => {
+                    return Some(instruction("cset", vec![gpr(rd, sf, false), inverted]))
+                }
+                0b10 if rn == 31 => {
+                    return Some(instruction("csetm", vec![gpr(rd, sf, false), inverted]))
+                }
+                0b01 => {
+                    return Some(instruction(
+                        "cinc",
+                        vec![gpr(rd, sf, false), gpr(rn, sf, false), inverted],
+                    ))
+                }
+                0b11 => {
+                    return Some(instruction(
+                        "cneg",
+                        vec![gpr(rd, sf, false), gpr(rn, sf, false), inverted],
+                    ))
+                }
+                _ => (),
+            }
+        }
+        let name = ["csel", "csinc", "csinv", "csneg"][op as usize];
+        return Some(instruction(
+            name,
+            vec![
+                gpr(rd, sf, false),
+                gpr(rn, sf, false),
+                gpr(rm, sf, false),
+                Operand::Condition(CONDITIONS[cond as usize]),
+            ],
+        ));
+    }
+    if bits(word, 30, 21) == 0b0011010110 {
+        // data processing (2 source)
+        let name = match bits(word, 15, 10) {
+            0b000010 => "udiv",
+            0b000011 => "sdiv",
+            0b001000 => "lsl",
+            0b001001 => "lsr",
+            0b001010 => "asr",
+            0b001011 => "ror",
+            _ => return None,
+        };
+        return Some(instruction(
+            name,
+            vec![gpr(rd, sf, false), gpr(rn, sf, false), gpr(rm, sf, false)],
+        ));
+    }
+    if bits(word, 30, 21) == 0b1011010110 && rm == 0 {
+        // data processing (1 source)
+        let name = match (bits(word, 15, 10), sf) {
+            (0b000000, _) => "rbit",
+            (0b000001, _) => "rev16",
+            (0b000010, false) => "rev",
+            (0b000010, true) => "rev32",
+            (0b000011, true) => "rev",
+            (0b000100, _) => "clz",
+            (0b000101, _) => "cls",
+            _ => return None,
+        };
+        return Some(instruction(
+            name,
+            vec![gpr(rd, sf, false), gpr(rn, sf, false)],
+        ));
+    }
+    if bits(word, 28, 24) == 0b11011 {
+        // data processing (3 source)
+        let ra = bits(word, 14, 10);
+        let o0 = bit(word, 15);
+        let name = match (bits(word, 23, 21), o0) {
+            (0b000, false) if ra == 31 => "mul",
+            (0b000, false) => "madd",
+            (0b000, true) if ra == 31 => "mneg",
+            (0b000, true) => "msub",
+            (0b010, false) => "smulh",
+            (0b110, false) => "umulh",
+            (0b001, false) if ra == 31 => "smull",
+            (0b001, false) => "smaddl",
+            (0b101, false) if ra == 31 => "umull",
+            (0b101, false) => "umaddl",
+            _ => return None,
+        };
+        let long = matches!(bits(word, 23, 21), 0b001 | 0b101);
+        let mut operands = vec![
+            gpr(rd, sf, false),
+            gpr(rn, sf && !long, false),
+            gpr(rm, sf && !long, false),
+        ];
+        if ra != 31 && !name.ends_with('h') {
+            operands.push(gpr(ra, sf, false));
+        }
+        return Some(instruction(name, operands));
+    }
+    None
+}
+
+fn integer_load_store(size: u32, opc: u32) -> Option<(&'static str, bool, u32)> {
+    // name, 64 bit destination, scale
+    match (size, opc) {
+        (0, 0) => Some(("strb", false, 0)),
+        (0, 1) => Some(("ldrb", false, 0)),
+        (0, 2) => Some(("ldrsb", true, 0)),
+        (0, 3) => Some(("ldrsb", false, 0)),
+        (1, 0) => Some(("strh", false, 1)),
+        (1, 1) => Some(("ldrh", false, 1)),
+        (1, 2) => Some(("ldrsh", true, 1)),
+        (1, 3) => Some(("ldrsh", false, 1)),
+        (2, 0) => Some(("str", false, 2)),
+        (2, 1) => Some(("ldr", false, 2)),
+        (2, 2) => Some(("ldrsw", true, 2)),
+        (3, 0) => Some(("str", true, 3)),
+        (3, 1) => Some(("ldr", true, 3)),
+        _ => None,
+    }
+}
+
+fn simd_load_store(size: u32, opc: u32) -> Option<(&'static str, u32)> {
+    let load = opc & 1 == 1;
+    let scale = if opc & 2 == 2 {
+        if size != 0 {
+            return None;
+        }
+        4
+    } else {
+        size
+    };
+    Some((if load { "ldr" } else { "str" }, scale))
+}
+
+fn unscaled(name: &str) -> String {
+    name.replacen("ldr", "ldur", 1).replacen("str", "stur", 1)
+}
+
+fn decode_load_store(
+    word: u32,
+    pc: u64,
+    reference: Option<&Reference>,
+    label: &mut dyn FnMut(u64) -> String,
+) -> Option<Instruction> {
+    let rt = bits(word, 4, 0);
+    let rn = bits(word, 9, 5);
+    let v = bit(word, 26);
+
+    // advanced simd structures
+    if !bit(word, 31) && bits(word, 29, 24) == 0b001100 {
+        return decode_structure_multiple(word);
+    }
+    if !bit(word, 31) && bits(word, 29, 24) == 0b001101 {
+        return decode_structure_single(word);
+    }
+
+    // load register (literal)
+    if bits(word, 29, 27) == 0b011 && bits(word, 25, 24) == 0b00 {
+        let opc = bits(word, 31, 30);
+        let offset = sign_extend(bits(word, 23, 5), 19) * 4;
+        let address = match reference {
+            Some(Reference::Branch(s)) | Some(Reference::Page(s)) => Operand::Label(s.to_string()),
+            _ => target(pc, offset, label),
+        };
+        let dst = if v {
+            scalar(rt, opc + 2)
+        } else {
+            match opc {
+                0b00 => gpr(rt, false, false),
+                0b01 => gpr(rt, true, false),
+                0b10 => return Some(instruction("ldrsw", vec![gpr(rt, true, false), address])),
+                _ => return None,
+            }
+        };
+        return Some(instruction("ldr", vec![dst, address]));
+    }
+
+    // load/store pair
+    if bits(word, 29, 27) == 0b101 {
+        let opc = bits(word, 31, 30);
+        let load = bit(word, 22);
+        let imm7 = sign_extend(bits(word, 21, 15), 7);
+        let index = match bits(word, 24, 23) {
+            0b01 => Some(true),
+            0b11 => Some(false),
+            0b10 => None,
+            _ => return None, // no-allocate pairs
+        };
+        let (name, first, second, scale) = if v {
+            if opc == 0b11 {
+                return None;
+            }
+            let scale = 2 + opc;
+            (
+                if load { "ldp" } else { "stp" },
+                scalar(rt, scale),
+                scalar(bits(word, 14, 10), scale),
+                scale,
+            )
+        } else {
+            match (opc, load) {
+                (0b00, _) | (0b10, _) => {
+                    let sf = opc == 0b10;
+                    (
+                        if load { "ldp" } else { "stp" },
+                        gpr(rt, sf, false),
+                        gpr(bits(word, 14, 10), sf, false),
+                        if sf { 3 } else { 2 },
+                    )
+                }
+                (0b01, true) => (
+                    "ldpsw",
+                    gpr(rt, true, false),
+                    gpr(bits(word, 14, 10), true, false),
+                    2,
+                ),
+                _ => return None,
+            }
+        };
+        let offset = imm7 << scale;
+        let offset = if offset == 0 && index.is_none() {
+            None
+        } else {
+            Some(offset)
+        };
+        return Some(instruction(
+            name,
+            vec![first, second, memory(rn, offset, index)],
+        ));
+    }
+
+    if bits(word, 29, 27) != 0b111 {
+        return None;
+    }
+    let size = bits(word, 31, 30);
+    let opc = bits(word, 23, 22);
+    let (name, dst, scale) = if v {
+        let (name, scale) = simd_load_store(size, opc)?;
+        (name, scalar(rt, scale), scale)
+    } else {
+        let (name, sf, scale) = integer_load_store(size, opc)?;
+        (name, gpr(rt, sf, false), scale)
+    };
+
+    // unsigned immediate offset
+    if bits(word, 25, 24) == 0b01 {
+        let imm = (bits(word, 21, 10) as i64) << scale;
+        let offset = match reference {
+            Some(Reference::PageOffset(_)) => None,
+            _ if imm == 0 => None,
+            _ => Some(imm),
+        };
+        return Some(instruction(name, vec![dst, memory(rn, offset, None)]));
+    }
+    if bits(word, 25, 24) != 0b00 {
+        return None;
+    }
+    if bit(word, 21) {
+        // register offset
+        if bits(word, 11, 10) != 0b10 {
+            return None;
+        }
+        let option = bits(word, 15, 13);
+        let register = gpr(bits(word, 20, 16), option & 1 == 1, false);
+        // the S bit shifts the index by the access size
+        let amount = if bit(word, 12) { scale as i64 } else { 0 };
+        let extend = match option {
+            0b010 => Some((Shift::Uxtw, amount)),
+            0b011 if amount == 0 => None,
+            0b011 => Some((Shift::Lsl, amount)),
+            0b110 => Some((Shift::Sxtw, amount)),
+            0b111 => Some((Shift::Sxtx, amount)),
+            _ => return None,
+        };
+        let (prefix, num) = base(rn);
+        return Some(instruction(
+            name,
+            vec![
+                dst,
+                Operand::Memory(prefix, num, None, Some(Box::new(register)), None, extend),
+            ],
+        ));
+    }
+    let imm9 = sign_extend(bits(word, 20, 12), 9);
+    match bits(word, 11, 10) {
+        0b00 => {
+            let offset = if imm9 == 0 { None } else { Some(imm9) };
+            Some(Instruction::from_operands(
+                unscaled(name),
+                vec![dst, memory(rn, offset, None)],
+            ))
+        }
+        0b01 => Some(instruction(
+            name,
+            vec![dst, memory(rn, Some(imm9), Some(true))],
+        )),
+        0b11 => Some(instruction(
+            name,
+            vec![dst, memory(rn, Some(imm9), Some(false))],
+        )),
+        _ => None,
+    }
+}
+
+fn decode_structure_multiple(word: u32) -> Option<Instruction> {
+    let q = bit(word, 30);
+    let post = bit(word, 23);
+    let load = bit(word, 22);
+    let rm = bits(word, 20, 16);
+    let rn = bits(word, 9, 5);
+    let rt = bits(word, 4, 0);
+    let size = bits(word, 11, 10);
+    if !post && rm != 0 {
+        return None;
+    }
+    let (structure, registers) = match bits(word, 15, 12) {
+        0b0000 => (4, 4),
+        0b0010 => (1, 4),
+        0b0100 => (3, 3),
+        0b0110 => (1, 3),
+        0b0111 => (1, 1),
+        0b1000 => (2, 2),
+        0b1010 => (1, 2),
+        _ => return None,
+    };
+    if size == 3 && !q && structure != 1 {
+        return None;
+    }
+    let name = format!("{}{}", if load { "ld" } else { "st" }, structure);
+    let mut operands: Vec<Operand> = (0..registers)
+        .map(|i| vector((rt + i) % 32, arrangement(size, q)))
+        .collect();
+    let (prefix, num) = base(rn);
+    operands.push(if !post {
+        Operand::Memory(prefix, num, None, None, None, None)
+    } else if rm == 31 {
+        let bytes = registers as i64 * if q { 16 } else { 8 };
+        Operand::Memory(prefix, num, Some(bytes), None, Some(true), None)
+    } else {
+        Operand::Memory(
+            prefix,
+            num,
+            None,
+            Some(Box::new(gpr(rm, true, false))),
+            Some(true),
+            None,
+        )
+    });
+    Some(Instruction::from_operands(name, operands))
+}
+
+fn decode_structure_single(word: u32) -> Option<Instruction> {
+    let q = bit(word, 30);
+    let post = bit(word, 23);
+    let load = bit(word, 22);
+    let r = bit(word, 21);
+    let rm = bits(word, 20, 16);
+    let opcode = bits(word, 15, 13);
+    let s = bits(word, 12, 12);
+    let size = bits(word, 11, 10);
+    let rn = bits(word, 9, 5);
+    let rt = bits(word, 4, 0);
+    if !post && rm != 0 {
+        return None;
+    }
+    let registers = ((opcode & 1) << 1 | r as u32) + 1;
+    let (prefix, num) = base(rn);
+    let post_operand = |bytes: i64| {
+        if !post {
+            Operand::Memory(prefix.clone(), num, None, None, None, None)
+        } else if rm == 31 {
+            Operand::Memory(prefix.clone(), num, Some(bytes), None, Some(true), None)
+        } else {
+            Operand::Memory(
+                prefix.clone(),
+                num,
+                None,
+                Some(Box::new(gpr(rm, true, false))),
+                Some(true),
+                None,
+            )
+        }
+    };
+
+    // replicate to all lanes
+    if opcode >> 1 == 0b11 {
+        if !load || s == 1 {
+            return None;
+        }
+        let name = format!("ld{}r", registers);
+        let mut operands: Vec<Operand> = (0..registers)
+            .map(|i| vector((rt + i) % 32, arrangement(size, q)))
+            .collect();
+        operands.push(post_operand(registers as i64 * (1 << size)));
+        return Some(Instruction::from_operands(name, operands));
+    }
+
+    let (lane, index) = match opcode >> 1 {
+        0b00 => (0, (q as u32) << 3 | s << 2 | size),
+        0b01 if size & 1 == 0 => (1, (q as u32) << 2 | s << 1 | size >> 1),
+        0b10 if size == 0 => (2, (q as u32) << 1 | s),
+        0b10 if size == 1 && s == 0 => (3, q as u32),
+        _ => return None,
+    };
+    let name = format!("{}{}", if load { "ld" } else { "st" }, registers);
+    let mut operands: Vec<Operand> = (0..registers)
+        .map(|i| {
+            Operand::VectorAccess(
+                RePrefix::V,
+                ((rt + i) % 32) as usize,
+                element(lane),
+                index as i64,
+            )
+        })
+        .collect();
+    operands.push(post_operand(registers as i64 * (1 << lane)));
+    Some(Instruction::from_operands(name, operands))
+}
+
+fn decode_simd(word: u32) -> Option<Instruction> {
+    let q = bit(word, 30);
+    let u = bit(word, 29);
+    let size = bits(word, 23, 22);
+    let rm = bits(word, 20, 16);
+    let rn = bits(word, 9, 5);
+    let rd = bits(word, 4, 0);
+
+    // conversion between floating point and general purpose registers (fmov only)
+    if bits(word, 30, 24) == 0b0011110 && bit(word, 21) && bits(word, 15, 10) == 0 {
+        let sf = bit(word, 31);
+        let ty = bits(word, 23, 22);
+        let rmode = bits(word, 20, 19);
+        let opcode = bits(word, 18, 16);
+        let simd = match (sf, ty, rmode) {
+            (false, 0b00, 0b00) => scalar(0, 2),
+            (true, 0b01, 0b00) => scalar(0, 3),
+            (true, 0b10, 0b01) => Operand::VectorAccess(RePrefix::V, 0, Arrangement::D, 1),
+            _ => return None,
+        };
+        let with = |n: u32| match simd.clone() {
+            Operand::Vector(p, _, a) => Operand::Vector(p, n as usize, a),
+            Operand::VectorAccess(p, _, a, i) => Operand::VectorAccess(p, n as usize, a, i),
+            other => other,
+        };
+        return match opcode {
+            0b110 => Some(instruction("fmov", vec![gpr(rd, sf, false), with(rn)])),
+            0b111 => Some(instruction("fmov", vec![with(rd), gpr(rn, sf, false)])),
+            _ => None,
+        };
+    }
+
+    // cryptographic aes
+    if word & 0xffff_cc00 == 0x4e28_4800 {
+        let name = match bits(word, 16, 12) {
+            0b00100 => "aese",
+            0b00101 => "aesd",
+            0b00110 => "aesmc",
+            0b00111 => "aesimc",
+            _ => return None,
+        };
+        return Some(instruction(
+            name,
+            vec![vector(rd, Arrangement::B16), vector(rn, Arrangement::B16)],
+        ));
+    }
+    // cryptographic three register sha
+    if bits(word, 31, 24) == 0b0101_1110
+        && bits(word, 23, 21) == 0
+        && bits(word, 11, 10) == 0
+        && !bit(word, 15)
+    {
+        let qreg = |n: u32| Operand::VectorRegister(RePrefix::V, n as usize);
+        let (name, operands) = match bits(word, 14, 12) {
+            0b000 => (
+                "sha1c",
+                vec![qreg(rd), scalar(rn, 2), vector(rm, Arrangement::S4)],
+            ),
+            0b001 => (
+                "sha1p",
+                vec![qreg(rd), scalar(rn, 2), vector(rm, Arrangement::S4)],
+            ),
+            0b010 => (
+                "sha1m",
+                vec![qreg(rd), scalar(rn, 2), vector(rm, Arrangement::S4)],
+            ),
+            0b011 => (
+                "sha1su0",
+                vec![
+                    vector(rd, Arrangement::S4),
+                    vector(rn, Arrangement::S4),
+                    vector(rm, Arrangement::S4),
+                ],
+            ),
+            0b100 => (
+                "sha256h",
+                vec![qreg(rd), qreg(rn), vector(rm, Arrangement::S4)],
+            ),
+            0b101 => (
+                "sha256h2",
+                vec![qreg(rd), qreg(rn), vector(rm, Arrangement::S4)],
+            ),
+            0b110 => (
+                "sha256su1",
+                vec![
+                    vector(rd, Arrangement::S4),
+                    vector(rn, Arrangement::S4),
+                    vector(rm, Arrangement::S4),
+                ],
+            ),
+            _ => return None,
+        };
+        return Some(instruction(name, operands));
+    }
+    // cryptographic two register sha
+    if word & 0xffff_0c00 == 0x5e28_0800 {
+        let name = match bits(word, 16, 12) {
+            0b00000 => "sha1h",
+            0b00001 => "sha1su1",
+            0b00010 => "sha256su0",
+            _ => return None,
+        };
+        if name == "sha1h" {
+            return Some(instruction(name, vec![scalar(rd, 2), scalar(rn, 2)]));
+        }
+        return Some(instruction(
+            name,
+            vec![vector(rd, Arrangement::S4), vector(rn, Arrangement::S4)],
+        ));
+    }
+
+    // cryptographic sha512
+    if bits(word, 31, 21) == 0b11001110011 && bits(word, 15, 12) == 0b1000 {
+        let qreg = |n: u32| Operand::VectorRegister(RePrefix::V, n as usize);
+        return match bits(word, 11, 10) {
+            0b00 => Some(instruction(
+                "sha512h",
+                vec![qreg(rd), qreg(rn), vector(rm, Arrangement::D2)],
+            )),
+            0b01 => Some(instruction(
+                "sha512h2",
+                vec![qreg(rd), qreg(rn), vector(rm, Arrangement::D2)],
+            )),
+            0b10 => Some(instruction(
+                "sha512su1",
+                vec![
+                    vector(rd, Arrangement::D2),
+                    vector(rn, Arrangement::D2),
+                    vector(rm, Arrangement::D2),
+                ],
+            )),
+            _ => None,
+        };
+    }
+    if bits(word, 31, 10) == 0b1100111011000000100000 {
+        return Some(instruction(
+            "sha512su0",
+            vec![vector(rd, Arrangement::D2), vector(rn, Arrangement::D2)],
+        ));
+    }
+
+    // scalar copy of one element, i.e. mov d0, v1.d[1]
+    if bits(word, 31, 21) == 0b01011110000 && bits(word, 15, 10) == 0b000001 {
+        let imm5 = bits(word, 20, 16);
+        if imm5 & 0xf == 0 {
+            return None;
+        }
+        let esize = imm5.trailing_zeros();
+        return Some(instruction(
+            "mov",
+            vec![
+                scalar(rd, esize),
+                Operand::VectorAccess(
+                    RePrefix::V,
+                    rn as usize,
+                    element(esize),
+                    (imm5 >> (esize + 1)) as i64,
+                ),
+            ],
+        ));
+    }
+
+    // table lookup
+    if bits(word, 31, 31) == 0
+        && bits(word, 29, 21) == 0b001110000
+        && bits(word, 15, 15) == 0
+        && bits(word, 11, 10) == 0
+    {
+        let a = arrangement(0, q);
+        let mut operands = vec![vector(rd, a.clone())];
+        for i in 0..=bits(word, 14, 13) {
+            operands.push(vector((rn + i) % 32, Arrangement::B16));
+        }
+        operands.push(vector(rm, a));
+        let name = if bit(word, 12) { "tbx" } else { "tbl" };
+        return Some(instruction(name, operands));
+    }
+
+    if bit(word, 31) || bits(word, 28, 24) == 0b11110 {
+        // scalar advanced simd is not handled
+        return None;
+    }
+
+    // modified immediate
+    if bits(word, 28, 19) == 0b0111100000 && bit(word, 10) && !bit(word, 11) {
+        return decode_modified_immediate(word);
+    }
+
+    // shift by immediate
+    if bits(word, 28, 23) == 0b011110 && bit(word, 10) && bits(word, 22, 19) != 0 {
+        return decode_shift_immediate(word);
+    }
+
+    if bits(word, 28, 24) != 0b01110 && bits(word, 28, 24) != 0b01111 {
+        return None;
+    }
+
+    // extract
+    if bits(word, 29, 21) == 0b101110000 && !bit(word, 15) && !bit(word, 10) {
+        let a = arrangement(0, q);
+        return Some(instruction(
+            "ext",
+            vec![
+                vector(rd, a.clone()),
+                vector(rn, a.clone()),
+                vector(rm, a),
+                Operand::Immediate(bits(word, 14, 11) as i64),
+            ],
+        ));
+    }
+
+    // copy
+    if bits(word, 28, 21) == 0b01110000 && !bit(word, 15) && bit(word, 10) {
+        let imm5 = bits(word, 20, 16);
+        let imm4 = bits(word, 14, 11);
+        if imm5 & 0xf == 0 {
+            return None;
+        }
+        let esize = imm5.trailing_zeros();
+        let index = (imm5 >> (esize + 1)) as i64;
+        let lane =
+            |n: u32, i: i64| Operand::VectorAccess(RePrefix::V, n as usize, element(esize), i);
+        return match (u, imm4) {
+            (false, 0b0000) => Some(instruction(
+                "dup",
+                vec![vector(rd, arrangement(esize, q)), lane(rn, index)],
+            )),
+            (false, 0b0001) => Some(instruction(
+                "dup",
+                vec![
+                    vector(rd, arrangement(esize, q)),
+                    gpr(rn, esize == 3, false),
+                ],
+            )),
+            (false, 0b0011) if q => Some(instruction(
+                "mov",
+                vec![lane(rd, index), gpr(rn, esize == 3, false)],
+            )),
+            (false, 0b0111) if (esize == 2 && !q) || (esize == 3 && q) => Some(instruction(
+                "mov",
+                vec![gpr(rd, esize == 3, false), lane(rn, index)],
+            )),
+            (false, 0b0111) => Some(instruction(
+                "umov",
+                vec![gpr(rd, false, false), lane(rn, index)],
+            )),
+            (false, 0b0101) => Some(instruction(
+                "smov",
+                vec![gpr(rd, q, false), lane(rn, index)],
+            )),
+            (true, _) if q => Some(instruction(
+                "mov",
+                vec![lane(rd, index), lane(rn, (imm4 >> esize) as i64)],
+            )),
+            _ => None,
+        };
+    }
+
+    // permute
+    if bits(word, 29, 24) == 0b001110
+        && !bit(word, 21)
+        && !bit(word, 15)
+        && bits(word, 11, 10) == 0b10
+    {
+        let name = match bits(word, 14, 12) {
+            0b001 => "uzp1",
+            0b010 => "trn1",
+            0b011 => "zip1",
+            0b101 => "uzp2",
+            0b110 => "trn2",
+            0b111 => "zip2",
+            _ => return None,
+        };
+        let a = arrangement(size, q);
+        return Some(instruction(
+            name,
+            vec![vector(rd, a.clone()), vector(rn, a.clone()), vector(rm, a)],
+        ));
+    }
+
+    if bits(word, 28, 24) != 0b01110 || !bit(word, 21) {
+        return None;
+    }
+
+    // two register miscellaneous
+    if bits(word, 20, 17) == 0b0000 && bits(word, 11, 10) == 0b10 {
+        let opcode = bits(word, 16, 12);
+        let a = arrangement(size, q);
+        let name = match (u, opcode) {
+            (false, 0b00000) => "rev64",
+            (true, 0b00000) => "rev32",
+            (false, 0b00001) => "rev16",
+            (false, 0b00101) => "cnt",
+            (true, 0b00101) if size == 0 => "mvn",
+            (true, 0b00101) => "rbit",
+            (false, 0b01011) => "abs",
+            (true, 0b01011) => "neg",
+            (false, 0b01001) => "cmeq",
+            (false, 0b10010) | (true, 0b10010) => {
+                let narrow = arrangement(size, q);
+                let wide = arrangement(size + 1, true);
+                let base = if u { "sqxtun" } else { "xtn" };
+                let name = if q {
+                    format!("{}2", base)
+                } else {
+                    base.to_string()
+                };
+                return Some(Instruction::from_operands(
+                    name,
+                    vec![vector(rd, narrow), vector(rn, wide)],
+                ));
+            }
+            _ => return None,
+        };
+        let mut operands = vec![vector(rd, a.clone()), vector(rn, a)];
+        if name == "cmeq" {
+            operands.push(Operand::Immediate(0));
+        }
+        return Some(instruction(name, operands));
+    }
+
+    // three different
+    if !bit(word, 10) && !bit(word, 11) {
+        let opcode = bits(word, 15, 12);
+        let base = match (u, opcode) {
+            (false, 0b0000) => "saddl",
+            (true, 0b0000) => "uaddl",
+            (false, 0b0010) => "ssubl",
+            (true, 0b0010) => "usubl",
+            (false, 0b1000) => "smlal",
+            (true, 0b1000) => "umlal",
+            (false, 0b1100) => "smull",
+            (true, 0b1100) => "umull",
+            (false, 0b1110) => "pmull",
+            _ => return None,
+        };
+        let name = if q {
+            format!("{}2", base)
+        } else {
+            base.to_string()
+        };
+        let wide = if size == 3 {
+            Operand::VectorRegister(RePrefix::V, rd as usize)
+        } else {
+            vector(rd, arrangement(size + 1, true))
+        };
+        let narrow = arrangement(size, q);
+        return Some(Instruction::from_operands(
+            name,
+            vec![wide, vector(rn, narrow.clone()), vector(rm, narrow)],
+        ));
+    }
+
+    // three same
+    if bit(word, 10) {
+        let opcode = bits(word, 15, 11);
+        let a = arrangement(size, q);
+        let name = match (u, opcode) {
+            (false, 0b00011) => {
+                let name = ["and", "bic", "orr", "orn"][size as usize];
+                let a = arrangement(0, q);
+                if name == "orr" && rn == rm {
+                    return Some(instruction(
+                        "mov",
+                        vec![vector(rd, a.clone()), vector(rn, a)],
+                    ));
+                }
+                return Some(instruction(
+                    name,
+                    vec![vector(rd, a.clone()), vector(rn, a.clone()), vector(rm, a)],
+                ));
+            }
+            (true, 0b00011) => {
+                let name = ["eor", "bsl", "bit", "bif"][size as usize];
+                let a = arrangement(0, q);
+                return Some(instruction(
+                    name,
+                    vec![vector(rd, a.clone()), vector(rn, a.clone()), vector(rm, a)],
+                ));
+            }
+            (false, 0b10000) => "add",
+            (true, 0b10000) => "sub",
+            (false, 0b10011) => "mul",
+            (true, 0b10011) => "pmul",
+            (false, 0b10001) => "cmtst",
+            (true, 0b10001) => "cmeq",
+            (false, 0b00110) => "cmgt",
+            (true, 0b00110) => "cmhi",
+            (false, 0b00111) => "cmge",
+            (true, 0b00111) => "cmhs",
+            (false, 0b01000) => "sshl",
+            (true, 0b01000) => "ushl",
+            (false, 0b01100) => "smax",
+            (true, 0b01100) => "umax",
+            (false, 0b01101) => "smin",
+            (true, 0b01101) => "umin",
+            (false, 0b10111) => "addp",
+            (false, 0b00001) => "sqadd",
+            (true, 0b00001) => "uqadd",
+            (false, 0b00101) => "sqsub",
+            (true, 0b00101) => "uqsub",
+            (false, 0b00010) => "srhadd",
+            (true, 0b00010) => "urhadd",
+            _ => return None,
+        };
+        return Some(instruction(
+            name,
+            vec![vector(rd, a.clone()), vector(rn, a.clone()), vector(rm, a)],
+        ));
+    }
+    None
+}
+
+fn decode_modified_immediate(word: u32) -> Option<Instruction> {
+    let q = bit(word, 30);
+    let op = bit(word, 29);
+    let cmode = bits(word, 15, 12);
+    let rd = bits(word, 4, 0);
+    let imm8 = (bits(word, 18, 16) << 5) | bits(word, 9, 5);
+    let imm = Operand::Immediate(imm8 as i64);
+    let (name, a, shifted) = match (cmode, op) {
+        (0b1110, false) => ("movi", arrangement(0, q), None),
+        (0b1110, true) => {
+            let mut value = 0u64;
+            for i in 0..8 {
+                if imm8 & (1 << i) != 0 {
+                    value |= 0xff << (i * 8);
+                }
+            }
+            let value = Operand::Immediate(value as i64);
+            if q {
+                return Some(instruction(
+                    "movi",
+                    vec![vector(rd, Arrangement::D2), value],
+                ));
+            }
+            return Some(instruction("movi", vec![scalar(rd, 3), value]));
+        }
+        (c, _) if c & 0b1001 == 0b0000 => (
+            if op { "mvni" } else { "movi" },
+            arrangement(2, q),
+            Some(shift(0, (c >> 1) * 8)),
+        ),
+        (c, _) if c & 0b1001 == 0b0001 => (
+            if op { "bic" } else { "orr" },
+            arrangement(2, q),
+            Some(shift(0, (c >> 1) * 8)),
+        ),
+        (c, _) if c & 0b1101 == 0b1000 => (
+            if op { "mvni" } else { "movi" },
+            arrangement(1, q),
+            Some(shift(0, ((c >> 1) & 1) * 8)),
+        ),
+        (c, _) if c & 0b1101 == 0b1001 => (
+            if op { "bic" } else { "orr" },
+            arrangement(1, q),
+            Some(shift(0, ((c >> 1) & 1) * 8)),
+        ),
+        (c, _) if c & 0b1110 == 0b1100 => (
+            if op { "mvni" } else { "movi" },
+            arrangement(2, q),
+            Some(Operand::Bitwise(Shift::Msl, (((c & 1) + 1) * 8) as i64)),
+        ),
+        _ => return None,
+    };
+    let mut operands = vec![vector(rd, a), imm];
+    if let Some(Operand::Bitwise(kind, amount)) = shifted {
+        if amount != 0 {
+            operands.push(Operand::Bitwise(kind, amount));
+        }
+    }
+    Some(instruction(name, operands))
+}
+
+fn decode_shift_immediate(word: u32) -> Option<Instruction> {
+    let q = bit(word, 30);
+    let u = bit(word, 29);
+    let immh = bits(word, 22, 19);
+    let immb = bits(word, 18, 16);
+    let opcode = bits(word, 15, 11);
+    let rn = bits(word, 9, 5);
+    let rd = bits(word, 4, 0);
+    let size = 31 - immh.leading_zeros();
+    let esize = 8u32 << size;
+    let combined = (immh << 3) | immb;
+    let right = Operand::Immediate((2 * esize - combined) as i64);
+    let left = Operand::Immediate((combined - esize) as i64);
+    let suffix = |name: &str| {
+        if q {
+            format!("{}2", name)
+        } else {
+            name.to_string()
+        }
+    };
+    let same = arrangement(size, q);
+    if size == 3 && !q {
+        return None;
+    }
+    match (u, opcode) {
+        (_, 0b00000) | (_, 0b00010) | (_, 0b00100) | (true, 0b01000) => {
+            let name = match (u, opcode) {
+                (false, 0b00000) => "sshr",
+                (true, 0b00000) => "ushr",
+                (false, 0b00010) => "ssra",
+                (true, 0b00010) => "usra",
+                (false, 0b00100) => "srshr",
+                (true, 0b00100) => "urshr",
+                _ => "sri",
+            };
+            Some(instruction(
+                name,
+                vec![vector(rd, same.clone()), vector(rn, same), right],
+            ))
+        }
+        (_, 0b01010) => Some(instruction(
+            if u { "sli" } else { "shl" },
+            vec![vector(rd, same.clone()), vector(rn, same), left],
+        )),
+        (_, 0b10000) | (_, 0b10001) | (true, 0b10010) | (true, 0b10011) => {
+            if size == 3 {
+                return None;
+            }
+            let base = match (u, opcode) {
+                (false, 0b10000) => "shrn",
+                (false, 0b10001) => "rshrn",
+                (true, 0b10000) => "sqshrun",
+                (true, 0b10001) => "sqrshrun",
+                (true, 0b10010) => "uqshrn",
+                _ => "uqrshrn",
+            };
+            Some(Instruction::from_operands(
+                suffix(base),
+                vec![
+                    vector(rd, same),
+                    vector(rn, arrangement(size + 1, true)),
+                    right,
+                ],
+            ))
+        }
+        (_, 0b10100) => {
+            if size == 3 {
+                return None;
+            }
+            let base = if u { "ushll" } else { "sshll" };
+            let wide = vector(rd, arrangement(size + 1, true));
+            Some(Instruction::from_operands(
+                suffix(base),
+                vec![wide, vector(rn, same), left],
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_word(word: u32) -> Instruction {
+        decode(word, 0x100, None, &mut |address| format!("L{:x}", address))
+            .expect("word should decode")
+    }
+
+    #[test]
+    fn test_decode_matches_text_parser() {
+        // words produced by llvm-mc for the text on the right
+        let cases = [
+            (0x91001020, "add x0,x1,#4"),
+            (0x910003fd, "mov x29,sp"),
+            (0xf100101f, "cmp x0,#4"),
+            (0xeb42083f, "cmp x1,x2,lsr#2"),
+            (0xf9400420, "ldr x0,[x1,#8]"),
+            (0x4cdfa000, "ld1 {v0.16b,v1.16b},[x0],#32"),
+            (0x4cc47800, "ld1 {v0.4s},[x0],x4"),
+            (0x0d009001, "st1 {v1.s}[1],[x0]"),
+            (0xa9bf7bfd, "stp x29,x30,[sp,#-16]!"),
+            (0xa8c17bfd, "ldp x29,x30,[sp],#16"),
+            (0x1a9f17e0, "cset w0,eq"),
+            (0x9a823020, "csel x0,x1,x2,lo"),
+            (0x52a00020, "mov w0,#65536"),
+            (0xf2a24680, "movk x0,#4660,lsl#16"),
+            (0x92800020, "mov x0,#-2"),
+            (0x92401c20, "and x0,x1,#0xff"),
+            (0xd37df020, "lsl x0,x1,#3"),
+            (0x53027c20, "lsr w0,w1,#2"),
+            (0x9b027c20, "mul x0,x1,x2"),
+            (0x4ea11c20, "mov v0.16b,v1.16b"),
+            (0x6e024020, "ext v0.16b,v1.16b,v2.16b,#8"),
+            (0x4e040c20, "dup v0.4s,w1"),
+            (0x2f08a420, "ushll v0.8h,v1.8b,#0"),
+            (0x4e284a40, "aese v0.16b,v18.16b"),
+            (0x4f07e420, "movi v0.16b,#225"),
+            (0xb8627820, "ldr w0,[x1,x2,lsl#2]"),
+            (0xf8617802, "ldr x2,[x0,x1,lsl #3]"),
+            (0x38614802, "ldrb w2,[x0,w1,uxtw]"),
+            (0xb861d802, "ldr w2,[x0,w1,sxtw #2]"),
+            (0xf85f8020, "ldur x0,[x1,#-8]"),
+            (0x5ac00863, "rev w3,w3"),
+        ];
+        for (word, text) in cases {
+            assert_eq!(
+                decode_word(word),
+                Instruction::new(text.to_string()),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_branches() {
+        assert_eq!(
+            decode_word(0x54fffe41),
+            Instruction::new("b.ne Lc8".to_string())
+        );
+        assert_eq!(
+            decode_word(0xd65f03c0),
+            Instruction::from_operands("ret".to_string(), vec![])
+        );
+        assert_eq!(
+            decode_word(0xb4fffe20),
+            Instruction::new("cbz x0,Lc4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_relocated() {
+        let mut label = |address: u64| format!("L{:x}", address);
+        let page = Reference::Page("K256".to_string());
+        let offset = Reference::PageOffset("K256".to_string());
+        assert_eq!(
+            decode(0x90000000, 0, Some(&page), &mut label),
+            Some(Instruction::new("adrp x0,K256@PAGE".to_string()))
+        );
+        assert_eq!(
+            decode(0x91000000, 4, Some(&offset), &mut label),
+            Some(Instruction::new("add x0,x0,K256@PAGEOFF".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown() {
+        assert_eq!(
+            decode(0x00000000, 0, None, &mut |a| format!("L{:x}", a)),
+            None
+        );
+    }
+}
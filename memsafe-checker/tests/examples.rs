@@ -6,6 +6,61 @@ fn init() {
     let _ = env_logger::builder().is_test(true).try_init();
 }
 
+// the program, the (register, name) inputs and the (name, kind, bytes) regions they point to
+fn new_engine<'ctx>(
+    ctx: &'ctx Context,
+    program: &[impl ToString],
+    inputs: &[(usize, &str)],
+    regions: &[(&str, RegionType, i64)],
+) -> bums::engine::ExecutionEngine<'ctx> {
+    let program = program.iter().map(|line| line.to_string()).collect();
+    let mut engine = bums::engine::ExecutionEngine::new(program, ctx);
+    for (register, name) in inputs {
+        engine.add_abstract_from(*register, name.to_string());
+    }
+    for (name, kind, bytes) in regions {
+        engine.add_region(
+            *kind,
+            name.to_string(),
+            AbstractExpression::Immediate(*bytes),
+        );
+    }
+    engine
+}
+
+// a 16 byte key and a 256 byte table, the key is secret, the body can branch to end
+fn secret_engine<'ctx>(ctx: &'ctx Context, body: &[&str]) -> bums::engine::ExecutionEngine<'ctx> {
+    let program = [&["start:"], body, &["end:", "ret"]].concat();
+    let mut engine = new_engine(
+        ctx,
+        &program,
+        &[(0, "key"), (1, "table")],
+        &[
+            ("key", RegionType::READ, 16),
+            ("table", RegionType::READ, 256),
+        ],
+    );
+    engine.add_secret_region("key".to_string());
+    engine
+}
+
+// the body runs between entry and ret, helper is a function it can call
+fn abi_engine<'ctx>(ctx: &'ctx Context, body: &[&str]) -> bums::engine::ExecutionEngine<'ctx> {
+    let program = [
+        &["start:"],
+        body,
+        &["ret", "helper:", "add x0, x0, #1", "ret"],
+    ]
+    .concat();
+    new_engine(ctx, &program, &[(0, "a")], &[])
+}
+
+// a is a 16 byte buffer the program only writes
+fn output_engine<'ctx>(ctx: &'ctx Context, body: &[&str]) -> bums::engine::ExecutionEngine<'ctx> {
+    let program = [&["start:"], body, &["ret"]].concat();
+    new_engine(ctx, &program, &[(0, "a")], &[("a", RegionType::WRITE, 16)])
+}
+
 #[test]
 fn example_stack_push_pop() {
     init();
@@ -13,9 +68,11 @@ fn example_stack_push_pop() {
     let mut program = Vec::new();
     let start_label = "test".to_string();
     program.push("test:".to_string());
+    program.push("sub sp,sp,#16".to_string());
     program.push("add x3,x3,#21".to_string());
     program.push("str x3,[sp,#4]".to_string());
     program.push("ldr x0,[sp,#4]".to_string());
+    program.push("add sp,sp,#16".to_string());
     program.push("ret".to_string());
 
     let cfg = Config::new();
@@ -29,6 +86,7 @@ fn example_stack_push_pop() {
     assert!(res.is_ok());
     assert_eq!(engine.get_register_output(0).base, None);
     assert_eq!(engine.get_register_output(0).offset, 22);
+    assert_eq!(engine.get_stack_depth(), Some(16));
 }
 
 /*
@@ -286,6 +344,48 @@ fn example_z3_real_bound_unsafe() -> std::io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn example_z3_violation_names_region_line_and_inputs() -> std::io::Result<()> {
+    init();
+
+    let mut program = Vec::new();
+    program.push("start:".to_string());
+    program.push("cmp x1, #0".to_string());
+    program.push("b.eq end".to_string());
+    program.push("ldr x2, [x0, #8] // past a short buffer".to_string());
+    program.push("end:".to_string());
+    program.push("ret".to_string());
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut engine = bums::engine::ExecutionEngine::new(program, &ctx);
+
+    engine.add_abstract_from(0, "base".to_string());
+    engine.add_abstract_from(1, "n".to_string());
+    engine.add_region(
+        RegionType::READ,
+        "base".to_string(),
+        AbstractExpression::Abstract("length".to_string()),
+    );
+
+    let res = engine.start("start".to_string());
+    let Err(e) = res else { panic!() };
+    let violation = bums::engine::memory_safety_violation(&e).expect("structured violation");
+    assert_eq!(violation.region.as_deref(), Some("base"));
+    assert_eq!(
+        violation.instruction.as_deref(),
+        Some("line 4: ldr x2, [x0, #8]")
+    );
+    assert!(!violation.path_condition.is_empty());
+    let length = violation
+        .counterexample
+        .iter()
+        .find(|(name, _)| name == "length")
+        .expect("length in counterexample");
+    assert!(length.1 < 8);
+    Ok(())
+}
+
 #[test]
 fn example_z3_simple_loop_with_no_mem_access() -> std::io::Result<()> {
     init();
@@ -468,3 +568,1459 @@ fn example_z3_example_branch() -> std::io::Result<()> {
     assert!(res.is_ok());
     Ok(())
 }
+
+// when the flags could go either way, each path of a csel goes on with the register it picked
+#[test]
+fn example_select_on_both_paths() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut engine = new_engine(
+        &ctx,
+        &[
+            "start:",
+            "add x4, x2, #512",
+            "cmp x0, x1",
+            "csel x2, x2, x4, eq",
+            "ldr x5, [x2]",
+            "ret",
+        ],
+        &[(0, "left"), (1, "right"), (2, "buf")],
+        &[("buf", RegionType::READ, 8)],
+    );
+
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn example_object_file_loop_and_data() -> std::io::Result<()> {
+    use object::write::{Object, Relocation, StandardSection, Symbol, SymbolSection};
+    use object::{
+        elf, Architecture, BinaryFormat, Endianness, RelocationFlags, SymbolFlags, SymbolKind,
+        SymbolScope,
+    };
+    init();
+
+    // llvm-mc output for:
+    // start: mov x1,#0; mov x2,#4
+    // loop:  ldr x3,[x0]; add x1,x1,#1; add x0,x0,#4; cmp x1,x2; b.ne loop
+    //        adrp x4,table; add x4,x4,:lo12:table; ldrb w5,[x4]; ret
+    let words: [u32; 11] = [
+        0xd2800001, 0xd2800082, 0xf9400003, 0x91000421, 0x91001000, 0xeb02003f, 0x54ffff81,
+        0x90000004, 0x91000084, 0x39400085, 0xd65f03c0,
+    ];
+    let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    let mut obj = Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+    let text = obj.section_id(StandardSection::Text);
+    obj.append_section_data(text, &code, 4);
+    let rodata = obj.section_id(StandardSection::ReadOnlyData);
+    obj.append_section_data(rodata, &[1, 2, 3, 4], 1);
+    obj.add_symbol(Symbol {
+        name: b"start".to_vec(),
+        value: 0,
+        size: code.len() as u64,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let table = obj.add_symbol(Symbol {
+        name: b"table".to_vec(),
+        value: 0,
+        size: 4,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: SymbolSection::Section(rodata),
+        flags: SymbolFlags::None,
+    });
+    for (offset, r_type) in [
+        (28, elf::R_AARCH64_ADR_PREL_PG_HI21),
+        (32, elf::R_AARCH64_ADD_ABS_LO12_NC),
+    ] {
+        obj.add_relocation(
+            text,
+            Relocation {
+                offset,
+                symbol: table,
+                addend: 0,
+                flags: RelocationFlags::Elf { r_type },
+            },
+        )
+        .expect("relocation should be valid");
+    }
+    let bytes = obj.write().expect("object should serialize");
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut engine = bums::engine::ExecutionEngine::new_from_object(&bytes, &ctx)?;
+
+    engine.add_abstract_from(0, "base".to_string());
+    engine.add_region(
+        RegionType::READ,
+        "base".to_string(),
+        AbstractExpression::Immediate(16),
+    );
+
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok());
+    Ok(())
+}
+
+#[test]
+fn example_object_file_out_of_bounds() -> std::io::Result<()> {
+    use object::write::{Object, StandardSection, Symbol, SymbolSection};
+    use object::{Architecture, BinaryFormat, Endianness, SymbolFlags, SymbolKind, SymbolScope};
+    init();
+
+    // start: ldr x3,[x0,#512]; ret
+    let words: [u32; 2] = [0xf9410003, 0xd65f03c0];
+    let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    let mut obj = Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+    let text = obj.section_id(StandardSection::Text);
+    obj.append_section_data(text, &code, 4);
+    obj.add_symbol(Symbol {
+        name: b"start".to_vec(),
+        value: 0,
+        size: code.len() as u64,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let bytes = obj.write().expect("object should serialize");
+
+    // the same object as the only member of an ar archive, name/ mtime uid gid mode size
+    let mut archive = b"!<arch>\n".to_vec();
+    archive.extend(
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            "start.o/",
+            0,
+            0,
+            0,
+            644,
+            bytes.len()
+        )
+        .bytes(),
+    );
+    archive.extend(&bytes);
+    if bytes.len() % 2 == 1 {
+        archive.push(b'\n');
+    }
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    for input in [bytes, archive] {
+        let mut engine = bums::engine::ExecutionEngine::new_from_object(&input, &ctx)?;
+        engine.add_abstract_from(0, "base".to_string());
+        engine.add_region(
+            RegionType::READ,
+            "base".to_string(),
+            AbstractExpression::Immediate(16),
+        );
+
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains("outside"), "{}", err);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_object_file_data_pointers() -> std::io::Result<()> {
+    use object::write::{Object, Relocation, StandardSection, Symbol, SymbolSection};
+    use object::{
+        elf, Architecture, BinaryFormat, Endianness, RelocationFlags, SymbolFlags, SymbolKind,
+        SymbolScope,
+    };
+    init();
+
+    // start: adrp x1,ptr; add x1,x1,:lo12:ptr; ldr x2,[x1]; str x0,[x2]; ret
+    // with ptr: .quad target in .data, target in .rodata or .bss
+    let words: [u32; 5] = [0x90000001, 0x91000021, 0xf9400022, 0xf9000040, 0xd65f03c0];
+    let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let object_writing_to = |target_section: StandardSection| {
+        let mut obj = Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+        let text = obj.section_id(StandardSection::Text);
+        obj.append_section_data(text, &code, 4);
+        let data = obj.section_id(StandardSection::Data);
+        obj.append_section_data(data, &[0; 8], 8);
+        let section = obj.section_id(target_section);
+        let offset = match target_section {
+            StandardSection::UninitializedData => obj.append_section_bss(section, 8, 8),
+            _ => obj.append_section_data(section, &[0; 8], 8),
+        };
+        let mut symbol = |name: &[u8], value, kind, section| {
+            obj.add_symbol(Symbol {
+                name: name.to_vec(),
+                value,
+                size: 8,
+                kind,
+                scope: SymbolScope::Linkage,
+                weak: false,
+                section: SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            })
+        };
+        symbol(b"start", 0, SymbolKind::Text, text);
+        let ptr = symbol(b"ptr", 0, SymbolKind::Data, data);
+        let target = symbol(b"target", offset, SymbolKind::Data, section);
+        for (section, offset, symbol, r_type) in [
+            (text, 0, ptr, elf::R_AARCH64_ADR_PREL_PG_HI21),
+            (text, 4, ptr, elf::R_AARCH64_ADD_ABS_LO12_NC),
+            (data, 0, target, elf::R_AARCH64_ABS64),
+        ] {
+            obj.add_relocation(
+                section,
+                Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Elf { r_type },
+                },
+            )
+            .expect("relocation should be valid");
+        }
+        obj.write().expect("object should serialize")
+    };
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // the pointer is followed into .bss, which can be written
+    let bytes = object_writing_to(StandardSection::UninitializedData);
+    let mut engine = bums::engine::ExecutionEngine::new_from_object(&bytes, &ctx)?;
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // and into .rodata, which cannot
+    let bytes = object_writing_to(StandardSection::ReadOnlyData);
+    let mut engine = bums::engine::ExecutionEngine::new_from_object(&bytes, &ctx)?;
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("constant"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn example_object_file_unsupported_relocation() {
+    use object::write::{Object, Relocation, StandardSection, Symbol, SymbolSection};
+    use object::{
+        elf, Architecture, BinaryFormat, Endianness, RelocationFlags, SymbolFlags, SymbolKind,
+        SymbolScope,
+    };
+
+    // start: adrp x3, :got:table; ret
+    let words: [u32; 2] = [0x90000003, 0xd65f03c0];
+    let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    let mut obj = Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+    let text = obj.section_id(StandardSection::Text);
+    obj.append_section_data(text, &code, 4);
+    obj.add_symbol(Symbol {
+        name: b"start".to_vec(),
+        value: 0,
+        size: code.len() as u64,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+    let table = obj.add_symbol(Symbol {
+        name: b"table".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    obj.add_relocation(
+        text,
+        Relocation {
+            offset: 0,
+            symbol: table,
+            addend: 0,
+            flags: RelocationFlags::Elf {
+                r_type: elf::R_AARCH64_ADR_GOT_PAGE,
+            },
+        },
+    )
+    .expect("relocation should be valid");
+    let bytes = obj.write().expect("object should serialize");
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let res = bums::engine::ExecutionEngine::new_from_object(&bytes, &ctx);
+    let err = res.err().expect("the relocation cannot be followed");
+    assert!(
+        err.to_string().contains("Unsupported relocation"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn example_object_file_not_an_object() {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let res = bums::engine::ExecutionEngine::new_from_object(b"start:\nret\n", &ctx);
+    assert!(res.is_err());
+}
+
+#[test]
+fn example_simd_structure_load_footprint() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let run = |program: &[&str], length: i64| {
+        let mut engine = new_engine(
+            &ctx,
+            &[&["start:"], program, &["ret"]].concat(),
+            &[(0, "base")],
+            &[("base", RegionType::READ, length)],
+        );
+        engine.start("start".to_string())
+    };
+
+    // the 64 bytes of ld1 fit in 64 bytes, not in 63
+    let load = ["ld1 {v0.16b-v3.16b}, [x0]"];
+    assert!(run(&load, 64).is_ok());
+    let err = run(&load, 63).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+
+    // the 32 bytes ld1 reads after the writeback of ld4 end at 96
+    let loads = ["ld4 {v0.4s-v3.4s}, [x0], #64", "ld1 {v4.16b, v5.16b}, [x0]"];
+    assert!(run(&loads, 96).is_ok());
+    let err = run(&loads, 95).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Accessing address outside allowable memory regions Abstract(\"base\"), 64"
+    );
+    Ok(())
+}
+
+#[test]
+fn example_pair_footprint() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let run = |pair: &str, length: i64| {
+        let mut engine = new_engine(
+            &ctx,
+            &["start:", pair, "ret"],
+            &[(0, "base")],
+            &[("base", RegionType::RW, length)],
+        );
+        engine.start("start".to_string())
+    };
+
+    // both registers of the pair, next to each other
+    for (pair, size) in [
+        ("stp w1, w2, [x0]", 8),
+        ("ldp w1, w2, [x0]", 8),
+        ("stp x1, x2, [x0]", 16),
+        ("ldp x1, x2, [x0]", 16),
+        ("stp d0, d1, [x0]", 16),
+        ("stp q0, q1, [x0]", 32),
+        ("ldp q0, q1, [x0]", 32),
+    ] {
+        let res = run(pair, size);
+        assert!(res.is_ok(), "{} on {} bytes: {:?}", pair, size, res);
+        let res = run(pair, size - 1);
+        assert!(res.is_err(), "{} on {} bytes", pair, size - 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_simd_structure_lanes_register_writeback() -> std::io::Result<()> {
+    init();
+
+    let mut program = Vec::new();
+    program.push("start:".to_string());
+    program.push("ld1 {v0.s}[1], [x0], x1".to_string());
+    program.push("ld1 {v0.s}[2], [x0], x1".to_string());
+    program.push("st2 {v0.d, v1.d}[0], [x0]".to_string());
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut engine = bums::engine::ExecutionEngine::new(program, &ctx);
+
+    engine.add_abstract_from(0, "base".to_string());
+    engine.add_immediate(String::from("x1"), 8);
+    // lanes at 0 and 8, then two doublewords at 16
+    engine.add_region(
+        RegionType::RW,
+        "base".to_string(),
+        AbstractExpression::Immediate(32),
+    );
+
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok());
+    assert_eq!(engine.get_register_output(0).offset, 16);
+    Ok(())
+}
+
+fn sve_copy_loop(condition: &str, store: &str, increment: &str) -> Vec<String> {
+    let mut program = Vec::new();
+    program.push("start:".to_string());
+    program.push("mov x3, #0".to_string());
+    program.push(condition.to_string());
+    program.push("b.none done".to_string());
+    program.push("loop:".to_string());
+    program.push("ld1w {z0.s}, p0/z, [x0, x3, lsl #2]".to_string());
+    program.push(store.to_string());
+    program.push(increment.to_string());
+    program.push(condition.to_string());
+    program.push("b.first loop".to_string());
+    program.push("done:".to_string());
+    program.push("ret".to_string());
+    program
+}
+
+#[test]
+fn example_sve_predicated_loop_safe_for_every_vector_length() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // skipping every other vector is still in bounds
+    for increment in ["incw x3", "incw x3, all, mul #2"] {
+        let program = sve_copy_loop(
+            "whilelo p0.s, x3, x2",
+            "st1w {z0.s}, p0, [x1, x3, lsl #2]",
+            increment,
+        );
+        // x0 and x1 are arrays of x2 words, copied a vector at a time
+        let mut engine = new_engine(&ctx, &program, &[(0, "src"), (1, "dst"), (2, "count")], &[]);
+        let bytes = generate_expression(
+            "*",
+            AbstractExpression::Abstract("count".to_string()),
+            AbstractExpression::Immediate(4),
+        );
+        engine.add_region(RegionType::READ, "src".to_string(), bytes.clone());
+        engine.add_region(RegionType::RW, "dst".to_string(), bytes);
+        let res = engine.start("start".to_string());
+        assert!(res.is_ok(), "{}: {:?}", increment, res);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_sve_predicated_loop_unsafe() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    let unsafe_loops = [
+        // the last store writes a whole vector
+        ("whilelo p0.s, x3, x2", "str z0, [x1, #0, mul vl]"),
+        // lanes up to and including x2 are active, one word too many
+        ("whilels p0.s, x3, x2", "st1w {z0.s}, p0, [x1, x3, lsl #2]"),
+    ];
+    for (condition, store) in unsafe_loops {
+        let program = sve_copy_loop(condition, store, "incw x3");
+        // x0 and x1 are arrays of x2 words, copied a vector at a time
+        let mut engine = new_engine(&ctx, &program, &[(0, "src"), (1, "dst"), (2, "count")], &[]);
+        let bytes = generate_expression(
+            "*",
+            AbstractExpression::Abstract("count".to_string()),
+            AbstractExpression::Immediate(4),
+        );
+        engine.add_region(RegionType::READ, "src".to_string(), bytes.clone());
+        engine.add_region(RegionType::RW, "dst".to_string(), bytes);
+        assert!(engine.start("start".to_string()).is_err(), "{}", store);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_sve_predicated_loop_pointer_induction() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // the destination pointer has to move as fast as the index, a vector per iteration
+    for (step, safe) in [("addvl x4, x4, #1", true), ("addvl x4, x4, #2", false)] {
+        let mut program = sve_copy_loop("whilelo p0.s, x3, x2", "st1w {z0.s}, p0, [x4]", "incw x3");
+        program.insert(1, "mov x4, x1".to_string());
+        program.insert(9, step.to_string());
+
+        // x0 and x1 are arrays of x2 words, copied a vector at a time
+        let mut engine = new_engine(&ctx, &program, &[(0, "src"), (1, "dst"), (2, "count")], &[]);
+        let bytes = generate_expression(
+            "*",
+            AbstractExpression::Abstract("count".to_string()),
+            AbstractExpression::Immediate(4),
+        );
+        engine.add_region(RegionType::READ, "src".to_string(), bytes.clone());
+        engine.add_region(RegionType::RW, "dst".to_string(), bytes);
+        assert_eq!(engine.start("start".to_string()).is_ok(), safe);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_struct_field_bounds_access() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // a struct field holds the input it was named after, an invariant on it bounds the access
+    for bounded in [true, false] {
+        let mut engine = new_engine(
+            &ctx,
+            &[
+                "start:",
+                "ldr w3, [x0, #240]",
+                "lsl x3, x3, #4",
+                "add x4, x0, x3",
+                "ldr x5, [x4]",
+            ],
+            &[(0, "key")],
+            &[("key", RegionType::READ, 244)],
+        );
+        engine.add_abstract_to_memory(
+            "key".to_string(),
+            240,
+            AbstractExpression::Abstract("key_fieldrounds".to_string()),
+        );
+        if bounded {
+            engine.add_invariant(generate_comparison(
+                "<=",
+                AbstractExpression::Abstract("key_fieldrounds".to_string()),
+                AbstractExpression::Immediate(14),
+            ));
+            engine.add_invariant(generate_comparison(
+                ">=",
+                AbstractExpression::Abstract("key_fieldrounds".to_string()),
+                AbstractExpression::Immediate(0),
+            ));
+        }
+        assert_eq!(engine.start("start".to_string()).is_ok(), bounded);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_pointer_loaded_from_region_checks_inner_buffer() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // descriptors hold pointers to buffers of their own, i.e. an array of {ptr, len}
+    for (load, violated) in [
+        ("ldr q0, [x5, #48]", None),
+        ("ldr q0, [x5, #56]", Some("inner")),
+    ] {
+        let mut engine = new_engine(
+            &ctx,
+            &["start:", "ldr x5, [x0, #16]", load],
+            &[(0, "descs")],
+            &[("descs", RegionType::READ, 32)],
+        );
+        engine.add_region(
+            RegionType::READ,
+            "inner".to_string(),
+            AbstractExpression::Abstract("inner_len".to_string()),
+        );
+        engine.add_abstract_to_memory(
+            "descs".to_string(),
+            16,
+            AbstractExpression::Abstract("inner".to_string()),
+        );
+        engine.add_invariant(generate_comparison(
+            "==",
+            AbstractExpression::Abstract("inner_len".to_string()),
+            AbstractExpression::Immediate(64),
+        ));
+
+        // the second load ends past the inner buffer
+        match (engine.start("start".to_string()), violated) {
+            (Ok(_), None) => (),
+            (Err(e), Some(region)) => {
+                let violation =
+                    bums::engine::memory_safety_violation(&e).expect("structured violation");
+                assert_eq!(violation.region.as_deref(), Some(region));
+            }
+            (res, _) => panic!("{}: {:?}", load, res),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn example_rows_loop_stays_in_rows() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // h rows, one vector per row, the pointer moves by a stride that can be negative. the rows
+    // can be narrower than the loads, or the loop can go over one row too many
+    for (row_width, extra_rows, safe) in [(16, 0, true), (8, 0, false), (16, 1, false)] {
+        let extra = format!("add x2, x2, #{}", extra_rows);
+        let mut engine = new_engine(
+            &ctx,
+            &[
+                "start:",
+                "cbz x2, end",
+                &extra,
+                "loop:",
+                "ldr q0, [x0]",
+                "add x0, x0, x1",
+                "sub x2, x2, #1",
+                "cbnz x2, loop",
+                "end:",
+                "ret",
+            ],
+            &[(0, "dst"), (1, "stride"), (2, "h")],
+            &[],
+        );
+        engine.add_region_2d(
+            RegionType::READ,
+            "dst".to_string(),
+            AbstractExpression::Immediate(row_width),
+            AbstractExpression::Abstract("stride".to_string()),
+            AbstractExpression::Abstract("h".to_string()),
+        );
+        let res = engine.start("start".to_string());
+        assert_eq!(res.is_ok(), safe, "{} {}: {:?}", row_width, extra_rows, res);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_postcondition_on_return_value() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let program = [
+        "start:",
+        "mov x2, #1",
+        "mov x3, #0",
+        "cmp x0, x1",
+        "csel x0, x2, x3, lo",
+        "ret",
+    ];
+    let inputs = [(0, "a"), (1, "b")];
+
+    let mut engine = new_engine(&ctx, &program, &inputs, &[]);
+    engine.add_postcondition(generate_comparison(
+        "<=",
+        AbstractExpression::Abstract("ret".to_string()),
+        AbstractExpression::Immediate(1),
+    ));
+    engine.add_postcondition(generate_comparison(
+        ">=",
+        AbstractExpression::Abstract("ret".to_string()),
+        AbstractExpression::Immediate(0),
+    ));
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // the carry is set whenever a is below b
+    let mut engine = new_engine(&ctx, &program, &inputs, &[]);
+    engine.add_postcondition(generate_comparison(
+        "<",
+        AbstractExpression::Abstract("ret".to_string()),
+        AbstractExpression::Immediate(1),
+    ));
+    let err = engine.start("start".to_string()).unwrap_err();
+    let violation = bums::engine::memory_safety_violation(&err).expect("postcondition violation");
+    assert!(violation.counterexample.contains(&("ret".to_string(), 1)));
+    Ok(())
+}
+
+#[test]
+fn example_secret_independent_execution() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // selecting on a secret is fine, branching on it is not
+    let mut engine = secret_engine(
+        &ctx,
+        &[
+            "ldrb w3, [x0]",
+            "eor x3, x2, x3",
+            "cmp x2, #0",
+            "csel x4, x2, x3, eq",
+            "udiv x5, x1, x1",
+        ],
+    );
+    engine.add_abstract_from(2, "counter".to_string());
+    engine.add_secret_argument(2);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    let mut engine = secret_engine(&ctx, &["ldrb w2, [x0, #3]", "cbz w2, end"]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("secret"), "{}", err);
+
+    // a table lookup at a secret index
+    let mut engine = secret_engine(&ctx, &["ldrb w2, [x0]", "ldrb w3, [x1, x2]"]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    let violation = bums::engine::memory_safety_violation(&err).expect("secret address");
+    assert_eq!(violation.region.as_deref(), Some("table"));
+
+    let mut engine = secret_engine(&ctx, &["ldrb w2, [x0]", "udiv x5, x3, x2"]);
+    assert!(engine.start("start".to_string()).is_err());
+    Ok(())
+}
+
+#[test]
+fn example_secret_spilled_to_stack() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // the public half of the spill stays public, the secret half comes back secret
+    let spill = [
+        "sub sp, sp, #16",
+        "ldr x2, [x0]",
+        "stp x2, xzr, [sp]",
+        "ldr x3, [sp, #8]",
+        "cbnz x3, end",
+        "ldr x4, [sp]",
+        "add sp, sp, #16",
+    ];
+    let mut engine = secret_engine(&ctx, &spill);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    let mut engine = secret_engine(&ctx, &[&spill[..6], &["cbz x4, end"]].concat());
+    assert!(engine.start("start".to_string()).is_err());
+    Ok(())
+}
+
+#[test]
+fn example_calling_convention_kept() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // x19, x20, d8 and d9 are saved around the call to helper and restored before ret
+    let program = [
+        "start:",
+        "stp x29, x30, [sp, #-32]!",
+        "stp x19, x20, [sp, #16]",
+        "stp d8, d9, [sp, #-16]!",
+        "mov x19, x0",
+        "fmov d8, x0",
+        "ins v9.d[1], x0",
+        "bl helper",
+        "add x20, x19, x0",
+        "ldp d8, d9, [sp], #16",
+        "ldp x19, x20, [sp, #16]",
+        "ldp x29, x30, [sp], #32",
+        "ret",
+        "helper:",
+        "add x0, x0, #1",
+        "ret",
+    ];
+    let mut engine = new_engine(&ctx, &program, &[(0, "a")], &[]);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_calling_convention_broken() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    let broken: [(&[&str], &str); 6] = [
+        (&["mov x19, x0"], "x19"),
+        (&["mov v8.16b, v0.16b"], "v8"),
+        (&["sub sp, sp, #16"], "Stack pointer not restored"),
+        (&["bl helper"], "x30"),
+        (
+            &["sub sp, sp, #8", "bl helper", "add sp, sp, #8"],
+            "16-byte aligned",
+        ),
+        (&["ldr x1, [sp, #8]"], "caller's stack"),
+    ];
+    for (body, expected) in broken {
+        let mut engine = abi_engine(&ctx, body);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{:?}: {}", body, err);
+    }
+
+    // the tenth argument is passed at sp+8
+    let mut engine = abi_engine(&ctx, &["ldr x1, [sp, #8]"]);
+    engine.add_abstract_from(9, "tenth".to_string());
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_stack_frame_bounds() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    let outside: [(&[&str], &str); 3] = [
+        (&["str x0, [sp, #-8]"], "below sp"),
+        (&["str x0, [sp]"], "caller's stack"),
+        (
+            &["sub sp, sp, #16", "ldr x1, [sp, #16]", "add sp, sp, #16"],
+            "caller's stack",
+        ),
+    ];
+    for (body, expected) in outside {
+        let mut engine = abi_engine(&ctx, body);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{:?}: {}", body, err);
+    }
+
+    // the deeper of the two paths, and a frame sized by an input
+    let mut engine = abi_engine(
+        &ctx,
+        &[
+            "stp x29, x30, [sp, #-16]!",
+            "cbz x0, small",
+            "sub sp, sp, #64",
+            "str x0, [sp, #56]",
+            "add sp, sp, #64",
+            "small:",
+            "ldp x29, x30, [sp], #16",
+        ],
+    );
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    assert_eq!(engine.get_stack_depth(), Some(80));
+
+    let mut engine = abi_engine(&ctx, &["sub sp, sp, x0", "str x0, [sp]", "add sp, sp, x0"]);
+    engine.add_invariant(generate_comparison(
+        ">=",
+        AbstractExpression::Abstract("a".to_string()),
+        AbstractExpression::Immediate(16),
+    ));
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    assert_eq!(engine.get_stack_depth(), None);
+    Ok(())
+}
+
+#[test]
+fn example_uninitialised_reads() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    let uninitialised: [(&[&str], &str); 3] = [
+        (&["str x1, [x0]", "ldr x2, [x0, #4]"], "at a + 8"),
+        (&["ldr x2, [x0, x1]"], "which nothing has been written to"),
+        (
+            &[
+                "sub sp, sp, #16",
+                "str x1, [sp]",
+                "ldp x2, x3, [sp]",
+                "add sp, sp, #16",
+            ],
+            "at sp + -8",
+        ),
+    ];
+    for (body, expected) in uninitialised {
+        let mut engine = output_engine(&ctx, body);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{:?}: {}", body, err);
+    }
+
+    let mut engine = output_engine(
+        &ctx,
+        &[
+            "stp x29, x30, [sp, #-16]!",
+            "str x1, [x0, #8]",
+            "ldr w2, [x0, #12]",
+            "ldp x29, x30, [sp], #16",
+        ],
+    );
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_output_initialised() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    let mut engine = output_engine(&ctx, &["str x1, [x0], #8", "str w1, [x0]"]);
+    engine.add_output_region("a".to_string());
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Does not initialise all of a, byte 12"),
+        "{}",
+        err
+    );
+
+    let mut engine = output_engine(&ctx, &["str x1, [x0], #8", "str x1, [x0]"]);
+    engine.add_output_region("a".to_string());
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // an atomic writes the bytes it swaps out
+    let mut engine = output_engine(&ctx, &["swp x1, x2, [x0]", "str x1, [x0, #8]"]);
+    engine.add_output_region("a".to_string());
+    engine.add_region_alignment("a".to_string(), 8);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_secrets_zeroised() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let zeroise = bums::computer::Zeroise {
+        stack: true,
+        registers: true,
+        returns: false,
+    };
+
+    let left: [(&[&str], &str); 4] = [
+        (&["ldr x2, [x0]"], "left in x2"),
+        (&["ldr q3, [x0]"], "left in v3"),
+        (
+            &[
+                "sub sp, sp, #32",
+                "ldp x2, x3, [x0]",
+                "stp x2, x3, [sp, #16]",
+                "mov x2, xzr",
+                "mov x3, xzr",
+                "add sp, sp, #32",
+            ],
+            "on the stack at sp + -16",
+        ),
+        (
+            &[
+                "sub sp, sp, #16",
+                "ldr q0, [x0]",
+                "str q0, [sp]",
+                "eor v0.16b, v0.16b, v0.16b",
+                "str d0, [sp]",
+                "add sp, sp, #16",
+            ],
+            "on the stack at sp + -8",
+        ),
+    ];
+    for (body, expected) in left {
+        let mut engine = secret_engine(&ctx, body);
+        engine.require_zeroised(zeroise);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{:?}: {}", body, err);
+    }
+
+    let mut engine = secret_engine(
+        &ctx,
+        &[
+            "sub sp, sp, #16",
+            "ldr q0, [x0]",
+            "str q0, [sp]",
+            "eor v0.16b, v0.16b, v0.16b",
+            "str q0, [sp]",
+            "add sp, sp, #16",
+        ],
+    );
+    engine.require_zeroised(zeroise);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // the result may depend on the key
+    let mut engine = secret_engine(&ctx, &["ldrb w0, [x0]"]);
+    engine.require_zeroised(bums::computer::Zeroise {
+        returns: true,
+        ..zeroise
+    });
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_aliasing_regions() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // input in x0 and output in x1, 16 bytes each, bad reads past the end of the input
+    let alias_engine = |body: &[&str]| {
+        let program = [
+            &["start:"],
+            body,
+            &["ret", "bad:", "ldr x2, [x0, #512]", "ret"],
+        ]
+        .concat();
+        new_engine(
+            &ctx,
+            &program,
+            &[(0, "input"), (1, "output")],
+            &[
+                ("input", RegionType::READ, 16),
+                ("output", RegionType::WRITE, 16),
+            ],
+        )
+    };
+
+    // nothing is known about where the regions are
+    let mut engine = alias_engine(&["cmp x0, x1", "b.eq bad"]);
+    assert!(engine.start("start".to_string()).is_err());
+
+    let mut engine = alias_engine(&["cmp x0, x1", "b.eq bad"]);
+    engine.add_disjoint_regions("input".to_string(), "output".to_string());
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // a 2D input spans its rows
+    let mut engine = new_engine(
+        &ctx,
+        &[
+            "start:",
+            "cmp x0, x1",
+            "b.eq bad",
+            "ret",
+            "bad:",
+            "ldr x2, [x0, #512]",
+            "ret",
+        ],
+        &[(0, "input"), (1, "output")],
+        &[("output", RegionType::WRITE, 16)],
+    );
+    engine.add_region_2d(
+        RegionType::READ,
+        "input".to_string(),
+        AbstractExpression::Immediate(8),
+        AbstractExpression::Abstract("stride".to_string()),
+        AbstractExpression::Abstract("h".to_string()),
+    );
+    engine.add_disjoint_regions("input".to_string(), "output".to_string());
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    let mut engine = alias_engine(&["cmp x0, x1", "b.ne bad"]);
+    engine.add_alias("input".to_string(), "output".to_string(), Aliasing::Exact);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // an output that may be the input holds what the input holds
+    let mut engine = alias_engine(&["ldr x2, [x1]"]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("uninitialised"), "{}", err);
+
+    let mut engine = alias_engine(&["ldr x2, [x1]"]);
+    engine.add_alias("input".to_string(), "output".to_string(), Aliasing::May);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_constant_data_write() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // K is 8 bytes of constant data and counter is writable data, both after the body
+    let static_data_engine = |body: &[&str]| {
+        let data = [
+            "ret",
+            ".section .rodata",
+            "K:",
+            ".long 0x428a2f98, 0x71374491",
+            ".data",
+            "counter:",
+            ".quad 0",
+        ];
+        new_engine(&ctx, &[&["start:"], body, &data].concat(), &[], &[])
+    };
+
+    let mut engine = static_data_engine(&[
+        "adr x1, K",
+        "ldr w2, [x1, #4]",
+        "adrp x3, counter@PAGE",
+        "add x3, x3, counter@PAGEOFF",
+        "str x2, [x3]",
+    ]);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // the two longs end at K + 8, where counter starts
+    let mut engine = static_data_engine(&["adr x1, K", "str x0, [x1, #8]"]);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    let constant: [(&[&str], &str); 2] = [
+        (&["adr x1, K", "str w0, [x1, #4]"], "constant data at K + 4"),
+        (
+            &["adr x1, start", "str w0, [x1]"],
+            "constant data at start + 0",
+        ),
+    ];
+    for (body, expected) in constant {
+        let mut engine = static_data_engine(body);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{:?}: {}", body, err);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_alignment_checked() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // words in x0, a buffer of u64 when it starts at a multiple of 8
+    let alignment_engine = |body: &[&str], alignment: Option<i64>| {
+        let program = [&["start:"], body, &["ret"]].concat();
+        let mut engine = new_engine(
+            &ctx,
+            &program,
+            &[(0, "words"), (1, "i")],
+            &[("words", RegionType::RW, 64)],
+        );
+        if let Some(alignment) = alignment {
+            engine.add_region_alignment("words".to_string(), alignment);
+        }
+        // i picks one of the eight words
+        engine.add_invariant(generate_comparison(
+            ">=",
+            AbstractExpression::Abstract("i".to_string()),
+            AbstractExpression::Immediate(0),
+        ));
+        engine.add_invariant(generate_comparison(
+            "<",
+            AbstractExpression::Abstract("i".to_string()),
+            AbstractExpression::Immediate(8),
+        ));
+        engine.check_alignment(bums::computer::AlignmentCheck::Natural);
+        engine
+    };
+
+    let aligned: [&[&str]; 5] = [
+        &["ldr x2, [x0, #8]", "ldr w2, [x0, #4]", "ldrb w2, [x0, #3]"],
+        &["ldp x2, x3, [x0, #16]!", "ldr x2, [x0], #8"],
+        &["lsl x1, x1, #3", "ldr x2, [x0, x1]"],
+        &["ldr x2, [x0, x1, lsl #3]"],
+        &["sub sp, sp, #32", "str x0, [sp, #8]", "add sp, sp, #32"],
+    ];
+    for body in aligned {
+        let mut engine = alignment_engine(body, Some(8));
+        let res = engine.start("start".to_string());
+        assert!(res.is_ok(), "{:?}: {:?}", body, res);
+    }
+
+    let misaligned: [(&[&str], Option<i64>); 4] = [
+        (&["ldr x2, [x0, #4]"], Some(8)),
+        (&["ldr w2, [x0]"], None),
+        (&["ldr x2, [x0, x1]"], Some(8)),
+        (&["ldr x2, [x0, x1, lsl #2]"], Some(8)),
+    ];
+    for (body, alignment) in misaligned {
+        let mut engine = alignment_engine(body, alignment);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains("aligned"), "{:?}: {}", body, err);
+    }
+
+    // only exclusive and atomic accesses are checked by default
+    let mut engine = alignment_engine(&["ldr x2, [x0, #4]"], Some(8));
+    engine.check_alignment(bums::computer::AlignmentCheck::Required);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+    Ok(())
+}
+
+#[test]
+fn example_register_offset_bounds() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // the index in x1 is extended, shifted and added to buf, then the access size is checked
+    let index_engine = |body: &[&str]| {
+        let program = [&["start:"], body, &["ret"]].concat();
+        new_engine(
+            &ctx,
+            &program,
+            &[(0, "buf"), (1, "i")],
+            &[("buf", RegionType::READ, 16)],
+        )
+    };
+
+    let in_bounds: [&[&str]; 3] = [
+        &["mov x1, #15", "ldrb w2, [x0, x1]"],
+        &["mov x1, #1", "ldr x2, [x0, x1, lsl #3]"],
+        &["mov w1, #3", "ldr w2, [x0, w1, uxtw #2]"],
+    ];
+    for body in in_bounds {
+        let mut engine = index_engine(body);
+        let res = engine.start("start".to_string());
+        assert!(res.is_ok(), "{:?}: {:?}", body, res);
+    }
+
+    let out_of_bounds: [&[&str]; 4] = [
+        &["mov x1, #100", "ldrb w2, [x0, x1]"],
+        &["mov x1, #2", "ldr x2, [x0, x1, lsl #3]"],
+        &["mov x1, #12", "ldr x2, [x0, x1]"],
+        &["ldrb w2, [x0, x1]"],
+    ];
+    for body in out_of_bounds {
+        let mut engine = index_engine(body);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(err.to_string().contains("outside"), "{:?}: {}", body, err);
+    }
+    Ok(())
+}
+
+#[test]
+fn example_jump_table() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // a jump table like dav1d's, picked by the entry in x4, each entry reads input in x0
+    let jump_table_engine = |targets: &[&str]| {
+        let dispatch = [
+            "start:",
+            "adr x6, Ltbl",
+            "ldrh w9, [x6, x4, lsl #1]",
+            "sub x6, x6, w9, uxtw",
+            "br x6",
+        ];
+        let table = ["Ltbl:", ".hword Ltbl - 8b", ".hword Ltbl - 4b"];
+        let engine = new_engine(
+            &ctx,
+            &[&dispatch, targets, &table].concat(),
+            &[(0, "input"), (4, "entry")],
+            &[("input", RegionType::READ, 16)],
+        );
+        engine.add_invariant(generate_comparison(
+            ">=",
+            AbstractExpression::Abstract("entry".to_string()),
+            AbstractExpression::Immediate(0),
+        ));
+        engine.add_invariant(generate_comparison(
+            "<",
+            AbstractExpression::Abstract("entry".to_string()),
+            AbstractExpression::Immediate(2),
+        ));
+        engine
+    };
+
+    let mut engine = jump_table_engine(&[
+        "8:",
+        "AARCH64_VALID_JUMP_TARGET",
+        "ldr x2, [x0, #8]",
+        "ret",
+        "4:",
+        "AARCH64_VALID_JUMP_TARGET",
+        "ldr w2, [x0, #4]",
+        "ret",
+    ]);
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // every entry is followed, not only the first
+    let mut engine = jump_table_engine(&[
+        "8:",
+        "ldr x2, [x0, #8]",
+        "ret",
+        "4:",
+        "ldr x2, [x0, #512]",
+        "ret",
+    ]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+
+    // an entry past the end of the table is read when x4 is not bounded
+    let program = [
+        "start:",
+        "adr x6, Ltbl",
+        "ldrh w9, [x6, x4, lsl #1]",
+        "ret",
+        "Ltbl:",
+        ".hword 0",
+        ".hword 4",
+    ];
+    let mut engine = new_engine(&ctx, &program, &[(4, "entry")], &[]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+
+    // a call through a register holding the address of a function in the program
+    let program = [
+        "start:",
+        "stp x29, x30, [sp, #-16]!",
+        "adr x5, helper",
+        "blr x5",
+        "ldp x29, x30, [sp], #16",
+        "ret",
+        "helper:",
+        "ldr x2, [x0, #512]",
+        "ret",
+    ];
+    let mut engine = new_engine(
+        &ctx,
+        &program,
+        &[(0, "input")],
+        &[("input", RegionType::READ, 16)],
+    );
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+
+    // a code pointer passed in can point anywhere
+    let mut engine = new_engine(&ctx, &["start:", "br x4", "ret"], &[(4, "callback")], &[]);
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("Cannot tell where"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn example_atomic_counter() -> std::io::Result<()> {
+    init();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    // an 8 byte counter in x0, aligned as atomics need
+    let counter_engine = |program: &[&str], ty: RegionType| {
+        let mut engine = new_engine(&ctx, program, &[(0, "counter")], &[("counter", ty, 8)]);
+        engine.add_region_alignment("counter".to_string(), 8);
+        engine
+    };
+
+    // a refcount increment, the retry is followed once before the store succeeds
+    let mut engine = counter_engine(
+        &[
+            "start:",
+            "retry:",
+            "ldaxr x2, [x0]",
+            "add x2, x2, #1",
+            "stlxr w3, x2, [x0]",
+            "cbnz w3, retry",
+            "ret",
+        ],
+        RegionType::RW,
+    );
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    let mut engine = counter_engine(
+        &[
+            "start:",
+            "mov x1, #1",
+            "ldaddal x1, x2, [x0]",
+            "swpal x2, x3, [x0]",
+            "casal x3, x1, [x0]",
+            "dmb ish",
+            "ldar w4, [x0]",
+            "stlr w4, [x0, #4]",
+            "ret",
+        ],
+        RegionType::RW,
+    );
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // byte and halfword forms are aligned to their size, casp to its pair
+    let mut engine = counter_engine(
+        &[
+            "start:",
+            "mov w1, #1",
+            "ldaddalb w1, w2, [x0, #3]",
+            "swph w2, w3, [x0, #6]",
+            "caslb w3, w1, [x0, #1]",
+            "casp w4, w5, w6, w7, [x0]",
+            "ret",
+        ],
+        RegionType::RW,
+    );
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    for misaligned in ["ldaddh w1, w2, [x0, #3]", "casp w4, w5, w6, w7, [x0, #4]"] {
+        let mut engine = counter_engine(&["start:", misaligned, "ret"], RegionType::RW);
+        let err = engine.start("start".to_string()).unwrap_err();
+        assert!(
+            err.to_string().contains("aligned"),
+            "{}: {}",
+            misaligned,
+            err
+        );
+    }
+
+    // a store exclusive that fails writes nothing
+    let mut engine = counter_engine(&["start:", "stxr w3, x1, [x0]", "ret"], RegionType::WRITE);
+    engine.add_output_region("counter".to_string());
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Does not initialise all of counter"),
+        "{}",
+        err
+    );
+
+    let mut engine = counter_engine(
+        &[
+            "start:",
+            "retry:",
+            "stxr w3, x1, [x0]",
+            "cbnz w3, retry",
+            "ret",
+        ],
+        RegionType::WRITE,
+    );
+    engine.add_output_region("counter".to_string());
+    let res = engine.start("start".to_string());
+    assert!(res.is_ok(), "{:?}", res);
+
+    // an atomic writes as well as reads
+    let mut engine = counter_engine(
+        &["start:", "mov x1, #1", "ldadd x1, x2, [x0]", "ret"],
+        RegionType::READ,
+    );
+    assert!(engine.start("start".to_string()).is_err());
+
+    let mut engine = counter_engine(
+        &[
+            "start:",
+            "add x0, x0, #512",
+            "ldxr x2, [x0]",
+            "stxr w3, x2, [x0]",
+            "ret",
+        ],
+        RegionType::RW,
+    );
+    let err = engine.start("start".to_string()).unwrap_err();
+    assert!(err.to_string().contains("outside"), "{}", err);
+    Ok(())
+}